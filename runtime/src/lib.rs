@@ -0,0 +1,687 @@
+//! Scene data that rusty-editor saves next to the scene, because engine's physics
+//! descriptors have no place for it: trimesh and heightfield sources, convex shapes,
+//! limits and motors of joints and dynamics of rigid bodies.
+//!
+//! A game depends on this crate, loads the scene as usual and then calls
+//! `ShapeSources::load` with scene's path and `ShapeSources::apply_to_scene` with the
+//! loaded scene. Until then scene's physics has plain engine shapes in place of these, no
+//! limits or motors on joints, and bodies with no damping, no CCD and no locked axes.
+//! Editor's simulation preview applies the same data the same way.
+
+use rg3d::{
+    core::{
+        algebra::{DMatrix, Matrix4, Point3, Vector3},
+        pool::Handle,
+        visitor::{Visit, VisitResult, Visitor},
+    },
+    engine::resource_manager::ResourceManager,
+    physics::{
+        dynamics::{JointHandle, JointParams, RigidBodyHandle, SpringModel},
+        geometry::{ColliderBuilder, ColliderHandle},
+    },
+    resource::texture::{Texture, TextureKind, TextureState},
+    scene::{graph::Graph, node::Node, physics::Physics, PhysicsBinder, Scene},
+};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+/// Collider of a body bound to a node. Index is the position of the collider among
+/// colliders of the body, in the order engine resolves them from scene's descriptors.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ColliderKey {
+    pub body: Handle<Node>,
+    pub index: u32,
+}
+
+impl Visit for ColliderKey {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.body.visit("Body", visitor)?;
+        self.index.visit("Index", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+/// Joint between bodies bound to nodes. Index tells apart joints that connect the same
+/// pair of bodies, it is their position in the order engine resolves them.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct JointKey {
+    pub body1: Handle<Node>,
+    pub body2: Handle<Node>,
+    pub index: u32,
+}
+
+impl Visit for JointKey {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.body1.visit("Body1", visitor)?;
+        self.body2.visit("Body2", visitor)?;
+        self.index.visit("Index", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+/// Parameters of trimesh collider. Engine's `TrimeshDesc` has no parameters, engine builds
+/// trimesh from every mesh of node bound to collider's body (including descendants) -
+/// default source does exactly the same.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TrimeshSource {
+    /// Mesh node to take geometry from, `NONE` means node bound to the body.
+    pub node: Handle<Node>,
+    /// Indices of surfaces to take geometry from, empty means all surfaces.
+    pub surfaces: Vec<u32>,
+}
+
+impl TrimeshSource {
+    pub fn takes_surface(&self, index: usize) -> bool {
+        self.surfaces.is_empty() || self.surfaces.contains(&(index as u32))
+    }
+}
+
+impl Visit for TrimeshSource {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.node.visit("Node", visitor)?;
+        self.surfaces.visit("Surfaces", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+/// Parameters of heightfield collider. Heights are taken from first channel of the texture,
+/// so it should be grayscale.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HeightfieldSource {
+    pub texture: Option<PathBuf>,
+    /// Size of heightfield along X and Z axes, Y is height of white pixels.
+    pub scale: Vector3<f32>,
+}
+
+impl Default for HeightfieldSource {
+    fn default() -> Self {
+        Self {
+            texture: None,
+            scale: Vector3::new(10.0, 1.0, 10.0),
+        }
+    }
+}
+
+impl Visit for HeightfieldSource {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.texture.visit("Texture", visitor)?;
+        self.scale.visit("Scale", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+/// Convex polyhedron collider. Engine's descriptors have no convex shape, so collider's
+/// descriptor holds a cuboid that encloses the hull, the cuboid is kept only if the hull
+/// is degenerate.
+#[derive(Clone, Debug, Default)]
+pub struct ConvexShape {
+    /// Point cloud in collider's space the hull was built from.
+    pub points: Vec<Vector3<f32>>,
+    /// Points that were closer than this to the hull were dropped.
+    pub tolerance: f32,
+    /// Vertices of the hull in collider's space.
+    pub hull: Vec<Vector3<f32>>,
+}
+
+impl Visit for ConvexShape {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.points.visit("Points", visitor)?;
+        self.tolerance.visit("Tolerance", visitor)?;
+        self.hull.visit("Hull", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+/// Limits and motor of revolute and prismatic joints. Values are angles in radians for
+/// revolute joints and distances along the axis for prismatic joints. Engine's revolute
+/// joints have no limits, only motor.
+#[derive(Clone, Debug, PartialEq)]
+pub struct JointMotion {
+    pub limits_enabled: bool,
+    pub min_limit: f32,
+    pub max_limit: f32,
+    pub motor_enabled: bool,
+    pub motor_target_velocity: f32,
+    pub motor_target_position: f32,
+    pub motor_stiffness: f32,
+    pub motor_damping: f32,
+    pub motor_max_impulse: f32,
+}
+
+impl Default for JointMotion {
+    fn default() -> Self {
+        Self {
+            limits_enabled: false,
+            min_limit: -1.0,
+            max_limit: 1.0,
+            motor_enabled: false,
+            motor_target_velocity: 0.0,
+            motor_target_position: 0.0,
+            motor_stiffness: 0.0,
+            motor_damping: 0.0,
+            motor_max_impulse: std::f32::MAX,
+        }
+    }
+}
+
+impl Visit for JointMotion {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.limits_enabled.visit("LimitsEnabled", visitor)?;
+        self.min_limit.visit("MinLimit", visitor)?;
+        self.max_limit.visit("MaxLimit", visitor)?;
+        self.motor_enabled.visit("MotorEnabled", visitor)?;
+        self.motor_target_velocity
+            .visit("MotorTargetVelocity", visitor)?;
+        self.motor_target_position
+            .visit("MotorTargetPosition", visitor)?;
+        self.motor_stiffness.visit("MotorStiffness", visitor)?;
+        self.motor_damping.visit("MotorDamping", visitor)?;
+        self.motor_max_impulse.visit("MotorMaxImpulse", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+/// Rigid body parameters that engine's body descriptor has no place for. Mass from
+/// density is the only one that gets to the descriptor, as body's mass.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BodyDynamics {
+    pub linear_damping: f32,
+    pub angular_damping: f32,
+    pub ccd_enabled: bool,
+    /// Locks of translation along X, Y and Z axes.
+    pub locked_translations: [bool; 3],
+    /// Locks of rotation around X, Y and Z axes.
+    pub locked_rotations: [bool; 3],
+    /// Mass is computed from volumes and densities of colliders instead of fixed `mass`.
+    pub mass_from_density: bool,
+}
+
+impl Default for BodyDynamics {
+    fn default() -> Self {
+        Self {
+            linear_damping: 0.0,
+            angular_damping: 0.0,
+            ccd_enabled: false,
+            locked_translations: [false; 3],
+            locked_rotations: [false; 3],
+            mass_from_density: false,
+        }
+    }
+}
+
+impl Visit for BodyDynamics {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.linear_damping.visit("LinearDamping", visitor)?;
+        self.angular_damping.visit("AngularDamping", visitor)?;
+        self.ccd_enabled.visit("CcdEnabled", visitor)?;
+        for (i, axis) in ["X", "Y", "Z"].iter().enumerate() {
+            self.locked_translations[i].visit(&format!("LockTranslation{}", axis), visitor)?;
+            self.locked_rotations[i].visit(&format!("LockRotation{}", axis), visitor)?;
+        }
+        let _ = self.mass_from_density.visit("MassFromDensity", visitor);
+
+        visitor.leave_region()
+    }
+}
+
+/// Shape (and joint or body) parameters that engine's physics descriptors can't hold,
+/// saved in a separate file next to the scene. Bodies are referenced by nodes they're
+/// bound to, so bodies that are not bound to any node can't have them.
+#[derive(Default)]
+pub struct ShapeSources {
+    pub trimeshes: HashMap<ColliderKey, TrimeshSource>,
+    pub heightfields: HashMap<ColliderKey, HeightfieldSource>,
+    pub convexes: HashMap<ColliderKey, ConvexShape>,
+    pub joint_motions: HashMap<JointKey, JointMotion>,
+    pub body_dynamics: HashMap<Handle<Node>, BodyDynamics>,
+    /// Material ids of colliders, engine does not need them.
+    pub materials: HashMap<ColliderKey, u32>,
+}
+
+impl Visit for ShapeSources {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.trimeshes.visit("Trimeshes", visitor)?;
+        self.heightfields.visit("Heightfields", visitor)?;
+        // Older files have no convex shapes.
+        let _ = self.convexes.visit("Convexes", visitor);
+        let _ = self.joint_motions.visit("JointMotions", visitor);
+        let _ = self.body_dynamics.visit("BodyDynamics", visitor);
+        let _ = self.materials.visit("Materials", visitor);
+
+        visitor.leave_region()
+    }
+}
+
+impl ShapeSources {
+    pub fn path(scene_path: &Path) -> PathBuf {
+        scene_path.with_extension("shapes")
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.trimeshes.is_empty()
+            && self.heightfields.is_empty()
+            && self.convexes.is_empty()
+            && self.joint_motions.is_empty()
+            && self.body_dynamics.is_empty()
+            && self.materials.is_empty()
+    }
+
+    pub fn load(scene_path: &Path) -> Option<Self> {
+        let mut visitor = Visitor::load_binary(Self::path(scene_path)).ok()?;
+        let mut sources = Self::default();
+        sources.visit("ShapeSources", &mut visitor).ok()?;
+        Some(sources)
+    }
+
+    /// Saves sources next to the scene, or removes stale file if there is nothing to save.
+    pub fn save(&mut self, scene_path: &Path) -> VisitResult {
+        let path = Self::path(scene_path);
+        if self.is_empty() {
+            let _ = std::fs::remove_file(path);
+            Ok(())
+        } else {
+            let mut visitor = Visitor::new();
+            self.visit("ShapeSources", &mut visitor)?;
+            visitor.save_binary(path)
+        }
+    }
+
+    /// Applies parameters to physics of a scene loaded from the file these sources were
+    /// saved with. Returns descriptions of parameters that couldn't be applied.
+    pub fn apply_to_scene(
+        &self,
+        scene: &mut Scene,
+        resource_manager: &ResourceManager,
+    ) -> Vec<String> {
+        self.apply_to_engine(
+            &mut scene.physics,
+            &scene.physics_binder,
+            &scene.graph,
+            resource_manager,
+        )
+    }
+
+    /// Applies parameters to engine physics, bodies are found through the binder. Returns
+    /// descriptions of parameters that couldn't be applied.
+    pub fn apply_to_engine(
+        &self,
+        physics: &mut Physics,
+        binder: &PhysicsBinder,
+        graph: &Graph,
+        resource_manager: &ResourceManager,
+    ) -> Vec<String> {
+        let mut warnings = Vec::new();
+
+        // Replaced colliders move to the end of body's list, so every collider is found
+        // before any of them is replaced.
+        let colliders = engine_colliders(physics, binder);
+
+        for (key, source) in self.trimeshes.iter() {
+            let handle = match colliders.get(key) {
+                Some(&handle) => handle,
+                None => {
+                    warnings.push(missing_collider(key, graph));
+                    continue;
+                }
+            };
+            let frame = match physics.colliders.get(handle) {
+                Some(collider) => match physics.bodies.get(collider.parent()) {
+                    Some(body) => body.position() * collider.position_wrt_parent(),
+                    None => continue,
+                },
+                None => continue,
+            };
+            let (vertices, indices) = build_trimesh(
+                source,
+                &trimesh_meshes_of(source.node, key.body, graph),
+                graph,
+                frame.inverse().to_homogeneous(),
+            );
+            if indices.is_empty() {
+                warnings.push(format!(
+                    "Trimesh collider {} has no triangles, engine's trimesh is kept.",
+                    describe_collider(key, graph)
+                ));
+            } else {
+                replace_engine_collider(
+                    physics,
+                    handle,
+                    ColliderBuilder::trimesh(vertices, indices),
+                );
+            }
+        }
+
+        for (key, source) in self.heightfields.iter() {
+            let path = match source.texture.as_ref() {
+                Some(path) => path,
+                None => continue,
+            };
+            let handle = match colliders.get(key) {
+                Some(&handle) => handle,
+                None => {
+                    warnings.push(missing_collider(key, graph));
+                    continue;
+                }
+            };
+            let texture = resource_manager.request_texture(path);
+            match read_heights(&texture) {
+                Some(heights) => {
+                    replace_engine_collider(
+                        physics,
+                        handle,
+                        ColliderBuilder::heightfield(heights, source.scale),
+                    );
+                }
+                None => warnings.push(format!(
+                    "Height map {} of heightfield collider {} is not loaded, engine's \
+                     heightfield is kept.",
+                    path.display(),
+                    describe_collider(key, graph)
+                )),
+            }
+        }
+
+        for (key, shape) in self.convexes.iter() {
+            let handle = match colliders.get(key) {
+                Some(&handle) => handle,
+                None => {
+                    warnings.push(missing_collider(key, graph));
+                    continue;
+                }
+            };
+            let points = shape
+                .hull
+                .iter()
+                .map(|&v| Point3::from(v))
+                .collect::<Vec<_>>();
+            match ColliderBuilder::convex_hull(&points) {
+                Some(builder) => replace_engine_collider(physics, handle, builder),
+                None => warnings.push(format!(
+                    "Convex collider {} has degenerate hull, its enclosing cuboid is used.",
+                    describe_collider(key, graph)
+                )),
+            }
+        }
+
+        let joints = engine_joints(physics, binder);
+
+        for (key, motion) in self.joint_motions.iter() {
+            let joint = match joints
+                .get(key)
+                .and_then(|&handle| physics.joints.get_mut(handle))
+            {
+                Some(joint) => joint,
+                None => {
+                    warnings.push(format!(
+                        "Joint between nodes {} and {} is not found, its limits and motor \
+                         are not applied.",
+                        node_name(key.body1, graph),
+                        node_name(key.body2, graph)
+                    ));
+                    continue;
+                }
+            };
+            match &mut joint.params {
+                JointParams::PrismaticJoint(prismatic) => {
+                    prismatic.limits_enabled = motion.limits_enabled;
+                    prismatic.limits = [motion.min_limit, motion.max_limit];
+                    if motion.motor_enabled {
+                        prismatic.configure_motor_model(SpringModel::VelocityBased);
+                        prismatic.configure_motor(
+                            motion.motor_target_position,
+                            motion.motor_target_velocity,
+                            motion.motor_stiffness,
+                            motion.motor_damping,
+                        );
+                        prismatic.motor_max_impulse = motion.motor_max_impulse;
+                    }
+                }
+                JointParams::RevoluteJoint(revolute) => {
+                    if motion.limits_enabled {
+                        warnings.push(format!(
+                            "Revolute joint between nodes {} and {} has limits, engine's \
+                             revolute joints can't be limited.",
+                            node_name(key.body1, graph),
+                            node_name(key.body2, graph)
+                        ));
+                    }
+                    if motion.motor_enabled {
+                        revolute.configure_motor_model(SpringModel::VelocityBased);
+                        revolute.configure_motor(
+                            motion.motor_target_position,
+                            motion.motor_target_velocity,
+                            motion.motor_stiffness,
+                            motion.motor_damping,
+                        );
+                        revolute.motor_max_impulse = motion.motor_max_impulse;
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        for (&node, dynamics) in self.body_dynamics.iter() {
+            match engine_body(binder, node).and_then(|handle| physics.bodies.get_mut(handle)) {
+                Some(body) => {
+                    body.linear_damping = dynamics.linear_damping;
+                    body.angular_damping = dynamics.angular_damping;
+                    body.enable_ccd(dynamics.ccd_enabled);
+                    let [x, y, z] = dynamics.locked_translations;
+                    body.restrict_translations(!x, !y, !z, true);
+                    let [x, y, z] = dynamics.locked_rotations;
+                    body.restrict_rotations(!x, !y, !z, true);
+                }
+                None => warnings.push(format!(
+                    "Body of node {} is not found, its damping, CCD and locks are not \
+                     applied.",
+                    node_name(node, graph)
+                )),
+            }
+        }
+
+        warnings
+    }
+}
+
+fn node_name(node: Handle<Node>, graph: &Graph) -> String {
+    if graph.is_valid_handle(node) {
+        graph[node].name().to_owned()
+    } else {
+        format!("<invalid {}:{}>", node.index(), node.generation())
+    }
+}
+
+fn describe_collider(key: &ColliderKey, graph: &Graph) -> String {
+    format!("{} of node {}", key.index, node_name(key.body, graph))
+}
+
+fn missing_collider(key: &ColliderKey, graph: &Graph) -> String {
+    format!(
+        "Collider {} is not found, its shape is not applied.",
+        describe_collider(key, graph)
+    )
+}
+
+/// Returns handle of engine body bound to given node.
+fn engine_body(binder: &PhysicsBinder, node: Handle<Node>) -> Option<RigidBodyHandle> {
+    binder.forward_map().get(&node).map(|&body| body.into())
+}
+
+/// Returns handles of engine colliders of every bound body.
+fn engine_colliders(
+    physics: &Physics,
+    binder: &PhysicsBinder,
+) -> HashMap<ColliderKey, ColliderHandle> {
+    let mut colliders = HashMap::new();
+    for (&node, &body) in binder.forward_map().iter() {
+        if let Some(body) = physics.bodies.get(body.into()) {
+            for (i, &collider) in body.colliders().iter().enumerate() {
+                colliders.insert(
+                    ColliderKey {
+                        body: node,
+                        index: i as u32,
+                    },
+                    collider,
+                );
+            }
+        }
+    }
+    colliders
+}
+
+/// Returns handles of engine joints between bound bodies.
+fn engine_joints(physics: &Physics, binder: &PhysicsBinder) -> HashMap<JointKey, JointHandle> {
+    let nodes = binder
+        .forward_map()
+        .iter()
+        .map(|(&node, &body)| (RigidBodyHandle::from(body), node))
+        .collect::<HashMap<_, _>>();
+    let mut joints = HashMap::new();
+    for (handle, joint) in physics.joints.iter() {
+        if let (Some(&body1), Some(&body2)) = (nodes.get(&joint.body1), nodes.get(&joint.body2)) {
+            let mut key = JointKey {
+                body1,
+                body2,
+                index: 0,
+            };
+            while joints.contains_key(&key) {
+                key.index += 1;
+            }
+            joints.insert(key, handle);
+        }
+    }
+    joints
+}
+
+/// Replaces collider of engine physics with the one made by given builder, the rest of
+/// collider's parameters are kept.
+fn replace_engine_collider(
+    physics: &mut Physics,
+    handle: ColliderHandle,
+    builder: ColliderBuilder,
+) {
+    let (parent, collider) = match physics.colliders.get(handle) {
+        Some(old) => {
+            let mut builder = builder
+                .friction(old.friction)
+                .restitution(old.restitution)
+                .sensor(old.is_sensor())
+                .position_wrt_parent(*old.position_wrt_parent())
+                .collision_groups(old.collision_groups())
+                .solver_groups(old.solver_groups());
+            if let Some(density) = old.density() {
+                builder = builder.density(density);
+            }
+            (old.parent(), builder.build())
+        }
+        None => return,
+    };
+    physics.colliders.remove(handle, &mut physics.bodies, true);
+    physics
+        .colliders
+        .insert(collider, parent, &mut physics.bodies);
+}
+
+/// Returns meshes trimesh takes its geometry from - the source node if it is a mesh, or
+/// every mesh in the hierarchy of the node bound to collider's body.
+pub fn trimesh_meshes_of(
+    source_node: Handle<Node>,
+    bound_node: Handle<Node>,
+    graph: &Graph,
+) -> Vec<Handle<Node>> {
+    if graph.is_valid_handle(source_node) {
+        if let Node::Mesh(_) = graph[source_node] {
+            vec![source_node]
+        } else {
+            vec![]
+        }
+    } else if graph.is_valid_handle(bound_node) {
+        graph
+            .traverse_handle_iter(bound_node)
+            .filter(|&handle| matches!(graph[handle], Node::Mesh(_)))
+            .collect()
+    } else {
+        vec![]
+    }
+}
+
+/// Collects triangles of surfaces taken by the source, `inv_frame` transforms them from
+/// world space to space of the collider.
+fn build_trimesh(
+    source: &TrimeshSource,
+    meshes: &[Handle<Node>],
+    graph: &Graph,
+    inv_frame: Matrix4<f32>,
+) -> (Vec<Point3<f32>>, Vec<[u32; 3]>) {
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+    for &handle in meshes {
+        if let Node::Mesh(mesh) = &graph[handle] {
+            let transform = inv_frame * mesh.global_transform();
+            for (i, surface) in mesh.surfaces().iter().enumerate() {
+                if !source.takes_surface(i) {
+                    continue;
+                }
+                let data = surface.data();
+                let data = data.read().unwrap();
+                let offset = vertices.len() as u32;
+                vertices.extend(
+                    data.get_vertices()
+                        .iter()
+                        .map(|v| transform.transform_point(&Point3::from(v.position))),
+                );
+                indices.extend(data.triangles().iter().map(|triangle| {
+                    [
+                        triangle[0] + offset,
+                        triangle[1] + offset,
+                        triangle[2] + offset,
+                    ]
+                }));
+            }
+        }
+    }
+    (vertices, indices)
+}
+
+/// Reads heights from first channel of the texture, rows go along Z axis and columns
+/// along X axis. Returns `None` if texture is not loaded (yet).
+pub fn read_heights(texture: &Texture) -> Option<DMatrix<f32>> {
+    let state = texture.state();
+    if let TextureState::Ok(data) = &*state {
+        if let TextureKind::Rectangle { width, height } = data.kind() {
+            let (width, height) = (width as usize, height as usize);
+            let bytes = data.data();
+            let bytes_per_pixel = bytes.len() / (width * height).max(1);
+            if bytes_per_pixel > 0 && width > 1 && height > 1 {
+                return Some(DMatrix::from_fn(height, width, |z, x| {
+                    bytes[(z * width + x) * bytes_per_pixel] as f32 / 255.0
+                }));
+            }
+        }
+    }
+    None
+}
@@ -1,19 +1,27 @@
-use crate::rg3d::core::math::Matrix4Ext;
+use crate::{
+    icons::NodeIcons,
+    rg3d::core::math::Matrix4Ext,
+    scene::{CommandGroup, MoveNodeCommand, RotateNodeCommand, SceneCommand},
+    Message, STARTUP_WORKING_DIR,
+};
 use rg3d::{
     core::{
         algebra::{UnitQuaternion, Vector2, Vector3},
         math::aabb::AxisAlignedBoundingBox,
         pool::Handle,
+        visitor::{Visit, VisitResult, Visitor},
     },
+    engine::resource_manager::ResourceManager,
     gui::message::{KeyCode, MouseButton},
     scene::{
-        base::BaseBuilder, camera::CameraBuilder, graph::Graph, node::Node,
+        base::BaseBuilder, camera::CameraBuilder, graph::Graph, light::Light, node::Node,
         transform::TransformBuilder,
     },
 };
 use std::{
     collections::hash_map::DefaultHasher,
     hash::{Hash, Hasher},
+    sync::mpsc::Sender,
 };
 
 pub struct CameraController {
@@ -26,9 +34,131 @@ pub struct CameraController {
     move_right: bool,
     move_forward: bool,
     move_backward: bool,
+    move_up: bool,
+    move_down: bool,
+    speed_up: bool,
+    slow_down: bool,
+    velocity: Vector3<f32>,
+    settings_changed: bool,
+    pub settings: CameraSettings,
     stack: Vec<Handle<Node>>,
     editor_context: PickContext,
     scene_context: PickContext,
+    pilot: Option<Pilot>,
+    pub icons: NodeIcons,
+}
+
+pub const CAMERA_SETTINGS_PATH: &str = "camera.bin";
+
+/// Editor camera preferences, they're shared between all scenes and saved next to
+/// the editor.
+#[derive(Clone, PartialEq, Debug)]
+pub struct CameraSettings {
+    /// Fly speed in units per second.
+    pub speed: f32,
+    /// Speed multiplier while Shift is held.
+    pub fast_multiplier: f32,
+    /// Speed multiplier while Ctrl is held.
+    pub slow_multiplier: f32,
+    /// How fast camera reaches desired velocity, higher values make movement snappier.
+    pub acceleration: f32,
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        Self {
+            speed: 10.0,
+            fast_multiplier: 4.0,
+            slow_multiplier: 0.25,
+            acceleration: 10.0,
+        }
+    }
+}
+
+impl Visit for CameraSettings {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.speed.visit("Speed", visitor)?;
+        self.fast_multiplier.visit("FastMultiplier", visitor)?;
+        self.slow_multiplier.visit("SlowMultiplier", visitor)?;
+        self.acceleration.visit("Acceleration", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+impl CameraSettings {
+    pub const MIN_SPEED: f32 = 0.1;
+    pub const MAX_SPEED: f32 = 1000.0;
+
+    pub fn load() -> Self {
+        let mut settings = Self::default();
+        if let Ok(mut visitor) = Visitor::load_binary(
+            STARTUP_WORKING_DIR
+                .lock()
+                .unwrap()
+                .join(CAMERA_SETTINGS_PATH),
+        ) {
+            if settings.visit("CameraSettings", &mut visitor).is_err() {
+                settings = Self::default();
+            }
+        }
+        settings
+    }
+
+    pub fn save(&mut self) -> VisitResult {
+        let mut visitor = Visitor::new();
+        self.visit("CameraSettings", &mut visitor)?;
+        visitor.save_binary(
+            STARTUP_WORKING_DIR
+                .lock()
+                .unwrap()
+                .join(CAMERA_SETTINGS_PATH),
+        )
+    }
+}
+
+/// State of pilot mode - when it is active, the editor camera looks through a scene
+/// camera (or a spot light) and every movement of the editor camera is applied to that
+/// node. Initial transform of the node is kept to produce commands when the mode ends,
+/// the rest is the editor camera state that has to be restored.
+struct Pilot {
+    node: Handle<Node>,
+    initial_position: Vector3<f32>,
+    initial_rotation: UnitQuaternion<f32>,
+    /// Rotation of the node relative to the yaw and pitch of editor camera, it keeps roll
+    /// of the node.
+    roll: UnitQuaternion<f32>,
+    /// Node is moved only once the editor camera is moved or rotated.
+    moved: bool,
+    pivot_position: Vector3<f32>,
+    yaw: f32,
+    pitch: f32,
+    fov: f32,
+    z_near: f32,
+    z_far: f32,
+}
+
+/// Spot lights shine along their negative Y axis while cameras look along positive Z,
+/// this rotation maps one onto another.
+fn spot_light_correction() -> UnitQuaternion<f32> {
+    UnitQuaternion::from_axis_angle(&Vector3::x_axis(), -90.0f32.to_radians())
+}
+
+/// Returns global rotation of piloted node that looks with given yaw and pitch, with no roll.
+fn pilot_rotation(yaw: f32, pitch: f32, node: &Node) -> UnitQuaternion<f32> {
+    let rotation = UnitQuaternion::from_axis_angle(&Vector3::y_axis(), yaw)
+        * UnitQuaternion::from_axis_angle(&Vector3::x_axis(), pitch);
+    if let Node::Light(_) = node {
+        rotation * spot_light_correction()
+    } else {
+        rotation
+    }
+}
+
+fn is_pilotable(node: &Node) -> bool {
+    matches!(node, Node::Camera(_) | Node::Light(Light::Spot(_)))
 }
 
 #[derive(Default)]
@@ -40,7 +170,7 @@ struct PickContext {
 }
 
 impl CameraController {
-    pub fn new(graph: &mut Graph, root: Handle<Node>) -> Self {
+    pub fn new(graph: &mut Graph, root: Handle<Node>, resource_manager: &ResourceManager) -> Self {
         let camera;
         let pivot = BaseBuilder::new()
             .with_children(&[{
@@ -59,6 +189,8 @@ impl CameraController {
 
         graph.link_nodes(pivot, root);
 
+        let icons = NodeIcons::new(graph, root, resource_manager);
+
         Self {
             pivot,
             camera,
@@ -69,14 +201,190 @@ impl CameraController {
             move_right: false,
             move_forward: false,
             move_backward: false,
+            move_up: false,
+            move_down: false,
+            speed_up: false,
+            slow_down: false,
+            velocity: Default::default(),
+            settings_changed: false,
+            settings: CameraSettings::load(),
             stack: Default::default(),
             editor_context: Default::default(),
             scene_context: Default::default(),
+            pilot: None,
+            icons,
+        }
+    }
+
+    /// Returns handle of a node that is currently piloted or `Handle::NONE` if pilot
+    /// mode is not active.
+    pub fn pilot(&self) -> Handle<Node> {
+        self.pilot.as_ref().map_or(Handle::NONE, |p| p.node)
+    }
+
+    pub fn is_piloting(&self) -> bool {
+        self.pilot.is_some()
+    }
+
+    /// Starts to look through given node. Only cameras and spot lights can be piloted,
+    /// for any other node this method does nothing and returns `false`. Editor camera has
+    /// only yaw and pitch, so roll of the node is kept as is.
+    pub fn begin_pilot(&mut self, graph: &mut Graph, node: Handle<Node>) -> bool {
+        if self.pilot.is_some() || !graph.is_valid_handle(node) || !is_pilotable(&graph[node]) {
+            return false;
+        }
+
+        let (fov, z_near, z_far) = {
+            let camera = graph[self.camera].as_camera();
+            (camera.fov(), camera.z_near(), camera.z_far())
+        };
+
+        let pilot_node = &graph[node];
+        let initial_position = **pilot_node.local_transform().position();
+        let initial_rotation = **pilot_node.local_transform().rotation();
+        let global_transform = pilot_node.global_transform();
+        let look = if let Node::Light(_) = pilot_node {
+            -global_transform.up()
+        } else {
+            global_transform.look()
         }
+        .try_normalize(std::f32::EPSILON)
+        .unwrap_or_else(Vector3::z);
+
+        let yaw = look.x.atan2(look.z);
+        let pitch = (-look.y).asin();
+        let global_rotation = UnitQuaternion::from_matrix(&global_transform.basis());
+        let roll = pilot_rotation(yaw, pitch, pilot_node).inverse() * global_rotation;
+
+        self.pilot = Some(Pilot {
+            node,
+            initial_position,
+            initial_rotation,
+            roll,
+            moved: false,
+            pivot_position: **graph[self.pivot].local_transform().position(),
+            yaw: self.yaw,
+            pitch: self.pitch,
+            fov,
+            z_near,
+            z_far,
+        });
+
+        self.yaw = yaw;
+        self.pitch = pitch;
+        graph[self.pivot]
+            .local_transform_mut()
+            .set_position(global_transform.position());
+
+        true
+    }
+
+    /// Stops pilot mode and restores editor camera. Returns a command that will commit
+    /// accumulated transform change of piloted node, `None` if node was not moved or if
+    /// it was deleted in the meantime.
+    pub fn end_pilot(&mut self, graph: &mut Graph) -> Option<SceneCommand> {
+        let pilot = self.pilot.take()?;
+
+        self.yaw = pilot.yaw;
+        self.pitch = pilot.pitch;
+        graph[self.pivot]
+            .local_transform_mut()
+            .set_position(pilot.pivot_position);
+        let camera = graph[self.camera].as_camera_mut();
+        camera.set_fov(pilot.fov);
+        camera.set_z_near(pilot.z_near);
+        camera.set_z_far(pilot.z_far);
+
+        if !pilot.moved || !graph.is_valid_handle(pilot.node) {
+            return None;
+        }
+
+        let transform = graph[pilot.node].local_transform_mut();
+        let new_position = **transform.position();
+        let new_rotation = **transform.rotation();
+
+        // Put node back to where it was, commands will move it again.
+        transform
+            .set_position(pilot.initial_position)
+            .set_rotation(pilot.initial_rotation);
+
+        Some(SceneCommand::CommandGroup(CommandGroup::from(vec![
+            SceneCommand::MoveNode(MoveNodeCommand::new(
+                pilot.node,
+                pilot.initial_position,
+                new_position,
+            )),
+            SceneCommand::RotateNode(RotateNodeCommand::new(
+                pilot.node,
+                pilot.initial_rotation,
+                new_rotation,
+            )),
+        ])))
+    }
+
+    fn sync_pilot(&mut self, graph: &mut Graph) {
+        let pilot_node = self.pilot();
+
+        if !graph.is_valid_handle(pilot_node) {
+            // Piloted node was deleted, nothing to commit.
+            let _ = self.end_pilot(graph);
+            return;
+        }
+
+        let (fov, z_near, z_far) = match &graph[pilot_node] {
+            Node::Camera(camera) => (camera.fov(), camera.z_near(), camera.z_far()),
+            Node::Light(Light::Spot(spot)) => (
+                spot.hotspot_cone_angle() + spot.falloff_angle_delta(),
+                self.pilot.as_ref().unwrap().z_near,
+                spot.distance(),
+            ),
+            _ => {
+                // Node can't be piloted anymore.
+                let _ = self.end_pilot(graph);
+                return;
+            }
+        };
+        let camera = graph[self.camera].as_camera_mut();
+        camera.set_fov(fov);
+        camera.set_z_near(z_near);
+        camera.set_z_far(z_far);
+
+        let pilot = self.pilot.as_ref().unwrap();
+        if !pilot.moved {
+            return;
+        }
+
+        let rotation = pilot_rotation(self.yaw, self.pitch, &graph[pilot_node]) * pilot.roll;
+        let position = **graph[self.pivot].local_transform().position();
+
+        // Convert world-space transform into parent's space of piloted node.
+        let parent = graph[pilot_node].parent();
+        let (local_position, local_rotation) = if parent.is_some() {
+            let parent_transform = graph[parent].global_transform();
+            let parent_rotation = UnitQuaternion::from_matrix(&parent_transform.basis());
+            (
+                parent_transform
+                    .try_inverse()
+                    .unwrap_or_default()
+                    .transform_point(&position.into())
+                    .coords,
+                parent_rotation.inverse() * rotation,
+            )
+        } else {
+            (position, rotation)
+        };
+
+        graph[pilot_node]
+            .local_transform_mut()
+            .set_position(local_position)
+            .set_rotation(local_rotation);
     }
 
     pub fn on_mouse_move(&mut self, delta: Vector2<f32>) {
         if self.rotate {
+            if let Some(pilot) = self.pilot.as_mut() {
+                pilot.moved |= delta != Vector2::default();
+            }
             self.yaw -= delta.x as f32 * 0.01;
             self.pitch += delta.y as f32 * 0.01;
             if self.pitch > 90.0f32.to_radians() {
@@ -89,6 +397,15 @@ impl CameraController {
     }
 
     pub fn on_mouse_wheel(&mut self, delta: f32, graph: &mut Graph) {
+        // Wheel adjusts fly speed while looking around.
+        if self.rotate {
+            self.settings.speed = (self.settings.speed * 1.2f32.powf(delta))
+                .max(CameraSettings::MIN_SPEED)
+                .min(CameraSettings::MAX_SPEED);
+            self.settings_changed = true;
+            return;
+        }
+
         let camera = &mut graph[self.camera];
 
         let look = camera.global_transform().look();
@@ -96,11 +413,31 @@ impl CameraController {
         if let Node::Base(pivot) = &mut graph[self.pivot] {
             pivot.local_transform_mut().offset(look.scale(delta));
         }
+        if let Some(pilot) = self.pilot.as_mut() {
+            pilot.moved |= delta != 0.0;
+        }
     }
 
-    pub fn on_mouse_button_up(&mut self, button: MouseButton) {
+    pub fn on_mouse_button_up(&mut self, button: MouseButton, sender: &Sender<Message>) {
         if button == MouseButton::Right {
             self.rotate = false;
+
+            if self.settings_changed {
+                self.settings_changed = false;
+                self.save_settings(sender);
+            }
+        }
+    }
+
+    /// Saves camera settings to disk, failure is not critical - it is just logged.
+    pub fn save_settings(&mut self, sender: &Sender<Message>) {
+        if let Err(e) = self.settings.save() {
+            sender
+                .send(Message::Log(format!(
+                    "Unable to save camera settings: {:?}",
+                    e
+                )))
+                .unwrap();
         }
     }
 
@@ -116,6 +453,10 @@ impl CameraController {
             KeyCode::S => self.move_backward = false,
             KeyCode::A => self.move_left = false,
             KeyCode::D => self.move_right = false,
+            KeyCode::E => self.move_up = false,
+            KeyCode::Q => self.move_down = false,
+            KeyCode::LShift | KeyCode::RShift => self.speed_up = false,
+            KeyCode::LControl | KeyCode::RControl => self.slow_down = false,
             _ => (),
         }
     }
@@ -126,6 +467,10 @@ impl CameraController {
             KeyCode::S => self.move_backward = true,
             KeyCode::A => self.move_left = true,
             KeyCode::D => self.move_right = true,
+            KeyCode::E => self.move_up = true,
+            KeyCode::Q => self.move_down = true,
+            KeyCode::LShift | KeyCode::RShift => self.speed_up = true,
+            KeyCode::LControl | KeyCode::RControl => self.slow_down = true,
             _ => (),
         }
     }
@@ -150,8 +495,34 @@ impl CameraController {
         if self.move_right {
             move_vec -= side;
         }
-        if let Some(v) = move_vec.try_normalize(std::f32::EPSILON) {
-            move_vec = v.scale(10.0 * dt);
+        if self.move_up {
+            move_vec += Vector3::y();
+        }
+        if self.move_down {
+            move_vec -= Vector3::y();
+        }
+
+        let mut speed = self.settings.speed;
+        if self.speed_up {
+            speed *= self.settings.fast_multiplier;
+        }
+        if self.slow_down {
+            speed *= self.settings.slow_multiplier;
+        }
+
+        let target_velocity = move_vec
+            .try_normalize(std::f32::EPSILON)
+            .map_or_else(Vector3::default, |v| v.scale(speed));
+
+        // Smoothly approach desired velocity, this gives both acceleration and deceleration.
+        let k = (self.settings.acceleration * dt).min(1.0);
+        self.velocity += (target_velocity - self.velocity).scale(k);
+        if target_velocity == Vector3::default() && self.velocity.norm() < 0.001 {
+            self.velocity = Vector3::default();
+        }
+        let move_vec = self.velocity.scale(dt);
+        if let Some(pilot) = self.pilot.as_mut() {
+            pilot.moved |= move_vec != Vector3::default();
         }
 
         if let Node::Camera(camera) = camera {
@@ -165,6 +536,10 @@ impl CameraController {
                 .set_rotation(yaw)
                 .offset(move_vec);
         }
+
+        if self.pilot.is_some() {
+            self.sync_pilot(graph);
+        }
     }
 
     pub fn pick<F>(
@@ -202,6 +577,11 @@ impl CameraController {
                     continue;
                 }
 
+                // Icons are picked on behalf of their nodes, they must not obstruct gizmos.
+                if editor_only && handle == self.icons.root() {
+                    continue;
+                }
+
                 let node = &graph[handle];
 
                 self.stack.extend_from_slice(node.children());
@@ -210,6 +590,18 @@ impl CameraController {
                     continue;
                 }
 
+                if let Some((position, radius)) =
+                    self.icons
+                        .bounds(handle, graph, camera.global_position(), camera.fov())
+                {
+                    if let Some(points) = ray.sphere_intersection_points(&position, radius) {
+                        let da = points[0].metric_distance(&ray.origin);
+                        let db = points[1].metric_distance(&ray.origin);
+                        context.pick_list.push((handle, da.min(db)));
+                    }
+                    continue;
+                }
+
                 let (aabb, surfaces) = match node {
                     Node::Mesh(mesh) => (mesh.bounding_box(), Some(mesh.surfaces())),
                     _ => (AxisAlignedBoundingBox::unit(), None),
@@ -0,0 +1,392 @@
+//! Automatic collider generation from mesh geometry. Colliders are fitted to vertices of
+//! a mesh (and its descendant meshes) in the space of the body the mesh is bound to, the
+//! result can be previewed (see `Physics::fit_preview`) before it is turned into commands.
+
+use crate::{
+    physics::{Collider, ConvexSource, Physics, RigidBody},
+    scene::{
+        CommandGroup, DeleteColliderCommand, SceneCommand, SetBodyCommand, SetColliderCommand,
+    },
+};
+use rg3d::{
+    core::{
+        algebra::{Isometry3, Matrix3, Point3, Rotation3, Translation3, UnitQuaternion, Vector3},
+        pool::Handle,
+    },
+    scene::{
+        graph::Graph,
+        node::Node,
+        physics::{BallDesc, CapsuleDesc, ColliderShapeDesc, CuboidDesc},
+    },
+};
+use std::cmp::Ordering;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum FitKind {
+    Cuboid = 0,
+    Sphere = 1,
+    Capsule = 2,
+    ConvexHull = 3,
+    ConvexDecomposition = 4,
+}
+
+impl FitKind {
+    pub const NAMES: [&'static str; 5] = [
+        "Cuboid",
+        "Sphere",
+        "Capsule",
+        "Convex Hull",
+        "Convex Decomposition",
+    ];
+
+    pub fn from_index(index: usize) -> Option<Self> {
+        match index {
+            0 => Some(FitKind::Cuboid),
+            1 => Some(FitKind::Sphere),
+            2 => Some(FitKind::Capsule),
+            3 => Some(FitKind::ConvexHull),
+            4 => Some(FitKind::ConvexDecomposition),
+            _ => None,
+        }
+    }
+}
+
+/// Max amount of pieces convex decomposition splits geometry into.
+const MAX_PIECES: usize = 8;
+/// Pieces with less vertices are not split further.
+const MIN_PIECE_POINTS: usize = 8;
+
+/// Default simplification tolerance of fitted convex hulls.
+pub const CONVEX_TOLERANCE: f32 = 0.01;
+
+#[derive(Clone, Debug)]
+pub struct FittedCollider {
+    pub collider: Collider,
+    /// Point cloud of convex collider, see `ConvexSource`.
+    pub convex: Option<ConvexSource>,
+}
+
+impl From<Collider> for FittedCollider {
+    fn from(collider: Collider) -> Self {
+        Self {
+            collider,
+            convex: None,
+        }
+    }
+}
+
+/// Set of colliders fitted to geometry of a node.
+#[derive(Clone, Debug)]
+pub struct ColliderFit {
+    pub node: Handle<Node>,
+    pub colliders: Vec<FittedCollider>,
+}
+
+/// Oriented bounding box, rotation's columns are box axes.
+struct OrientedBox {
+    center: Vector3<f32>,
+    rotation: Matrix3<f32>,
+    half_extents: Vector3<f32>,
+}
+
+impl OrientedBox {
+    fn volume(&self) -> f32 {
+        self.half_extents.x * self.half_extents.y * self.half_extents.z
+    }
+
+    fn from_axes(points: &[Vector3<f32>], axes: Matrix3<f32>) -> Self {
+        let mut min = Vector3::repeat(std::f32::MAX);
+        let mut max = Vector3::repeat(-std::f32::MAX);
+        for point in points {
+            let local = axes.tr_mul(point);
+            min = min.inf(&local);
+            max = max.sup(&local);
+        }
+        Self {
+            center: axes * (min + max).scale(0.5),
+            rotation: axes,
+            half_extents: (max - min).scale(0.5),
+        }
+    }
+
+    /// Builds box along principal axes of the point cloud, falls back to axis-aligned
+    /// box if it is tighter - principal axes are not always the best choice.
+    fn fit(points: &[Vector3<f32>]) -> Self {
+        let aabb = Self::from_axes(points, Matrix3::identity());
+
+        let mean = points
+            .iter()
+            .fold(Vector3::default(), |acc, p| acc + p)
+            .scale(1.0 / points.len() as f32);
+        let mut covariance = Matrix3::zeros();
+        for point in points {
+            let d = point - mean;
+            covariance += d * d.transpose();
+        }
+
+        let mut axes = covariance.symmetric_eigen().eigenvectors;
+        // Make sure basis is right-handed, otherwise it is not a rotation.
+        if axes.determinant() < 0.0 {
+            let flipped = -axes.column(2);
+            axes.set_column(2, &flipped);
+        }
+
+        let obb = Self::from_axes(points, axes);
+        if obb.volume() < aabb.volume() {
+            obb
+        } else {
+            aabb
+        }
+    }
+
+    fn quaternion(&self) -> UnitQuaternion<f32> {
+        UnitQuaternion::from_rotation_matrix(&Rotation3::from_matrix_unchecked(self.rotation))
+    }
+
+    fn longest_axis(&self) -> usize {
+        self.half_extents.imax()
+    }
+}
+
+/// Ritter's bounding sphere - not the smallest one, but close enough.
+fn bounding_sphere(points: &[Vector3<f32>]) -> (Vector3<f32>, f32) {
+    let farthest = |from: Vector3<f32>| {
+        *points
+            .iter()
+            .max_by(|a, b| {
+                a.metric_distance(&from)
+                    .partial_cmp(&b.metric_distance(&from))
+                    .unwrap_or(Ordering::Equal)
+            })
+            .unwrap()
+    };
+
+    let a = farthest(points[0]);
+    let b = farthest(a);
+    let mut center = (a + b).scale(0.5);
+    let mut radius = a.metric_distance(&b) * 0.5;
+
+    for point in points {
+        let distance = point.metric_distance(&center);
+        if distance > radius {
+            let new_radius = (radius + distance) * 0.5;
+            center += (point - center).scale((new_radius - radius) / distance);
+            radius = new_radius;
+        }
+    }
+
+    (center, radius)
+}
+
+fn make_cuboid(obb: &OrientedBox) -> Collider {
+    Collider {
+        shape: ColliderShapeDesc::Cuboid(CuboidDesc {
+            half_extents: obb.half_extents,
+        }),
+        translation: obb.center,
+        rotation: obb.quaternion(),
+        ..Default::default()
+    }
+}
+
+fn make_capsule(points: &[Vector3<f32>]) -> Collider {
+    let obb = OrientedBox::fit(points);
+    let axis = obb.rotation.column(obb.longest_axis()).into_owned();
+
+    // Radius is the largest distance from the axis line.
+    let mut radius = 0.0f32;
+    let mut min_t = std::f32::MAX;
+    let mut max_t = -std::f32::MAX;
+    for point in points {
+        let d = point - obb.center;
+        let t = d.dot(&axis);
+        radius = radius.max((d - axis.scale(t)).norm());
+        min_t = min_t.min(t);
+        max_t = max_t.max(t);
+    }
+
+    // Caps are hemispheres, so segment is shorter by radius on each side.
+    let begin_t = (min_t + radius).min(0.0);
+    let end_t = (max_t - radius).max(0.0);
+
+    Collider {
+        shape: ColliderShapeDesc::Capsule(CapsuleDesc {
+            begin: axis.scale(begin_t),
+            end: axis.scale(end_t),
+            radius,
+        }),
+        translation: obb.center,
+        ..Default::default()
+    }
+}
+
+/// Makes convex polyhedron collider around given points (in body space), hull is built
+/// relative to points' center so its enclosing cuboid is tight.
+pub fn make_convex(points: &[Vector3<f32>]) -> FittedCollider {
+    let center = points
+        .iter()
+        .fold(Vector3::default(), |acc, p| acc + p)
+        .scale(1.0 / points.len().max(1) as f32);
+    let source = ConvexSource::new(
+        points.iter().map(|p| p - center).collect(),
+        CONVEX_TOLERANCE,
+    );
+    FittedCollider {
+        collider: Collider {
+            shape: ColliderShapeDesc::Cuboid(source.enclosing_cuboid()),
+            translation: center,
+            ..Default::default()
+        },
+        convex: Some(source),
+    }
+}
+
+/// Splits point cloud into convex pieces by cutting the largest piece in half along its
+/// longest axis until there are enough pieces, every piece becomes convex hull of its
+/// points.
+fn decompose(points: Vec<Vector3<f32>>) -> Vec<FittedCollider> {
+    let mut pieces = vec![(OrientedBox::fit(&points), points)];
+
+    while pieces.len() < MAX_PIECES {
+        let largest = pieces
+            .iter()
+            .enumerate()
+            .filter(|(_, (obb, points))| {
+                points.len() >= MIN_PIECE_POINTS * 2 && obb.volume().is_finite()
+            })
+            .max_by(|(_, (a, _)), (_, (b, _))| {
+                a.volume()
+                    .partial_cmp(&b.volume())
+                    .unwrap_or(Ordering::Equal)
+            })
+            .map(|(i, _)| i);
+
+        let index = match largest {
+            Some(index) => index,
+            None => break,
+        };
+
+        let (obb, points) = pieces.remove(index);
+        let axis = obb.rotation.column(obb.longest_axis()).into_owned();
+        let split = obb.center.dot(&axis);
+        let (left, right): (Vec<_>, Vec<_>) =
+            points.into_iter().partition(|p| p.dot(&axis) < split);
+
+        if left.len() < MIN_PIECE_POINTS || right.len() < MIN_PIECE_POINTS {
+            // Can't split it any further, put it back as is.
+            let mut points = left;
+            points.extend(right);
+            pieces.push((obb, points));
+            break;
+        }
+
+        pieces.push((OrientedBox::fit(&left), left));
+        pieces.push((OrientedBox::fit(&right), right));
+    }
+
+    pieces
+        .iter()
+        .map(|(_, points)| make_convex(points))
+        .collect()
+}
+
+/// Returns global isometry of the node, bodies and their colliders are drawn (and
+/// simulated) in this frame.
+fn body_frame(graph: &Graph, node: Handle<Node>) -> Isometry3<f32> {
+    let (rotation, position) = graph.isometric_global_rotation_position(node);
+    Isometry3 {
+        rotation,
+        translation: Translation3 { vector: position },
+    }
+}
+
+/// Collects vertices of every mesh in the hierarchy of given node in body space.
+pub fn collect_points(graph: &Graph, node: Handle<Node>) -> Vec<Vector3<f32>> {
+    let inv_frame = body_frame(graph, node).inverse();
+
+    let mut points = Vec::new();
+    for descendant in graph.traverse_handle_iter(node) {
+        if let Node::Mesh(mesh) = &graph[descendant] {
+            let transform = mesh.global_transform();
+            for surface in mesh.surfaces() {
+                let data = surface.data();
+                let data = data.read().unwrap();
+                for vertex in data.get_vertices() {
+                    let world = transform.transform_point(&Point3::from(vertex.position));
+                    points.push(inv_frame.transform_point(&world).coords);
+                }
+            }
+        }
+    }
+    points
+}
+
+impl ColliderFit {
+    /// Fits colliders to geometry of given node, returns `None` if there is no geometry.
+    pub fn new(kind: FitKind, node: Handle<Node>, graph: &Graph) -> Option<Self> {
+        let points = collect_points(graph, node);
+        if points.len() < 2 {
+            return None;
+        }
+
+        let colliders = match kind {
+            FitKind::Cuboid => vec![make_cuboid(&OrientedBox::fit(&points)).into()],
+            FitKind::Sphere => {
+                let (center, radius) = bounding_sphere(&points);
+                vec![Collider {
+                    shape: ColliderShapeDesc::Ball(BallDesc { radius }),
+                    translation: center,
+                    ..Default::default()
+                }
+                .into()]
+            }
+            FitKind::Capsule => vec![make_capsule(&points).into()],
+            FitKind::ConvexHull => vec![make_convex(&points)],
+            FitKind::ConvexDecomposition => decompose(points),
+        };
+
+        Some(Self { node, colliders })
+    }
+
+    /// Transform of the body colliders are attached to, used to draw preview.
+    pub fn frame(&self, graph: &Graph) -> Isometry3<f32> {
+        body_frame(graph, self.node)
+    }
+
+    /// Makes command that replaces colliders of node's body with fitted ones. Body is
+    /// created if node has none.
+    pub fn into_command(self, physics: &Physics, graph: &Graph) -> SceneCommand {
+        let mut commands = Vec::new();
+
+        match physics.binder.value_of(&self.node) {
+            Some(&body) => {
+                for &collider in physics.bodies[body].colliders.iter() {
+                    commands.push(SceneCommand::DeleteCollider(DeleteColliderCommand::new(
+                        collider.into(),
+                    )));
+                }
+            }
+            None => {
+                let frame = self.frame(graph);
+                commands.push(SceneCommand::SetBody(SetBodyCommand::new(
+                    self.node,
+                    RigidBody {
+                        position: frame.translation.vector,
+                        rotation: frame.rotation,
+                        ..Default::default()
+                    },
+                )));
+            }
+        }
+
+        for fitted in self.colliders {
+            let mut command = SetColliderCommand::for_node(self.node, fitted.collider);
+            if let Some(convex) = fitted.convex {
+                command = command.with_convex_source(convex);
+            }
+            commands.push(SceneCommand::SetCollider(command));
+        }
+
+        SceneCommand::CommandGroup(CommandGroup::from(commands))
+    }
+}
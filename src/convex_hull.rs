@@ -0,0 +1,253 @@
+//! Incremental convex hull of a point cloud. It is used for convex polyhedron colliders,
+//! so input is usually small (vertices of a prop), which makes simple O(n*f) algorithm
+//! good enough.
+
+use rg3d::core::algebra::Vector3;
+use std::{cmp::Ordering, collections::HashSet};
+
+/// Points closer than this to hull's surface are considered to lie on it.
+const EPSILON: f32 = 1.0e-5;
+
+#[derive(Clone, Debug, Default)]
+pub struct ConvexHull {
+    pub vertices: Vec<Vector3<f32>>,
+    /// Triangles with counter-clockwise winding when looking from outside.
+    pub triangles: Vec<[u32; 3]>,
+}
+
+struct Face {
+    indices: [usize; 3],
+    normal: Vector3<f32>,
+    offset: f32,
+}
+
+impl Face {
+    fn new(points: &[Vector3<f32>], a: usize, b: usize, c: usize) -> Self {
+        let normal = (points[b] - points[a])
+            .cross(&(points[c] - points[a]))
+            .try_normalize(std::f32::EPSILON)
+            .unwrap_or_default();
+        Self {
+            indices: [a, b, c],
+            normal,
+            offset: normal.dot(&points[a]),
+        }
+    }
+
+    fn distance(&self, point: &Vector3<f32>) -> f32 {
+        self.normal.dot(point) - self.offset
+    }
+}
+
+/// Finds four points that form a tetrahedron with non-zero volume.
+fn initial_simplex(points: &[Vector3<f32>], tolerance: f32) -> Option<[usize; 4]> {
+    let farthest_from = |f: &dyn Fn(&Vector3<f32>) -> f32| {
+        (0..points.len())
+            .max_by(|&a, &b| {
+                f(&points[a])
+                    .partial_cmp(&f(&points[b]))
+                    .unwrap_or(Ordering::Equal)
+            })
+            .unwrap()
+    };
+
+    let a = farthest_from(&|p| p.x);
+    let b = farthest_from(&|p| p.metric_distance(&points[a]));
+    if points[a].metric_distance(&points[b]) <= tolerance {
+        return None;
+    }
+
+    let ab = (points[b] - points[a]).normalize();
+    let line_distance = |p: &Vector3<f32>| {
+        let d = p - points[a];
+        (d - ab.scale(d.dot(&ab))).norm()
+    };
+    let c = farthest_from(&line_distance);
+    if line_distance(&points[c]) <= tolerance {
+        return None;
+    }
+
+    let plane = Face::new(points, a, b, c);
+    let d = farthest_from(&|p| plane.distance(p).abs());
+    if plane.distance(&points[d]).abs() <= tolerance {
+        return None;
+    }
+
+    Some([a, b, c, d])
+}
+
+impl ConvexHull {
+    /// Builds hull of given points. Points that are closer than `tolerance` to the hull
+    /// are skipped, so bigger tolerance gives simpler hull. Returns empty hull if points
+    /// are degenerate (all lie on a plane or a line).
+    pub fn new(points: &[Vector3<f32>], tolerance: f32) -> Self {
+        let tolerance = tolerance.max(EPSILON);
+
+        if points.len() < 4 {
+            return Self::default();
+        }
+
+        let simplex = match initial_simplex(points, tolerance) {
+            Some(simplex) => simplex,
+            None => return Self::default(),
+        };
+
+        let center = simplex
+            .iter()
+            .fold(Vector3::default(), |acc, &i| acc + points[i])
+            .scale(0.25);
+
+        let make_outward = |a: usize, b: usize, c: usize| {
+            let face = Face::new(points, a, b, c);
+            if face.distance(&center) > 0.0 {
+                Face::new(points, a, c, b)
+            } else {
+                face
+            }
+        };
+
+        let [a, b, c, d] = simplex;
+        let mut faces = vec![
+            make_outward(a, b, c),
+            make_outward(a, b, d),
+            make_outward(a, c, d),
+            make_outward(b, c, d),
+        ];
+
+        for (i, point) in points.iter().enumerate() {
+            if simplex.contains(&i) {
+                continue;
+            }
+
+            let visible = faces
+                .iter()
+                .map(|face| face.distance(point) > tolerance)
+                .collect::<Vec<_>>();
+
+            if !visible.iter().any(|&v| v) {
+                continue;
+            }
+
+            // Horizon is formed by edges of visible faces that are not shared with other
+            // visible face.
+            let mut visible_edges = HashSet::new();
+            for (face, _) in faces.iter().zip(visible.iter()).filter(|&(_, &v)| v) {
+                let [a, b, c] = face.indices;
+                visible_edges.insert((a, b));
+                visible_edges.insert((b, c));
+                visible_edges.insert((c, a));
+            }
+
+            let horizon = visible_edges
+                .iter()
+                .filter(|&&(a, b)| !visible_edges.contains(&(b, a)))
+                .cloned()
+                .collect::<Vec<_>>();
+
+            let mut visible = visible.into_iter();
+            faces.retain(|_| !visible.next().unwrap());
+
+            for (a, b) in horizon {
+                faces.push(Face::new(points, a, b, i));
+            }
+        }
+
+        // Compact vertices - keep only those that are used by faces.
+        let mut remap = vec![None; points.len()];
+        let mut hull = Self::default();
+        for face in faces.iter() {
+            let mut triangle = [0; 3];
+            for (k, &index) in face.indices.iter().enumerate() {
+                triangle[k] = *remap[index].get_or_insert_with(|| {
+                    hull.vertices.push(points[index]);
+                    (hull.vertices.len() - 1) as u32
+                });
+            }
+            hull.triangles.push(triangle);
+        }
+        hull
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.triangles.is_empty()
+    }
+
+    /// Returns unique edges of the hull, used for wireframe drawing.
+    pub fn edges(&self) -> Vec<(Vector3<f32>, Vector3<f32>)> {
+        let mut edges = HashSet::new();
+        for triangle in self.triangles.iter() {
+            for k in 0..3 {
+                let a = triangle[k];
+                let b = triangle[(k + 1) % 3];
+                edges.insert((a.min(b), a.max(b)));
+            }
+        }
+        edges
+            .into_iter()
+            .map(|(a, b)| (self.vertices[a as usize], self.vertices[b as usize]))
+            .collect()
+    }
+
+    /// Returns min and max corners of axis-aligned box around the hull.
+    pub fn bounds(&self) -> (Vector3<f32>, Vector3<f32>) {
+        let mut min = Vector3::repeat(std::f32::MAX);
+        let mut max = Vector3::repeat(-std::f32::MAX);
+        for vertex in self.vertices.iter() {
+            min = min.inf(vertex);
+            max = max.sup(vertex);
+        }
+        (min, max)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn hull_of_cube() {
+        let mut points = Vec::new();
+        for i in 0..8 {
+            points.push(Vector3::new(
+                if i & 1 == 0 { -1.0 } else { 1.0 },
+                if i & 2 == 0 { -1.0 } else { 1.0 },
+                if i & 4 == 0 { -1.0 } else { 1.0 },
+            ));
+        }
+        // Inner point and center of a face are not vertices of the hull.
+        points.push(Vector3::default());
+        points.push(Vector3::new(1.0, 0.0, 0.0));
+
+        let hull = ConvexHull::new(&points, 0.0);
+
+        assert_eq!(hull.vertices.len(), 8);
+        assert!(hull
+            .vertices
+            .iter()
+            .all(|v| v.abs() == Vector3::repeat(1.0)));
+        // Closed surface with 8 vertices has 12 triangles and 18 edges.
+        assert_eq!(hull.triangles.len(), 12);
+        assert_eq!(hull.edges().len(), 18);
+        for triangle in hull.triangles.iter() {
+            let [a, b, c] = [
+                hull.vertices[triangle[0] as usize],
+                hull.vertices[triangle[1] as usize],
+                hull.vertices[triangle[2] as usize],
+            ];
+            let normal = (b - a).cross(&(c - a));
+            assert!(normal.dot(&(a + b + c)) > 0.0, "triangle faces inwards");
+        }
+        assert_eq!(hull.bounds(), (Vector3::repeat(-1.0), Vector3::repeat(1.0)));
+    }
+
+    #[test]
+    fn flat_points_have_no_hull() {
+        let points = [
+            Vector3::new(0.0, 0.0, 0.0),
+            Vector3::new(1.0, 0.0, 0.0),
+            Vector3::new(1.0, 0.0, 1.0),
+            Vector3::new(0.0, 0.0, 1.0),
+        ];
+        assert!(ConvexHull::new(&points, 0.0).is_empty());
+    }
+}
@@ -0,0 +1,201 @@
+use crate::STARTUP_WORKING_DIR;
+use rg3d::{
+    core::{algebra::Vector3, pool::Handle},
+    engine::resource_manager::ResourceManager,
+    resource::texture::Texture,
+    scene::{base::BaseBuilder, graph::Graph, node::Node, sprite::SpriteBuilder},
+};
+use std::collections::{hash_map::Entry, HashMap};
+
+/// Size of icon in world units at distance of one unit from camera with 90 degrees
+/// field of view. Icons are scaled with distance so they look the same on screen.
+const ICON_SIZE: f32 = 0.06;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum IconKind {
+    Pivot,
+    Light,
+    Camera,
+}
+
+/// Sprite of a node icon with the last state written to it, so the sprite is touched
+/// only when its owner moves or camera changes.
+struct Icon {
+    sprite: Handle<Node>,
+    position: Vector3<f32>,
+    visibility: bool,
+    size: f32,
+}
+
+/// Billboard icons for nodes that have no visual representation. Icons are sprites
+/// that live under editor root, so they're never saved with the scene.
+///
+/// Sound sources have no icons: in this engine version they live in engine's sound
+/// context instead of the scene graph, so they are not saved with the scene and there
+/// is no node the icon could select.
+pub struct NodeIcons {
+    root: Handle<Node>,
+    icons: HashMap<Handle<Node>, Icon>,
+    alive: HashMap<Handle<Node>, IconKind>,
+    pivot_texture: Texture,
+    light_texture: Texture,
+    camera_texture: Texture,
+    stack: Vec<Handle<Node>>,
+    pub show_pivots: bool,
+    pub show_lights: bool,
+    pub show_cameras: bool,
+}
+
+fn load_texture(path: &str, resource_manager: &ResourceManager) -> Texture {
+    resource_manager.request_texture(STARTUP_WORKING_DIR.lock().unwrap().join(path))
+}
+
+pub fn icon_size(distance: f32, fov: f32) -> f32 {
+    distance * (fov * 0.5).tan() * ICON_SIZE
+}
+
+impl NodeIcons {
+    pub fn new(graph: &mut Graph, root: Handle<Node>, resource_manager: &ResourceManager) -> Self {
+        let icons_root = BaseBuilder::new().with_name("EditorIcons").build(graph);
+        graph.link_nodes(icons_root, root);
+
+        Self {
+            root: icons_root,
+            icons: Default::default(),
+            alive: Default::default(),
+            pivot_texture: load_texture("resources/cube.png", resource_manager),
+            light_texture: load_texture("resources/light.png", resource_manager),
+            camera_texture: load_texture("resources/camera.png", resource_manager),
+            stack: Default::default(),
+            show_pivots: true,
+            show_lights: true,
+            show_cameras: true,
+        }
+    }
+
+    fn kind_of(&self, node: &Node) -> Option<IconKind> {
+        match node {
+            Node::Base(_) if self.show_pivots => Some(IconKind::Pivot),
+            Node::Light(_) if self.show_lights => Some(IconKind::Light),
+            Node::Camera(_) if self.show_cameras => Some(IconKind::Camera),
+            _ => None,
+        }
+    }
+
+    pub fn toggle(&mut self, kind: IconKind) {
+        let flag = match kind {
+            IconKind::Pivot => &mut self.show_pivots,
+            IconKind::Light => &mut self.show_lights,
+            IconKind::Camera => &mut self.show_cameras,
+        };
+        *flag = !*flag;
+    }
+
+    pub fn root(&self) -> Handle<Node> {
+        self.root
+    }
+
+    /// Returns true if given scene node currently has an icon.
+    pub fn has_icon(&self, node: Handle<Node>) -> bool {
+        self.icons.contains_key(&node)
+    }
+
+    /// Synchronizes icons with scene nodes - creates icons for new nodes, removes icons
+    /// of deleted nodes (or nodes which type was toggled off) and moves the rest.
+    pub fn update(&mut self, graph: &mut Graph, editor_root: Handle<Node>, camera: Handle<Node>) {
+        let camera_position = graph[camera].global_position();
+        let fov = graph[camera].as_camera().fov();
+
+        self.alive.clear();
+        self.stack.clear();
+        self.stack.push(graph.get_root());
+        while let Some(handle) = self.stack.pop() {
+            if handle == editor_root {
+                continue;
+            }
+            let node = &graph[handle];
+            // Scene root is a pivot too, but it makes no sense to show it.
+            if handle != graph.get_root() {
+                if let Some(kind) = self.kind_of(node) {
+                    self.alive.insert(handle, kind);
+                }
+            }
+            self.stack.extend_from_slice(node.children());
+        }
+
+        let alive = &self.alive;
+        self.icons.retain(|owner, icon| {
+            let keep = alive.contains_key(owner);
+            if !keep {
+                graph.remove_node(icon.sprite);
+            }
+            keep
+        });
+
+        for (&owner, &kind) in self.alive.iter() {
+            let position = graph[owner].global_position();
+            let visibility = graph[owner].global_visibility();
+            let size = icon_size(position.metric_distance(&camera_position), fov);
+
+            let icon = match self.icons.entry(owner) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => {
+                    let texture = match kind {
+                        IconKind::Pivot => self.pivot_texture.clone(),
+                        IconKind::Light => self.light_texture.clone(),
+                        IconKind::Camera => self.camera_texture.clone(),
+                    };
+                    let sprite = SpriteBuilder::new(
+                        BaseBuilder::new()
+                            .with_name("EditorIcon")
+                            .with_visibility(visibility),
+                    )
+                    .with_texture(texture)
+                    .with_size(size)
+                    .build(graph);
+                    graph[sprite].local_transform_mut().set_position(position);
+                    graph.link_nodes(sprite, self.root);
+                    entry.insert(Icon {
+                        sprite,
+                        position,
+                        visibility,
+                        size,
+                    })
+                }
+            };
+
+            if icon.visibility != visibility {
+                icon.visibility = visibility;
+                graph[icon.sprite].set_visibility(visibility);
+            }
+            if icon.position != position {
+                icon.position = position;
+                graph[icon.sprite]
+                    .local_transform_mut()
+                    .set_position(position);
+            }
+            if icon.size != size {
+                icon.size = size;
+                graph[icon.sprite].as_sprite_mut().set_size(size);
+            }
+        }
+    }
+
+    /// Returns world-space position and radius of the icon of given node, it is used
+    /// for picking.
+    pub fn bounds(
+        &self,
+        node: Handle<Node>,
+        graph: &Graph,
+        camera_position: Vector3<f32>,
+        fov: f32,
+    ) -> Option<(Vector3<f32>, f32)> {
+        if self.has_icon(node) {
+            let position = graph[node].global_position();
+            let size = icon_size(position.metric_distance(&camera_position), fov);
+            Some((position, size * 0.5))
+        } else {
+            None
+        }
+    }
+}
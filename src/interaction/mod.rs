@@ -1,6 +1,9 @@
 use crate::{
     gui::UiNode,
-    interaction::navmesh::EditNavmeshMode,
+    interaction::{
+        navmesh::{path_test::NavmeshTestMode, EditNavmeshMode},
+        physics::EditPhysicsMode,
+    },
     scene::{
         ChangeSelectionCommand, CommandGroup, EditorScene, GraphSelection, MoveNodeCommand,
         RotateNodeCommand, ScaleNodeCommand, SceneCommand, Selection,
@@ -28,6 +31,7 @@ use rg3d::{
 use std::sync::{mpsc::Sender, Arc, RwLock};
 
 pub mod navmesh;
+pub mod physics;
 
 pub trait InteractionModeTrait {
     fn on_left_mouse_button_down(
@@ -59,7 +63,7 @@ pub trait InteractionModeTrait {
         camera: Handle<Node>,
         engine: &mut GameEngine,
     );
-    fn deactivate(&mut self, editor_scene: &EditorScene, engine: &mut GameEngine);
+    fn deactivate(&mut self, editor_scene: &mut EditorScene, engine: &mut GameEngine);
     fn on_key_down(
         &mut self,
         _key: KeyCode,
@@ -604,7 +608,7 @@ impl InteractionModeTrait for MoveInteractionMode {
         }
     }
 
-    fn deactivate(&mut self, editor_scene: &EditorScene, engine: &mut GameEngine) {
+    fn deactivate(&mut self, editor_scene: &mut EditorScene, engine: &mut GameEngine) {
         let graph = &mut engine.scenes[editor_scene.scene].graph;
         self.move_gizmo.set_visible(graph, false);
     }
@@ -1062,7 +1066,7 @@ impl InteractionModeTrait for ScaleInteractionMode {
         }
     }
 
-    fn deactivate(&mut self, editor_scene: &EditorScene, engine: &mut GameEngine) {
+    fn deactivate(&mut self, editor_scene: &mut EditorScene, engine: &mut GameEngine) {
         let graph = &mut engine.scenes[editor_scene.scene].graph;
         self.scale_gizmo.set_visible(graph, false);
     }
@@ -1459,7 +1463,7 @@ impl InteractionModeTrait for RotateInteractionMode {
         }
     }
 
-    fn deactivate(&mut self, editor_scene: &EditorScene, engine: &mut GameEngine) {
+    fn deactivate(&mut self, editor_scene: &mut EditorScene, engine: &mut GameEngine) {
         let graph = &mut engine.scenes[editor_scene.scene].graph;
         self.rotation_gizmo.set_visible(graph, false);
     }
@@ -1639,7 +1643,7 @@ impl InteractionModeTrait for SelectInteractionMode {
     ) {
     }
 
-    fn deactivate(&mut self, _editor_scene: &EditorScene, _engine: &mut GameEngine) {}
+    fn deactivate(&mut self, _editor_scene: &mut EditorScene, _engine: &mut GameEngine) {}
 }
 
 /// Helper enum to be able to access interaction modes in array directly.
@@ -1651,6 +1655,8 @@ pub enum InteractionModeKind {
     Scale = 2,
     Rotate = 3,
     Navmesh = 4,
+    Physics = 5,
+    NavmeshTest = 6,
 }
 
 pub enum InteractionMode {
@@ -1659,6 +1665,8 @@ pub enum InteractionMode {
     Scale(ScaleInteractionMode),
     Rotate(RotateInteractionMode),
     Navmesh(EditNavmeshMode),
+    Physics(EditPhysicsMode),
+    NavmeshTest(NavmeshTestMode),
 }
 
 macro_rules! static_dispatch {
@@ -1669,6 +1677,8 @@ macro_rules! static_dispatch {
             InteractionMode::Scale(v) => v.$func($($args),*),
             InteractionMode::Rotate(v) => v.$func($($args),*),
             InteractionMode::Navmesh(v) => v.$func($($args),*),
+            InteractionMode::Physics(v) => v.$func($($args),*),
+            InteractionMode::NavmeshTest(v) => v.$func($($args),*),
         }
     }
 }
@@ -1746,7 +1756,7 @@ impl InteractionModeTrait for InteractionMode {
         static_dispatch!(self, update, editor_scene, camera, engine)
     }
 
-    fn deactivate(&mut self, editor_scene: &EditorScene, engine: &mut GameEngine) {
+    fn deactivate(&mut self, editor_scene: &mut EditorScene, engine: &mut GameEngine) {
         scope_profile!();
 
         static_dispatch!(self, deactivate, editor_scene, engine)
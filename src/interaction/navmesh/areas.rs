@@ -0,0 +1,574 @@
+//! Navmesh areas - project-wide table of area types (roads, grass, water, ...) with their
+//! traversal costs. Each navmesh triangle refers to an area by its index in the table.
+//!
+//! Areas, physics layers and physics materials are project tables and all of them work the
+//! same way: a table is loaded from working directory together with a scene and kept in
+//! `EditorScene`, it is edited only in its window, changes are not undoable and the table
+//! is saved right after each change, save errors are written to the log. Scene data that
+//! depends on a table (collider groups, for example) is updated by the window directly.
+
+use crate::{
+    gui::{BuildContext, Ui, UiMessage, UiNode},
+    interaction::navmesh::data_model::Navmesh,
+    scene::EditorScene,
+    send_sync_message,
+    sidebar::{make_button, make_dropdown_list_option, make_text_mark},
+    Message,
+};
+use rg3d::{
+    core::{
+        color::Color,
+        pool::{Handle, Pool},
+        visitor::{Visit, VisitResult, Visitor},
+    },
+    gui::{
+        color::ColorFieldBuilder,
+        dropdown_list::DropdownListBuilder,
+        grid::{Column, GridBuilder, Row},
+        message::{
+            ButtonMessage, ColorFieldMessage, DropdownListMessage, MessageDirection,
+            NumericUpDownMessage, TextBoxMessage, UiMessageData, WidgetMessage, WindowMessage,
+        },
+        numeric::NumericUpDownBuilder,
+        stack_panel::StackPanelBuilder,
+        text_box::TextBoxBuilder,
+        widget::WidgetBuilder,
+        window::{WindowBuilder, WindowTitle},
+        HorizontalAlignment, Orientation, Thickness,
+    },
+};
+use std::{
+    path::{Path, PathBuf},
+    sync::mpsc::Sender,
+};
+
+/// Areas belong to a project rather than a scene, so they're saved in working directory.
+pub const NAVMESH_AREAS_PATH: &str = "navmesh_areas.bin";
+
+/// Area of new triangles, it is the first one in the table.
+pub const DEFAULT_AREA: usize = 0;
+
+/// Triangles of areas missing in the table are drawn with this color and cost nothing extra.
+const UNKNOWN_AREA_COLOR: Color = Color::opaque(255, 0, 255);
+const UNKNOWN_AREA_COST: f32 = 1.0;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct NavmeshArea {
+    pub name: String,
+    pub color: Color,
+    /// Multiplier of distance travelled over the area.
+    pub cost: f32,
+}
+
+impl Default for NavmeshArea {
+    fn default() -> Self {
+        Self::new("Area", Color::opaque(200, 200, 200), 1.0)
+    }
+}
+
+impl Visit for NavmeshArea {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.name.visit("Name", visitor)?;
+        self.color.visit("Color", visitor)?;
+        self.cost.visit("Cost", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+impl NavmeshArea {
+    pub fn new(name: &str, color: Color, cost: f32) -> Self {
+        Self {
+            name: name.to_owned(),
+            color,
+            cost,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct NavmeshAreaTable {
+    pub areas: Vec<NavmeshArea>,
+}
+
+impl Default for NavmeshAreaTable {
+    fn default() -> Self {
+        Self {
+            areas: vec![
+                NavmeshArea::new("Walkable", Color::opaque(0, 200, 0), 1.0),
+                NavmeshArea::new("Road", Color::opaque(160, 160, 160), 0.5),
+                NavmeshArea::new("Grass", Color::opaque(120, 220, 60), 2.0),
+                NavmeshArea::new("Water", Color::opaque(30, 90, 255), 10.0),
+            ],
+        }
+    }
+}
+
+impl Visit for NavmeshAreaTable {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.areas.visit("Areas", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+impl NavmeshAreaTable {
+    /// Loads areas of current project, missing or malformed file gives default areas.
+    pub fn load() -> Self {
+        let mut table = Self::default();
+        if let Ok(mut visitor) = Visitor::load_binary(NAVMESH_AREAS_PATH) {
+            if table.visit("NavmeshAreas", &mut visitor).is_err() || table.areas.is_empty() {
+                table = Self::default();
+            }
+        }
+        table
+    }
+
+    pub fn save(&mut self) -> VisitResult {
+        let mut visitor = Visitor::new();
+        self.visit("NavmeshAreas", &mut visitor)?;
+        visitor.save_binary(NAVMESH_AREAS_PATH)
+    }
+
+    pub fn color(&self, area: usize) -> Color {
+        self.areas
+            .get(area)
+            .map_or(UNKNOWN_AREA_COLOR, |area| area.color)
+    }
+
+    pub fn cost(&self, area: usize) -> f32 {
+        self.areas
+            .get(area)
+            .map_or(UNKNOWN_AREA_COST, |area| area.cost)
+    }
+}
+
+/// Areas and costs of triangles of a single navmesh, in the order of engine's triangles.
+#[derive(Default, Debug)]
+pub struct NavmeshTriangleAreas {
+    pub areas: Vec<u32>,
+    pub costs: Vec<f32>,
+}
+
+impl Visit for NavmeshTriangleAreas {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.areas.visit("Areas", visitor)?;
+        self.costs.visit("Costs", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+/// Areas of navmesh triangles with their costs, engine's navmeshes can't hold them so they're
+/// saved in a separate file next to the scene for a game to read. Navmeshes are in the same
+/// order as in the saved scene, the editor reads areas back when the scene is loaded.
+#[derive(Default, Debug)]
+pub struct NavmeshAreaCosts {
+    pub area_names: Vec<String>,
+    pub navmeshes: Vec<NavmeshTriangleAreas>,
+}
+
+impl Visit for NavmeshAreaCosts {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.area_names.visit("AreaNames", visitor)?;
+        self.navmeshes.visit("Navmeshes", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+impl NavmeshAreaCosts {
+    pub fn new(navmeshes: &Pool<Navmesh>, table: &NavmeshAreaTable) -> Self {
+        Self {
+            area_names: table.areas.iter().map(|area| area.name.clone()).collect(),
+            navmeshes: navmeshes
+                .iter()
+                .map(|navmesh| NavmeshTriangleAreas {
+                    areas: navmesh.triangles.iter().map(|t| t.area as u32).collect(),
+                    costs: navmesh
+                        .triangles
+                        .iter()
+                        .map(|t| table.cost(t.area))
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+
+    pub fn path(scene_path: &Path) -> PathBuf {
+        scene_path.with_extension("navareas")
+    }
+
+    pub fn load(scene_path: &Path) -> Option<Self> {
+        let mut visitor = Visitor::load_binary(Self::path(scene_path)).ok()?;
+        let mut costs = Self::default();
+        costs.visit("NavmeshAreaCosts", &mut visitor).ok()?;
+        Some(costs)
+    }
+
+    /// Saves costs next to the scene, or removes stale file if there are no navmeshes.
+    pub fn save(&mut self, scene_path: &Path) -> VisitResult {
+        let path = Self::path(scene_path);
+        if self.navmeshes.is_empty() {
+            let _ = std::fs::remove_file(path);
+            Ok(())
+        } else {
+            let mut visitor = Visitor::new();
+            self.visit("NavmeshAreaCosts", &mut visitor)?;
+            visitor.save_binary(path)
+        }
+    }
+
+    /// Assigns areas to triangles of navmeshes loaded from the scene.
+    pub fn apply(&self, navmeshes: &mut Pool<Navmesh>) {
+        for (navmesh, entry) in navmeshes.iter_mut().zip(self.navmeshes.iter()) {
+            for (triangle, &area) in navmesh.triangles.iter_mut().zip(entry.areas.iter()) {
+                triangle.area = area as usize;
+            }
+        }
+    }
+}
+
+pub struct NavmeshAreasWindow {
+    window: Handle<UiNode>,
+    areas: Handle<UiNode>,
+    name: Handle<UiNode>,
+    color: Handle<UiNode>,
+    cost: Handle<UiNode>,
+    add: Handle<UiNode>,
+    remove: Handle<UiNode>,
+    selected: Option<usize>,
+    cached_names: Vec<String>,
+    sender: Sender<Message>,
+}
+
+impl NavmeshAreasWindow {
+    pub fn new(ctx: &mut BuildContext, sender: Sender<Message>) -> Self {
+        let areas;
+        let name;
+        let color;
+        let cost;
+        let add;
+        let remove;
+        let window = WindowBuilder::new(WidgetBuilder::new().with_width(300.0).with_height(200.0))
+            .open(false)
+            .with_title(WindowTitle::Text("Navmesh Areas".to_owned()))
+            .with_content(
+                GridBuilder::new(
+                    WidgetBuilder::new()
+                        .with_child(
+                            GridBuilder::new(
+                                WidgetBuilder::new()
+                                    .on_row(0)
+                                    .with_child(make_text_mark(ctx, "Area", 0))
+                                    .with_child({
+                                        areas = DropdownListBuilder::new(
+                                            WidgetBuilder::new()
+                                                .on_row(0)
+                                                .on_column(1)
+                                                .with_margin(Thickness::uniform(1.0)),
+                                        )
+                                        .with_close_on_selection(true)
+                                        .build(ctx);
+                                        areas
+                                    })
+                                    .with_child(make_text_mark(ctx, "Name", 1))
+                                    .with_child({
+                                        name = TextBoxBuilder::new(
+                                            WidgetBuilder::new()
+                                                .on_row(1)
+                                                .on_column(1)
+                                                .with_margin(Thickness::uniform(1.0)),
+                                        )
+                                        .build(ctx);
+                                        name
+                                    })
+                                    .with_child(make_text_mark(ctx, "Color", 2))
+                                    .with_child({
+                                        color = ColorFieldBuilder::new(
+                                            WidgetBuilder::new()
+                                                .on_row(2)
+                                                .on_column(1)
+                                                .with_margin(Thickness::uniform(1.0)),
+                                        )
+                                        .build(ctx);
+                                        color
+                                    })
+                                    .with_child(make_text_mark(ctx, "Cost", 3))
+                                    .with_child({
+                                        cost = NumericUpDownBuilder::new(
+                                            WidgetBuilder::new()
+                                                .on_row(3)
+                                                .on_column(1)
+                                                .with_margin(Thickness::uniform(1.0)),
+                                        )
+                                        .with_min_value(0.0)
+                                        .with_step(0.1)
+                                        .build(ctx);
+                                        cost
+                                    }),
+                            )
+                            .add_row(Row::strict(25.0))
+                            .add_row(Row::strict(25.0))
+                            .add_row(Row::strict(25.0))
+                            .add_row(Row::strict(25.0))
+                            .add_column(Column::strict(100.0))
+                            .add_column(Column::stretch())
+                            .build(ctx),
+                        )
+                        .with_child(
+                            StackPanelBuilder::new(
+                                WidgetBuilder::new()
+                                    .on_row(1)
+                                    .with_horizontal_alignment(HorizontalAlignment::Right)
+                                    .with_child({
+                                        add = make_button(ctx, "Add");
+                                        add
+                                    })
+                                    .with_child({
+                                        remove = make_button(ctx, "Remove");
+                                        remove
+                                    }),
+                            )
+                            .with_orientation(Orientation::Horizontal)
+                            .build(ctx),
+                        ),
+                )
+                .add_row(Row::stretch())
+                .add_row(Row::strict(25.0))
+                .add_column(Column::stretch())
+                .build(ctx),
+            )
+            .build(ctx);
+
+        Self {
+            window,
+            areas,
+            name,
+            color,
+            cost,
+            add,
+            remove,
+            selected: None,
+            cached_names: Default::default(),
+            sender,
+        }
+    }
+
+    pub fn open(&self, ui: &Ui) {
+        ui.send_message(WindowMessage::open(
+            self.window,
+            MessageDirection::ToWidget,
+            true,
+        ));
+    }
+
+    pub fn sync_to_model(&mut self, table: &NavmeshAreaTable, ui: &mut Ui) {
+        let names = table
+            .areas
+            .iter()
+            .map(|a| a.name.clone())
+            .collect::<Vec<_>>();
+        if self.cached_names != names {
+            let ctx = &mut ui.build_ctx();
+            let items = names
+                .iter()
+                .map(|name| make_dropdown_list_option(ctx, name))
+                .collect::<Vec<_>>();
+            send_sync_message(
+                ui,
+                DropdownListMessage::items(self.areas, MessageDirection::ToWidget, items),
+            );
+            self.cached_names = names;
+        }
+
+        if self.selected.map_or(false, |i| i >= table.areas.len()) {
+            self.selected = table.areas.len().checked_sub(1);
+        }
+
+        send_sync_message(
+            ui,
+            DropdownListMessage::selection(self.areas, MessageDirection::ToWidget, self.selected),
+        );
+
+        if let Some(area) = self.selected.map(|i| &table.areas[i]) {
+            send_sync_message(
+                ui,
+                TextBoxMessage::text(self.name, MessageDirection::ToWidget, area.name.clone()),
+            );
+            send_sync_message(
+                ui,
+                ColorFieldMessage::color(self.color, MessageDirection::ToWidget, area.color),
+            );
+            send_sync_message(
+                ui,
+                NumericUpDownMessage::value(self.cost, MessageDirection::ToWidget, area.cost),
+            );
+        }
+
+        for &widget in &[self.name, self.color, self.cost] {
+            send_sync_message(
+                ui,
+                WidgetMessage::enabled(widget, MessageDirection::ToWidget, self.selected.is_some()),
+            );
+        }
+
+        // Triangles refer to areas by index, so only the last area can be removed without
+        // changing areas of other triangles. The default area is always kept.
+        send_sync_message(
+            ui,
+            WidgetMessage::enabled(
+                self.remove,
+                MessageDirection::ToWidget,
+                self.selected
+                    .map_or(false, |i| i != DEFAULT_AREA && i + 1 == table.areas.len()),
+            ),
+        );
+    }
+
+    /// Table is not a part of scene, so its changes are not undoable and saved at once.
+    pub fn handle_message(&mut self, message: &UiMessage, editor_scene: &mut EditorScene) {
+        if message.direction() != MessageDirection::FromWidget {
+            return;
+        }
+
+        let table = &mut editor_scene.navmesh_areas;
+        let old_table = table.clone();
+
+        match message.data() {
+            &UiMessageData::DropdownList(DropdownListMessage::SelectionChanged(selection))
+                if message.destination() == self.areas =>
+            {
+                if self.selected != selection {
+                    self.selected = selection;
+                    self.sender.send(Message::SyncToModel).unwrap();
+                }
+            }
+            UiMessageData::TextBox(TextBoxMessage::Text(text))
+                if message.destination() == self.name =>
+            {
+                if let Some(i) = self.selected {
+                    table.areas[i].name = text.clone();
+                }
+            }
+            &UiMessageData::ColorField(ColorFieldMessage::Color(color))
+                if message.destination() == self.color =>
+            {
+                if let Some(i) = self.selected {
+                    table.areas[i].color = color;
+                }
+            }
+            &UiMessageData::NumericUpDown(NumericUpDownMessage::Value(value))
+                if message.destination() == self.cost =>
+            {
+                if let Some(i) = self.selected {
+                    table.areas[i].cost = value;
+                }
+            }
+            UiMessageData::Button(ButtonMessage::Click) => {
+                if message.destination() == self.add {
+                    table.areas.push(Default::default());
+                    self.selected = Some(table.areas.len() - 1);
+                } else if message.destination() == self.remove {
+                    if let Some(i) = self.selected {
+                        if i != DEFAULT_AREA && i + 1 == table.areas.len() {
+                            table.areas.pop();
+                        }
+                    }
+                }
+            }
+            _ => (),
+        }
+
+        let table = &mut editor_scene.navmesh_areas;
+        if *table != old_table {
+            if let Err(e) = table.save() {
+                self.sender
+                    .send(Message::Log(format!(
+                        "Unable to save navmesh areas: {:?}",
+                        e
+                    )))
+                    .unwrap();
+            }
+            self.sender.send(Message::SyncToModel).unwrap();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn scene_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("rusty_editor_{}_{}.rgs", name, std::process::id()))
+    }
+
+    #[test]
+    fn areas_are_restored_on_navmeshes_of_loaded_scene() {
+        // Freed slot between navmeshes makes their handles differ from handles of navmeshes
+        // of loaded scene, only the order must matter.
+        let mut navmeshes = Pool::new();
+        let mut first = Navmesh::new();
+        for (i, triangle) in first.triangles.iter_mut().enumerate() {
+            triangle.area = i + 1;
+        }
+        navmeshes.spawn(first);
+        let removed = navmeshes.spawn(Navmesh::new());
+        let mut last = Navmesh::new();
+        for triangle in last.triangles.iter_mut() {
+            triangle.area = 3;
+        }
+        navmeshes.spawn(last);
+        navmeshes.free(removed);
+
+        let table = NavmeshAreaTable::default();
+        let path = scene_path("areas");
+        NavmeshAreaCosts::new(&navmeshes, &table)
+            .save(&path)
+            .unwrap();
+        let costs = NavmeshAreaCosts::load(&path).unwrap();
+        let _ = std::fs::remove_file(NavmeshAreaCosts::path(&path));
+
+        assert_eq!(costs.area_names.len(), table.areas.len());
+        assert_eq!(costs.navmeshes.len(), 2);
+        assert_eq!(costs.navmeshes[0].areas, vec![1, 2]);
+        assert_eq!(costs.navmeshes[0].costs, vec![table.cost(1), table.cost(2)]);
+        assert_eq!(costs.navmeshes[1].costs, vec![table.cost(3), table.cost(3)]);
+
+        let mut loaded = Pool::new();
+        loaded.spawn(Navmesh::new());
+        loaded.spawn(Navmesh::new());
+        costs.apply(&mut loaded);
+
+        let areas = loaded
+            .iter()
+            .map(|navmesh| navmesh.triangles.iter().map(|t| t.area).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        assert_eq!(areas, vec![vec![1, 2], vec![3, 3]]);
+    }
+
+    #[test]
+    fn stale_file_is_removed_when_there_are_no_navmeshes() {
+        let mut navmeshes = Pool::new();
+        navmeshes.spawn(Navmesh::new());
+        let table = NavmeshAreaTable::default();
+        let path = scene_path("stale_areas");
+        NavmeshAreaCosts::new(&navmeshes, &table)
+            .save(&path)
+            .unwrap();
+        assert!(NavmeshAreaCosts::path(&path).exists());
+
+        NavmeshAreaCosts::new(&Pool::new(), &table)
+            .save(&path)
+            .unwrap();
+        assert!(!NavmeshAreaCosts::path(&path).exists());
+    }
+}
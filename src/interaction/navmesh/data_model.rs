@@ -1,5 +1,8 @@
+use crate::interaction::navmesh::areas::DEFAULT_AREA;
 use rg3d::core::algebra::Vector3;
 use rg3d::core::pool::{Handle, Pool};
+use rg3d::sound::math::TriangleDefinition;
+use std::collections::HashMap;
 
 #[derive(Debug, Clone)]
 pub struct NavmeshVertex {
@@ -11,6 +14,8 @@ pub struct NavmeshTriangle {
     pub a: Handle<NavmeshVertex>,
     pub b: Handle<NavmeshVertex>,
     pub c: Handle<NavmeshVertex>,
+    /// Index of triangle's area in `NavmeshAreaTable`.
+    pub area: usize,
 }
 
 #[derive(PartialEq, Copy, Clone, Debug, Eq)]
@@ -20,10 +25,37 @@ pub struct NavmeshEdge {
 }
 
 impl NavmeshTriangle {
+    /// Creates a triangle of default area.
+    pub fn new(
+        a: Handle<NavmeshVertex>,
+        b: Handle<NavmeshVertex>,
+        c: Handle<NavmeshVertex>,
+    ) -> Self {
+        Self {
+            a,
+            b,
+            c,
+            area: DEFAULT_AREA,
+        }
+    }
+
     pub fn vertices(&self) -> [Handle<NavmeshVertex>; 3] {
         [self.a, self.b, self.c]
     }
 
+    /// Returns non-normalized normal of the triangle, its length is twice the area.
+    pub fn normal(&self, vertices: &Pool<NavmeshVertex>) -> Vector3<f32> {
+        let a = vertices[self.a].position;
+        let b = vertices[self.b].position;
+        let c = vertices[self.c].position;
+        (b - a).cross(&(c - a))
+    }
+
+    pub fn center(&self, vertices: &Pool<NavmeshVertex>) -> Vector3<f32> {
+        (vertices[self.a].position + vertices[self.b].position + vertices[self.c].position)
+            .scale(1.0 / 3.0)
+    }
+
     pub fn edges(&self) -> [NavmeshEdge; 3] {
         [
             NavmeshEdge {
@@ -42,16 +74,54 @@ impl NavmeshTriangle {
     }
 }
 
+/// Off-mesh connection between two points of a navmesh, for movement that can't be described
+/// by triangles - jumps, ladders, teleports.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NavmeshLink {
+    pub begin: Vector3<f32>,
+    pub end: Vector3<f32>,
+    /// Triangles the endpoints are snapped to.
+    pub begin_triangle: Handle<NavmeshTriangle>,
+    pub end_triangle: Handle<NavmeshTriangle>,
+    /// Link can be traversed from end to begin too.
+    pub bidirectional: bool,
+    pub cost: f32,
+    /// User-defined kind of the link, a game decides how agents traverse it.
+    pub tag: String,
+}
+
+impl NavmeshLink {
+    pub fn new(
+        begin: Vector3<f32>,
+        begin_triangle: Handle<NavmeshTriangle>,
+        end: Vector3<f32>,
+        end_triangle: Handle<NavmeshTriangle>,
+    ) -> Self {
+        Self {
+            begin,
+            end,
+            begin_triangle,
+            end_triangle,
+            bidirectional: false,
+            cost: (end - begin).norm(),
+            tag: Default::default(),
+        }
+    }
+}
+
 #[derive(PartialEq, Copy, Clone, Debug, Eq)]
 pub enum NavmeshEntity {
     Vertex(Handle<NavmeshVertex>),
     Edge(NavmeshEdge),
+    Triangle(Handle<NavmeshTriangle>),
+    Link(Handle<NavmeshLink>),
 }
 
 #[derive(Debug, Default)]
 pub struct Navmesh {
     pub vertices: Pool<NavmeshVertex>,
     pub triangles: Pool<NavmeshTriangle>,
+    pub links: Pool<NavmeshLink>,
 }
 
 impl Navmesh {
@@ -73,12 +143,44 @@ impl Navmesh {
 
         let mut triangles = Pool::new();
 
-        let _ = triangles.spawn(NavmeshTriangle { a, b, c });
-        let _ = triangles.spawn(NavmeshTriangle { a, b: c, c: d });
+        let _ = triangles.spawn(NavmeshTriangle::new(a, b, c));
+        let _ = triangles.spawn(NavmeshTriangle::new(a, c, d));
 
         Self {
             vertices,
             triangles,
+            links: Default::default(),
         }
     }
+
+    /// Returns vertex positions and triangles with handles replaced by indices of vertices,
+    /// this is the layout engine's navmesh is built from.
+    pub fn to_dense(&self) -> (Vec<Vector3<f32>>, Vec<TriangleDefinition>) {
+        // Sparse-to-dense mapping - handle to index.
+        let mut vertex_map = HashMap::new();
+
+        let vertices = self
+            .vertices
+            .pair_iter()
+            .enumerate()
+            .map(|(i, (handle, vertex))| {
+                vertex_map.insert(handle, i);
+                vertex.position
+            })
+            .collect::<Vec<_>>();
+
+        let triangles = self
+            .triangles
+            .iter()
+            .map(|triangle| {
+                TriangleDefinition([
+                    vertex_map[&triangle.a] as u32,
+                    vertex_map[&triangle.b] as u32,
+                    vertex_map[&triangle.c] as u32,
+                ])
+            })
+            .collect::<Vec<_>>();
+
+        (vertices, triangles)
+    }
 }
@@ -0,0 +1,357 @@
+//! Validity checks of navmeshes. Editing tools don't prevent broken topology, so problems are
+//! searched by a separate pass and shown to the user instead.
+
+use crate::interaction::navmesh::data_model::{
+    Navmesh, NavmeshEdge, NavmeshEntity, NavmeshTriangle, NavmeshVertex,
+};
+use rg3d::core::{algebra::Vector3, color::Color, pool::Handle};
+use std::{cmp::Ordering, collections::HashMap};
+
+/// Triangles with doubled area less than this are degenerate.
+const DEGENERATE_AREA: f32 = 0.0001;
+
+/// Vertices closer than this to an edge of another triangle form T-junctions.
+const T_JUNCTION_TOLERANCE: f32 = 0.01;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum NavmeshProblemKind {
+    DegenerateTriangle,
+    TJunction,
+    NonManifoldEdge,
+    Island,
+}
+
+impl NavmeshProblemKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            NavmeshProblemKind::DegenerateTriangle => "Degenerate Triangle",
+            NavmeshProblemKind::TJunction => "T-Junction",
+            NavmeshProblemKind::NonManifoldEdge => "Non-Manifold Edge",
+            NavmeshProblemKind::Island => "Island",
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            NavmeshProblemKind::DegenerateTriangle => Color::opaque(255, 0, 0),
+            NavmeshProblemKind::TJunction => Color::opaque(255, 255, 0),
+            NavmeshProblemKind::NonManifoldEdge => Color::opaque(255, 0, 255),
+            NavmeshProblemKind::Island => Color::opaque(0, 255, 255),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct NavmeshProblem {
+    pub kind: NavmeshProblemKind,
+    /// Entity which is selected when the problem is picked in the list.
+    pub entity: NavmeshEntity,
+    /// Edges highlighted in the viewport.
+    pub edges: Vec<NavmeshEdge>,
+}
+
+type EdgeKey = (Handle<NavmeshVertex>, Handle<NavmeshVertex>);
+
+fn edge_key(edge: NavmeshEdge) -> EdgeKey {
+    if edge.begin.index() < edge.end.index() {
+        (edge.begin, edge.end)
+    } else {
+        (edge.end, edge.begin)
+    }
+}
+
+fn find_root(parents: &mut [usize], mut i: usize) -> usize {
+    while parents[i] != i {
+        parents[i] = parents[parents[i]];
+        i = parents[i];
+    }
+    i
+}
+
+/// Returns distance from the point to the segment, if the projection of the point lies
+/// strictly inside the segment.
+fn distance_to_segment_interior(
+    point: Vector3<f32>,
+    begin: Vector3<f32>,
+    end: Vector3<f32>,
+) -> Option<f32> {
+    let dir = end - begin;
+    let length_sqr = dir.norm_squared();
+    if length_sqr <= std::f32::EPSILON {
+        return None;
+    }
+    let t = (point - begin).dot(&dir) / length_sqr;
+    let margin = T_JUNCTION_TOLERANCE / length_sqr.sqrt();
+    if t > margin && t < 1.0 - margin {
+        Some((begin + dir.scale(t) - point).norm())
+    } else {
+        None
+    }
+}
+
+fn find_degenerate_triangles(navmesh: &Navmesh, problems: &mut Vec<NavmeshProblem>) {
+    for triangle in navmesh.triangles.iter() {
+        if triangle.normal(&navmesh.vertices).norm() >= DEGENERATE_AREA {
+            continue;
+        }
+        let edges = triangle.edges();
+        let length = |edge: &NavmeshEdge| {
+            (navmesh.vertices[edge.end].position - navmesh.vertices[edge.begin].position).norm()
+        };
+        // The longest edge is the one the triangle collapsed onto.
+        let longest = *edges
+            .iter()
+            .max_by(|a, b| length(a).partial_cmp(&length(b)).unwrap_or(Ordering::Equal))
+            .unwrap();
+        problems.push(NavmeshProblem {
+            kind: NavmeshProblemKind::DegenerateTriangle,
+            entity: NavmeshEntity::Edge(longest),
+            edges: edges.to_vec(),
+        });
+    }
+}
+
+fn find_non_manifold_edges(
+    edges: &HashMap<EdgeKey, Vec<Handle<NavmeshTriangle>>>,
+    problems: &mut Vec<NavmeshProblem>,
+) {
+    for (&(begin, end), triangles) in edges.iter() {
+        if triangles.len() > 2 {
+            let edge = NavmeshEdge { begin, end };
+            problems.push(NavmeshProblem {
+                kind: NavmeshProblemKind::NonManifoldEdge,
+                entity: NavmeshEntity::Edge(edge),
+                edges: vec![edge],
+            });
+        }
+    }
+}
+
+/// Looks for vertices lying on border edges of other triangles. Edges are put into a grid on
+/// XZ plane so each vertex is tested only against edges nearby.
+fn find_t_junctions(
+    navmesh: &Navmesh,
+    edges: &HashMap<EdgeKey, Vec<Handle<NavmeshTriangle>>>,
+    problems: &mut Vec<NavmeshProblem>,
+) {
+    let border_edges = edges
+        .iter()
+        .filter(|(_, triangles)| triangles.len() == 1)
+        .map(|(&(begin, end), _)| NavmeshEdge { begin, end })
+        .collect::<Vec<_>>();
+    if border_edges.is_empty() {
+        return;
+    }
+
+    let position = |vertex: Handle<NavmeshVertex>| navmesh.vertices[vertex].position;
+
+    let average_length = border_edges
+        .iter()
+        .map(|edge| (position(edge.end) - position(edge.begin)).norm())
+        .sum::<f32>()
+        / border_edges.len() as f32;
+    let cell_size = average_length.max(0.5);
+    let cell_coord = |value: f32| (value / cell_size).floor() as i32;
+
+    let mut cells = HashMap::new();
+    for (index, edge) in border_edges.iter().enumerate() {
+        let margin = Vector3::repeat(T_JUNCTION_TOLERANCE);
+        let min = position(edge.begin).inf(&position(edge.end)) - margin;
+        let max = position(edge.begin).sup(&position(edge.end)) + margin;
+        for x in cell_coord(min.x)..=cell_coord(max.x) {
+            for z in cell_coord(min.z)..=cell_coord(max.z) {
+                cells.entry((x, z)).or_insert_with(Vec::new).push(index);
+            }
+        }
+    }
+
+    for (handle, vertex) in navmesh.vertices.pair_iter() {
+        let point = vertex.position;
+        let junction = cells
+            .get(&(cell_coord(point.x), cell_coord(point.z)))
+            .and_then(|indices| {
+                indices.iter().map(|&i| border_edges[i]).find(|edge| {
+                    edge.begin != handle
+                        && edge.end != handle
+                        && distance_to_segment_interior(
+                            point,
+                            position(edge.begin),
+                            position(edge.end),
+                        )
+                        .map_or(false, |distance| distance < T_JUNCTION_TOLERANCE)
+                })
+            });
+        if let Some(edge) = junction {
+            problems.push(NavmeshProblem {
+                kind: NavmeshProblemKind::TJunction,
+                entity: NavmeshEntity::Vertex(handle),
+                edges: vec![edge],
+            });
+        }
+    }
+}
+
+/// Groups triangles connected by edges, every group except the largest one is an island.
+fn find_islands(
+    navmesh: &Navmesh,
+    edges: &HashMap<EdgeKey, Vec<Handle<NavmeshTriangle>>>,
+    problems: &mut Vec<NavmeshProblem>,
+) {
+    let triangles = navmesh
+        .triangles
+        .pair_iter()
+        .map(|(handle, _)| handle)
+        .collect::<Vec<_>>();
+    let indices = triangles
+        .iter()
+        .enumerate()
+        .map(|(i, &handle)| (handle, i))
+        .collect::<HashMap<_, _>>();
+
+    let mut parents = (0..triangles.len()).collect::<Vec<_>>();
+    for shared in edges.values() {
+        for pair in shared.windows(2) {
+            let a = find_root(&mut parents, indices[&pair[0]]);
+            let b = find_root(&mut parents, indices[&pair[1]]);
+            parents[a] = b;
+        }
+    }
+
+    let mut groups: Vec<Vec<Handle<NavmeshTriangle>>> = Vec::new();
+    let mut root_groups = HashMap::new();
+    for (i, &triangle) in triangles.iter().enumerate() {
+        let root = find_root(&mut parents, i);
+        let group = *root_groups.entry(root).or_insert_with(|| {
+            groups.push(Vec::new());
+            groups.len() - 1
+        });
+        groups[group].push(triangle);
+    }
+    groups.sort_by_key(|group| std::cmp::Reverse(group.len()));
+
+    for group in groups.iter().skip(1) {
+        problems.push(NavmeshProblem {
+            kind: NavmeshProblemKind::Island,
+            entity: NavmeshEntity::Vertex(navmesh.triangles[group[0]].a),
+            edges: group
+                .iter()
+                .flat_map(|&triangle| navmesh.triangles[triangle].edges().to_vec())
+                .collect(),
+        });
+    }
+}
+
+/// Finds degenerate triangles, T-junctions, non-manifold edges and disconnected islands.
+pub fn find_problems(navmesh: &Navmesh) -> Vec<NavmeshProblem> {
+    let mut edges = HashMap::new();
+    for (handle, triangle) in navmesh.triangles.pair_iter() {
+        for &edge in triangle.edges().iter() {
+            edges
+                .entry(edge_key(edge))
+                .or_insert_with(Vec::new)
+                .push(handle);
+        }
+    }
+
+    let mut problems = Vec::new();
+    find_degenerate_triangles(navmesh, &mut problems);
+    find_t_junctions(navmesh, &edges, &mut problems);
+    find_non_manifold_edges(&edges, &mut problems);
+    find_islands(navmesh, &edges, &mut problems);
+    problems
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn add_triangle(navmesh: &mut Navmesh, points: [Vector3<f32>; 3]) -> Handle<NavmeshTriangle> {
+        let [a, b, c] = points;
+        let mut vertex = |position| navmesh.vertices.spawn(NavmeshVertex { position });
+        let (a, b, c) = (vertex(a), vertex(b), vertex(c));
+        navmesh.triangles.spawn(NavmeshTriangle::new(a, b, c))
+    }
+
+    fn kinds(navmesh: &Navmesh) -> Vec<NavmeshProblemKind> {
+        find_problems(navmesh)
+            .into_iter()
+            .map(|problem| problem.kind)
+            .collect()
+    }
+
+    #[test]
+    fn valid_navmesh_has_no_problems() {
+        assert!(kinds(&Navmesh::new()).is_empty());
+    }
+
+    #[test]
+    fn degenerate_triangle() {
+        let mut navmesh = Navmesh::default();
+        add_triangle(
+            &mut navmesh,
+            [
+                Vector3::new(0.0, 0.0, 0.0),
+                Vector3::new(1.0, 0.0, 0.0),
+                Vector3::new(3.0, 0.0, 0.0),
+            ],
+        );
+        assert!(kinds(&navmesh).contains(&NavmeshProblemKind::DegenerateTriangle));
+    }
+
+    #[test]
+    fn t_junction() {
+        let mut navmesh = Navmesh::new();
+        // Vertex of this triangle lies in the middle of the border edge from (1, 0, -1)
+        // to (1, 0, 1).
+        add_triangle(
+            &mut navmesh,
+            [
+                Vector3::new(1.0, 0.0, 0.0),
+                Vector3::new(2.0, 0.0, -1.0),
+                Vector3::new(2.0, 0.0, 1.0),
+            ],
+        );
+        let problems = find_problems(&navmesh);
+        assert!(problems
+            .iter()
+            .any(|problem| problem.kind == NavmeshProblemKind::TJunction
+                && problem.entity
+                    == NavmeshEntity::Vertex(navmesh.vertices.pair_iter().nth(4).unwrap().0)));
+    }
+
+    #[test]
+    fn non_manifold_edge() {
+        let mut navmesh = Navmesh::new();
+        let (a, c) = {
+            let mut vertices = navmesh.vertices.pair_iter().map(|(handle, _)| handle);
+            (vertices.next().unwrap(), vertices.nth(1).unwrap())
+        };
+        let top = navmesh.vertices.spawn(NavmeshVertex {
+            position: Vector3::new(0.0, 1.0, 0.0),
+        });
+        navmesh.triangles.spawn(NavmeshTriangle::new(a, c, top));
+
+        let problems = find_problems(&navmesh);
+        assert_eq!(
+            problems
+                .iter()
+                .filter(|problem| problem.kind == NavmeshProblemKind::NonManifoldEdge)
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn island() {
+        let mut navmesh = Navmesh::new();
+        add_triangle(
+            &mut navmesh,
+            [
+                Vector3::new(10.0, 0.0, 10.0),
+                Vector3::new(11.0, 0.0, 10.0),
+                Vector3::new(11.0, 0.0, 11.0),
+            ],
+        );
+        assert_eq!(kinds(&navmesh), vec![NavmeshProblemKind::Island]);
+    }
+}
@@ -0,0 +1,944 @@
+//! Navmesh generation from scene geometry. It uses the usual voxel approach: static
+//! triangles are rasterized into a heightfield of solid spans, tops of spans with gentle
+//! slope and enough free space above become walkable cells, cells closer than agent radius
+//! to an edge are eroded away, the rest is split into monotone regions (they never have
+//! holes) and simplified outlines of the regions are triangulated.
+
+use crate::{
+    interaction::navmesh::data_model::{Navmesh, NavmeshTriangle, NavmeshVertex},
+    physics::Physics,
+    scene::{for_each_mesh_triangle, scene_nodes},
+};
+use rg3d::{
+    core::{
+        algebra::{Isometry3, Matrix4, Point3, Translation3, Vector3},
+        pool::Handle,
+    },
+    scene::{
+        graph::Graph,
+        node::Node,
+        physics::{BodyStatusDesc, ColliderShapeDesc},
+    },
+};
+use std::collections::{HashMap, VecDeque};
+
+/// Offsets of neighbour cells, directions are -X, +Z, +X, -Z.
+const DIRECTIONS: [(i32, i32); 4] = [(-1, 0), (0, 1), (1, 0), (0, -1)];
+
+/// Contour walk is stopped after this many steps, it can only happen on broken input.
+const MAX_CONTOUR_STEPS: usize = 65536;
+
+/// Maximum amount of heightfield columns, larger grids would take gigabytes of memory.
+const MAX_GRID_CELLS: usize = 2048 * 2048;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct NavmeshGenerationSettings {
+    /// Horizontal size of a voxel.
+    pub cell_size: f32,
+    /// Vertical size of a voxel.
+    pub cell_height: f32,
+    pub agent_radius: f32,
+    pub agent_height: f32,
+    /// Maximum slope of walkable surface in degrees.
+    pub max_slope: f32,
+    /// Maximum height of a ledge agent can climb.
+    pub step_height: f32,
+    /// Maximum distance between simplified outline of walkable area and its voxels.
+    pub max_edge_error: f32,
+}
+
+impl Default for NavmeshGenerationSettings {
+    fn default() -> Self {
+        Self {
+            cell_size: 0.3,
+            cell_height: 0.2,
+            agent_radius: 0.5,
+            agent_height: 2.0,
+            max_slope: 45.0,
+            step_height: 0.4,
+            max_edge_error: 0.4,
+        }
+    }
+}
+
+/// Returns true if node is not moved by simulation - neither it nor its ancestors are
+/// bound to dynamic or kinematic bodies.
+fn is_static(node: Handle<Node>, graph: &Graph, physics: &Physics) -> bool {
+    let mut node = node;
+    while node.is_some() {
+        if let Some(&body) = physics.binder.value_of(&node) {
+            return matches!(physics.bodies[body].status, BodyStatusDesc::Static);
+        }
+        node = graph[node].parent();
+    }
+    true
+}
+
+fn push_box(
+    triangles: &mut Vec<[Vector3<f32>; 3]>,
+    min: Vector3<f32>,
+    max: Vector3<f32>,
+    transform: &Matrix4<f32>,
+) {
+    let corner = |i: usize| {
+        let local = Vector3::new(
+            if i & 1 == 0 { min.x } else { max.x },
+            if i & 2 == 0 { min.y } else { max.y },
+            if i & 4 == 0 { min.z } else { max.z },
+        );
+        transform.transform_point(&Point3::from(local)).coords
+    };
+    // Two triangles per face, faces are -X, +X, -Y, +Y, -Z, +Z.
+    const FACES: [[usize; 4]; 6] = [
+        [0, 4, 6, 2],
+        [1, 3, 7, 5],
+        [0, 1, 5, 4],
+        [2, 6, 7, 3],
+        [0, 2, 3, 1],
+        [4, 5, 7, 6],
+    ];
+    for face in FACES.iter() {
+        triangles.push([corner(face[0]), corner(face[1]), corner(face[2])]);
+        triangles.push([corner(face[0]), corner(face[2]), corner(face[3])]);
+    }
+}
+
+/// Returns local bounds of a primitive shape, shapes without volume or with geometry
+/// stored elsewhere have none.
+fn shape_bounds(shape: &ColliderShapeDesc) -> Option<(Vector3<f32>, Vector3<f32>)> {
+    match shape {
+        ColliderShapeDesc::Ball(ball) => {
+            let extents = Vector3::repeat(ball.radius);
+            Some((-extents, extents))
+        }
+        ColliderShapeDesc::Cuboid(cuboid) => Some((-cuboid.half_extents, cuboid.half_extents)),
+        ColliderShapeDesc::Cylinder(cylinder) => {
+            let extents = Vector3::new(cylinder.radius, cylinder.half_height, cylinder.radius);
+            Some((-extents, extents))
+        }
+        ColliderShapeDesc::RoundCylinder(cylinder) => {
+            let extents = Vector3::new(cylinder.radius, cylinder.half_height, cylinder.radius);
+            Some((-extents, extents))
+        }
+        ColliderShapeDesc::Cone(cone) => {
+            let extents = Vector3::new(cone.radius, cone.half_height, cone.radius);
+            Some((-extents, extents))
+        }
+        ColliderShapeDesc::Capsule(capsule) => {
+            let radius = Vector3::repeat(capsule.radius);
+            Some((
+                capsule.begin.inf(&capsule.end) - radius,
+                capsule.begin.sup(&capsule.end) + radius,
+            ))
+        }
+        ColliderShapeDesc::Segment(_)
+        | ColliderShapeDesc::Triangle(_)
+        | ColliderShapeDesc::Trimesh(_)
+        | ColliderShapeDesc::Heightfield(_) => None,
+    }
+}
+
+/// Gathers world-space triangles of static geometry of the scene: meshes that are not moved
+/// by simulation and colliders of static bodies, nodes under `editor_root` are skipped.
+/// Convex colliders use their hulls, other primitives are approximated by their bounds.
+/// Trimesh colliders are covered by their meshes, heightfields are not supported yet.
+pub fn collect_static_geometry(
+    editor_root: Handle<Node>,
+    graph: &Graph,
+    physics: &Physics,
+) -> Vec<[Vector3<f32>; 3]> {
+    let mut triangles = Vec::new();
+
+    for handle in scene_nodes(graph, editor_root) {
+        if let Node::Mesh(mesh) = &graph[handle] {
+            if is_static(handle, graph, physics) {
+                for_each_mesh_triangle(mesh, |triangle| triangles.push(triangle));
+            }
+        }
+    }
+
+    for (handle, collider) in physics.colliders.pair_iter() {
+        let body = collider.parent.into();
+        if !matches!(physics.bodies[body].status, BodyStatusDesc::Static) {
+            continue;
+        }
+        let transform = physics.body_frame(body, graph).to_homogeneous()
+            * Isometry3 {
+                rotation: collider.rotation,
+                translation: Translation3 {
+                    vector: collider.translation,
+                },
+            }
+            .to_homogeneous();
+
+        if let Some(source) = physics.convex_sources.get(&handle) {
+            let hull = source.hull();
+            let point = |i: u32| {
+                transform
+                    .transform_point(&Point3::from(hull.vertices[i as usize]))
+                    .coords
+            };
+            for triangle in hull.triangles.iter() {
+                triangles.push([point(triangle[0]), point(triangle[1]), point(triangle[2])]);
+            }
+        } else if let Some((min, max)) = shape_bounds(&collider.shape) {
+            push_box(&mut triangles, min, max, &transform);
+        }
+    }
+
+    triangles
+}
+
+/// Clips polygon by axis-aligned plane, keeps part where coordinate along `axis` is greater
+/// (or less if `keep_greater` is false) than `value`.
+fn clip_polygon(
+    points: &[Vector3<f32>],
+    axis: usize,
+    value: f32,
+    keep_greater: bool,
+) -> Vec<Vector3<f32>> {
+    let side = |p: &Vector3<f32>| {
+        if keep_greater {
+            p[axis] - value
+        } else {
+            value - p[axis]
+        }
+    };
+    let mut result = Vec::with_capacity(points.len() + 2);
+    for (i, a) in points.iter().enumerate() {
+        let b = &points[(i + 1) % points.len()];
+        let (da, db) = (side(a), side(b));
+        if da >= 0.0 {
+            result.push(*a);
+        }
+        if (da >= 0.0) != (db >= 0.0) {
+            result.push(a + (b - a).scale(da / (da - db)));
+        }
+    }
+    result
+}
+
+fn clip_polygon_to_slab(
+    points: &[Vector3<f32>],
+    axis: usize,
+    min: f32,
+    max: f32,
+) -> Vec<Vector3<f32>> {
+    clip_polygon(&clip_polygon(points, axis, min, true), axis, max, false)
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Span {
+    min: i32,
+    max: i32,
+    walkable: bool,
+}
+
+/// Columns of solid spans sorted from bottom to top.
+struct Heightfield {
+    width: usize,
+    depth: usize,
+    origin: Vector3<f32>,
+    columns: Vec<Vec<Span>>,
+}
+
+impl Heightfield {
+    /// Adds span to a column merging it with every span it overlaps. Walkable flag of the
+    /// merged span is taken from the top-most surface, surfaces closer than `merge_threshold`
+    /// are considered to be at the same height.
+    fn add_span(&mut self, x: usize, z: usize, mut span: Span, merge_threshold: i32) {
+        let column = &mut self.columns[z * self.width + x];
+        let mut i = 0;
+        while i < column.len() {
+            let current = column[i];
+            if current.min > span.max {
+                break;
+            }
+            if current.max < span.min {
+                i += 1;
+                continue;
+            }
+            if (current.max - span.max).abs() <= merge_threshold {
+                span.walkable |= current.walkable;
+            } else if current.max > span.max {
+                span.walkable = current.walkable;
+            }
+            span.min = span.min.min(current.min);
+            span.max = span.max.max(current.max);
+            column.remove(i);
+        }
+        column.insert(i, span);
+    }
+
+    fn rasterize_triangle(
+        &mut self,
+        triangle: &[Vector3<f32>; 3],
+        walkable: bool,
+        settings: &NavmeshGenerationSettings,
+        merge_threshold: i32,
+    ) {
+        let cs = settings.cell_size;
+        let min = triangle[0].inf(&triangle[1]).inf(&triangle[2]);
+        let max = triangle[0].sup(&triangle[1]).sup(&triangle[2]);
+        let cell = |value: f32, origin: f32, count: usize| {
+            (((value - origin) / cs).floor().max(0.0) as usize).min(count - 1)
+        };
+        let (x0, x1) = (
+            cell(min.x, self.origin.x, self.width),
+            cell(max.x, self.origin.x, self.width),
+        );
+        let (z0, z1) = (
+            cell(min.z, self.origin.z, self.depth),
+            cell(max.z, self.origin.z, self.depth),
+        );
+
+        for z in z0..=z1 {
+            let cz = self.origin.z + z as f32 * cs;
+            let row = clip_polygon_to_slab(triangle, 2, cz, cz + cs);
+            if row.len() < 3 {
+                continue;
+            }
+            for x in x0..=x1 {
+                let cx = self.origin.x + x as f32 * cs;
+                let polygon = clip_polygon_to_slab(&row, 0, cx, cx + cs);
+                if polygon.len() < 3 {
+                    continue;
+                }
+                let (bottom, top) = polygon
+                    .iter()
+                    .fold((std::f32::MAX, -std::f32::MAX), |(bottom, top), p| {
+                        (bottom.min(p.y), top.max(p.y))
+                    });
+                let span_min = ((bottom - self.origin.y) / settings.cell_height).floor() as i32;
+                let span_max = ((top - self.origin.y) / settings.cell_height).ceil() as i32;
+                self.add_span(
+                    x,
+                    z,
+                    Span {
+                        min: span_min.min(span_max - 1),
+                        max: span_max,
+                        walkable,
+                    },
+                    merge_threshold,
+                );
+            }
+        }
+    }
+}
+
+/// Top of a walkable span, `ceiling` is the bottom of the next span in the column.
+#[derive(Clone, Debug)]
+struct Cell {
+    x: usize,
+    z: usize,
+    y: i32,
+    ceiling: i32,
+    neighbours: [Option<usize>; 4],
+}
+
+/// Walkable cells stored column by column.
+struct CompactHeightfield {
+    width: usize,
+    depth: usize,
+    cells: Vec<Cell>,
+    /// First cell and cell count of each column.
+    columns: Vec<(usize, usize)>,
+}
+
+impl CompactHeightfield {
+    fn new(heightfield: &Heightfield, agent_height: i32, step_height: i32) -> Self {
+        let mut cells = Vec::new();
+        let mut columns = Vec::with_capacity(heightfield.columns.len());
+        for (index, column) in heightfield.columns.iter().enumerate() {
+            let start = cells.len();
+            for (i, span) in column.iter().enumerate() {
+                let ceiling = column.get(i + 1).map_or(std::i32::MAX, |next| next.min);
+                if span.walkable && ceiling.saturating_sub(span.max) >= agent_height {
+                    cells.push(Cell {
+                        x: index % heightfield.width,
+                        z: index / heightfield.width,
+                        y: span.max,
+                        ceiling,
+                        neighbours: [None; 4],
+                    });
+                }
+            }
+            columns.push((start, cells.len() - start));
+        }
+
+        let mut compact = Self {
+            width: heightfield.width,
+            depth: heightfield.depth,
+            cells,
+            columns,
+        };
+
+        for i in 0..compact.cells.len() {
+            let mut neighbours = [None; 4];
+            let cell = &compact.cells[i];
+            for (dir, &(dx, dz)) in DIRECTIONS.iter().enumerate() {
+                let (nx, nz) = (cell.x as i32 + dx, cell.z as i32 + dz);
+                if nx < 0 || nz < 0 || nx >= compact.width as i32 || nz >= compact.depth as i32 {
+                    continue;
+                }
+                neighbours[dir] = compact.column(nx as usize, nz as usize).find(|&j| {
+                    let other = &compact.cells[j];
+                    let floor = cell.y.max(other.y);
+                    let ceiling = cell.ceiling.min(other.ceiling);
+                    ceiling.saturating_sub(floor) >= agent_height
+                        && (other.y - cell.y).abs() <= step_height
+                });
+            }
+            compact.cells[i].neighbours = neighbours;
+        }
+
+        compact
+    }
+
+    fn column(&self, x: usize, z: usize) -> std::ops::Range<usize> {
+        let (start, count) = self.columns[z * self.width + x];
+        start..start + count
+    }
+
+    /// Removes cells closer than `radius` cells to the border of walkable area.
+    fn erode(&mut self, radius: usize) {
+        if radius == 0 {
+            return;
+        }
+
+        let mut distances = vec![std::usize::MAX; self.cells.len()];
+        let mut queue = VecDeque::new();
+        for (i, cell) in self.cells.iter().enumerate() {
+            if cell.neighbours.iter().any(|n| n.is_none()) {
+                distances[i] = 0;
+                queue.push_back(i);
+            }
+        }
+        while let Some(i) = queue.pop_front() {
+            for &n in self.cells[i].neighbours.iter().flatten() {
+                if distances[n] > distances[i] + 1 {
+                    distances[n] = distances[i] + 1;
+                    queue.push_back(n);
+                }
+            }
+        }
+
+        let mut remap = vec![None; self.cells.len()];
+        let mut cells = Vec::with_capacity(self.cells.len());
+        for (i, cell) in self.cells.iter().enumerate() {
+            if distances[i] >= radius {
+                remap[i] = Some(cells.len());
+                cells.push(cell.clone());
+            }
+        }
+        for cell in cells.iter_mut() {
+            for neighbour in cell.neighbours.iter_mut() {
+                *neighbour = neighbour.and_then(|n| remap[n]);
+            }
+        }
+        for column in self.columns.iter_mut() {
+            let (start, count) = *column;
+            let kept = (start..start + count)
+                .filter_map(|i| remap[i])
+                .collect::<Vec<_>>();
+            *column = (kept.first().cloned().unwrap_or(0), kept.len());
+        }
+        self.cells = cells;
+    }
+
+    /// Splits cells into monotone regions: cells are swept row by row, runs of connected
+    /// cells in a row are merged with a region of previous row only if they are the only
+    /// run connected to it. Returns region of each cell, regions start from 1.
+    fn build_regions(&self) -> Vec<usize> {
+        #[derive(Copy, Clone)]
+        enum SweepNeighbour {
+            Unknown,
+            Region(usize),
+            Many,
+        }
+
+        struct Sweep {
+            neighbour: SweepNeighbour,
+            samples: usize,
+            id: usize,
+        }
+
+        let mut regions = vec![0; self.cells.len()];
+        let mut next_region = 1;
+        let mut previous_row_samples = Vec::new();
+
+        for z in 0..self.depth {
+            // Index 0 is reserved for "no sweep".
+            let mut sweeps = vec![Sweep {
+                neighbour: SweepNeighbour::Unknown,
+                samples: 0,
+                id: 0,
+            }];
+            previous_row_samples.clear();
+            previous_row_samples.resize(next_region, 0);
+
+            for x in 0..self.width {
+                for i in self.column(x, z) {
+                    let cell = &self.cells[i];
+                    // Cells to the left are already assigned to sweeps of this row.
+                    let mut sweep = cell.neighbours[0].map_or(0, |n| regions[n]);
+                    if sweep == 0 {
+                        sweeps.push(Sweep {
+                            neighbour: SweepNeighbour::Unknown,
+                            samples: 0,
+                            id: 0,
+                        });
+                        sweep = sweeps.len() - 1;
+                    }
+
+                    if let Some(n) = cell.neighbours[3] {
+                        let region = regions[n];
+                        let sweep = &mut sweeps[sweep];
+                        match sweep.neighbour {
+                            SweepNeighbour::Unknown => {
+                                sweep.neighbour = SweepNeighbour::Region(region);
+                                sweep.samples += 1;
+                                previous_row_samples[region] += 1;
+                            }
+                            SweepNeighbour::Region(r) if r == region => {
+                                sweep.samples += 1;
+                                previous_row_samples[region] += 1;
+                            }
+                            _ => sweep.neighbour = SweepNeighbour::Many,
+                        }
+                    }
+
+                    regions[i] = sweep;
+                }
+            }
+
+            for sweep in sweeps.iter_mut().skip(1) {
+                sweep.id = match sweep.neighbour {
+                    SweepNeighbour::Region(region)
+                        if previous_row_samples[region] == sweep.samples =>
+                    {
+                        region
+                    }
+                    _ => {
+                        next_region += 1;
+                        next_region - 1
+                    }
+                };
+            }
+
+            for x in 0..self.width {
+                for i in self.column(x, z) {
+                    regions[i] = sweeps[regions[i]].id;
+                }
+            }
+        }
+
+        regions
+    }
+
+    /// Height of a cell corner in direction `dir` and next direction clockwise, it is the
+    /// highest of cells sharing the corner so neighbour regions get the same vertex.
+    fn corner_height(&self, i: usize, dir: usize) -> i32 {
+        let next_dir = (dir + 1) % 4;
+        let cell = &self.cells[i];
+        let mut height = cell.y;
+        if let Some(a) = cell.neighbours[dir] {
+            height = height.max(self.cells[a].y);
+            if let Some(b) = self.cells[a].neighbours[next_dir] {
+                height = height.max(self.cells[b].y);
+            }
+        }
+        if let Some(a) = cell.neighbours[next_dir] {
+            height = height.max(self.cells[a].y);
+            if let Some(b) = self.cells[a].neighbours[dir] {
+                height = height.max(self.cells[b].y);
+            }
+        }
+        height
+    }
+
+    /// Walks along the border of a region starting from cell `start`, clears edge flags of
+    /// visited edges.
+    fn walk_contour(&self, regions: &[usize], flags: &mut [u8], start: usize) -> Vec<ContourPoint> {
+        let mut i = start;
+        let mut dir = (0..4).find(|d| flags[i] & (1 << d) != 0).unwrap_or(0);
+        let start_dir = dir;
+        let mut points = Vec::new();
+
+        for _ in 0..MAX_CONTOUR_STEPS {
+            let cell = &self.cells[i];
+            if flags[i] & (1 << dir) != 0 {
+                let (mut x, mut z) = (cell.x as i32, cell.z as i32);
+                match dir {
+                    0 => z += 1,
+                    1 => {
+                        x += 1;
+                        z += 1;
+                    }
+                    2 => x += 1,
+                    _ => (),
+                }
+                points.push(ContourPoint {
+                    x,
+                    y: self.corner_height(i, dir),
+                    z,
+                    region: cell.neighbours[dir].map_or(0, |n| regions[n]),
+                });
+                flags[i] &= !(1 << dir);
+                dir = (dir + 1) % 4;
+            } else {
+                match cell.neighbours[dir] {
+                    Some(n) => i = n,
+                    None => break,
+                }
+                dir = (dir + 3) % 4;
+            }
+            if i == start && dir == start_dir {
+                break;
+            }
+        }
+
+        points
+    }
+}
+
+/// Corner of a cell in grid coordinates, `region` is the region behind the edge that ends
+/// at this point (0 is a wall).
+#[derive(Copy, Clone, Debug)]
+struct ContourPoint {
+    x: i32,
+    y: i32,
+    z: i32,
+    region: usize,
+}
+
+fn distance_to_segment_squared(p: &ContourPoint, a: &ContourPoint, b: &ContourPoint) -> f32 {
+    let (dx, dz) = ((b.x - a.x) as f32, (b.z - a.z) as f32);
+    let (px, pz) = ((p.x - a.x) as f32, (p.z - a.z) as f32);
+    let length_squared = dx * dx + dz * dz;
+    let t = if length_squared > 0.0 {
+        ((px * dx + pz * dz) / length_squared).max(0.0).min(1.0)
+    } else {
+        0.0
+    };
+    let (ex, ez) = (px - t * dx, pz - t * dz);
+    ex * ex + ez * ez
+}
+
+/// Keeps points where neighbour region changes and adds wall points until outline deviates
+/// from raw contour by less than `max_error` (in cells). Portals between regions are kept
+/// straight, so both regions get exactly the same vertices on them.
+fn simplify_contour(raw: &[ContourPoint], max_error: f32) -> Vec<ContourPoint> {
+    let n = raw.len();
+    let mut simplified = (0..n)
+        .filter(|&i| raw[i].region != raw[(i + 1) % n].region)
+        .collect::<Vec<_>>();
+
+    if simplified.is_empty() {
+        // No portals - start from lower-left and upper-right points.
+        let lower_left = (0..n).min_by_key(|&i| (raw[i].x, raw[i].z)).unwrap();
+        let upper_right = (0..n).max_by_key(|&i| (raw[i].x, raw[i].z)).unwrap();
+        simplified.push(lower_left.min(upper_right));
+        if lower_left != upper_right {
+            simplified.push(lower_left.max(upper_right));
+        }
+    }
+
+    let mut i = 0;
+    while i < simplified.len() {
+        let (ai, bi) = (simplified[i], simplified[(i + 1) % simplified.len()]);
+        let (a, b) = (&raw[ai], &raw[bi]);
+
+        // Traverse the segment in the same direction regardless of winding.
+        let (mut ci, step, end, a, b) = if (b.x, b.z) > (a.x, a.z) {
+            ((ai + 1) % n, 1, bi, a, b)
+        } else {
+            ((bi + n - 1) % n, n - 1, ai, b, a)
+        };
+
+        let mut max_deviation = 0.0;
+        let mut max_index = None;
+        if raw[ci].region == 0 {
+            while ci != end {
+                let deviation = distance_to_segment_squared(&raw[ci], a, b);
+                if deviation > max_deviation {
+                    max_deviation = deviation;
+                    max_index = Some(ci);
+                }
+                ci = (ci + step) % n;
+            }
+        }
+
+        match max_index {
+            Some(index) if max_deviation > max_error * max_error => simplified.insert(i + 1, index),
+            _ => i += 1,
+        }
+    }
+
+    let mut points = simplified.into_iter().map(|i| raw[i]).collect::<Vec<_>>();
+    points.dedup_by(|a, b| a.x == b.x && a.z == b.z);
+    while points.len() > 1 {
+        let (first, last) = (points[0], points[points.len() - 1]);
+        if first.x == last.x && first.z == last.z {
+            points.pop();
+        } else {
+            break;
+        }
+    }
+    points
+}
+
+fn cross_2d(a: &ContourPoint, b: &ContourPoint, c: &ContourPoint) -> i64 {
+    (b.x - a.x) as i64 * (c.z - a.z) as i64 - (b.z - a.z) as i64 * (c.x - a.x) as i64
+}
+
+/// Triangulates simple polygon by ear clipping. Returns triangles as indices of the points,
+/// winding is counter-clockwise in XZ plane, and `false` if the outline is self-intersecting
+/// and only part of it was triangulated.
+fn triangulate(points: &[ContourPoint]) -> (Vec<[usize; 3]>, bool) {
+    let mut indices = (0..points.len()).collect::<Vec<_>>();
+    let area = (0..points.len()).fold(0i64, |area, i| {
+        let (a, b) = (&points[i], &points[(i + 1) % points.len()]);
+        area + a.x as i64 * b.z as i64 - b.x as i64 * a.z as i64
+    });
+    if area < 0 {
+        indices.reverse();
+    }
+
+    let mut triangles = Vec::new();
+    while indices.len() > 3 {
+        let count = indices.len();
+        let ear = (0..count).find(|&i| {
+            let (a, b, c) = (
+                &points[indices[(i + count - 1) % count]],
+                &points[indices[i]],
+                &points[indices[(i + 1) % count]],
+            );
+            if cross_2d(a, b, c) <= 0 {
+                return false;
+            }
+            // No other vertex may lie inside of the ear or on its border, otherwise
+            // neighbour triangles won't share edges.
+            !indices.iter().any(|&j| {
+                let p = &points[j];
+                let is_corner = [a, b, c].iter().any(|v| v.x == p.x && v.z == p.z);
+                !is_corner
+                    && cross_2d(a, b, p) >= 0
+                    && cross_2d(b, c, p) >= 0
+                    && cross_2d(c, a, p) >= 0
+            })
+        });
+
+        match ear {
+            Some(i) => {
+                triangles.push([
+                    indices[(i + count - 1) % count],
+                    indices[i],
+                    indices[(i + 1) % count],
+                ]);
+                indices.remove(i);
+            }
+            // Self-intersecting outline, leave the rest for manual editing.
+            None => return (triangles, false),
+        }
+    }
+
+    if indices.len() == 3
+        && cross_2d(
+            &points[indices[0]],
+            &points[indices[1]],
+            &points[indices[2]],
+        ) > 0
+    {
+        triangles.push([indices[0], indices[1], indices[2]]);
+    }
+
+    (triangles, true)
+}
+
+/// Builds navmesh for given world-space triangles. Navmesh is empty if there is no walkable
+/// area, error is returned if the geometry is too large for given cell size. Amount of
+/// regions that were triangulated only partially is returned along with the navmesh.
+pub fn generate_navmesh(
+    triangles: &[[Vector3<f32>; 3]],
+    settings: &NavmeshGenerationSettings,
+) -> Result<(Navmesh, usize), String> {
+    let mut navmesh = Navmesh::default();
+    let mut partial_regions = 0;
+
+    if triangles.is_empty() || settings.cell_size <= 0.0 || settings.cell_height <= 0.0 {
+        return Ok((navmesh, partial_regions));
+    }
+
+    let (min, max) = triangles.iter().flatten().fold(
+        (
+            Vector3::repeat(std::f32::MAX),
+            Vector3::repeat(-std::f32::MAX),
+        ),
+        |(min, max), p| (min.inf(p), max.sup(p)),
+    );
+
+    let cs = settings.cell_size;
+    let ch = settings.cell_height;
+    let agent_height = (settings.agent_height / ch).ceil() as i32;
+    let step_height = (settings.step_height / ch).floor() as i32;
+    let agent_radius = (settings.agent_radius / cs).ceil() as usize;
+
+    let size = (max - min) / cs;
+    if !size.x.is_finite() || !size.z.is_finite() {
+        return Err("scene geometry has invalid coordinates".to_owned());
+    }
+    let width = size.x.ceil() as usize + 1;
+    let depth = size.z.ceil() as usize + 1;
+    if width.saturating_mul(depth) > MAX_GRID_CELLS {
+        return Err(format!(
+            "grid of {}x{} cells is too large, maximum is {} cells; increase cell size",
+            width, depth, MAX_GRID_CELLS
+        ));
+    }
+    let mut heightfield = Heightfield {
+        width,
+        depth,
+        origin: min,
+        columns: vec![Vec::new(); width * depth],
+    };
+
+    let min_normal_y = settings.max_slope.to_radians().cos();
+    for triangle in triangles {
+        let normal = (triangle[1] - triangle[0]).cross(&(triangle[2] - triangle[0]));
+        let walkable = match normal.try_normalize(std::f32::EPSILON) {
+            Some(normal) => normal.y.abs() >= min_normal_y,
+            None => continue,
+        };
+        heightfield.rasterize_triangle(triangle, walkable, settings, step_height);
+    }
+
+    let mut compact = CompactHeightfield::new(&heightfield, agent_height, step_height);
+    compact.erode(agent_radius);
+    let regions = compact.build_regions();
+
+    // Edge flags, bit is set for each direction where neighbour belongs to other region.
+    let mut flags = compact
+        .cells
+        .iter()
+        .enumerate()
+        .map(|(i, cell)| {
+            let mut flags = 0u8;
+            for (dir, neighbour) in cell.neighbours.iter().enumerate() {
+                if neighbour.map_or(true, |n| regions[n] != regions[i]) {
+                    flags |= 1 << dir;
+                }
+            }
+            flags
+        })
+        .collect::<Vec<_>>();
+
+    let mut vertex_map: HashMap<(i32, i32), Vec<(i32, Handle<NavmeshVertex>)>> = HashMap::new();
+    let vertices = &mut navmesh.vertices;
+    let max_error = settings.max_edge_error / cs;
+
+    for i in 0..compact.cells.len() {
+        // Skip cells without borders and single cell regions.
+        if flags[i] == 0 || flags[i] == 0b1111 {
+            flags[i] = 0;
+            continue;
+        }
+
+        let raw = compact.walk_contour(&regions, &mut flags, i);
+        if raw.len() < 3 {
+            continue;
+        }
+        let contour = simplify_contour(&raw, max_error);
+        if contour.len() < 3 {
+            continue;
+        }
+
+        let mut vertex = |point: &ContourPoint| -> Handle<NavmeshVertex> {
+            let candidates = vertex_map.entry((point.x, point.z)).or_default();
+            // Corners shared by regions may get slightly different heights.
+            if let Some(&(_, handle)) = candidates.iter().find(|(y, _)| (y - point.y).abs() <= 2) {
+                return handle;
+            }
+            let handle = vertices.spawn(NavmeshVertex {
+                position: Vector3::new(
+                    min.x + point.x as f32 * cs,
+                    min.y + point.y as f32 * ch,
+                    min.z + point.z as f32 * cs,
+                ),
+            });
+            candidates.push((point.y, handle));
+            handle
+        };
+
+        let (triangles, complete) = triangulate(&contour);
+        if !complete {
+            partial_regions += 1;
+        }
+        for triangle in triangles {
+            let a = vertex(&contour[triangle[0]]);
+            let b = vertex(&contour[triangle[1]]);
+            let c = vertex(&contour[triangle[2]]);
+            if a != b && b != c && c != a {
+                navmesh.triangles.spawn(NavmeshTriangle::new(a, b, c));
+            }
+        }
+    }
+
+    Ok((navmesh, partial_regions))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn plane(half_size: f32) -> Vec<[Vector3<f32>; 3]> {
+        let a = Vector3::new(-half_size, 0.0, -half_size);
+        let b = Vector3::new(half_size, 0.0, -half_size);
+        let c = Vector3::new(half_size, 0.0, half_size);
+        let d = Vector3::new(-half_size, 0.0, half_size);
+        vec![[a, c, b], [a, d, c]]
+    }
+
+    #[test]
+    fn flat_plane_gives_walkable_navmesh() {
+        let settings = NavmeshGenerationSettings::default();
+        let (navmesh, partial_regions) = generate_navmesh(&plane(5.0), &settings).unwrap();
+
+        assert_eq!(partial_regions, 0);
+        assert!(navmesh.triangles.iter().next().is_some());
+        // Walkable area is shrunk by agent radius, but not more than a few cells.
+        let inner = 5.0 - settings.agent_radius + settings.cell_size;
+        let outer = 5.0 - settings.agent_radius - settings.cell_size * 3.0;
+        for vertex in navmesh.vertices.iter() {
+            let p = vertex.position;
+            assert!(p.y.abs() <= settings.cell_height * 2.0);
+            assert!(p.x.abs() <= inner && p.z.abs() <= inner);
+        }
+        assert!(navmesh
+            .vertices
+            .iter()
+            .any(|v| v.position.x >= outer && v.position.z >= outer));
+
+        for triangle in navmesh.triangles.iter() {
+            let [a, b, c] = [
+                navmesh.vertices[triangle.a].position,
+                navmesh.vertices[triangle.b].position,
+                navmesh.vertices[triangle.c].position,
+            ];
+            assert!((b - a).cross(&(c - a)).y.abs() > std::f32::EPSILON);
+        }
+    }
+
+    #[test]
+    fn oversized_grid_is_rejected() {
+        let settings = NavmeshGenerationSettings::default();
+        assert!(generate_navmesh(&plane(10000.0), &settings).is_err());
+    }
+
+    #[test]
+    fn no_geometry_gives_empty_navmesh() {
+        let settings = NavmeshGenerationSettings::default();
+        let (navmesh, _) = generate_navmesh(&[], &settings).unwrap();
+        assert!(navmesh.triangles.iter().next().is_none());
+    }
+}
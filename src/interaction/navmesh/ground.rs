@@ -0,0 +1,116 @@
+//! Projection of navmesh vertices onto scene geometry. Triangles of every mesh are put into
+//! a uniform grid on XZ plane, so a vertical ray has to be tested only against triangles of
+//! a single cell.
+
+use crate::{
+    interaction::navmesh::ray_triangle_intersection,
+    scene::{for_each_mesh_triangle, scene_nodes},
+};
+use rg3d::{
+    core::{algebra::Vector3, math::ray::Ray, pool::Handle},
+    scene::{graph::Graph, node::Node},
+};
+use std::{cmp::Ordering, collections::HashMap};
+
+const CELL_SIZE: f32 = 2.0;
+
+/// Vertices are allowed to rise up to this height, so vertices which sunk into a floor are
+/// pulled back to its surface.
+const MAX_RISE: f32 = 1.0;
+
+pub struct GroundGeometry {
+    triangles: Vec<[Vector3<f32>; 3]>,
+    cells: HashMap<(i32, i32), Vec<usize>>,
+}
+
+fn cell_coord(value: f32) -> i32 {
+    (value / CELL_SIZE).floor() as i32
+}
+
+impl GroundGeometry {
+    /// Collects world-space triangles of all visible meshes of the scene, nodes under
+    /// `editor_root` are skipped.
+    pub fn from_graph(editor_root: Handle<Node>, graph: &Graph) -> Self {
+        let mut triangles = Vec::new();
+        for handle in scene_nodes(graph, editor_root) {
+            if let Node::Mesh(mesh) = &graph[handle] {
+                if mesh.global_visibility() {
+                    for_each_mesh_triangle(mesh, |triangle| triangles.push(triangle));
+                }
+            }
+        }
+        Self::from_triangles(triangles)
+    }
+
+    fn from_triangles(triangles: Vec<[Vector3<f32>; 3]>) -> Self {
+        let mut cells = HashMap::new();
+        for (index, triangle) in triangles.iter().enumerate() {
+            let (mut min, mut max) = (triangle[0], triangle[0]);
+            for point in triangle.iter() {
+                min = min.inf(point);
+                max = max.sup(point);
+            }
+            for x in cell_coord(min.x)..=cell_coord(max.x) {
+                for z in cell_coord(min.z)..=cell_coord(max.z) {
+                    cells.entry((x, z)).or_insert_with(Vec::new).push(index);
+                }
+            }
+        }
+
+        Self { triangles, cells }
+    }
+
+    /// Returns the highest point of geometry right below the given point, or `None` if there
+    /// is nothing below.
+    pub fn project(&self, point: Vector3<f32>) -> Option<Vector3<f32>> {
+        let origin = Vector3::new(point.x, point.y + MAX_RISE, point.z);
+        let ray = Ray::new(origin, Vector3::new(0.0, -1.0, 0.0));
+        self.cells
+            .get(&(cell_coord(point.x), cell_coord(point.z)))?
+            .iter()
+            .filter_map(|&i| ray_triangle_intersection(&ray, self.triangles[i]))
+            .min_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+            .map(|distance| Vector3::new(point.x, origin.y - distance, point.z))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Square floor at given height, it spans several grid cells.
+    fn floor(y: f32) -> Vec<[Vector3<f32>; 3]> {
+        let a = Vector3::new(-5.0, y, -5.0);
+        let b = Vector3::new(5.0, y, -5.0);
+        let c = Vector3::new(5.0, y, 5.0);
+        let d = Vector3::new(-5.0, y, 5.0);
+        vec![[a, b, c], [a, c, d]]
+    }
+
+    #[test]
+    fn point_is_projected_onto_floor_below() {
+        let ground = GroundGeometry::from_triangles(floor(0.0));
+
+        let projected = ground.project(Vector3::new(3.0, 0.5, -4.0)).unwrap();
+        assert_eq!((projected.x, projected.z), (3.0, -4.0));
+        assert!(projected.y.abs() < 0.0001);
+        // Sunk vertex is pulled back, but only from a limited depth.
+        let projected = ground.project(Vector3::new(1.0, -0.5, 2.0)).unwrap();
+        assert!(projected.y.abs() < 0.0001);
+        assert!(ground.project(Vector3::new(1.0, -2.0, 2.0)).is_none());
+        // Nothing is below.
+        assert!(ground.project(Vector3::new(20.0, 0.5, 0.0)).is_none());
+    }
+
+    #[test]
+    fn highest_floor_below_is_chosen() {
+        let mut triangles = floor(0.0);
+        triangles.extend(floor(3.0));
+        let ground = GroundGeometry::from_triangles(triangles);
+
+        let projected = ground.project(Vector3::new(0.5, 3.2, 1.5)).unwrap();
+        assert!((projected.y - 3.0).abs() < 0.0001);
+        let projected = ground.project(Vector3::new(0.5, 1.0, 1.5)).unwrap();
+        assert!(projected.y.abs() < 0.0001);
+    }
+}
@@ -0,0 +1,343 @@
+//! Off-mesh links - saving them next to the scene and their arcs in the viewport. Engine's
+//! navmesh has no notion of links, so a game reads them from a separate file.
+
+use crate::interaction::navmesh::data_model::{Navmesh, NavmeshLink, NavmeshTriangle};
+use rg3d::core::{
+    algebra::Vector3,
+    pool::{Handle, Pool},
+    visitor::{Visit, VisitResult, Visitor},
+};
+use std::{
+    cmp::Ordering,
+    path::{Path, PathBuf},
+};
+
+/// Amount of segments of an arc of a link.
+pub const ARC_SEGMENTS: usize = 16;
+
+/// Link as saved on disk, triangles are indices of triangles in engine's navmesh.
+#[derive(Default, Debug)]
+pub struct NavmeshLinkDefinition {
+    pub begin: Vector3<f32>,
+    pub end: Vector3<f32>,
+    pub begin_triangle: u32,
+    pub end_triangle: u32,
+    pub bidirectional: bool,
+    pub cost: f32,
+    pub tag: String,
+}
+
+impl Visit for NavmeshLinkDefinition {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.begin.visit("Begin", visitor)?;
+        self.end.visit("End", visitor)?;
+        self.begin_triangle.visit("BeginTriangle", visitor)?;
+        self.end_triangle.visit("EndTriangle", visitor)?;
+        self.bidirectional.visit("Bidirectional", visitor)?;
+        self.cost.visit("Cost", visitor)?;
+        self.tag.visit("Tag", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+#[derive(Default, Debug)]
+pub struct NavmeshLinkDefinitions {
+    pub links: Vec<NavmeshLinkDefinition>,
+}
+
+impl Visit for NavmeshLinkDefinitions {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.links.visit("Links", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+/// Links of every navmesh of a scene, navmeshes are in the same order as in the saved scene.
+#[derive(Default, Debug)]
+pub struct NavmeshLinks {
+    pub navmeshes: Vec<NavmeshLinkDefinitions>,
+}
+
+impl Visit for NavmeshLinks {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.navmeshes.visit("Navmeshes", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+/// Returns index of a triangle in engine's navmesh. Triangle of a link may be removed by
+/// later edits, then the link is attached to the triangle with the closest center and a
+/// warning is added.
+fn triangle_index(
+    navmesh: &Navmesh,
+    triangle: Handle<NavmeshTriangle>,
+    point: Vector3<f32>,
+    describe: &dyn Fn() -> String,
+    warnings: &mut Vec<String>,
+) -> Option<u32> {
+    if let Some(index) = navmesh
+        .triangles
+        .pair_iter()
+        .position(|(handle, _)| handle == triangle)
+    {
+        return Some(index as u32);
+    }
+    let closest = navmesh
+        .triangles
+        .iter()
+        .map(|t| (t.center(&navmesh.vertices) - point).norm_squared())
+        .enumerate()
+        .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+        .map(|(index, _)| index as u32);
+    warnings.push(match closest {
+        Some(_) => format!(
+            "{}: triangle was removed, link is attached to the closest one.",
+            describe()
+        ),
+        None => format!(
+            "{}: navmesh has no triangles, link is not saved.",
+            describe()
+        ),
+    });
+    closest
+}
+
+impl NavmeshLinks {
+    /// Makes definitions of links of every navmesh, warnings are added for links whose
+    /// triangles were removed.
+    pub fn new(navmeshes: &Pool<Navmesh>, warnings: &mut Vec<String>) -> Self {
+        let mut links = Self::default();
+        for (i, navmesh) in navmeshes.iter().enumerate() {
+            let mut definitions = NavmeshLinkDefinitions::default();
+            for (j, link) in navmesh.links.iter().enumerate() {
+                let begin_triangle = triangle_index(
+                    navmesh,
+                    link.begin_triangle,
+                    link.begin,
+                    &|| format!("Begin of link {} of navmesh {}", j, i),
+                    warnings,
+                );
+                let end_triangle = triangle_index(
+                    navmesh,
+                    link.end_triangle,
+                    link.end,
+                    &|| format!("End of link {} of navmesh {}", j, i),
+                    warnings,
+                );
+                if let (Some(begin_triangle), Some(end_triangle)) = (begin_triangle, end_triangle) {
+                    definitions.links.push(NavmeshLinkDefinition {
+                        begin: link.begin,
+                        end: link.end,
+                        begin_triangle,
+                        end_triangle,
+                        bidirectional: link.bidirectional,
+                        cost: link.cost,
+                        tag: link.tag.clone(),
+                    });
+                }
+            }
+            links.navmeshes.push(definitions);
+        }
+        links
+    }
+
+    pub fn path(scene_path: &Path) -> PathBuf {
+        scene_path.with_extension("navlinks")
+    }
+
+    pub fn load(scene_path: &Path) -> Option<Self> {
+        let mut visitor = Visitor::load_binary(Self::path(scene_path)).ok()?;
+        let mut links = Self::default();
+        links.visit("NavmeshLinks", &mut visitor).ok()?;
+        Some(links)
+    }
+
+    /// Saves links next to the scene, or removes stale file if there are no links.
+    pub fn save(&mut self, scene_path: &Path) -> VisitResult {
+        let path = Self::path(scene_path);
+        if self
+            .navmeshes
+            .iter()
+            .all(|navmesh| navmesh.links.is_empty())
+        {
+            let _ = std::fs::remove_file(path);
+            Ok(())
+        } else {
+            let mut visitor = Visitor::new();
+            self.visit("NavmeshLinks", &mut visitor)?;
+            visitor.save_binary(path)
+        }
+    }
+
+    /// Adds links to navmeshes loaded from the scene, triangles of loaded navmeshes are in
+    /// the same order as in engine's navmeshes. Returns warnings for links that refer to
+    /// missing triangles, such links are skipped.
+    pub fn apply(self, navmeshes: &mut Pool<Navmesh>) -> Vec<String> {
+        let mut warnings = Vec::new();
+        for (i, (navmesh, definitions)) in navmeshes
+            .iter_mut()
+            .zip(self.navmeshes.into_iter())
+            .enumerate()
+        {
+            for (j, definition) in definitions.links.into_iter().enumerate() {
+                let triangle = |index: u32| {
+                    navmesh
+                        .triangles
+                        .pair_iter()
+                        .nth(index as usize)
+                        .map(|(handle, _)| handle)
+                };
+                match (
+                    triangle(definition.begin_triangle),
+                    triangle(definition.end_triangle),
+                ) {
+                    (Some(begin_triangle), Some(end_triangle)) => {
+                        navmesh.links.spawn(NavmeshLink {
+                            begin: definition.begin,
+                            end: definition.end,
+                            begin_triangle,
+                            end_triangle,
+                            bidirectional: definition.bidirectional,
+                            cost: definition.cost,
+                            tag: definition.tag,
+                        });
+                    }
+                    _ => warnings.push(format!(
+                        "Link {} of navmesh {} refers to missing triangle and was skipped.",
+                        j, i
+                    )),
+                }
+            }
+        }
+        warnings
+    }
+}
+
+/// Returns points of a parabolic arc between endpoints of a link, the arc rises above the
+/// highest endpoint so links going down a ledge are visible too.
+pub fn arc_points(link: &NavmeshLink) -> Vec<Vector3<f32>> {
+    let height = ((link.end - link.begin).norm() * 0.25).max(0.5);
+    let top = link.begin.y.max(link.end.y) + height;
+    // Height of the top above the middle of the straight line between endpoints.
+    let rise = top - (link.begin.y + link.end.y) * 0.5;
+    (0..=ARC_SEGMENTS)
+        .map(|i| {
+            let t = i as f32 / ARC_SEGMENTS as f32;
+            let mut point = link.begin.lerp(&link.end, t);
+            point.y += rise * 4.0 * t * (1.0 - t);
+            point
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn triangle_handles(navmesh: &Navmesh) -> Vec<Handle<NavmeshTriangle>> {
+        navmesh
+            .triangles
+            .pair_iter()
+            .map(|(handle, _)| handle)
+            .collect()
+    }
+
+    fn link(navmesh: &Navmesh, begin: usize, end: usize) -> NavmeshLink {
+        let triangles = triangle_handles(navmesh);
+        let mut link = NavmeshLink::new(
+            Vector3::new(-0.5, 0.0, 0.5),
+            triangles[begin],
+            Vector3::new(0.5, 0.0, -0.5),
+            triangles[end],
+        );
+        link.bidirectional = true;
+        link.tag = "Jump".to_owned();
+        link
+    }
+
+    #[test]
+    fn links_are_restored_on_navmeshes_of_loaded_scene() {
+        // Freed slot between navmeshes makes their handles differ from handles of navmeshes
+        // of loaded scene, only the order must matter.
+        let mut navmeshes = Pool::new();
+        navmeshes.spawn(Navmesh::new());
+        let removed = navmeshes.spawn(Navmesh::new());
+        let mut last = Navmesh::new();
+        let last_link = link(&last, 1, 0);
+        last.links.spawn(last_link);
+        navmeshes.spawn(last);
+        navmeshes.free(removed);
+
+        let mut warnings = Vec::new();
+        let path =
+            std::env::temp_dir().join(format!("rusty_editor_links_{}.rgs", std::process::id()));
+        NavmeshLinks::new(&navmeshes, &mut warnings)
+            .save(&path)
+            .unwrap();
+        let links = NavmeshLinks::load(&path).unwrap();
+        let _ = std::fs::remove_file(NavmeshLinks::path(&path));
+        assert!(warnings.is_empty());
+
+        let mut loaded = Pool::new();
+        loaded.spawn(Navmesh::new());
+        let last = loaded.spawn(Navmesh::new());
+        assert!(links.apply(&mut loaded).is_empty());
+
+        let navmesh = &loaded[last];
+        let triangles = triangle_handles(navmesh);
+        let restored = navmesh.links.iter().collect::<Vec<_>>();
+        assert_eq!(restored.len(), 1);
+        assert_eq!(restored[0].begin_triangle, triangles[1]);
+        assert_eq!(restored[0].end_triangle, triangles[0]);
+        assert_eq!(restored[0].begin, Vector3::new(-0.5, 0.0, 0.5));
+        assert_eq!(restored[0].end, Vector3::new(0.5, 0.0, -0.5));
+        assert!(restored[0].bidirectional);
+        assert_eq!(restored[0].tag, "Jump");
+        assert!(loaded.iter().next().unwrap().links.iter().next().is_none());
+    }
+
+    #[test]
+    fn link_of_removed_triangle_is_attached_to_closest_one() {
+        let mut navmesh = Navmesh::new();
+        let navmesh_link = link(&navmesh, 0, 1);
+        navmesh.links.spawn(navmesh_link);
+        let removed = triangle_handles(&navmesh)[0];
+        navmesh.triangles.free(removed);
+        let mut navmeshes = Pool::new();
+        navmeshes.spawn(navmesh);
+
+        let mut warnings = Vec::new();
+        let links = NavmeshLinks::new(&navmeshes, &mut warnings);
+
+        assert_eq!(warnings.len(), 1);
+        let definition = &links.navmeshes[0].links[0];
+        assert_eq!((definition.begin_triangle, definition.end_triangle), (0, 0));
+    }
+
+    #[test]
+    fn link_to_missing_triangle_is_skipped_on_load() {
+        let links = NavmeshLinks {
+            navmeshes: vec![NavmeshLinkDefinitions {
+                links: vec![NavmeshLinkDefinition {
+                    begin_triangle: 0,
+                    end_triangle: 2,
+                    ..Default::default()
+                }],
+            }],
+        };
+        let mut navmeshes = Pool::new();
+        let handle = navmeshes.spawn(Navmesh::new());
+
+        assert_eq!(links.apply(&mut navmeshes).len(), 1);
+        assert!(navmeshes[handle].links.iter().next().is_none());
+    }
+}
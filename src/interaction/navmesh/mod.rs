@@ -1,10 +1,26 @@
-use crate::scene::ConnectNavmeshEdgesCommand;
+use crate::scene::{
+    AddNavmeshLinkCommand, ConnectNavmeshEdgesCommand, DeleteNavmeshLinkCommand,
+    DeleteNavmeshTrianglesCommand, ExtrudeNavmeshTrianglesCommand, FlattenNavmeshTrianglesCommand,
+    FlipNavmeshEdgeCommand, SetNavmeshLinkCommand, SetNavmeshTrianglesAreaCommand,
+    SplitNavmeshEdgeCommand, SubdivideNavmeshTrianglesCommand, WeldNavmeshVerticesCommand,
+};
 use crate::{
-    gui::{BuildContext, UiMessage, UiNode},
+    gui::{BuildContext, Ui, UiMessage, UiNode},
     interaction::{
         calculate_gizmo_distance_scaling,
-        navmesh::data_model::{Navmesh, NavmeshEdge, NavmeshEntity, NavmeshVertex},
+        navmesh::areas::DEFAULT_AREA,
+        navmesh::data_model::{
+            Navmesh, NavmeshEdge, NavmeshEntity, NavmeshLink, NavmeshTriangle, NavmeshVertex,
+        },
+        navmesh::diagnostics::{find_problems, NavmeshProblem},
+        navmesh::generator::{
+            collect_static_geometry, generate_navmesh, NavmeshGenerationSettings,
+        },
+        navmesh::ground::GroundGeometry,
+        navmesh::links::arc_points,
+        navmesh::obj::{export_obj, import_obj},
         navmesh::selection::NavmeshSelection,
+        navmesh::topology,
         InteractionModeTrait, MoveGizmo,
     },
     scene::{
@@ -12,7 +28,9 @@ use crate::{
         DeleteNavmeshCommand, DeleteNavmeshVertexCommand, EditorScene, MoveNavmeshVertexCommand,
         SceneCommand, Selection,
     },
-    send_sync_message, GameEngine, Message, MSG_SYNC_FLAG,
+    send_sync_message,
+    sidebar::make_dropdown_list_option,
+    GameEngine, Message, MSG_SYNC_FLAG,
 };
 use rg3d::core::scope_profile;
 use rg3d::gui::stack_panel::StackPanelBuilder;
@@ -21,32 +39,103 @@ use rg3d::{
     core::{
         algebra::{Vector2, Vector3},
         color::Color,
-        math::ray::CylinderKind,
+        math::ray::{CylinderKind, Ray},
         pool::Handle,
     },
     gui::{
         border::BorderBuilder,
+        brush::Brush,
         button::ButtonBuilder,
         check_box::CheckBoxBuilder,
         decorator::DecoratorBuilder,
+        dropdown_list::DropdownListBuilder,
+        file_browser::{FileSelectorBuilder, Filter},
         grid::{Column, GridBuilder, Row},
         list_view::ListViewBuilder,
         message::{
-            ButtonMessage, KeyCode, ListViewMessage, MessageDirection, UiMessageData, WidgetMessage,
+            ButtonMessage, CheckBoxMessage, DropdownListMessage, FileSelectorMessage, KeyCode,
+            ListViewMessage, MessageDirection, NumericUpDownMessage, TextBoxMessage, UiMessageData,
+            WidgetMessage, WindowMessage,
         },
+        numeric::NumericUpDownBuilder,
         text::TextBuilder,
+        text_box::TextBoxBuilder,
         widget::WidgetBuilder,
         window::{WindowBuilder, WindowTitle},
+        wrap_panel::WrapPanelBuilder,
         Thickness, VerticalAlignment,
     },
-    scene::{camera::Camera, node::Node},
+    scene::{camera::Camera, node::Node, SceneDrawingContext},
+};
+use std::{
+    cell::RefCell,
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    path::Path,
+    rc::Rc,
+    sync::mpsc::Sender,
 };
-use std::{collections::HashMap, rc::Rc, sync::mpsc::Sender};
 
+pub mod areas;
 pub mod data_model;
+pub mod diagnostics;
+pub mod generator;
+pub mod ground;
+pub mod links;
+pub mod obj;
+pub mod path_test;
 pub mod selection;
+pub mod topology;
 
 const VERTEX_RADIUS: f32 = 0.2;
+const DEFAULT_WELD_TOLERANCE: f32 = 0.1;
+const DEFAULT_EXTRUDE_DISTANCE: f32 = 1.0;
+
+/// Names of generation settings in the order of their fields in the panel.
+const GENERATION_SETTINGS: [&str; 7] = [
+    "Cell Size",
+    "Cell Height",
+    "Agent Radius",
+    "Agent Height",
+    "Max Slope",
+    "Step Height",
+    "Edge Error",
+];
+
+fn generation_setting_mut(settings: &mut NavmeshGenerationSettings, index: usize) -> &mut f32 {
+    match index {
+        0 => &mut settings.cell_size,
+        1 => &mut settings.cell_height,
+        2 => &mut settings.agent_radius,
+        3 => &mut settings.agent_height,
+        4 => &mut settings.max_slope,
+        5 => &mut settings.step_height,
+        _ => &mut settings.max_edge_error,
+    }
+}
+
+fn make_obj_file_filter() -> Rc<RefCell<Filter>> {
+    Rc::new(RefCell::new(|p: &Path| {
+        if let Some(ext) = p.extension() {
+            ext.to_string_lossy().eq_ignore_ascii_case("obj")
+        } else {
+            p.is_dir()
+        }
+    }))
+}
+
+fn open_file_selector(ui: &Ui, file_selector: Handle<UiNode>) {
+    ui.send_message(WindowMessage::open_modal(
+        file_selector,
+        MessageDirection::ToWidget,
+        true,
+    ));
+    ui.send_message(FileSelectorMessage::root(
+        file_selector,
+        MessageDirection::ToWidget,
+        Some(std::env::current_dir().unwrap()),
+    ));
+}
 
 pub struct NavmeshPanel {
     pub window: Handle<UiNode>,
@@ -54,6 +143,40 @@ pub struct NavmeshPanel {
     add: Handle<UiNode>,
     connect: Handle<UiNode>,
     remove: Handle<UiNode>,
+    generate: Handle<UiNode>,
+    weld: Handle<UiNode>,
+    weld_tolerance_field: Handle<UiNode>,
+    split_edge: Handle<UiNode>,
+    subdivide: Handle<UiNode>,
+    flip_edge: Handle<UiNode>,
+    delete_triangles: Handle<UiNode>,
+    extrude: Handle<UiNode>,
+    extrude_distance_field: Handle<UiNode>,
+    flatten: Handle<UiNode>,
+    snap_to_ground: Handle<UiNode>,
+    conform_to_ground: Handle<UiNode>,
+    area: Handle<UiNode>,
+    paint_area: Handle<UiNode>,
+    import: Handle<UiNode>,
+    export: Handle<UiNode>,
+    check: Handle<UiNode>,
+    problem_list: Handle<UiNode>,
+    add_link: Handle<UiNode>,
+    link_bidirectional: Handle<UiNode>,
+    link_cost: Handle<UiNode>,
+    link_tag: Handle<UiNode>,
+    /// Tag that is typed in, but not committed yet - it is committed when the field loses
+    /// focus or Enter is pressed.
+    pending_link_tag: Option<String>,
+    problems: Vec<NavmeshProblem>,
+    import_file_selector: Handle<UiNode>,
+    export_file_selector: Handle<UiNode>,
+    selected_area: usize,
+    cached_area_names: Vec<String>,
+    weld_tolerance: f32,
+    extrude_distance: f32,
+    settings_fields: [Handle<UiNode>; GENERATION_SETTINGS.len()],
+    settings: NavmeshGenerationSettings,
     sender: Sender<Message>,
     selected: Handle<Navmesh>,
 }
@@ -64,6 +187,171 @@ impl NavmeshPanel {
         let remove;
         let navmeshes;
         let connect;
+        let generate;
+        let weld;
+        let weld_tolerance_field;
+        let split_edge;
+        let subdivide;
+        let flip_edge;
+        let delete_triangles;
+        let extrude;
+        let extrude_distance_field;
+        let flatten;
+        let snap_to_ground;
+        let conform_to_ground;
+        let area;
+        let paint_area;
+        let import;
+        let export;
+        let check;
+        let problem_list;
+        let add_link;
+        let link_bidirectional;
+        let link_cost;
+        let link_tag;
+
+        let mut settings = NavmeshGenerationSettings::default();
+        let mut settings_fields = [Handle::NONE; GENERATION_SETTINGS.len()];
+        let mut settings_children = Vec::new();
+        for (row, name) in GENERATION_SETTINGS.iter().enumerate() {
+            settings_children.push(
+                TextBuilder::new(
+                    WidgetBuilder::new()
+                        .with_vertical_alignment(VerticalAlignment::Center)
+                        .with_margin(Thickness::left(4.0))
+                        .on_row(row)
+                        .on_column(0),
+                )
+                .with_text(*name)
+                .build(ctx),
+            );
+            settings_fields[row] = NumericUpDownBuilder::new(
+                WidgetBuilder::new()
+                    .with_margin(Thickness::uniform(1.0))
+                    .on_row(row)
+                    .on_column(1),
+            )
+            .with_min_value(0.0)
+            .with_step(0.05)
+            .with_value(*generation_setting_mut(&mut settings, row))
+            .build(ctx);
+            settings_children.push(settings_fields[row]);
+        }
+        let mut settings_grid = GridBuilder::new(
+            WidgetBuilder::new()
+                .on_row(4)
+                .with_children(&settings_children),
+        )
+        .add_column(Column::strict(100.0))
+        .add_column(Column::stretch());
+        for _ in 0..GENERATION_SETTINGS.len() {
+            settings_grid = settings_grid.add_row(Row::strict(22.0));
+        }
+        let settings_grid = settings_grid.build(ctx);
+
+        let mut tool_button = |text: &str| {
+            ButtonBuilder::new(WidgetBuilder::new().with_margin(Thickness::uniform(1.0)))
+                .with_text(text)
+                .build(ctx)
+        };
+        weld = tool_button("Weld");
+        split_edge = tool_button("Split Edge");
+        subdivide = tool_button("Subdivide");
+        flip_edge = tool_button("Flip Edge");
+        delete_triangles = tool_button("Delete Triangles");
+        extrude = tool_button("Extrude");
+        flatten = tool_button("Flatten");
+        conform_to_ground = tool_button("Conform To Ground");
+        check = tool_button("Check");
+        add_link = tool_button("Add Link");
+        link_bidirectional =
+            CheckBoxBuilder::new(WidgetBuilder::new().with_margin(Thickness::uniform(1.0)))
+                .with_content(
+                    TextBuilder::new(
+                        WidgetBuilder::new()
+                            .with_margin(Thickness::uniform(1.0))
+                            .with_vertical_alignment(VerticalAlignment::Center),
+                    )
+                    .with_text("Bidirectional")
+                    .build(ctx),
+                )
+                .checked(Some(false))
+                .build(ctx);
+        link_cost = NumericUpDownBuilder::new(
+            WidgetBuilder::new()
+                .with_width(60.0)
+                .with_margin(Thickness::uniform(1.0)),
+        )
+        .with_min_value(0.0)
+        .with_step(0.1)
+        .build(ctx);
+        link_tag = TextBoxBuilder::new(
+            WidgetBuilder::new()
+                .with_width(100.0)
+                .with_margin(Thickness::uniform(1.0)),
+        )
+        .build(ctx);
+        paint_area = tool_button("Paint Area");
+        area = DropdownListBuilder::new(
+            WidgetBuilder::new()
+                .with_width(100.0)
+                .with_margin(Thickness::uniform(1.0)),
+        )
+        .with_close_on_selection(true)
+        .build(ctx);
+        snap_to_ground =
+            CheckBoxBuilder::new(WidgetBuilder::new().with_margin(Thickness::uniform(1.0)))
+                .with_content(
+                    TextBuilder::new(
+                        WidgetBuilder::new()
+                            .with_margin(Thickness::uniform(1.0))
+                            .with_vertical_alignment(VerticalAlignment::Center),
+                    )
+                    .with_text("Snap To Ground")
+                    .build(ctx),
+                )
+                .checked(Some(false))
+                .build(ctx);
+        weld_tolerance_field = NumericUpDownBuilder::new(
+            WidgetBuilder::new()
+                .with_width(60.0)
+                .with_margin(Thickness::uniform(1.0)),
+        )
+        .with_min_value(0.0)
+        .with_step(0.05)
+        .with_value(DEFAULT_WELD_TOLERANCE)
+        .build(ctx);
+        extrude_distance_field = NumericUpDownBuilder::new(
+            WidgetBuilder::new()
+                .with_width(60.0)
+                .with_margin(Thickness::uniform(1.0)),
+        )
+        .with_step(0.1)
+        .with_value(DEFAULT_EXTRUDE_DISTANCE)
+        .build(ctx);
+        let tools = WrapPanelBuilder::new(WidgetBuilder::new().on_row(3).with_children(&[
+            weld,
+            weld_tolerance_field,
+            split_edge,
+            subdivide,
+            flip_edge,
+            delete_triangles,
+            extrude,
+            extrude_distance_field,
+            flatten,
+            conform_to_ground,
+            snap_to_ground,
+            area,
+            paint_area,
+            check,
+            add_link,
+            link_bidirectional,
+            link_cost,
+            link_tag,
+        ]))
+        .with_orientation(Orientation::Horizontal)
+        .build(ctx);
+
         let window = WindowBuilder::new(WidgetBuilder::new())
             .with_title(WindowTitle::text("Navmesh"))
             .with_content(
@@ -100,6 +388,33 @@ impl NavmeshPanel {
                                         .with_text("Connect")
                                         .build(ctx);
                                         connect
+                                    })
+                                    .with_child({
+                                        generate = ButtonBuilder::new(
+                                            WidgetBuilder::new()
+                                                .with_margin(Thickness::uniform(1.0)),
+                                        )
+                                        .with_text("Generate")
+                                        .build(ctx);
+                                        generate
+                                    })
+                                    .with_child({
+                                        import = ButtonBuilder::new(
+                                            WidgetBuilder::new()
+                                                .with_margin(Thickness::uniform(1.0)),
+                                        )
+                                        .with_text("Import OBJ")
+                                        .build(ctx);
+                                        import
+                                    })
+                                    .with_child({
+                                        export = ButtonBuilder::new(
+                                            WidgetBuilder::new()
+                                                .with_margin(Thickness::uniform(1.0)),
+                                        )
+                                        .with_text("Export OBJ")
+                                        .build(ctx);
+                                        export
                                     }),
                             )
                             .with_orientation(Orientation::Horizontal)
@@ -139,16 +454,43 @@ impl NavmeshPanel {
                             .add_column(Column::stretch())
                             .add_column(Column::stretch())
                             .build(ctx),
-                        ),
+                        )
+                        .with_child(tools)
+                        .with_child(settings_grid)
+                        .with_child({
+                            problem_list =
+                                ListViewBuilder::new(WidgetBuilder::new().on_row(5)).build(ctx);
+                            problem_list
+                        }),
                 )
                 .add_column(Column::stretch())
                 .add_row(Row::strict(20.0))
                 .add_row(Row::stretch())
                 .add_row(Row::strict(24.0))
+                .add_row(Row::auto())
+                .add_row(Row::auto())
+                .add_row(Row::strict(100.0))
                 .build(ctx),
             )
             .build(ctx);
 
+        let import_file_selector = FileSelectorBuilder::new(
+            WindowBuilder::new(WidgetBuilder::new().with_width(300.0).with_height(400.0))
+                .open(false)
+                .with_title(WindowTitle::text("Import Navmesh")),
+        )
+        .with_filter(make_obj_file_filter())
+        .build(ctx);
+
+        let export_file_selector = FileSelectorBuilder::new(
+            WindowBuilder::new(WidgetBuilder::new().with_width(300.0).with_height(400.0))
+                .open(false)
+                .with_title(WindowTitle::text("Export Navmesh")),
+        )
+        .with_path("./navmesh.obj")
+        .with_filter(make_obj_file_filter())
+        .build(ctx);
+
         Self {
             window,
             sender,
@@ -156,6 +498,38 @@ impl NavmeshPanel {
             remove,
             navmeshes,
             connect,
+            generate,
+            weld,
+            weld_tolerance_field,
+            split_edge,
+            subdivide,
+            flip_edge,
+            delete_triangles,
+            extrude,
+            extrude_distance_field,
+            flatten,
+            snap_to_ground,
+            conform_to_ground,
+            area,
+            paint_area,
+            import,
+            export,
+            check,
+            problem_list,
+            add_link,
+            link_bidirectional,
+            link_cost,
+            link_tag,
+            pending_link_tag: None,
+            problems: Default::default(),
+            import_file_selector,
+            export_file_selector,
+            selected_area: DEFAULT_AREA,
+            cached_area_names: Default::default(),
+            weld_tolerance: DEFAULT_WELD_TOLERANCE,
+            extrude_distance: DEFAULT_EXTRUDE_DISTANCE,
+            settings_fields,
+            settings,
             selected: Default::default(),
         }
     }
@@ -184,8 +558,56 @@ impl NavmeshPanel {
             })
             .collect::<Vec<_>>();
 
+        let problem_items = self
+            .problems
+            .iter()
+            .map(|problem| {
+                DecoratorBuilder::new(BorderBuilder::new(
+                    WidgetBuilder::new().with_height(22.0).with_child(
+                        TextBuilder::new(
+                            WidgetBuilder::new()
+                                .with_foreground(Brush::Solid(problem.kind.color())),
+                        )
+                        .with_text(problem.kind.name())
+                        .build(ctx),
+                    ),
+                ))
+                .build(ctx)
+            })
+            .collect::<Vec<_>>();
+
+        let area_names = editor_scene
+            .navmesh_areas
+            .areas
+            .iter()
+            .map(|area| area.name.clone())
+            .collect::<Vec<_>>();
+        if self.cached_area_names != area_names {
+            let area_items = area_names
+                .iter()
+                .map(|name| make_dropdown_list_option(ctx, name))
+                .collect::<Vec<_>>();
+            send_sync_message(
+                &engine.user_interface,
+                DropdownListMessage::items(self.area, MessageDirection::ToWidget, area_items),
+            );
+            self.cached_area_names = area_names;
+        }
+        self.selected_area = self
+            .selected_area
+            .min(editor_scene.navmesh_areas.areas.len().saturating_sub(1));
+
         let ui = &mut engine.user_interface;
 
+        send_sync_message(
+            ui,
+            DropdownListMessage::selection(
+                self.area,
+                MessageDirection::ToWidget,
+                Some(self.selected_area),
+            ),
+        );
+
         let new_selection = if let Selection::Navmesh(selection) = &editor_scene.selection {
             let selected_vertex_count = selection
                 .entities()
@@ -202,15 +624,99 @@ impl NavmeshPanel {
                 ),
             );
 
+            let (vertices, edge, triangles) =
+                if let Some(navmesh) = editor_scene.navmeshes.try_borrow(selection.navmesh()) {
+                    let vertices = selected_vertices(selection, navmesh);
+                    let triangles = selected_triangles(selection, navmesh);
+                    (vertices.len(), selected_edge(selection), triangles.len())
+                } else {
+                    (0, None, 0)
+                };
+            for &(button, enabled) in [
+                (self.weld, vertices >= 2),
+                (self.split_edge, edge.is_some()),
+                (self.flip_edge, edge.is_some()),
+                (self.subdivide, triangles > 0),
+                (self.delete_triangles, triangles > 0),
+                (self.extrude, triangles > 0),
+                (self.flatten, triangles > 0),
+                (self.paint_area, triangles > 0),
+            ]
+            .iter()
+            {
+                send_sync_message(
+                    ui,
+                    WidgetMessage::enabled(button, MessageDirection::ToWidget, enabled),
+                );
+            }
+
+            let link = selected_link(selection).and_then(|link| {
+                editor_scene
+                    .navmeshes
+                    .try_borrow(selection.navmesh())
+                    .and_then(|navmesh| navmesh.links.try_borrow(link))
+            });
+            for &widget in [self.link_bidirectional, self.link_cost, self.link_tag].iter() {
+                send_sync_message(
+                    ui,
+                    WidgetMessage::enabled(widget, MessageDirection::ToWidget, link.is_some()),
+                );
+            }
+            if let Some(link) = link {
+                send_sync_message(
+                    ui,
+                    CheckBoxMessage::checked(
+                        self.link_bidirectional,
+                        MessageDirection::ToWidget,
+                        Some(link.bidirectional),
+                    ),
+                );
+                send_sync_message(
+                    ui,
+                    NumericUpDownMessage::value(
+                        self.link_cost,
+                        MessageDirection::ToWidget,
+                        link.cost,
+                    ),
+                );
+                if self.pending_link_tag.is_none() {
+                    send_sync_message(
+                        ui,
+                        TextBoxMessage::text(
+                            self.link_tag,
+                            MessageDirection::ToWidget,
+                            link.tag.clone(),
+                        ),
+                    );
+                }
+            }
+
             editor_scene
                 .navmeshes
                 .pair_iter()
                 .position(|(i, _)| i == selection.navmesh())
         } else {
-            send_sync_message(
-                ui,
-                WidgetMessage::enabled(self.connect, MessageDirection::ToWidget, false),
-            );
+            for &button in [
+                self.connect,
+                self.weld,
+                self.split_edge,
+                self.flip_edge,
+                self.subdivide,
+                self.delete_triangles,
+                self.extrude,
+                self.flatten,
+                self.paint_area,
+                self.link_bidirectional,
+                self.link_cost,
+                self.link_tag,
+            ]
+            .iter()
+            {
+                send_sync_message(
+                    ui,
+                    WidgetMessage::enabled(button, MessageDirection::ToWidget, false),
+                );
+            }
 
             None
         };
@@ -226,11 +732,19 @@ impl NavmeshPanel {
             items,
         ));
 
-        engine.user_interface.send_message(WidgetMessage::enabled(
-            self.remove,
+        engine.user_interface.send_message(ListViewMessage::items(
+            self.problem_list,
             MessageDirection::ToWidget,
-            editor_scene.navmeshes.is_valid_handle(self.selected),
+            problem_items,
         ));
+
+        for &widget in [self.remove, self.export, self.check, self.add_link].iter() {
+            engine.user_interface.send_message(WidgetMessage::enabled(
+                widget,
+                MessageDirection::ToWidget,
+                editor_scene.navmeshes.is_valid_handle(self.selected),
+            ));
+        }
     }
 
     pub fn handle_message(
@@ -258,6 +772,43 @@ impl NavmeshPanel {
                             )))
                             .unwrap();
                     }
+                } else if message.destination() == self.generate {
+                    let graph = &engine.scenes[editor_scene.scene].graph;
+                    let triangles =
+                        collect_static_geometry(editor_scene.root, graph, &editor_scene.physics);
+                    match generate_navmesh(&triangles, &self.settings) {
+                        Ok((navmesh, partial_regions))
+                            if navmesh.triangles.iter().next().is_some() =>
+                        {
+                            if partial_regions > 0 {
+                                self.sender
+                                    .send(Message::Log(format!(
+                                        "Outlines of {} region(s) are self-intersecting, they \
+                                         were triangulated partially and need manual editing.",
+                                        partial_regions
+                                    )))
+                                    .unwrap();
+                            }
+                            self.sender
+                                .send(Message::DoSceneCommand(SceneCommand::AddNavmesh(
+                                    AddNavmeshCommand::new(navmesh),
+                                )))
+                                .unwrap();
+                        }
+                        Ok(_) => {
+                            self.sender
+                                .send(Message::Log(
+                                    "Unable to generate navmesh: no walkable surfaces found."
+                                        .to_owned(),
+                                ))
+                                .unwrap();
+                        }
+                        Err(e) => {
+                            self.sender
+                                .send(Message::Log(format!("Unable to generate navmesh: {}", e)))
+                                .unwrap();
+                        }
+                    }
                 } else if message.destination() == self.connect {
                     if let Selection::Navmesh(selection) = &editor_scene.selection {
                         let vertices = selection
@@ -281,8 +832,125 @@ impl NavmeshPanel {
                             )))
                             .unwrap();
                     }
+                } else if message.destination() == self.import {
+                    open_file_selector(&engine.user_interface, self.import_file_selector);
+                } else if message.destination() == self.export {
+                    open_file_selector(&engine.user_interface, self.export_file_selector);
+                } else if message.destination() == self.check {
+                    if let Some(navmesh) = editor_scene.navmeshes.try_borrow(self.selected) {
+                        self.problems = find_problems(navmesh);
+                        edit_mode.problems = self.problems.clone();
+                        if self.problems.is_empty() {
+                            self.sender
+                                .send(Message::Log("No navmesh problems found.".to_owned()))
+                                .unwrap();
+                        }
+                        self.sender.send(Message::SyncToModel).unwrap();
+                    }
+                } else if message.destination() == self.add_link {
+                    if editor_scene.navmeshes.is_valid_handle(self.selected) {
+                        edit_mode.begin_link_placement();
+                    }
+                } else if message.destination() == self.conform_to_ground {
+                    self.conform_to_ground(editor_scene, engine);
+                } else if let Selection::Navmesh(selection) = &editor_scene.selection {
+                    self.handle_topology_tool(message.destination(), selection, editor_scene);
+                }
+            }
+            UiMessageData::FileSelector(FileSelectorMessage::Commit(path)) => {
+                if message.destination() == self.import_file_selector {
+                    match import_obj(path) {
+                        Ok(navmesh) => self
+                            .sender
+                            .send(Message::DoSceneCommand(SceneCommand::AddNavmesh(
+                                AddNavmeshCommand::new(navmesh),
+                            )))
+                            .unwrap(),
+                        Err(e) => self
+                            .sender
+                            .send(Message::Log(format!(
+                                "Unable to import navmesh from {}: {}",
+                                path.display(),
+                                e
+                            )))
+                            .unwrap(),
+                    }
+                } else if message.destination() == self.export_file_selector {
+                    if let Some(navmesh) = editor_scene.navmeshes.try_borrow(self.selected) {
+                        let mut path = path.clone();
+                        if path.extension().is_none() {
+                            path.set_extension("obj");
+                        }
+                        if let Err(e) = export_obj(navmesh, &path) {
+                            self.sender
+                                .send(Message::Log(format!(
+                                    "Unable to export navmesh to {}: {}",
+                                    path.display(),
+                                    e
+                                )))
+                                .unwrap();
+                        }
+                    }
+                }
+            }
+            UiMessageData::DropdownList(DropdownListMessage::SelectionChanged(Some(area))) => {
+                if message.destination() == self.area
+                    && message.direction() == MessageDirection::FromWidget
+                {
+                    self.selected_area = *area;
+                }
+            }
+            UiMessageData::CheckBox(CheckBoxMessage::Check(value)) => {
+                if message.direction() == MessageDirection::FromWidget {
+                    if message.destination() == self.snap_to_ground {
+                        edit_mode.snap_to_ground = value.unwrap_or_default();
+                    } else if message.destination() == self.link_bidirectional {
+                        let bidirectional = value.unwrap_or_default();
+                        self.modify_selected_link(editor_scene, |link| {
+                            link.bidirectional = bidirectional
+                        });
+                    }
+                }
+            }
+            UiMessageData::TextBox(TextBoxMessage::Text(value)) => {
+                if message.destination() == self.link_tag
+                    && message.direction() == MessageDirection::FromWidget
+                {
+                    self.pending_link_tag = Some(value.clone());
+                }
+            }
+            UiMessageData::Widget(WidgetMessage::LostFocus)
+            | UiMessageData::Widget(WidgetMessage::KeyDown(KeyCode::Return))
+                if message.destination() == self.link_tag =>
+            {
+                if let Some(tag) = self.pending_link_tag.take() {
+                    self.modify_selected_link(editor_scene, |link| link.tag = tag);
+                }
+            }
+            UiMessageData::NumericUpDown(NumericUpDownMessage::Value(value)) => {
+                if message.direction() == MessageDirection::FromWidget {
+                    if message.destination() == self.weld_tolerance_field {
+                        self.weld_tolerance = *value;
+                    } else if message.destination() == self.extrude_distance_field {
+                        self.extrude_distance = *value;
+                    } else if message.destination() == self.link_cost {
+                        let cost = *value;
+                        self.modify_selected_link(editor_scene, |link| link.cost = cost);
+                    } else if let Some(index) = self
+                        .settings_fields
+                        .iter()
+                        .position(|&field| field == message.destination())
+                    {
+                        *generation_setting_mut(&mut self.settings, index) = *value;
+                    }
                 }
             }
+            UiMessageData::ListView(ListViewMessage::SelectionChanged(Some(index)))
+                if message.destination() == self.problem_list
+                    && message.direction() == MessageDirection::FromWidget =>
+            {
+                self.select_problem(*index, editor_scene);
+            }
             UiMessageData::ListView(ListViewMessage::SelectionChanged(selection)) => {
                 if message.destination() == self.navmeshes
                     && message.direction() == MessageDirection::FromWidget
@@ -302,11 +970,21 @@ impl NavmeshPanel {
                         self.selected = new_selection;
                         edit_mode.navmesh = self.selected;
 
-                        engine.user_interface.send_message(WidgetMessage::enabled(
-                            self.remove,
-                            MessageDirection::ToWidget,
-                            editor_scene.navmeshes.is_valid_handle(self.selected),
-                        ));
+                        // Problems belong to previous navmesh.
+                        if !self.problems.is_empty() {
+                            self.problems.clear();
+                            edit_mode.problems.clear();
+                            self.sender.send(Message::SyncToModel).unwrap();
+                        }
+
+                        for &widget in [self.remove, self.export, self.check, self.add_link].iter()
+                        {
+                            engine.user_interface.send_message(WidgetMessage::enabled(
+                                widget,
+                                MessageDirection::ToWidget,
+                                editor_scene.navmeshes.is_valid_handle(self.selected),
+                            ));
+                        }
 
                         if !message.has_flags(MSG_SYNC_FLAG) {
                             let new_selection =
@@ -329,11 +1007,404 @@ impl NavmeshPanel {
             _ => {}
         }
     }
+
+    /// Changes the only selected link, nothing is done if the change does not modify it.
+    fn modify_selected_link<F: FnOnce(&mut NavmeshLink)>(
+        &self,
+        editor_scene: &EditorScene,
+        modify: F,
+    ) {
+        if let Selection::Navmesh(selection) = &editor_scene.selection {
+            if let Some(handle) = selected_link(selection) {
+                if let Some(link) = editor_scene
+                    .navmeshes
+                    .try_borrow(selection.navmesh())
+                    .and_then(|navmesh| navmesh.links.try_borrow(handle))
+                {
+                    let mut new_link = link.clone();
+                    modify(&mut new_link);
+                    if new_link != *link {
+                        self.sender
+                            .send(Message::DoSceneCommand(SceneCommand::SetNavmeshLink(
+                                SetNavmeshLinkCommand::new(selection.navmesh(), handle, new_link),
+                            )))
+                            .unwrap();
+                    }
+                }
+            }
+        }
+    }
+
+    /// Selects vertex or edge of a problem, problems found before recent edits may refer to
+    /// removed vertices and are ignored then.
+    fn select_problem(&self, index: usize, editor_scene: &EditorScene) {
+        let (problem, navmesh) = match (
+            self.problems.get(index),
+            editor_scene.navmeshes.try_borrow(self.selected),
+        ) {
+            (Some(problem), Some(navmesh)) => (problem, navmesh),
+            _ => return,
+        };
+
+        let is_valid = match problem.entity {
+            NavmeshEntity::Vertex(vertex) => navmesh.vertices.is_valid_handle(vertex),
+            NavmeshEntity::Edge(edge) => {
+                navmesh.vertices.is_valid_handle(edge.begin)
+                    && navmesh.vertices.is_valid_handle(edge.end)
+            }
+            NavmeshEntity::Triangle(triangle) => navmesh.triangles.is_valid_handle(triangle),
+            NavmeshEntity::Link(link) => navmesh.links.is_valid_handle(link),
+        };
+        if !is_valid {
+            return;
+        }
+
+        let new_selection =
+            Selection::Navmesh(NavmeshSelection::new(self.selected, vec![problem.entity]));
+        if new_selection != editor_scene.selection {
+            self.sender
+                .send(Message::DoSceneCommand(SceneCommand::ChangeSelection(
+                    ChangeSelectionCommand::new(new_selection, editor_scene.selection.clone()),
+                )))
+                .unwrap();
+        }
+    }
+
+    /// Projects every vertex of selected navmesh onto scene geometry below it, vertices with
+    /// nothing below stay in place.
+    fn conform_to_ground(&self, editor_scene: &EditorScene, engine: &GameEngine) {
+        let navmesh = match editor_scene.navmeshes.try_borrow(self.selected) {
+            Some(navmesh) => navmesh,
+            None => return,
+        };
+
+        let ground =
+            GroundGeometry::from_graph(editor_scene.root, &engine.scenes[editor_scene.scene].graph);
+        let commands = navmesh
+            .vertices
+            .pair_iter()
+            .filter_map(|(handle, vertex)| {
+                ground
+                    .project(vertex.position)
+                    .filter(|&position| position != vertex.position)
+                    .map(|position| {
+                        SceneCommand::MoveNavmeshVertex(MoveNavmeshVertexCommand::new(
+                            self.selected,
+                            handle,
+                            vertex.position,
+                            position,
+                        ))
+                    })
+            })
+            .collect::<Vec<_>>();
+
+        if !commands.is_empty() {
+            self.sender
+                .send(Message::DoSceneCommand(SceneCommand::CommandGroup(
+                    CommandGroup::from(commands),
+                )))
+                .unwrap();
+        }
+    }
+
+    fn handle_topology_tool(
+        &self,
+        button: Handle<UiNode>,
+        selection: &NavmeshSelection,
+        editor_scene: &EditorScene,
+    ) {
+        let navmesh_handle = selection.navmesh();
+        let navmesh = match editor_scene.navmeshes.try_borrow(navmesh_handle) {
+            Some(navmesh) => navmesh,
+            None => return,
+        };
+
+        let command = if button == self.weld {
+            let vertices = selected_vertices(selection, navmesh)
+                .into_iter()
+                .collect::<Vec<_>>();
+            let plan = topology::weld_vertices(navmesh, &vertices, self.weld_tolerance);
+            if plan.is_empty() {
+                self.sender
+                    .send(Message::Log(
+                        "Nothing to weld: no selected vertices are within tolerance.".to_owned(),
+                    ))
+                    .unwrap();
+                return;
+            }
+
+            // Welded vertices are removed, so they must be removed from selection too.
+            let remaining = vertices
+                .iter()
+                .filter(|v| !plan.removed_vertices.contains(v))
+                .map(|&v| NavmeshEntity::Vertex(v))
+                .collect();
+            SceneCommand::CommandGroup(CommandGroup::from(vec![
+                SceneCommand::ChangeSelection(ChangeSelectionCommand::new(
+                    Selection::Navmesh(NavmeshSelection::new(navmesh_handle, remaining)),
+                    editor_scene.selection.clone(),
+                )),
+                SceneCommand::WeldNavmeshVertices(WeldNavmeshVerticesCommand::new(
+                    navmesh_handle,
+                    vertices,
+                    self.weld_tolerance,
+                )),
+            ]))
+        } else if button == self.split_edge {
+            match selected_edge(selection) {
+                Some(edge) => SceneCommand::SplitNavmeshEdge(SplitNavmeshEdgeCommand::new(
+                    navmesh_handle,
+                    edge,
+                )),
+                None => return,
+            }
+        } else if button == self.flip_edge {
+            match selected_edge(selection) {
+                Some(edge) if topology::flip_edge(navmesh, edge).is_some() => {
+                    SceneCommand::FlipNavmeshEdge(FlipNavmeshEdgeCommand::new(navmesh_handle, edge))
+                }
+                Some(_) => {
+                    self.sender
+                        .send(Message::Log(
+                            "Unable to flip edge: it must be shared by two triangles forming a convex quad."
+                                .to_owned(),
+                        ))
+                        .unwrap();
+                    return;
+                }
+                None => return,
+            }
+        } else if [
+            self.subdivide,
+            self.delete_triangles,
+            self.extrude,
+            self.flatten,
+            self.paint_area,
+        ]
+        .contains(&button)
+        {
+            let triangles = selected_triangles(selection, navmesh);
+            if triangles.is_empty() {
+                return;
+            }
+            if button == self.subdivide {
+                SceneCommand::SubdivideNavmeshTriangles(SubdivideNavmeshTrianglesCommand::new(
+                    navmesh_handle,
+                    triangles,
+                ))
+            } else if button == self.extrude {
+                SceneCommand::ExtrudeNavmeshTriangles(ExtrudeNavmeshTrianglesCommand::new(
+                    navmesh_handle,
+                    triangles,
+                    self.extrude_distance,
+                ))
+            } else if button == self.paint_area {
+                SceneCommand::SetNavmeshTrianglesArea(SetNavmeshTrianglesAreaCommand::new(
+                    navmesh_handle,
+                    triangles,
+                    self.selected_area,
+                ))
+            } else if button == self.flatten {
+                SceneCommand::FlattenNavmeshTriangles(FlattenNavmeshTrianglesCommand::new(
+                    navmesh_handle,
+                    triangles,
+                ))
+            } else {
+                delete_triangles_command(navmesh_handle, triangles, &editor_scene.selection)
+            }
+        } else {
+            return;
+        };
+
+        self.sender.send(Message::DoSceneCommand(command)).unwrap();
+    }
+}
+
+/// Returns every vertex of selected entities.
+fn selected_vertices(
+    selection: &NavmeshSelection,
+    navmesh: &Navmesh,
+) -> HashSet<Handle<NavmeshVertex>> {
+    let mut vertices = HashSet::new();
+    for entity in selection.entities() {
+        match *entity {
+            NavmeshEntity::Vertex(vertex) => {
+                vertices.insert(vertex);
+            }
+            NavmeshEntity::Edge(edge) => {
+                vertices.insert(edge.begin);
+                vertices.insert(edge.end);
+            }
+            NavmeshEntity::Triangle(triangle) => {
+                if let Some(triangle) = navmesh.triangles.try_borrow(triangle) {
+                    vertices.extend(triangle.vertices().iter());
+                }
+            }
+            NavmeshEntity::Link(_) => (),
+        }
+    }
+    vertices
+}
+
+/// Returns selected triangles, if there are none - every triangle which vertices are all
+/// selected, so face tools work on vertex selection too.
+fn selected_triangles(
+    selection: &NavmeshSelection,
+    navmesh: &Navmesh,
+) -> Vec<Handle<NavmeshTriangle>> {
+    let triangles = selection
+        .entities()
+        .iter()
+        .filter_map(|entity| match *entity {
+            NavmeshEntity::Triangle(triangle) if navmesh.triangles.is_valid_handle(triangle) => {
+                Some(triangle)
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    if triangles.is_empty() {
+        topology::triangles_of_vertices(navmesh, &selected_vertices(selection, navmesh))
+    } else {
+        triangles
+    }
+}
+
+/// Deletes triangles and removes them from selection, so it won't point to dead triangles.
+fn delete_triangles_command(
+    navmesh: Handle<Navmesh>,
+    triangles: Vec<Handle<NavmeshTriangle>>,
+    current_selection: &Selection,
+) -> SceneCommand {
+    let mut new_selection = NavmeshSelection::empty(navmesh);
+    if let Selection::Navmesh(selection) = current_selection {
+        for entity in selection.entities() {
+            match entity {
+                NavmeshEntity::Triangle(triangle) if triangles.contains(triangle) => (),
+                _ => new_selection.add(*entity),
+            }
+        }
+    }
+
+    SceneCommand::CommandGroup(CommandGroup::from(vec![
+        SceneCommand::ChangeSelection(ChangeSelectionCommand::new(
+            Selection::Navmesh(new_selection),
+            current_selection.clone(),
+        )),
+        SceneCommand::DeleteNavmeshTriangles(DeleteNavmeshTrianglesCommand::new(
+            navmesh, triangles,
+        )),
+    ]))
+}
+
+/// Returns distance along the ray to its intersection with a triangle.
+fn ray_triangle_intersection(ray: &Ray, triangle: [Vector3<f32>; 3]) -> Option<f32> {
+    let ab = triangle[1] - triangle[0];
+    let ac = triangle[2] - triangle[0];
+    let p = ray.dir.cross(&ac);
+    let determinant = ab.dot(&p);
+    if determinant.abs() <= std::f32::EPSILON {
+        return None;
+    }
+    let t = ray.origin - triangle[0];
+    let u = t.dot(&p) / determinant;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+    let q = t.cross(&ab);
+    let v = ray.dir.dot(&q) / determinant;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+    let distance = ac.dot(&q) / determinant;
+    if distance >= 0.0 {
+        Some(distance)
+    } else {
+        None
+    }
+}
+
+/// Returns triangle hit by the ray closest to ray's origin and distance to it along the ray.
+pub fn pick_triangle(navmesh: &Navmesh, ray: &Ray) -> Option<(Handle<NavmeshTriangle>, f32)> {
+    navmesh
+        .triangles
+        .pair_iter()
+        .filter_map(|(handle, triangle)| {
+            ray_triangle_intersection(
+                ray,
+                [
+                    navmesh.vertices[triangle.a].position,
+                    navmesh.vertices[triangle.b].position,
+                    navmesh.vertices[triangle.c].position,
+                ],
+            )
+            .map(|distance| (handle, distance))
+        })
+        .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+}
+
+/// Returns selected edge if it is the only selected entity.
+/// Draws arc of a link with arrows showing directions it can be traversed in.
+fn draw_link(drawing_context: &mut SceneDrawingContext, link: &NavmeshLink, color: Color) {
+    let points = arc_points(link);
+    for segment in points.windows(2) {
+        drawing_context.add_line(rg3d::scene::Line {
+            begin: segment[0],
+            end: segment[1],
+            color,
+        });
+    }
+
+    let mut draw_arrow = |tip: Vector3<f32>, from: Vector3<f32>| {
+        let dir = (tip - from)
+            .try_normalize(std::f32::EPSILON)
+            .unwrap_or_default();
+        let side = dir
+            .cross(&Vector3::y())
+            .try_normalize(std::f32::EPSILON)
+            .unwrap_or_else(Vector3::x);
+        for &sign in &[-1.0, 1.0] {
+            drawing_context.add_line(rg3d::scene::Line {
+                begin: tip,
+                end: tip - dir.scale(0.3) + side.scale(0.15 * sign),
+                color,
+            });
+        }
+    };
+    let n = points.len();
+    draw_arrow(points[n - 1], points[n - 2]);
+    if link.bidirectional {
+        draw_arrow(points[0], points[1]);
+    }
+
+    for &point in &[link.begin, link.end] {
+        drawing_context.draw_sphere(point, 6, 6, VERTEX_RADIUS * 0.5, color);
+    }
+}
+
+/// Returns the link if it is the only selected entity.
+fn selected_link(selection: &NavmeshSelection) -> Option<Handle<NavmeshLink>> {
+    match selection.entities() {
+        [NavmeshEntity::Link(link)] => Some(*link),
+        _ => None,
+    }
+}
+
+fn selected_edge(selection: &NavmeshSelection) -> Option<NavmeshEdge> {
+    match selection.entities() {
+        [NavmeshEntity::Edge(edge)] => Some(*edge),
+        _ => None,
+    }
 }
 
 enum DragContext {
     MoveSelection {
         initial_positions: HashMap<Handle<NavmeshVertex>, Vector3<f32>>,
+        /// Total offset since the drag has started, vertices are placed relative to their
+        /// initial positions so snapping does not accumulate errors.
+        offset: Vector3<f32>,
+        /// Scene geometry to snap vertices to, collected once per drag.
+        ground: Option<GroundGeometry>,
     },
     EdgeDuplication {
         vertices: [NavmeshVertex; 2],
@@ -347,11 +1418,25 @@ impl DragContext {
     }
 }
 
+/// Links are placed by two clicks on the navmesh - at the begin and at the end.
+enum LinkPlacement {
+    Begin,
+    End {
+        begin: Vector3<f32>,
+        begin_triangle: Handle<NavmeshTriangle>,
+    },
+}
+
 pub struct EditNavmeshMode {
     navmesh: Handle<Navmesh>,
+    /// Moved vertices are projected onto scene geometry below them.
+    pub snap_to_ground: bool,
+    /// Problems found by the last check of the navmesh, they're highlighted in the viewport.
+    pub problems: Vec<NavmeshProblem>,
     move_gizmo: MoveGizmo,
     message_sender: Sender<Message>,
     drag_context: Option<DragContext>,
+    link_placement: Option<LinkPlacement>,
 }
 
 impl EditNavmeshMode {
@@ -362,11 +1447,19 @@ impl EditNavmeshMode {
     ) -> Self {
         Self {
             navmesh: Default::default(),
+            snap_to_ground: false,
+            problems: Default::default(),
             move_gizmo: MoveGizmo::new(editor_scene, engine),
             message_sender,
             drag_context: None,
+            link_placement: None,
         }
     }
+
+    /// Next two clicks on the navmesh place a link, Escape cancels placement.
+    pub fn begin_link_placement(&mut self) {
+        self.link_placement = Some(LinkPlacement::Begin);
+    }
 }
 
 impl InteractionModeTrait for EditNavmeshMode {
@@ -383,6 +1476,42 @@ impl InteractionModeTrait for EditNavmeshMode {
             let camera: &Camera = &scene.graph[editor_scene.camera_controller.camera].as_camera();
             let ray = camera.make_ray(mouse_pos, frame_size);
 
+            if let Some(placement) = self.link_placement.take() {
+                // Clicks outside of the navmesh are ignored and placement goes on.
+                self.link_placement = match pick_triangle(navmesh, &ray) {
+                    Some((triangle, t)) => {
+                        let point = ray.origin + ray.dir.scale(t);
+                        match placement {
+                            LinkPlacement::Begin => Some(LinkPlacement::End {
+                                begin: point,
+                                begin_triangle: triangle,
+                            }),
+                            LinkPlacement::End {
+                                begin,
+                                begin_triangle,
+                            } => {
+                                self.message_sender
+                                    .send(Message::DoSceneCommand(SceneCommand::AddNavmeshLink(
+                                        AddNavmeshLinkCommand::new(
+                                            self.navmesh,
+                                            NavmeshLink::new(
+                                                begin,
+                                                begin_triangle,
+                                                point,
+                                                triangle,
+                                            ),
+                                        ),
+                                    )))
+                                    .unwrap();
+                                None
+                            }
+                        }
+                    }
+                    None => Some(placement),
+                };
+                return;
+            }
+
             let camera = editor_scene.camera_controller.camera;
             let camera_pivot = editor_scene.camera_controller.pivot;
             let editor_node = editor_scene.camera_controller.pick(
@@ -402,7 +1531,19 @@ impl InteractionModeTrait for EditNavmeshMode {
                 for (handle, vertex) in navmesh.vertices.pair_iter() {
                     initial_positions.insert(handle, vertex.position);
                 }
-                self.drag_context = Some(DragContext::MoveSelection { initial_positions });
+                let ground = if self.snap_to_ground {
+                    Some(GroundGeometry::from_graph(
+                        editor_scene.root,
+                        &engine.scenes[editor_scene.scene].graph,
+                    ))
+                } else {
+                    None
+                };
+                self.drag_context = Some(DragContext::MoveSelection {
+                    initial_positions,
+                    offset: Default::default(),
+                    ground,
+                });
             } else {
                 let mut new_selection = if engine.user_interface.keyboard_modifiers().shift {
                     if let Selection::Navmesh(navmesh_selection) = &editor_scene.selection {
@@ -426,6 +1567,25 @@ impl InteractionModeTrait for EditNavmeshMode {
                     }
                 }
 
+                if !picked {
+                    for (handle, link) in navmesh.links.pair_iter() {
+                        let points = arc_points(link);
+                        if points.windows(2).any(|segment| {
+                            ray.cylinder_intersection(
+                                &segment[0],
+                                &segment[1],
+                                VERTEX_RADIUS,
+                                CylinderKind::Finite,
+                            )
+                            .is_some()
+                        }) {
+                            new_selection.add(NavmeshEntity::Link(handle));
+                            picked = true;
+                            break;
+                        }
+                    }
+                }
+
                 if !picked {
                     for triangle in navmesh.triangles.iter() {
                         for edge in &triangle.edges() {
@@ -441,12 +1601,19 @@ impl InteractionModeTrait for EditNavmeshMode {
                                 .is_some()
                             {
                                 new_selection.add(NavmeshEntity::Edge(*edge));
+                                picked = true;
                                 break;
                             }
                         }
                     }
                 }
 
+                if !picked {
+                    if let Some((triangle, _)) = pick_triangle(navmesh, &ray) {
+                        new_selection.add(NavmeshEntity::Triangle(triangle));
+                    }
+                }
+
                 let new_selection = Selection::Navmesh(new_selection);
 
                 if new_selection != editor_scene.selection {
@@ -476,9 +1643,11 @@ impl InteractionModeTrait for EditNavmeshMode {
                 let mut commands = Vec::new();
 
                 match drag_context {
-                    DragContext::MoveSelection { initial_positions } => {
+                    DragContext::MoveSelection {
+                        initial_positions, ..
+                    } => {
                         if let Selection::Navmesh(navmesh_selection) = &mut editor_scene.selection {
-                            for vertex in navmesh_selection.unique_vertices().iter() {
+                            for vertex in navmesh_selection.unique_vertices(navmesh).iter() {
                                 commands.push(SceneCommand::MoveNavmeshVertex(
                                     MoveNavmeshVertexCommand::new(
                                         self.navmesh,
@@ -569,10 +1738,19 @@ impl InteractionModeTrait for EditNavmeshMode {
 
             if let Some(drag_context) = self.drag_context.as_mut() {
                 match drag_context {
-                    DragContext::MoveSelection { .. } => {
+                    DragContext::MoveSelection {
+                        initial_positions,
+                        offset: total_offset,
+                        ground,
+                    } => {
+                        *total_offset += offset;
                         if let Selection::Navmesh(navmesh_selection) = &mut editor_scene.selection {
-                            for &vertex in navmesh_selection.unique_vertices() {
-                                navmesh.vertices[vertex].position += offset;
+                            for &vertex in navmesh_selection.unique_vertices(navmesh) {
+                                let position = initial_positions[&vertex] + *total_offset;
+                                navmesh.vertices[vertex].position = ground
+                                    .as_ref()
+                                    .and_then(|ground| ground.project(position))
+                                    .unwrap_or(position);
                             }
                         }
                     }
@@ -607,7 +1785,7 @@ impl InteractionModeTrait for EditNavmeshMode {
                         10,
                         10,
                         VERTEX_RADIUS,
-                        if navmesh_selection.unique_vertices().contains(&handle) {
+                        if navmesh_selection.unique_vertices(navmesh).contains(&handle) {
                             Color::RED
                         } else {
                             Color::GREEN
@@ -615,7 +1793,34 @@ impl InteractionModeTrait for EditNavmeshMode {
                     );
                 }
 
-                for triangle in navmesh.triangles.iter() {
+                for (handle, triangle) in navmesh.triangles.pair_iter() {
+                    // Area is shown by a smaller triangle inside, so it doesn't hide edges.
+                    let center = triangle.center(&navmesh.vertices);
+                    let inset = |v: Handle<NavmeshVertex>| {
+                        center + (navmesh.vertices[v].position - center).scale(0.8)
+                    };
+                    scene.drawing_context.draw_triangle(
+                        inset(triangle.a),
+                        inset(triangle.b),
+                        inset(triangle.c),
+                        editor_scene.navmesh_areas.color(triangle.area),
+                    );
+
+                    if navmesh_selection.contains_triangle(handle) {
+                        // Draw selected face slightly above the surface to avoid z-fighting.
+                        let offset = triangle
+                            .normal(&navmesh.vertices)
+                            .try_normalize(std::f32::EPSILON)
+                            .unwrap_or_default()
+                            .scale(0.01);
+                        scene.drawing_context.draw_triangle(
+                            navmesh.vertices[triangle.a].position + offset,
+                            navmesh.vertices[triangle.b].position + offset,
+                            navmesh.vertices[triangle.c].position + offset,
+                            Color::opaque(255, 120, 0),
+                        );
+                    }
+
                     for edge in &triangle.edges() {
                         scene.drawing_context.add_line(rg3d::scene::Line {
                             begin: navmesh.vertices[edge.begin].position,
@@ -628,6 +1833,55 @@ impl InteractionModeTrait for EditNavmeshMode {
                         });
                     }
                 }
+
+                for problem in self.problems.iter() {
+                    let color = problem.kind.color();
+                    for edge in problem.edges.iter() {
+                        if let (Some(begin), Some(end)) = (
+                            navmesh.vertices.try_borrow(edge.begin),
+                            navmesh.vertices.try_borrow(edge.end),
+                        ) {
+                            scene.drawing_context.add_line(rg3d::scene::Line {
+                                begin: begin.position,
+                                end: end.position,
+                                color,
+                            });
+                        }
+                    }
+                    if let NavmeshEntity::Vertex(vertex) = problem.entity {
+                        if let Some(vertex) = navmesh.vertices.try_borrow(vertex) {
+                            scene.drawing_context.draw_sphere(
+                                vertex.position,
+                                10,
+                                10,
+                                VERTEX_RADIUS * 1.5,
+                                color,
+                            );
+                        }
+                    }
+                }
+
+                for (handle, link) in navmesh.links.pair_iter() {
+                    let color = if navmesh_selection
+                        .entities()
+                        .contains(&NavmeshEntity::Link(handle))
+                    {
+                        Color::RED
+                    } else {
+                        Color::opaque(0, 160, 255)
+                    };
+                    draw_link(&mut scene.drawing_context, link, color);
+                }
+
+                if let Some(LinkPlacement::End { begin, .. }) = self.link_placement {
+                    scene.drawing_context.draw_sphere(
+                        begin,
+                        10,
+                        10,
+                        VERTEX_RADIUS * 0.5,
+                        Color::opaque(0, 160, 255),
+                    );
+                }
             }
 
             if let Some(DragContext::EdgeDuplication {
@@ -672,18 +1926,22 @@ impl InteractionModeTrait for EditNavmeshMode {
             }
 
             if let Selection::Navmesh(navmesh_selection) = &editor_scene.selection {
-                if let Some(first) = navmesh_selection.first() {
-                    self.move_gizmo.set_visible(&mut scene.graph, true);
-
-                    let gizmo_position = match *first {
-                        NavmeshEntity::Vertex(v) => navmesh.vertices[v].position,
-                        NavmeshEntity::Edge(edge) => {
-                            let a = navmesh.vertices[edge.begin].position;
-                            let b = navmesh.vertices[edge.end].position;
-                            (a + b).scale(0.5)
-                        }
-                    };
+                // Links aren't moved by the gizmo, so it is shown for other entities only.
+                let gizmo_position = navmesh_selection.first().and_then(|first| match *first {
+                    NavmeshEntity::Vertex(v) => Some(navmesh.vertices[v].position),
+                    NavmeshEntity::Edge(edge) => {
+                        let a = navmesh.vertices[edge.begin].position;
+                        let b = navmesh.vertices[edge.end].position;
+                        Some((a + b).scale(0.5))
+                    }
+                    NavmeshEntity::Triangle(triangle) => {
+                        Some(navmesh.triangles[triangle].center(&navmesh.vertices))
+                    }
+                    NavmeshEntity::Link(_) => None,
+                });
 
+                if let Some(gizmo_position) = gizmo_position {
+                    self.move_gizmo.set_visible(&mut scene.graph, true);
                     self.move_gizmo
                         .transform(&mut scene.graph)
                         .set_scale(scale)
@@ -693,7 +1951,7 @@ impl InteractionModeTrait for EditNavmeshMode {
         }
     }
 
-    fn deactivate(&mut self, editor_scene: &EditorScene, engine: &mut GameEngine) {
+    fn deactivate(&mut self, editor_scene: &mut EditorScene, engine: &mut GameEngine) {
         let scene = &mut engine.scenes[editor_scene.scene];
         self.move_gizmo.set_visible(&mut scene.graph, false);
     }
@@ -707,11 +1965,51 @@ impl InteractionModeTrait for EditNavmeshMode {
         match key {
             KeyCode::Delete => {
                 if editor_scene.navmeshes.is_valid_handle(self.navmesh) {
-                    if let Selection::Navmesh(navmesh_selection) = &mut editor_scene.selection {
+                    if let Selection::Navmesh(navmesh_selection) = &editor_scene.selection {
                         if !navmesh_selection.is_empty() {
+                            let navmesh = &editor_scene.navmeshes[self.navmesh];
                             let mut commands = Vec::new();
 
-                            for &vertex in navmesh_selection.unique_vertices() {
+                            // Selected faces are deleted without their vertices, vertices are
+                            // deleted only if they were selected explicitly or by edges.
+                            let mut triangles = Vec::new();
+                            let mut vertices = HashSet::new();
+                            let mut links = Vec::new();
+                            for entity in navmesh_selection.entities() {
+                                match *entity {
+                                    NavmeshEntity::Vertex(vertex) => {
+                                        vertices.insert(vertex);
+                                    }
+                                    NavmeshEntity::Edge(edge) => {
+                                        vertices.insert(edge.begin);
+                                        vertices.insert(edge.end);
+                                    }
+                                    NavmeshEntity::Triangle(triangle) => {
+                                        if navmesh.triangles.is_valid_handle(triangle) {
+                                            triangles.push(triangle);
+                                        }
+                                    }
+                                    NavmeshEntity::Link(link) => {
+                                        if navmesh.links.is_valid_handle(link) {
+                                            links.push(link);
+                                        }
+                                    }
+                                }
+                            }
+
+                            for link in links {
+                                commands.push(SceneCommand::DeleteNavmeshLink(
+                                    DeleteNavmeshLinkCommand::new(self.navmesh, link),
+                                ));
+                            }
+
+                            if !triangles.is_empty() {
+                                commands.push(SceneCommand::DeleteNavmeshTriangles(
+                                    DeleteNavmeshTrianglesCommand::new(self.navmesh, triangles),
+                                ));
+                            }
+
+                            for vertex in vertices {
                                 commands.push(SceneCommand::DeleteNavmeshVertex(
                                     DeleteNavmeshVertexCommand::new(self.navmesh, vertex),
                                 ));
@@ -733,6 +2031,9 @@ impl InteractionModeTrait for EditNavmeshMode {
                     }
                 }
             }
+            KeyCode::Escape => {
+                self.link_placement = None;
+            }
             KeyCode::A if engine.user_interface.keyboard_modifiers().control => {
                 if editor_scene.navmeshes.is_valid_handle(self.navmesh) {
                     let navmesh = &editor_scene.navmeshes[self.navmesh];
@@ -740,9 +2041,15 @@ impl InteractionModeTrait for EditNavmeshMode {
                     let selection = NavmeshSelection::new(
                         self.navmesh,
                         navmesh
-                            .vertices
+                            .triangles
                             .pair_iter()
-                            .map(|(handle, _)| NavmeshEntity::Vertex(handle))
+                            .map(|(handle, _)| NavmeshEntity::Triangle(handle))
+                            .chain(
+                                navmesh
+                                    .vertices
+                                    .pair_iter()
+                                    .map(|(handle, _)| NavmeshEntity::Vertex(handle)),
+                            )
                             .collect(),
                     );
 
@@ -760,3 +2067,54 @@ impl InteractionModeTrait for EditNavmeshMode {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn triangle(y: f32) -> [Vector3<f32>; 3] {
+        [
+            Vector3::new(0.0, y, 0.0),
+            Vector3::new(0.0, y, 1.0),
+            Vector3::new(1.0, y, 0.0),
+        ]
+    }
+
+    fn down_ray(x: f32, z: f32) -> Ray {
+        Ray::new(Vector3::new(x, 5.0, z), Vector3::new(0.0, -1.0, 0.0))
+    }
+
+    #[test]
+    fn ray_hits_triangle() {
+        let distance = ray_triangle_intersection(&down_ray(0.25, 0.25), triangle(1.0)).unwrap();
+
+        assert!((distance - 4.0).abs() < 0.0001);
+    }
+
+    #[test]
+    fn ray_misses_triangle() {
+        // Outside of the triangle, parallel to it and pointing away from it.
+        assert!(ray_triangle_intersection(&down_ray(0.75, 0.75), triangle(1.0)).is_none());
+        let parallel = Ray::new(Vector3::new(-1.0, 1.0, 0.25), Vector3::new(1.0, 0.0, 0.0));
+        assert!(ray_triangle_intersection(&parallel, triangle(1.0)).is_none());
+        assert!(ray_triangle_intersection(&down_ray(0.25, 0.25), triangle(6.0)).is_none());
+    }
+
+    #[test]
+    fn closest_triangle_is_picked() {
+        let mut navmesh = Navmesh::new();
+        let [a, b, c] = triangle(1.0);
+        let mut vertex = |position| navmesh.vertices.spawn(NavmeshVertex { position });
+        let (a, b, c) = (vertex(a), vertex(b), vertex(c));
+        let upper = navmesh.triangles.spawn(NavmeshTriangle::new(a, b, c));
+
+        let (picked, distance) = pick_triangle(&navmesh, &down_ray(0.25, 0.25)).unwrap();
+        assert_eq!(picked, upper);
+        assert!((distance - 4.0).abs() < 0.0001);
+
+        // Only the plane of the navmesh is under this point.
+        let (picked, distance) = pick_triangle(&navmesh, &down_ray(-0.5, -0.5)).unwrap();
+        assert_ne!(picked, upper);
+        assert!((distance - 5.0).abs() < 0.0001);
+    }
+}
@@ -0,0 +1,183 @@
+//! Wavefront OBJ import and export of navmeshes, so they can be edited in external tools.
+//! Only positions and faces are used, everything else in a file is ignored.
+
+use crate::interaction::navmesh::data_model::{Navmesh, NavmeshTriangle, NavmeshVertex};
+use rg3d::core::{algebra::Vector3, pool::Handle};
+use std::{collections::HashMap, fmt::Write, path::Path};
+
+/// Vertices closer than this are merged on import.
+const MERGE_TOLERANCE: f32 = 0.0001;
+
+pub fn export_obj(navmesh: &Navmesh, path: &Path) -> Result<(), String> {
+    let (vertices, triangles) = navmesh.to_dense();
+
+    let mut text = String::new();
+    writeln!(text, "# Navmesh exported from rusty-editor").unwrap();
+    for vertex in vertices.iter() {
+        writeln!(text, "v {} {} {}", vertex.x, vertex.y, vertex.z).unwrap();
+    }
+    for triangle in triangles.iter() {
+        // Indices in OBJ are one-based.
+        let [a, b, c] = triangle.0;
+        writeln!(text, "f {} {} {}", a + 1, b + 1, c + 1).unwrap();
+    }
+
+    std::fs::write(path, text).map_err(|e| e.to_string())
+}
+
+/// Parses index of a face corner, it may be followed by texture and normal indices (`1/2/3`)
+/// or be negative which means relative to the end of vertices read so far.
+fn parse_index(token: &str, vertex_count: usize, line: usize) -> Result<usize, String> {
+    let index = token
+        .split('/')
+        .next()
+        .unwrap_or_default()
+        .parse::<i64>()
+        .map_err(|_| format!("Line {}: invalid face index {}", line, token))?;
+    let resolved = if index < 0 {
+        vertex_count as i64 + index
+    } else {
+        index - 1
+    };
+    if resolved < 0 || resolved >= vertex_count as i64 {
+        Err(format!(
+            "Line {}: face index {} is out of bounds",
+            line, index
+        ))
+    } else {
+        Ok(resolved as usize)
+    }
+}
+
+pub fn import_obj(path: &Path) -> Result<Navmesh, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+
+    let mut positions = Vec::new();
+    let mut faces = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line_number = i + 1;
+        let mut tokens = line.split_whitespace();
+        match tokens.next() {
+            Some("v") => {
+                let mut coords = [0.0; 3];
+                for coord in coords.iter_mut() {
+                    *coord = tokens
+                        .next()
+                        .and_then(|token| token.parse::<f32>().ok())
+                        .ok_or_else(|| format!("Line {}: invalid vertex", line_number))?;
+                }
+                positions.push(Vector3::new(coords[0], coords[1], coords[2]));
+            }
+            Some("f") => {
+                let face = tokens
+                    .map(|token| parse_index(token, positions.len(), line_number))
+                    .collect::<Result<Vec<_>, _>>()?;
+                if face.len() < 3 {
+                    return Err(format!(
+                        "Line {}: face has less than 3 vertices",
+                        line_number
+                    ));
+                }
+                faces.push(face);
+            }
+            _ => (),
+        }
+    }
+
+    let mut navmesh = Navmesh::default();
+
+    // Vertices are spawned only when a face refers to them, duplicates share one handle.
+    let mut merged = HashMap::new();
+    let mut handles = vec![Handle::<NavmeshVertex>::NONE; positions.len()];
+    let mut vertex_handle = |index: usize, navmesh: &mut Navmesh| {
+        if handles[index].is_none() {
+            let position: Vector3<f32> = positions[index];
+            let key = (
+                (position.x / MERGE_TOLERANCE).round() as i64,
+                (position.y / MERGE_TOLERANCE).round() as i64,
+                (position.z / MERGE_TOLERANCE).round() as i64,
+            );
+            handles[index] = *merged
+                .entry(key)
+                .or_insert_with(|| navmesh.vertices.spawn(NavmeshVertex { position }));
+        }
+        handles[index]
+    };
+
+    for face in faces {
+        // Polygons are triangulated as a fan, this is fine for convex ones which is what
+        // modelling tools usually produce.
+        for i in 1..face.len() - 1 {
+            let a = vertex_handle(face[0], &mut navmesh);
+            let b = vertex_handle(face[i], &mut navmesh);
+            let c = vertex_handle(face[i + 1], &mut navmesh);
+            // Merging may collapse a triangle.
+            if a != b && b != c && c != a {
+                navmesh.triangles.spawn(NavmeshTriangle::new(a, b, c));
+            }
+        }
+    }
+
+    if navmesh.triangles.iter().next().is_none() {
+        Err("File has no faces".to_owned())
+    } else {
+        Ok(navmesh)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("rusty_editor_{}_{}.obj", name, std::process::id()))
+    }
+
+    #[test]
+    fn export_import_round_trip() {
+        let navmesh = Navmesh::new();
+        let path = temp_path("round_trip");
+
+        export_obj(&navmesh, &path).unwrap();
+        let imported = import_obj(&path);
+        let _ = std::fs::remove_file(&path);
+        let imported = imported.unwrap();
+
+        let (vertices, triangles) = navmesh.to_dense();
+        let (imported_vertices, imported_triangles) = imported.to_dense();
+        assert_eq!(imported_vertices, vertices);
+        assert_eq!(
+            imported_triangles.iter().map(|t| t.0).collect::<Vec<_>>(),
+            triangles.iter().map(|t| t.0).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn import_merges_vertices() {
+        let path = temp_path("merge");
+        // Two triangles with duplicated shared edge, a quad and a degenerate face which
+        // collapses after merging.
+        std::fs::write(
+            &path,
+            "v 0 0 0\nv 1 0 0\nv 1 0 1\nv 0 0 0.00001\nv 1 0 1\nv 0 0 1\n\
+             f 1 2 3\nf 4 5 6\nf 1/1/1 2/2/2 3 6\nf 1 4 2\n",
+        )
+        .unwrap();
+        let imported = import_obj(&path);
+        let _ = std::fs::remove_file(&path);
+        let navmesh = imported.unwrap();
+
+        assert_eq!(navmesh.vertices.iter().count(), 4);
+        assert_eq!(navmesh.triangles.iter().count(), 4);
+    }
+
+    #[test]
+    fn import_rejects_bad_indices() {
+        let path = temp_path("bad_indices");
+        std::fs::write(&path, "v 0 0 0\nv 1 0 0\nv 1 0 1\nf 1 2 4\n").unwrap();
+        let imported = import_obj(&path);
+        let _ = std::fs::remove_file(&path);
+        assert!(imported.is_err());
+    }
+}
@@ -0,0 +1,418 @@
+//! Path test mode - start and end markers are dragged over a navmesh and the path between
+//! them is found by engine's pathfinder on a navmesh built exactly as it is on save.
+//! Engine does not tell which triangles its search has visited, so only the triangles
+//! the path goes along are highlighted.
+
+use crate::{
+    interaction::{
+        navmesh::{data_model::Navmesh, pick_triangle},
+        InteractionModeTrait,
+    },
+    scene::{EditorScene, Selection},
+    GameEngine, Message,
+};
+use rg3d::{
+    core::{
+        algebra::{Vector2, Vector3},
+        color::Color,
+        math::ray::Ray,
+        pool::Handle,
+    },
+    scene::{node::Node, Line},
+    utils::navmesh::{Navmesh as EngineNavmesh, PathKind},
+};
+use std::{cmp::Ordering, sync::mpsc::Sender};
+
+const MARKER_RADIUS: f32 = 0.25;
+const MARKER_COLORS: [Color; 2] = [Color::GREEN, Color::RED];
+/// Maximum distance between path point and navmesh vertex it is matched to.
+const VERTEX_MATCH_DISTANCE: f32 = 0.001;
+
+/// Engine's navmesh together with the data it was built from, so it is rebuilt only when
+/// editor's navmesh changes.
+struct BuiltNavmesh {
+    source: Handle<Navmesh>,
+    vertices: Vec<Vector3<f32>>,
+    triangles: Vec<[u32; 3]>,
+    navmesh: EngineNavmesh,
+}
+
+enum PathTestResult {
+    /// There is no navmesh to test.
+    None,
+    NoPath,
+    Path {
+        /// Path points from start to end.
+        points: Vec<Vector3<f32>>,
+        /// Triangles along the path - engine's pathfinder walks over navmesh vertices, so
+        /// these are triangles with an edge between two consecutive path points.
+        path_triangles: Vec<usize>,
+    },
+}
+
+impl PathTestResult {
+    fn is_path(&self) -> bool {
+        matches!(self, PathTestResult::Path { .. })
+    }
+}
+
+pub struct NavmeshTestMode {
+    /// Start and end markers, placed at first use.
+    markers: Option<[Vector3<f32>; 2]>,
+    dragged_marker: Option<usize>,
+    built: Option<BuiltNavmesh>,
+    result: PathTestResult,
+    needs_update: bool,
+    message_sender: Sender<Message>,
+}
+
+impl NavmeshTestMode {
+    pub fn new(message_sender: Sender<Message>) -> Self {
+        Self {
+            markers: None,
+            dragged_marker: None,
+            built: None,
+            result: PathTestResult::None,
+            needs_update: true,
+            message_sender,
+        }
+    }
+}
+
+/// Navmesh being tested - selected one or first one in the scene.
+fn tested_navmesh(editor_scene: &EditorScene) -> Handle<Navmesh> {
+    if let Selection::Navmesh(selection) = &editor_scene.selection {
+        if editor_scene.navmeshes.is_valid_handle(selection.navmesh()) {
+            return selection.navmesh();
+        }
+    }
+    editor_scene
+        .navmeshes
+        .pair_iter()
+        .next()
+        .map(|(handle, _)| handle)
+        .unwrap_or_default()
+}
+
+/// Returns index of navmesh vertex at given point, if there is one.
+fn closest_vertex(vertices: &[Vector3<f32>], point: &Vector3<f32>) -> Option<u32> {
+    vertices
+        .iter()
+        .map(|v| v.metric_distance(point))
+        .enumerate()
+        .filter(|(_, distance)| *distance <= VERTEX_MATCH_DISTANCE)
+        .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+        .map(|(i, _)| i as u32)
+}
+
+/// Returns closest point of navmesh surface hit by the ray.
+fn pick_surface(navmesh: &Navmesh, ray: &Ray) -> Option<Vector3<f32>> {
+    pick_triangle(navmesh, ray).map(|(_, distance)| ray.origin + ray.dir.scale(distance))
+}
+
+impl NavmeshTestMode {
+    /// Rebuilds engine's navmesh if editor's one has changed.
+    fn sync_navmesh(&mut self, editor_scene: &EditorScene) {
+        let handle = tested_navmesh(editor_scene);
+        if handle.is_none() {
+            if self.built.is_some() {
+                self.built = None;
+                self.needs_update = true;
+            }
+            return;
+        }
+
+        let (vertices, definitions) = editor_scene.navmeshes[handle].to_dense();
+        let triangles = definitions.iter().map(|t| t.0).collect::<Vec<_>>();
+
+        let changed = self.built.as_ref().map_or(true, |built| {
+            built.source != handle || built.vertices != vertices || built.triangles != triangles
+        });
+        if changed {
+            if self.markers.is_none() && !vertices.is_empty() {
+                self.markers = Some([vertices[0], vertices[vertices.len() - 1]]);
+            }
+            self.built = Some(BuiltNavmesh {
+                source: handle,
+                navmesh: EngineNavmesh::new(&definitions, &vertices),
+                vertices,
+                triangles,
+            });
+            self.needs_update = true;
+        }
+    }
+
+    fn find_path(&mut self) -> PathTestResult {
+        let (built, markers) = match (self.built.as_mut(), self.markers) {
+            (Some(built), Some(markers)) => (built, markers),
+            _ => return PathTestResult::None,
+        };
+
+        let (from, to) = match (
+            built.navmesh.query_closest(markers[0]),
+            built.navmesh.query_closest(markers[1]),
+        ) {
+            (Some(from), Some(to)) => (from, to),
+            _ => return PathTestResult::NoPath,
+        };
+
+        let mut points = Vec::new();
+        match built.navmesh.build_path(from, to, &mut points) {
+            Ok(PathKind::Full) => {
+                if points.first() != Some(&built.vertices[from]) {
+                    points.reverse();
+                }
+
+                let indices = points
+                    .iter()
+                    .filter_map(|p| closest_vertex(&built.vertices, p))
+                    .collect::<Vec<_>>();
+                let path_triangles = built
+                    .triangles
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, triangle)| {
+                        indices
+                            .windows(2)
+                            .any(|edge| triangle.contains(&edge[0]) && triangle.contains(&edge[1]))
+                    })
+                    .map(|(i, _)| i)
+                    .collect();
+
+                PathTestResult::Path {
+                    points,
+                    path_triangles,
+                }
+            }
+            // Partial path leads to the closest reachable point - target is in other region.
+            _ => PathTestResult::NoPath,
+        }
+    }
+}
+
+impl InteractionModeTrait for NavmeshTestMode {
+    fn on_left_mouse_button_down(
+        &mut self,
+        editor_scene: &mut EditorScene,
+        engine: &mut GameEngine,
+        mouse_pos: Vector2<f32>,
+        frame_size: Vector2<f32>,
+    ) {
+        if let Some(markers) = self.markers {
+            let graph = &engine.scenes[editor_scene.scene].graph;
+            let ray = graph[editor_scene.camera_controller.camera]
+                .as_camera()
+                .make_ray(mouse_pos, frame_size);
+            self.dragged_marker = markers
+                .iter()
+                .enumerate()
+                .filter_map(|(i, position)| {
+                    ray.sphere_intersection(position, MARKER_RADIUS)
+                        .map(|result| (i, result.min))
+                })
+                .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+                .map(|(i, _)| i);
+        }
+    }
+
+    fn on_left_mouse_button_up(
+        &mut self,
+        _editor_scene: &mut EditorScene,
+        _engine: &mut GameEngine,
+        _mouse_pos: Vector2<f32>,
+        _frame_size: Vector2<f32>,
+    ) {
+        self.dragged_marker = None;
+    }
+
+    fn on_mouse_move(
+        &mut self,
+        _mouse_offset: Vector2<f32>,
+        mouse_position: Vector2<f32>,
+        camera: Handle<Node>,
+        editor_scene: &mut EditorScene,
+        engine: &mut GameEngine,
+        frame_size: Vector2<f32>,
+    ) {
+        if let (Some(marker), Some(markers)) = (self.dragged_marker, self.markers.as_mut()) {
+            let navmesh = tested_navmesh(editor_scene);
+            if navmesh.is_some() {
+                let graph = &engine.scenes[editor_scene.scene].graph;
+                let ray = graph[camera]
+                    .as_camera()
+                    .make_ray(mouse_position, frame_size);
+                if let Some(position) = pick_surface(&editor_scene.navmeshes[navmesh], &ray) {
+                    markers[marker] = position;
+                    self.needs_update = true;
+                }
+            }
+        }
+    }
+
+    fn update(
+        &mut self,
+        editor_scene: &mut EditorScene,
+        _camera: Handle<Node>,
+        engine: &mut GameEngine,
+    ) {
+        self.sync_navmesh(editor_scene);
+
+        if self.needs_update {
+            self.needs_update = false;
+            let result = self.find_path();
+            match (&result, &self.result) {
+                (PathTestResult::Path { points, .. }, previous) if !previous.is_path() => {
+                    let length = points
+                        .windows(2)
+                        .map(|segment| segment[0].metric_distance(&segment[1]))
+                        .sum::<f32>();
+                    self.message_sender
+                        .send(Message::Log(format!(
+                            "Path test: path found, {} points, length {:.2}.",
+                            points.len(),
+                            length
+                        )))
+                        .unwrap();
+                }
+                (PathTestResult::NoPath, previous) if previous.is_path() => {
+                    self.message_sender
+                        .send(Message::Log(
+                            "Path test: no path between markers.".to_owned(),
+                        ))
+                        .unwrap();
+                }
+                _ => (),
+            }
+            self.result = result;
+        }
+
+        let context = &mut engine.scenes[editor_scene.scene].drawing_context;
+
+        if let Some(built) = self.built.as_ref() {
+            let gray = Color::opaque(120, 120, 120);
+            for triangle in built.triangles.iter() {
+                for i in 0..3 {
+                    context.add_line(Line {
+                        begin: built.vertices[triangle[i] as usize],
+                        end: built.vertices[triangle[(i + 1) % 3] as usize],
+                        color: gray,
+                    });
+                }
+            }
+
+            if let PathTestResult::Path { path_triangles, .. } = &self.result {
+                for &i in path_triangles.iter() {
+                    let triangle = built.triangles[i];
+                    context.draw_triangle(
+                        built.vertices[triangle[0] as usize],
+                        built.vertices[triangle[1] as usize],
+                        built.vertices[triangle[2] as usize],
+                        Color::opaque(255, 200, 0),
+                    );
+                }
+            }
+        }
+
+        if let Some(markers) = self.markers {
+            for (position, &color) in markers.iter().zip(MARKER_COLORS.iter()) {
+                context.draw_sphere(*position, 10, 10, MARKER_RADIUS, color);
+            }
+
+            match &self.result {
+                PathTestResult::Path { points, .. } => {
+                    let mut previous = markers[0];
+                    for &point in points.iter().chain(std::iter::once(&markers[1])) {
+                        context.add_line(Line {
+                            begin: previous,
+                            end: point,
+                            color: Color::opaque(0, 200, 255),
+                        });
+                        previous = point;
+                    }
+                }
+                PathTestResult::NoPath => context.add_line(Line {
+                    begin: markers[0],
+                    end: markers[1],
+                    color: Color::RED,
+                }),
+                PathTestResult::None => (),
+            }
+        }
+    }
+
+    fn deactivate(&mut self, _editor_scene: &mut EditorScene, _engine: &mut GameEngine) {
+        self.dragged_marker = None;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rg3d::core::math::TriangleDefinition;
+    use std::sync::mpsc::channel;
+
+    fn test_mode(vertices: Vec<Vector3<f32>>, triangles: Vec<[u32; 3]>) -> NavmeshTestMode {
+        let definitions = triangles
+            .iter()
+            .map(|&t| TriangleDefinition(t))
+            .collect::<Vec<_>>();
+        let mut mode = NavmeshTestMode::new(channel().0);
+        mode.markers = Some([vertices[0], vertices[vertices.len() - 1]]);
+        mode.built = Some(BuiltNavmesh {
+            source: Default::default(),
+            navmesh: EngineNavmesh::new(&definitions, &vertices),
+            vertices,
+            triangles,
+        });
+        mode
+    }
+
+    #[test]
+    fn path_goes_along_connected_triangles() {
+        let vertices = vec![
+            Vector3::new(0.0, 0.0, 0.0),
+            Vector3::new(1.0, 0.0, 0.0),
+            Vector3::new(1.0, 0.0, 1.0),
+            Vector3::new(0.0, 0.0, 1.0),
+            Vector3::new(2.0, 0.0, 0.0),
+            Vector3::new(2.0, 0.0, 1.0),
+        ];
+        let triangles = vec![[0, 1, 2], [0, 2, 3], [1, 4, 5], [1, 5, 2]];
+        let mut mode = test_mode(vertices.clone(), triangles);
+
+        match mode.find_path() {
+            PathTestResult::Path {
+                points,
+                path_triangles,
+            } => {
+                assert_eq!(points.first(), Some(&vertices[0]));
+                assert_eq!(points.last(), Some(&vertices[5]));
+                assert!(!path_triangles.is_empty());
+                assert!(path_triangles.iter().all(|&i| i < 4));
+            }
+            _ => panic!("path must be found"),
+        }
+    }
+
+    #[test]
+    fn disconnected_triangles_have_no_path() {
+        let vertices = vec![
+            Vector3::new(0.0, 0.0, 0.0),
+            Vector3::new(1.0, 0.0, 0.0),
+            Vector3::new(1.0, 0.0, 1.0),
+            Vector3::new(5.0, 0.0, 0.0),
+            Vector3::new(6.0, 0.0, 0.0),
+            Vector3::new(6.0, 0.0, 1.0),
+        ];
+        let mut mode = test_mode(vertices, vec![[0, 1, 2], [3, 4, 5]]);
+
+        assert!(matches!(mode.find_path(), PathTestResult::NoPath));
+    }
+
+    #[test]
+    fn no_navmesh_gives_no_result() {
+        let mut mode = NavmeshTestMode::new(channel().0);
+
+        assert!(matches!(mode.find_path(), PathTestResult::None));
+    }
+}
@@ -1,4 +1,4 @@
-use crate::interaction::navmesh::data_model::{Navmesh, NavmeshEdge};
+use crate::interaction::navmesh::data_model::{Navmesh, NavmeshEdge, NavmeshTriangle};
 use crate::interaction::navmesh::{NavmeshEntity, NavmeshVertex};
 use rg3d::core::pool::Handle;
 use std::collections::HashSet;
@@ -53,7 +53,9 @@ impl NavmeshSelection {
         self.entities.is_empty()
     }
 
-    pub fn unique_vertices(&mut self) -> &HashSet<Handle<NavmeshVertex>> {
+    /// Returns vertices of every selected entity, navmesh is used to find vertices of
+    /// selected triangles.
+    pub fn unique_vertices(&mut self, navmesh: &Navmesh) -> &HashSet<Handle<NavmeshVertex>> {
         if self.dirty {
             self.unique_vertices.clear();
             for entity in self.entities.iter() {
@@ -65,6 +67,12 @@ impl NavmeshSelection {
                         self.unique_vertices.insert(edge.begin);
                         self.unique_vertices.insert(edge.end);
                     }
+                    NavmeshEntity::Triangle(triangle) => {
+                        if let Some(triangle) = navmesh.triangles.try_borrow(*triangle) {
+                            self.unique_vertices.extend(triangle.vertices().iter());
+                        }
+                    }
+                    NavmeshEntity::Link(_) => (),
                 }
             }
         }
@@ -79,4 +87,8 @@ impl NavmeshSelection {
     pub fn contains_edge(&self, edge: NavmeshEdge) -> bool {
         self.entities.contains(&NavmeshEntity::Edge(edge))
     }
+
+    pub fn contains_triangle(&self, triangle: Handle<NavmeshTriangle>) -> bool {
+        self.entities.contains(&NavmeshEntity::Triangle(triangle))
+    }
 }
@@ -0,0 +1,534 @@
+//! Topology tools for navmeshes - weld, split, subdivide, flip, delete, extrude and flatten
+//! triangles. Each tool only describes a change as a plan, the plan is applied by an undoable
+//! scene command.
+
+use crate::interaction::navmesh::data_model::{
+    Navmesh, NavmeshEdge, NavmeshTriangle, NavmeshVertex,
+};
+use rg3d::core::{algebra::Vector3, pool::Handle};
+use std::collections::{HashMap, HashSet};
+
+/// Corner of a triangle in a plan, it is either an existing vertex or a vertex added by the
+/// same plan (index in `added_vertices`).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum NavmeshCorner {
+    Existing(Handle<NavmeshVertex>),
+    Added(usize),
+}
+
+/// Description of a topology change. Triangles are modified in place when possible so their
+/// handles and areas stay the same, added triangles come with their areas.
+#[derive(Debug, Default)]
+pub struct NavmeshTopologyPlan {
+    pub added_vertices: Vec<NavmeshVertex>,
+    pub added_triangles: Vec<([NavmeshCorner; 3], usize)>,
+    pub modified_triangles: Vec<(Handle<NavmeshTriangle>, [NavmeshCorner; 3])>,
+    pub moved_vertices: Vec<(Handle<NavmeshVertex>, Vector3<f32>)>,
+    pub removed_vertices: Vec<Handle<NavmeshVertex>>,
+    pub removed_triangles: Vec<Handle<NavmeshTriangle>>,
+}
+
+impl NavmeshTopologyPlan {
+    pub fn is_empty(&self) -> bool {
+        self.added_vertices.is_empty()
+            && self.added_triangles.is_empty()
+            && self.modified_triangles.is_empty()
+            && self.moved_vertices.is_empty()
+            && self.removed_vertices.is_empty()
+            && self.removed_triangles.is_empty()
+    }
+}
+
+fn existing(triangle: &NavmeshTriangle) -> [NavmeshCorner; 3] {
+    let [a, b, c] = triangle.vertices();
+    [
+        NavmeshCorner::Existing(a),
+        NavmeshCorner::Existing(b),
+        NavmeshCorner::Existing(c),
+    ]
+}
+
+/// Returns triangles which all three vertices are in the given set.
+pub fn triangles_of_vertices(
+    navmesh: &Navmesh,
+    vertices: &HashSet<Handle<NavmeshVertex>>,
+) -> Vec<Handle<NavmeshTriangle>> {
+    navmesh
+        .triangles
+        .pair_iter()
+        .filter(|(_, triangle)| triangle.vertices().iter().all(|v| vertices.contains(v)))
+        .map(|(handle, _)| handle)
+        .collect()
+}
+
+/// Returns triangles sharing the edge, direction of the edge does not matter.
+pub fn edge_triangles(navmesh: &Navmesh, edge: NavmeshEdge) -> Vec<Handle<NavmeshTriangle>> {
+    navmesh
+        .triangles
+        .pair_iter()
+        .filter(|(_, triangle)| {
+            let vertices = triangle.vertices();
+            vertices.contains(&edge.begin) && vertices.contains(&edge.end)
+        })
+        .map(|(handle, _)| handle)
+        .collect()
+}
+
+/// Splits a triangle by midpoints of its edges, `midpoints[i]` is the midpoint of the edge
+/// between corners `i` and `i + 1`. Winding of the triangle is preserved, the first returned
+/// triangle replaces the original one.
+fn split_triangle(
+    corners: [NavmeshCorner; 3],
+    midpoints: [Option<NavmeshCorner>; 3],
+) -> Vec<[NavmeshCorner; 3]> {
+    let split_count = midpoints.iter().filter(|m| m.is_some()).count();
+    // Rotate the triangle so the split edges come first.
+    let rotation = (0..3)
+        .find(|&i| match split_count {
+            1 => midpoints[i].is_some(),
+            2 => midpoints[(i + 2) % 3].is_none(),
+            _ => i == 0,
+        })
+        .unwrap_or(0);
+    let c = |i: usize| corners[(rotation + i) % 3];
+    let m = |i: usize| midpoints[(rotation + i) % 3].unwrap();
+
+    match split_count {
+        0 => vec![corners],
+        1 => vec![[c(0), m(0), c(2)], [m(0), c(1), c(2)]],
+        2 => vec![[c(0), m(0), m(1)], [m(0), c(1), m(1)], [c(0), m(1), c(2)]],
+        _ => vec![
+            [m(0), m(1), m(2)],
+            [c(0), m(0), m(2)],
+            [m(0), c(1), m(1)],
+            [m(2), m(1), c(2)],
+        ],
+    }
+}
+
+/// Adds midpoints to the given edges and splits every triangle using these edges, so no
+/// T-junctions are left.
+fn split_edges(navmesh: &Navmesh, edges: &[NavmeshEdge]) -> NavmeshTopologyPlan {
+    let mut plan = NavmeshTopologyPlan::default();
+
+    let mut midpoints = HashMap::new();
+    for edge in edges {
+        if midpoints.contains_key(&(edge.begin, edge.end)) {
+            continue;
+        }
+        let index = plan.added_vertices.len();
+        plan.added_vertices.push(NavmeshVertex {
+            position: (navmesh.vertices[edge.begin].position + navmesh.vertices[edge.end].position)
+                .scale(0.5),
+        });
+        midpoints.insert((edge.begin, edge.end), NavmeshCorner::Added(index));
+        midpoints.insert((edge.end, edge.begin), NavmeshCorner::Added(index));
+    }
+
+    for (handle, triangle) in navmesh.triangles.pair_iter() {
+        let vertices = triangle.vertices();
+        let mut edge_midpoints = [None; 3];
+        for (i, midpoint) in edge_midpoints.iter_mut().enumerate() {
+            *midpoint = midpoints
+                .get(&(vertices[i], vertices[(i + 1) % 3]))
+                .cloned();
+        }
+        if edge_midpoints.iter().any(|m| m.is_some()) {
+            let mut parts = split_triangle(existing(triangle), edge_midpoints).into_iter();
+            plan.modified_triangles
+                .push((handle, parts.next().unwrap()));
+            plan.added_triangles
+                .extend(parts.map(|corners| (corners, triangle.area)));
+        }
+    }
+
+    plan
+}
+
+/// Merges vertices closer than `tolerance` to each other. Each group of close vertices is
+/// replaced by its first vertex moved to the center of the group, triangles which collapse
+/// after that are removed.
+pub fn weld_vertices(
+    navmesh: &Navmesh,
+    vertices: &[Handle<NavmeshVertex>],
+    tolerance: f32,
+) -> NavmeshTopologyPlan {
+    let mut plan = NavmeshTopologyPlan::default();
+
+    let vertices = vertices
+        .iter()
+        .cloned()
+        .filter(|&v| navmesh.vertices.is_valid_handle(v))
+        .collect::<Vec<_>>();
+
+    let mut replacements = HashMap::new();
+    let mut visited = vec![false; vertices.len()];
+    for first in 0..vertices.len() {
+        if visited[first] {
+            continue;
+        }
+        visited[first] = true;
+
+        // Gather the group, vertices are close if there is a chain of close vertices between.
+        let mut group = vec![first];
+        let mut i = 0;
+        while i < group.len() {
+            let position = navmesh.vertices[vertices[group[i]]].position;
+            for other in 0..vertices.len() {
+                if !visited[other]
+                    && navmesh.vertices[vertices[other]]
+                        .position
+                        .metric_distance(&position)
+                        <= tolerance
+                {
+                    visited[other] = true;
+                    group.push(other);
+                }
+            }
+            i += 1;
+        }
+
+        if group.len() > 1 {
+            let kept = vertices[first];
+            let center = group
+                .iter()
+                .fold(Vector3::default(), |sum, &i| {
+                    sum + navmesh.vertices[vertices[i]].position
+                })
+                .scale(1.0 / group.len() as f32);
+            plan.moved_vertices.push((kept, center));
+            for &i in group.iter().skip(1) {
+                replacements.insert(vertices[i], kept);
+                plan.removed_vertices.push(vertices[i]);
+            }
+        }
+    }
+
+    for (handle, triangle) in navmesh.triangles.pair_iter() {
+        let vertices = triangle.vertices();
+        if vertices.iter().any(|v| replacements.contains_key(v)) {
+            let replace = |v| *replacements.get(&v).unwrap_or(&v);
+            let (a, b, c) = (
+                replace(vertices[0]),
+                replace(vertices[1]),
+                replace(vertices[2]),
+            );
+            if a == b || b == c || c == a {
+                plan.removed_triangles.push(handle);
+            } else {
+                plan.modified_triangles.push((
+                    handle,
+                    [
+                        NavmeshCorner::Existing(a),
+                        NavmeshCorner::Existing(b),
+                        NavmeshCorner::Existing(c),
+                    ],
+                ));
+            }
+        }
+    }
+
+    plan
+}
+
+/// Adds a vertex in the middle of the edge and splits triangles sharing the edge in two.
+pub fn split_edge(navmesh: &Navmesh, edge: NavmeshEdge) -> NavmeshTopologyPlan {
+    if !navmesh.vertices.is_valid_handle(edge.begin) || !navmesh.vertices.is_valid_handle(edge.end)
+    {
+        return Default::default();
+    }
+    split_edges(navmesh, &[edge])
+}
+
+/// Splits each triangle in four by midpoints of its edges. Neighbour triangles are split at
+/// the same midpoints to keep the mesh connected.
+pub fn subdivide_triangles(
+    navmesh: &Navmesh,
+    triangles: &[Handle<NavmeshTriangle>],
+) -> NavmeshTopologyPlan {
+    let edges = triangles
+        .iter()
+        .filter_map(|&t| navmesh.triangles.try_borrow(t))
+        .flat_map(|triangle| triangle.edges().to_vec())
+        .collect::<Vec<_>>();
+    split_edges(navmesh, &edges)
+}
+
+/// Replaces the edge shared by two triangles with the other diagonal of quad they form.
+/// Returns `None` if the edge is not shared by exactly two triangles or the quad is concave,
+/// flipping the edge would make triangles overlap in this case.
+pub fn flip_edge(navmesh: &Navmesh, edge: NavmeshEdge) -> Option<NavmeshTopologyPlan> {
+    let triangles = edge_triangles(navmesh, edge);
+    if triangles.len() != 2 {
+        return None;
+    }
+
+    // Orient the quad by the first triangle - (p, q, r) where p -> q is the edge.
+    let first = navmesh.triangles[triangles[0]].vertices();
+    let i = (0..3).find(|&i| !edge_contains(edge, first[i]))?;
+    let (p, q, r) = (first[(i + 1) % 3], first[(i + 2) % 3], first[i]);
+    let s = *navmesh.triangles[triangles[1]]
+        .vertices()
+        .iter()
+        .find(|&&v| !edge_contains(edge, v))?;
+
+    let position = |v: Handle<NavmeshVertex>| navmesh.vertices[v].position;
+    let normal = |a, b, c| (position(b) - position(a)).cross(&(position(c) - position(a)));
+    let quad_normal = normal(p, q, r) + normal(q, p, s);
+    if normal(p, s, r).dot(&quad_normal) <= 0.0 || normal(s, q, r).dot(&quad_normal) <= 0.0 {
+        return None;
+    }
+
+    let corners = |a, b, c| {
+        [
+            NavmeshCorner::Existing(a),
+            NavmeshCorner::Existing(b),
+            NavmeshCorner::Existing(c),
+        ]
+    };
+    Some(NavmeshTopologyPlan {
+        modified_triangles: vec![
+            (triangles[0], corners(s, q, r)),
+            (triangles[1], corners(p, s, r)),
+        ],
+        ..Default::default()
+    })
+}
+
+fn edge_contains(edge: NavmeshEdge, vertex: Handle<NavmeshVertex>) -> bool {
+    edge.begin == vertex || edge.end == vertex
+}
+
+/// Removes triangles, their vertices are kept.
+pub fn delete_triangles(
+    navmesh: &Navmesh,
+    triangles: &[Handle<NavmeshTriangle>],
+) -> NavmeshTopologyPlan {
+    NavmeshTopologyPlan {
+        removed_triangles: triangles
+            .iter()
+            .cloned()
+            .filter(|&t| navmesh.triangles.is_valid_handle(t))
+            .collect(),
+        ..Default::default()
+    }
+}
+
+/// Moves triangles along their normals by `distance`, each vertex is moved along the average
+/// normal of selected triangles sharing it. Triangles are detached from the rest of the mesh
+/// and connected with it by walls built on the border edges of the selection.
+pub fn extrude_triangles(
+    navmesh: &Navmesh,
+    triangles: &[Handle<NavmeshTriangle>],
+    distance: f32,
+) -> NavmeshTopologyPlan {
+    let mut plan = NavmeshTopologyPlan::default();
+
+    let triangles = triangles
+        .iter()
+        .cloned()
+        .filter(|&t| navmesh.triangles.is_valid_handle(t))
+        .collect::<Vec<_>>();
+
+    // Copies of vertices with accumulated normals.
+    let mut copies = HashMap::new();
+    let mut normals = Vec::new();
+    for &handle in triangles.iter() {
+        let triangle = &navmesh.triangles[handle];
+        let normal = triangle
+            .normal(&navmesh.vertices)
+            .try_normalize(std::f32::EPSILON)
+            .unwrap_or_default();
+        for &vertex in triangle.vertices().iter() {
+            let index = *copies.entry(vertex).or_insert_with(|| {
+                plan.added_vertices.push(navmesh.vertices[vertex].clone());
+                normals.push(Vector3::default());
+                normals.len() - 1
+            });
+            normals[index] += normal;
+        }
+    }
+    for (vertex, normal) in plan.added_vertices.iter_mut().zip(normals) {
+        vertex.position += normal
+            .try_normalize(std::f32::EPSILON)
+            .unwrap_or_default()
+            .scale(distance);
+    }
+
+    // Count how many selected triangles use each edge, border edges are used by one.
+    let mut edge_use = HashMap::new();
+    for &handle in triangles.iter() {
+        for edge in navmesh.triangles[handle].edges().iter() {
+            *edge_use.entry((edge.begin, edge.end)).or_insert(0) += 1;
+            *edge_use.entry((edge.end, edge.begin)).or_insert(0) += 1;
+        }
+    }
+
+    let copy = |v: Handle<NavmeshVertex>| NavmeshCorner::Added(copies[&v]);
+    for &handle in triangles.iter() {
+        let triangle = &navmesh.triangles[handle];
+        plan.modified_triangles.push((
+            handle,
+            [copy(triangle.a), copy(triangle.b), copy(triangle.c)],
+        ));
+        for edge in triangle.edges().iter() {
+            if edge_use[&(edge.begin, edge.end)] == 1 {
+                let (p, q) = (
+                    NavmeshCorner::Existing(edge.begin),
+                    NavmeshCorner::Existing(edge.end),
+                );
+                plan.added_triangles
+                    .push(([p, q, copy(edge.end)], triangle.area));
+                plan.added_triangles
+                    .push(([p, copy(edge.end), copy(edge.begin)], triangle.area));
+            }
+        }
+    }
+
+    plan
+}
+
+/// Moves every vertex of triangles to the average height of these vertices.
+pub fn flatten_triangles(
+    navmesh: &Navmesh,
+    triangles: &[Handle<NavmeshTriangle>],
+) -> NavmeshTopologyPlan {
+    let vertices = triangles
+        .iter()
+        .filter_map(|&t| navmesh.triangles.try_borrow(t))
+        .flat_map(|triangle| triangle.vertices().to_vec())
+        .collect::<HashSet<_>>();
+    if vertices.is_empty() {
+        return Default::default();
+    }
+
+    let height = vertices
+        .iter()
+        .map(|&v| navmesh.vertices[v].position.y)
+        .sum::<f32>()
+        / vertices.len() as f32;
+
+    NavmeshTopologyPlan {
+        moved_vertices: vertices
+            .into_iter()
+            .map(|v| {
+                let position = navmesh.vertices[v].position;
+                (v, Vector3::new(position.x, height, position.z))
+            })
+            .collect(),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Two triangles forming a square, `a-c` is the diagonal.
+    fn square() -> (Navmesh, [Handle<NavmeshVertex>; 4]) {
+        let navmesh = Navmesh::new();
+        let vertices = navmesh
+            .vertices
+            .pair_iter()
+            .map(|(handle, _)| handle)
+            .collect::<Vec<_>>();
+        (
+            navmesh,
+            [vertices[0], vertices[1], vertices[2], vertices[3]],
+        )
+    }
+
+    fn triangle_handles(navmesh: &Navmesh) -> Vec<Handle<NavmeshTriangle>> {
+        navmesh
+            .triangles
+            .pair_iter()
+            .map(|(handle, _)| handle)
+            .collect()
+    }
+
+    #[test]
+    fn weld_merges_close_vertices() {
+        let mut navmesh = Navmesh::default();
+        let mut vertex = |x, z| {
+            navmesh.vertices.spawn(NavmeshVertex {
+                position: Vector3::new(x, 0.0, z),
+            })
+        };
+        let (a, b, c) = (vertex(0.0, 0.0), vertex(1.0, 0.0), vertex(1.0, 1.0));
+        let (a2, c2, d) = (vertex(0.0, 0.001), vertex(1.0, 1.001), vertex(0.0, 1.0));
+        navmesh.triangles.spawn(NavmeshTriangle::new(a, b, c));
+        let second = navmesh.triangles.spawn(NavmeshTriangle::new(a2, c2, d));
+
+        let plan = weld_vertices(&navmesh, &[a, b, c, a2, c2, d], 0.01);
+
+        assert_eq!(plan.moved_vertices.len(), 2);
+        assert_eq!(plan.removed_vertices.len(), 2);
+        assert!(plan.removed_vertices.contains(&a2));
+        assert!(plan.removed_vertices.contains(&c2));
+        assert!(plan.removed_triangles.is_empty());
+        assert_eq!(
+            plan.modified_triangles,
+            vec![(
+                second,
+                [
+                    NavmeshCorner::Existing(a),
+                    NavmeshCorner::Existing(c),
+                    NavmeshCorner::Existing(d)
+                ]
+            )]
+        );
+    }
+
+    #[test]
+    fn weld_removes_collapsed_triangles() {
+        let (navmesh, [a, b, ..]) = square();
+
+        let plan = weld_vertices(&navmesh, &[a, b], 10.0);
+
+        assert_eq!(plan.removed_vertices, vec![b]);
+        assert_eq!(plan.removed_triangles.len(), 1);
+        assert_eq!(plan.modified_triangles.len(), 0);
+    }
+
+    #[test]
+    fn split_edge_splits_both_triangles() {
+        let (navmesh, [a, _, c, _]) = square();
+
+        let plan = split_edge(&navmesh, NavmeshEdge { begin: a, end: c });
+
+        assert_eq!(plan.added_vertices.len(), 1);
+        assert_eq!(plan.added_vertices[0].position, Vector3::default());
+        assert_eq!(plan.modified_triangles.len(), 2);
+        assert_eq!(plan.added_triangles.len(), 2);
+        assert!(plan.removed_triangles.is_empty());
+    }
+
+    #[test]
+    fn flip_edge_swaps_diagonal() {
+        let (navmesh, [a, b, c, d]) = square();
+
+        let plan = flip_edge(&navmesh, NavmeshEdge { begin: a, end: c }).unwrap();
+
+        assert_eq!(plan.modified_triangles.len(), 2);
+        for (_, corners) in plan.modified_triangles.iter() {
+            assert!(corners.contains(&NavmeshCorner::Existing(b)));
+            assert!(corners.contains(&NavmeshCorner::Existing(d)));
+        }
+        // Border edge belongs to one triangle only.
+        assert!(flip_edge(&navmesh, NavmeshEdge { begin: a, end: b }).is_none());
+    }
+
+    #[test]
+    fn subdivide_keeps_neighbours_connected() {
+        let (navmesh, _) = square();
+        let first = triangle_handles(&navmesh)[0];
+
+        let plan = subdivide_triangles(&navmesh, &[first]);
+
+        // Three midpoints, subdivided triangle gives four parts and its neighbour is split
+        // in two at the shared midpoint.
+        assert_eq!(plan.added_vertices.len(), 3);
+        assert_eq!(plan.modified_triangles.len(), 2);
+        assert_eq!(plan.added_triangles.len(), 4);
+    }
+}
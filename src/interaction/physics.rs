@@ -0,0 +1,905 @@
+//! Physics edit mode - shows handles for shape sizes, collider offset and joint anchors of
+//! selected node's body, and allows to drag them with move gizmo. Values are changed
+//! directly while dragging and committed as a single command when dragging ends.
+//! Collider rotation has no handle - move gizmo is aligned with collider's frame, rotating
+//! that frame while dragging would make it jump, so rotation is edited in the sidebar.
+
+use crate::{
+    interaction::{
+        calculate_gizmo_distance_scaling, InteractionModeTrait, MoveGizmo, MoveGizmoMode,
+    },
+    physics::{Collider, Joint, Physics, RigidBody},
+    scene::{
+        ChangeSelectionCommand, EditorScene, GraphSelection, SceneCommand, Selection,
+        SetBallJointAnchor1Command, SetBallJointAnchor2Command, SetBallRadiusCommand,
+        SetCapsuleBeginCommand, SetCapsuleEndCommand, SetCapsuleRadiusCommand,
+        SetColliderPositionCommand, SetConeHalfHeightCommand, SetConeRadiusCommand,
+        SetCuboidHalfExtentsCommand, SetCylinderHalfHeightCommand, SetCylinderRadiusCommand,
+        SetFixedJointAnchor1TranslationCommand, SetFixedJointAnchor2TranslationCommand,
+        SetPrismaticJointAnchor1Command, SetPrismaticJointAnchor2Command,
+        SetPrismaticJointAxis1Command, SetPrismaticJointAxis2Command,
+        SetRevoluteJointAnchor1Command, SetRevoluteJointAnchor2Command,
+        SetRevoluteJointAxis1Command, SetRevoluteJointAxis2Command,
+    },
+    GameEngine, Message,
+};
+use rg3d::{
+    core::{
+        algebra::{Isometry3, Point3, Translation3, UnitQuaternion, Vector2, Vector3},
+        color::Color,
+        pool::Handle,
+    },
+    scene::{
+        graph::Graph,
+        node::Node,
+        physics::{ColliderShapeDesc, JointParamsDesc},
+        Line,
+    },
+};
+use std::{cmp::Ordering, sync::mpsc::Sender};
+
+const HANDLE_RADIUS: f32 = 0.08;
+/// Sizes can't be zero, engine will panic on degenerated shapes.
+const MIN_SIZE: f32 = 0.001;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum PhysicsHandle {
+    ColliderOrigin,
+    /// Face of a cuboid, dragging it changes half extent along the axis.
+    HalfExtent {
+        axis: usize,
+        positive: bool,
+    },
+    Radius,
+    HalfHeight,
+    CapsuleBegin,
+    CapsuleEnd,
+    Anchor1,
+    Anchor2,
+    Axis1,
+    Axis2,
+}
+
+impl PhysicsHandle {
+    fn is_joint_handle(self) -> bool {
+        matches!(
+            self,
+            PhysicsHandle::Anchor1
+                | PhysicsHandle::Anchor2
+                | PhysicsHandle::Axis1
+                | PhysicsHandle::Axis2
+        )
+    }
+
+    fn color(self) -> Color {
+        match self {
+            PhysicsHandle::ColliderOrigin => Color::opaque(255, 0, 255),
+            PhysicsHandle::Anchor1 | PhysicsHandle::Anchor2 => Color::BLUE,
+            PhysicsHandle::Axis1 | PhysicsHandle::Axis2 => Color::opaque(0, 200, 255),
+            _ => Color::opaque(255, 200, 0),
+        }
+    }
+}
+
+/// Body, collider and joint that are edited - they're taken from current selection.
+#[derive(Copy, Clone, PartialEq, Eq)]
+struct Target {
+    body: Handle<RigidBody>,
+    collider: Handle<Collider>,
+    joint: Handle<Joint>,
+}
+
+impl Target {
+    fn from_selection(editor_scene: &EditorScene) -> Option<Self> {
+        if let Selection::Graph(selection) = &editor_scene.selection {
+            if selection.is_single_selection() {
+                let physics = &editor_scene.physics;
+                if let Some(&body) = physics.binder.value_of(&selection.nodes()[0]) {
+                    let collider = physics.bodies[body]
+                        .colliders
+                        .first()
+                        .map(|&c| c.into())
+                        // Convex colliders are edited through their points.
+                        .filter(|c| !physics.convex_sources.contains_key(c))
+                        .unwrap_or_default();
+                    return Some(Self {
+                        body,
+                        collider,
+                        joint: physics.find_joint(body),
+                    });
+                }
+            }
+        }
+        None
+    }
+
+    fn collider_frame(&self, physics: &Physics, graph: &Graph) -> Isometry3<f32> {
+        let collider = &physics.colliders[self.collider];
+        physics.body_frame(self.body, graph)
+            * Isometry3 {
+                rotation: collider.rotation,
+                translation: Translation3 {
+                    vector: collider.translation,
+                },
+            }
+    }
+
+    /// Frames of joint's first and second bodies, second one is `None` if joint is not
+    /// connected yet.
+    fn joint_frames(
+        &self,
+        physics: &Physics,
+        graph: &Graph,
+    ) -> (Isometry3<f32>, Option<Isometry3<f32>>) {
+        let joint = &physics.joints[self.joint];
+        let body2: Handle<RigidBody> = joint.body2.into();
+        (
+            physics.body_frame(self.body, graph),
+            if physics.bodies.is_valid_handle(body2) {
+                Some(physics.body_frame(body2, graph))
+            } else {
+                None
+            },
+        )
+    }
+
+    /// Returns every handle of the target with its position in world space.
+    fn handles(&self, physics: &Physics, graph: &Graph) -> Vec<(PhysicsHandle, Vector3<f32>)> {
+        let mut handles = Vec::new();
+
+        if self.collider.is_some() {
+            let frame = self.collider_frame(physics, graph);
+            let mut add = |handle, local: Vector3<f32>| {
+                handles.push((handle, frame.transform_point(&Point3::from(local)).coords));
+            };
+
+            add(PhysicsHandle::ColliderOrigin, Vector3::default());
+
+            match &physics.colliders[self.collider].shape {
+                ColliderShapeDesc::Ball(ball) => {
+                    add(PhysicsHandle::Radius, Vector3::new(ball.radius, 0.0, 0.0));
+                }
+                ColliderShapeDesc::Cylinder(cylinder) => {
+                    add(
+                        PhysicsHandle::Radius,
+                        Vector3::new(cylinder.radius, 0.0, 0.0),
+                    );
+                    add(
+                        PhysicsHandle::HalfHeight,
+                        Vector3::new(0.0, cylinder.half_height, 0.0),
+                    );
+                }
+                ColliderShapeDesc::Cone(cone) => {
+                    add(PhysicsHandle::Radius, Vector3::new(cone.radius, 0.0, 0.0));
+                    add(
+                        PhysicsHandle::HalfHeight,
+                        Vector3::new(0.0, cone.half_height, 0.0),
+                    );
+                }
+                ColliderShapeDesc::Cuboid(cuboid) => {
+                    for axis in 0..3 {
+                        for &positive in &[false, true] {
+                            let mut local = Vector3::default();
+                            local[axis] = if positive {
+                                cuboid.half_extents[axis]
+                            } else {
+                                -cuboid.half_extents[axis]
+                            };
+                            add(PhysicsHandle::HalfExtent { axis, positive }, local);
+                        }
+                    }
+                }
+                ColliderShapeDesc::Capsule(capsule) => {
+                    add(PhysicsHandle::CapsuleBegin, capsule.begin);
+                    add(PhysicsHandle::CapsuleEnd, capsule.end);
+                    add(
+                        PhysicsHandle::Radius,
+                        (capsule.begin + capsule.end).scale(0.5)
+                            + capsule_side(capsule.begin, capsule.end).scale(capsule.radius),
+                    );
+                }
+                _ => (),
+            }
+        }
+
+        if self.joint.is_some() {
+            let (frame1, frame2) = self.joint_frames(physics, graph);
+            let anchor = |frame: &Isometry3<f32>, local: Vector3<f32>| {
+                frame.transform_point(&Point3::from(local)).coords
+            };
+            let axis_end = |frame: &Isometry3<f32>, anchor: Vector3<f32>, axis: Vector3<f32>| {
+                anchor + frame.rotation * axis.try_normalize(std::f32::EPSILON).unwrap_or_default()
+            };
+
+            let (anchor1, anchor2, axes) = match &physics.joints[self.joint].params {
+                JointParamsDesc::BallJoint(ball) => (ball.local_anchor1, ball.local_anchor2, None),
+                JointParamsDesc::FixedJoint(fixed) => (
+                    fixed.local_anchor1_translation,
+                    fixed.local_anchor2_translation,
+                    None,
+                ),
+                JointParamsDesc::RevoluteJoint(revolute) => (
+                    revolute.local_anchor1,
+                    revolute.local_anchor2,
+                    Some((revolute.local_axis1, revolute.local_axis2)),
+                ),
+                JointParamsDesc::PrismaticJoint(prismatic) => (
+                    prismatic.local_anchor1,
+                    prismatic.local_anchor2,
+                    Some((prismatic.local_axis1, prismatic.local_axis2)),
+                ),
+            };
+
+            let world_anchor1 = anchor(&frame1, anchor1);
+            handles.push((PhysicsHandle::Anchor1, world_anchor1));
+            if let Some((axis1, _)) = axes {
+                handles.push((
+                    PhysicsHandle::Axis1,
+                    axis_end(&frame1, world_anchor1, axis1),
+                ));
+            }
+            if let Some(frame2) = frame2 {
+                let world_anchor2 = anchor(&frame2, anchor2);
+                handles.push((PhysicsHandle::Anchor2, world_anchor2));
+                if let Some((_, axis2)) = axes {
+                    handles.push((
+                        PhysicsHandle::Axis2,
+                        axis_end(&frame2, world_anchor2, axis2),
+                    ));
+                }
+            }
+        }
+
+        handles
+    }
+
+    /// Rotation of the frame handle is defined in, move gizmo is aligned with it.
+    fn handle_rotation(
+        &self,
+        handle: PhysicsHandle,
+        physics: &Physics,
+        graph: &Graph,
+    ) -> UnitQuaternion<f32> {
+        match handle {
+            PhysicsHandle::ColliderOrigin => physics.body_frame(self.body, graph).rotation,
+            PhysicsHandle::Anchor1 | PhysicsHandle::Axis1 => {
+                self.joint_frames(physics, graph).0.rotation
+            }
+            PhysicsHandle::Anchor2 | PhysicsHandle::Axis2 => self
+                .joint_frames(physics, graph)
+                .1
+                .map(|frame| frame.rotation)
+                .unwrap_or_default(),
+            _ => self.collider_frame(physics, graph).rotation,
+        }
+    }
+
+    /// Moves handle to given world position by changing corresponding property.
+    fn set_handle_position(
+        &self,
+        handle: PhysicsHandle,
+        position: Vector3<f32>,
+        physics: &mut Physics,
+        graph: &Graph,
+    ) {
+        if handle.is_joint_handle() {
+            let (frame1, frame2) = self.joint_frames(physics, graph);
+            let to_local = |frame: &Isometry3<f32>| {
+                frame
+                    .inverse_transform_point(&Point3::from(position))
+                    .coords
+            };
+            let local1 = to_local(&frame1);
+            let local2 = frame2.as_ref().map(to_local).unwrap_or_default();
+
+            // Axis is a direction from anchor to the handle.
+            let to_axis = |local: Vector3<f32>, anchor: Vector3<f32>, old: Vector3<f32>| {
+                (local - anchor)
+                    .try_normalize(std::f32::EPSILON)
+                    .unwrap_or(old)
+            };
+
+            match &mut physics.joints[self.joint].params {
+                JointParamsDesc::BallJoint(ball) => match handle {
+                    PhysicsHandle::Anchor1 => ball.local_anchor1 = local1,
+                    PhysicsHandle::Anchor2 => ball.local_anchor2 = local2,
+                    _ => (),
+                },
+                JointParamsDesc::FixedJoint(fixed) => match handle {
+                    PhysicsHandle::Anchor1 => fixed.local_anchor1_translation = local1,
+                    PhysicsHandle::Anchor2 => fixed.local_anchor2_translation = local2,
+                    _ => (),
+                },
+                JointParamsDesc::RevoluteJoint(revolute) => match handle {
+                    PhysicsHandle::Anchor1 => revolute.local_anchor1 = local1,
+                    PhysicsHandle::Anchor2 => revolute.local_anchor2 = local2,
+                    PhysicsHandle::Axis1 => {
+                        revolute.local_axis1 =
+                            to_axis(local1, revolute.local_anchor1, revolute.local_axis1)
+                    }
+                    PhysicsHandle::Axis2 => {
+                        revolute.local_axis2 =
+                            to_axis(local2, revolute.local_anchor2, revolute.local_axis2)
+                    }
+                    _ => (),
+                },
+                JointParamsDesc::PrismaticJoint(prismatic) => match handle {
+                    PhysicsHandle::Anchor1 => prismatic.local_anchor1 = local1,
+                    PhysicsHandle::Anchor2 => prismatic.local_anchor2 = local2,
+                    PhysicsHandle::Axis1 => {
+                        prismatic.local_axis1 =
+                            to_axis(local1, prismatic.local_anchor1, prismatic.local_axis1)
+                    }
+                    PhysicsHandle::Axis2 => {
+                        prismatic.local_axis2 =
+                            to_axis(local2, prismatic.local_anchor2, prismatic.local_axis2)
+                    }
+                    _ => (),
+                },
+            }
+        } else if handle == PhysicsHandle::ColliderOrigin {
+            physics.colliders[self.collider].translation = physics
+                .body_frame(self.body, graph)
+                .inverse_transform_point(&Point3::from(position))
+                .coords;
+        } else {
+            let local = self
+                .collider_frame(physics, graph)
+                .inverse_transform_point(&Point3::from(position))
+                .coords;
+            let radial = Vector2::new(local.x, local.z).norm().max(MIN_SIZE);
+            let height = local.y.abs().max(MIN_SIZE);
+
+            match &mut physics.colliders[self.collider].shape {
+                ColliderShapeDesc::Ball(ball) => ball.radius = local.norm().max(MIN_SIZE),
+                ColliderShapeDesc::Cylinder(cylinder) => match handle {
+                    PhysicsHandle::Radius => cylinder.radius = radial,
+                    PhysicsHandle::HalfHeight => cylinder.half_height = height,
+                    _ => (),
+                },
+                ColliderShapeDesc::Cone(cone) => match handle {
+                    PhysicsHandle::Radius => cone.radius = radial,
+                    PhysicsHandle::HalfHeight => cone.half_height = height,
+                    _ => (),
+                },
+                ColliderShapeDesc::Cuboid(cuboid) => {
+                    if let PhysicsHandle::HalfExtent { axis, .. } = handle {
+                        cuboid.half_extents[axis] = local[axis].abs().max(MIN_SIZE);
+                    }
+                }
+                ColliderShapeDesc::Capsule(capsule) => match handle {
+                    PhysicsHandle::CapsuleBegin => capsule.begin = local,
+                    PhysicsHandle::CapsuleEnd => capsule.end = local,
+                    PhysicsHandle::Radius => {
+                        let center = (capsule.begin + capsule.end).scale(0.5);
+                        capsule.radius = (local - center)
+                            .dot(&capsule_side(capsule.begin, capsule.end))
+                            .max(MIN_SIZE);
+                    }
+                    _ => (),
+                },
+                _ => (),
+            }
+        }
+    }
+}
+
+/// Direction perpendicular to capsule's segment, radius handle is placed along it.
+fn capsule_side(begin: Vector3<f32>, end: Vector3<f32>) -> Vector3<f32> {
+    let axis = (end - begin)
+        .try_normalize(std::f32::EPSILON)
+        .unwrap_or_else(Vector3::y);
+    let side = axis.cross(&Vector3::z());
+    side.try_normalize(std::f32::EPSILON)
+        .unwrap_or_else(|| axis.cross(&Vector3::x()).normalize())
+}
+
+/// Makes command that sets current value of handle's property, property itself is set
+/// back to its initial value (taken from `initial` snapshot) so command can do the change.
+fn make_command(
+    handle: PhysicsHandle,
+    target: Target,
+    initial: &Snapshot,
+    physics: &mut Physics,
+) -> Option<SceneCommand> {
+    macro_rules! commit {
+        ($command:ident, $variant:ident, $handle:expr, $current:expr, $initial:expr) => {{
+            let value = std::mem::replace($current, $initial);
+            Some(SceneCommand::$variant($command::new($handle, value)))
+        }};
+    }
+
+    if handle.is_joint_handle() {
+        let initial = initial.joint.as_ref()?;
+        let joint = &mut physics.joints[target.joint];
+        match (&mut joint.params, &initial.params) {
+            (JointParamsDesc::BallJoint(current), JointParamsDesc::BallJoint(initial)) => {
+                match handle {
+                    PhysicsHandle::Anchor1 => commit!(
+                        SetBallJointAnchor1Command,
+                        SetBallJointAnchor1,
+                        target.joint,
+                        &mut current.local_anchor1,
+                        initial.local_anchor1
+                    ),
+                    PhysicsHandle::Anchor2 => commit!(
+                        SetBallJointAnchor2Command,
+                        SetBallJointAnchor2,
+                        target.joint,
+                        &mut current.local_anchor2,
+                        initial.local_anchor2
+                    ),
+                    _ => None,
+                }
+            }
+            (JointParamsDesc::FixedJoint(current), JointParamsDesc::FixedJoint(initial)) => {
+                match handle {
+                    PhysicsHandle::Anchor1 => commit!(
+                        SetFixedJointAnchor1TranslationCommand,
+                        SetFixedJointAnchor1Translation,
+                        target.joint,
+                        &mut current.local_anchor1_translation,
+                        initial.local_anchor1_translation
+                    ),
+                    PhysicsHandle::Anchor2 => commit!(
+                        SetFixedJointAnchor2TranslationCommand,
+                        SetFixedJointAnchor2Translation,
+                        target.joint,
+                        &mut current.local_anchor2_translation,
+                        initial.local_anchor2_translation
+                    ),
+                    _ => None,
+                }
+            }
+            (JointParamsDesc::RevoluteJoint(current), JointParamsDesc::RevoluteJoint(initial)) => {
+                match handle {
+                    PhysicsHandle::Anchor1 => commit!(
+                        SetRevoluteJointAnchor1Command,
+                        SetRevoluteJointAnchor1,
+                        target.joint,
+                        &mut current.local_anchor1,
+                        initial.local_anchor1
+                    ),
+                    PhysicsHandle::Anchor2 => commit!(
+                        SetRevoluteJointAnchor2Command,
+                        SetRevoluteJointAnchor2,
+                        target.joint,
+                        &mut current.local_anchor2,
+                        initial.local_anchor2
+                    ),
+                    PhysicsHandle::Axis1 => commit!(
+                        SetRevoluteJointAxis1Command,
+                        SetRevoluteJointAxis1,
+                        target.joint,
+                        &mut current.local_axis1,
+                        initial.local_axis1
+                    ),
+                    PhysicsHandle::Axis2 => commit!(
+                        SetRevoluteJointAxis2Command,
+                        SetRevoluteJointAxis2,
+                        target.joint,
+                        &mut current.local_axis2,
+                        initial.local_axis2
+                    ),
+                    _ => None,
+                }
+            }
+            (
+                JointParamsDesc::PrismaticJoint(current),
+                JointParamsDesc::PrismaticJoint(initial),
+            ) => match handle {
+                PhysicsHandle::Anchor1 => commit!(
+                    SetPrismaticJointAnchor1Command,
+                    SetPrismaticJointAnchor1,
+                    target.joint,
+                    &mut current.local_anchor1,
+                    initial.local_anchor1
+                ),
+                PhysicsHandle::Anchor2 => commit!(
+                    SetPrismaticJointAnchor2Command,
+                    SetPrismaticJointAnchor2,
+                    target.joint,
+                    &mut current.local_anchor2,
+                    initial.local_anchor2
+                ),
+                PhysicsHandle::Axis1 => commit!(
+                    SetPrismaticJointAxis1Command,
+                    SetPrismaticJointAxis1,
+                    target.joint,
+                    &mut current.local_axis1,
+                    initial.local_axis1
+                ),
+                PhysicsHandle::Axis2 => commit!(
+                    SetPrismaticJointAxis2Command,
+                    SetPrismaticJointAxis2,
+                    target.joint,
+                    &mut current.local_axis2,
+                    initial.local_axis2
+                ),
+                _ => None,
+            },
+            _ => None,
+        }
+    } else {
+        let initial = initial.collider.as_ref()?;
+        let collider = &mut physics.colliders[target.collider];
+        if handle == PhysicsHandle::ColliderOrigin {
+            return commit!(
+                SetColliderPositionCommand,
+                SetColliderPosition,
+                target.collider,
+                &mut collider.translation,
+                initial.translation
+            );
+        }
+        match (&mut collider.shape, &initial.shape) {
+            (ColliderShapeDesc::Ball(current), ColliderShapeDesc::Ball(initial)) => {
+                commit!(
+                    SetBallRadiusCommand,
+                    SetBallRadius,
+                    target.collider,
+                    &mut current.radius,
+                    initial.radius
+                )
+            }
+            (ColliderShapeDesc::Cylinder(current), ColliderShapeDesc::Cylinder(initial)) => {
+                match handle {
+                    PhysicsHandle::Radius => commit!(
+                        SetCylinderRadiusCommand,
+                        SetCylinderRadius,
+                        target.collider,
+                        &mut current.radius,
+                        initial.radius
+                    ),
+                    PhysicsHandle::HalfHeight => commit!(
+                        SetCylinderHalfHeightCommand,
+                        SetCylinderHalfHeight,
+                        target.collider,
+                        &mut current.half_height,
+                        initial.half_height
+                    ),
+                    _ => None,
+                }
+            }
+            (ColliderShapeDesc::Cone(current), ColliderShapeDesc::Cone(initial)) => match handle {
+                PhysicsHandle::Radius => commit!(
+                    SetConeRadiusCommand,
+                    SetConeRadius,
+                    target.collider,
+                    &mut current.radius,
+                    initial.radius
+                ),
+                PhysicsHandle::HalfHeight => commit!(
+                    SetConeHalfHeightCommand,
+                    SetConeHalfHeight,
+                    target.collider,
+                    &mut current.half_height,
+                    initial.half_height
+                ),
+                _ => None,
+            },
+            (ColliderShapeDesc::Cuboid(current), ColliderShapeDesc::Cuboid(initial)) => {
+                commit!(
+                    SetCuboidHalfExtentsCommand,
+                    SetCuboidHalfExtents,
+                    target.collider,
+                    &mut current.half_extents,
+                    initial.half_extents
+                )
+            }
+            (ColliderShapeDesc::Capsule(current), ColliderShapeDesc::Capsule(initial)) => {
+                match handle {
+                    PhysicsHandle::CapsuleBegin => commit!(
+                        SetCapsuleBeginCommand,
+                        SetCapsuleBegin,
+                        target.collider,
+                        &mut current.begin,
+                        initial.begin
+                    ),
+                    PhysicsHandle::CapsuleEnd => commit!(
+                        SetCapsuleEndCommand,
+                        SetCapsuleEnd,
+                        target.collider,
+                        &mut current.end,
+                        initial.end
+                    ),
+                    PhysicsHandle::Radius => commit!(
+                        SetCapsuleRadiusCommand,
+                        SetCapsuleRadius,
+                        target.collider,
+                        &mut current.radius,
+                        initial.radius
+                    ),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+/// State of edited entities at the moment dragging has started.
+struct Snapshot {
+    collider: Option<Collider>,
+    joint: Option<Joint>,
+}
+
+struct DragContext {
+    target: Target,
+    handle: PhysicsHandle,
+    /// World position of the handle, accumulates gizmo offsets.
+    position: Vector3<f32>,
+    initial: Snapshot,
+}
+
+pub struct EditPhysicsMode {
+    move_gizmo: MoveGizmo,
+    message_sender: Sender<Message>,
+    /// Selected handle, gizmo is shown at its position.
+    active: Option<(Target, PhysicsHandle)>,
+    drag_context: Option<DragContext>,
+    /// Set if last click has picked a handle, such click must not change selection.
+    handle_clicked: bool,
+}
+
+impl EditPhysicsMode {
+    pub fn new(
+        editor_scene: &EditorScene,
+        engine: &mut GameEngine,
+        message_sender: Sender<Message>,
+    ) -> Self {
+        Self {
+            move_gizmo: MoveGizmo::new(editor_scene, engine),
+            message_sender,
+            active: None,
+            drag_context: None,
+            handle_clicked: false,
+        }
+    }
+
+    /// Returns active handle if it still belongs to current selection.
+    fn active_handle(&self, editor_scene: &EditorScene) -> Option<(Target, PhysicsHandle)> {
+        let (target, handle) = self.active?;
+        if Target::from_selection(editor_scene) == Some(target) {
+            Some((target, handle))
+        } else {
+            None
+        }
+    }
+}
+
+impl InteractionModeTrait for EditPhysicsMode {
+    fn on_left_mouse_button_down(
+        &mut self,
+        editor_scene: &mut EditorScene,
+        engine: &mut GameEngine,
+        mouse_pos: Vector2<f32>,
+        frame_size: Vector2<f32>,
+    ) {
+        self.handle_clicked = false;
+
+        let target = match Target::from_selection(editor_scene) {
+            Some(target) => target,
+            None => return,
+        };
+
+        let graph = &engine.scenes[editor_scene.scene].graph;
+
+        let camera = editor_scene.camera_controller.camera;
+        let camera_pivot = editor_scene.camera_controller.pivot;
+        let editor_node = editor_scene.camera_controller.pick(
+            mouse_pos,
+            graph,
+            editor_scene.root,
+            frame_size,
+            true,
+            |handle, _| {
+                handle != camera && handle != camera_pivot && handle != self.move_gizmo.origin
+            },
+        );
+
+        if let Some((target, handle)) = self.active_handle(editor_scene) {
+            if self
+                .move_gizmo
+                .handle_pick(editor_node, editor_scene, engine)
+            {
+                let graph = &engine.scenes[editor_scene.scene].graph;
+                let physics = &editor_scene.physics;
+                if let Some(&(_, position)) = target
+                    .handles(physics, graph)
+                    .iter()
+                    .find(|(h, _)| *h == handle)
+                {
+                    self.drag_context = Some(DragContext {
+                        target,
+                        handle,
+                        position,
+                        initial: Snapshot {
+                            collider: physics.colliders.try_borrow(target.collider).cloned(),
+                            joint: physics.joints.try_borrow(target.joint).cloned(),
+                        },
+                    });
+                    self.handle_clicked = true;
+                }
+                return;
+            }
+        }
+
+        let graph = &engine.scenes[editor_scene.scene].graph;
+        let ray = graph[camera].as_camera().make_ray(mouse_pos, frame_size);
+        let picked = target
+            .handles(&editor_scene.physics, graph)
+            .into_iter()
+            .filter_map(|(handle, position)| {
+                ray.sphere_intersection(&position, HANDLE_RADIUS)
+                    .map(|result| (handle, result.min))
+            })
+            .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+            .map(|(handle, _)| handle);
+
+        if let Some(handle) = picked {
+            self.active = Some((target, handle));
+            self.handle_clicked = true;
+        }
+    }
+
+    fn on_left_mouse_button_up(
+        &mut self,
+        editor_scene: &mut EditorScene,
+        engine: &mut GameEngine,
+        mouse_pos: Vector2<f32>,
+        frame_size: Vector2<f32>,
+    ) {
+        if let Some(drag_context) = self.drag_context.take() {
+            if let Some(command) = make_command(
+                drag_context.handle,
+                drag_context.target,
+                &drag_context.initial,
+                &mut editor_scene.physics,
+            ) {
+                self.message_sender
+                    .send(Message::DoSceneCommand(command))
+                    .unwrap();
+            }
+        } else if !self.handle_clicked {
+            // Click on empty space - select other node, as in other modes.
+            let graph = &engine.scenes[editor_scene.scene].graph;
+            let picked = editor_scene.camera_controller.pick(
+                mouse_pos,
+                graph,
+                editor_scene.root,
+                frame_size,
+                false,
+                |_, _| true,
+            );
+            let new_selection = Selection::Graph(GraphSelection::single_or_empty(picked));
+            if new_selection != editor_scene.selection {
+                self.active = None;
+                self.message_sender
+                    .send(Message::DoSceneCommand(SceneCommand::ChangeSelection(
+                        ChangeSelectionCommand::new(new_selection, editor_scene.selection.clone()),
+                    )))
+                    .unwrap();
+            }
+        }
+
+        let graph = &mut engine.scenes[editor_scene.scene].graph;
+        self.move_gizmo.set_mode(MoveGizmoMode::None, graph);
+    }
+
+    fn on_mouse_move(
+        &mut self,
+        mouse_offset: Vector2<f32>,
+        mouse_position: Vector2<f32>,
+        camera: Handle<Node>,
+        editor_scene: &mut EditorScene,
+        engine: &mut GameEngine,
+        frame_size: Vector2<f32>,
+    ) {
+        if self.drag_context.is_some() {
+            let offset = self.move_gizmo.calculate_offset(
+                editor_scene,
+                camera,
+                mouse_offset,
+                mouse_position,
+                engine,
+                frame_size,
+            );
+
+            let graph = &engine.scenes[editor_scene.scene].graph;
+            let drag_context = self.drag_context.as_mut().unwrap();
+            drag_context.position += offset;
+            drag_context.target.set_handle_position(
+                drag_context.handle,
+                drag_context.position,
+                &mut editor_scene.physics,
+                graph,
+            );
+        }
+    }
+
+    fn update(
+        &mut self,
+        editor_scene: &mut EditorScene,
+        camera: Handle<Node>,
+        engine: &mut GameEngine,
+    ) {
+        let scene = &mut engine.scenes[editor_scene.scene];
+        self.move_gizmo.set_visible(&mut scene.graph, false);
+
+        let target = match Target::from_selection(editor_scene) {
+            Some(target) => target,
+            None => return,
+        };
+        let active = self.active_handle(editor_scene);
+        let physics = &editor_scene.physics;
+        let handles = target.handles(physics, &scene.graph);
+
+        for &(handle, position) in handles.iter() {
+            let color = if active.map(|(_, h)| h) == Some(handle) {
+                Color::opaque(255, 255, 0)
+            } else {
+                handle.color()
+            };
+            scene
+                .drawing_context
+                .draw_sphere(position, 6, 6, HANDLE_RADIUS, color);
+        }
+
+        // Connect anchors with their axes and with each other so it is clear what belongs to what.
+        let position_of = |handle| {
+            handles
+                .iter()
+                .find(|(h, _)| *h == handle)
+                .map(|&(_, position)| position)
+        };
+        for &(begin, end) in &[
+            (PhysicsHandle::Anchor1, PhysicsHandle::Axis1),
+            (PhysicsHandle::Anchor2, PhysicsHandle::Axis2),
+            (PhysicsHandle::Anchor1, PhysicsHandle::Anchor2),
+            (PhysicsHandle::CapsuleBegin, PhysicsHandle::CapsuleEnd),
+        ] {
+            if let (Some(begin), Some(end)) = (position_of(begin), position_of(end)) {
+                scene.drawing_context.add_line(Line {
+                    begin,
+                    end,
+                    color: Color::opaque(0, 200, 255),
+                });
+            }
+        }
+
+        if let Some((target, handle)) = active {
+            if let Some(position) = position_of(handle) {
+                let scale =
+                    calculate_gizmo_distance_scaling(&scene.graph, camera, self.move_gizmo.origin);
+                let rotation = target.handle_rotation(handle, physics, &scene.graph);
+                self.move_gizmo.set_visible(&mut scene.graph, true);
+                self.move_gizmo
+                    .transform(&mut scene.graph)
+                    .set_scale(scale)
+                    .set_rotation(rotation)
+                    .set_position(position);
+            }
+        }
+    }
+
+    fn deactivate(&mut self, editor_scene: &mut EditorScene, engine: &mut GameEngine) {
+        // Mode can be switched by a shortcut while dragging, keep the change undoable.
+        if let Some(drag_context) = self.drag_context.take() {
+            if let Some(command) = make_command(
+                drag_context.handle,
+                drag_context.target,
+                &drag_context.initial,
+                &mut editor_scene.physics,
+            ) {
+                self.message_sender
+                    .send(Message::DoSceneCommand(command))
+                    .unwrap();
+            }
+        }
+
+        let scene = &mut engine.scenes[editor_scene.scene];
+        self.move_gizmo.set_visible(&mut scene.graph, false);
+        self.active = None;
+    }
+}
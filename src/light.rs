@@ -1,41 +1,263 @@
 use crate::{
     gui::{UiMessage, UiNode},
     scene::EditorScene,
-    GameEngine,
+    GameEngine, Message,
 };
 use rg3d::core::scope_profile;
 use rg3d::{
     core::pool::Handle,
+    engine::resource_manager::ResourceManager,
     gui::{
         button::ButtonBuilder,
         grid::{Column, GridBuilder, Row},
         message::ButtonMessage,
-        message::{MessageDirection, NumericUpDownMessage, UiMessageData},
+        message::{
+            MessageDirection, NumericUpDownMessage, ProgressBarMessage, TextBoxMessage,
+            TextMessage, UiMessageData, WidgetMessage,
+        },
         numeric::NumericUpDownBuilder,
+        progress_bar::ProgressBarBuilder,
         text::TextBuilder,
+        text_box::TextBoxBuilder,
         widget::WidgetBuilder,
         window::{WindowBuilder, WindowTitle},
         Thickness, VerticalAlignment,
     },
-    scene::node::Node,
-    utils::lightmap::Lightmap,
+    renderer::surface::{Surface, SurfaceSharedData},
+    scene::{node::Node, Scene},
+    utils::lightmap::{
+        CancellationToken, Lightmap, LightmapGenerationError, ProgressIndicator, ProgressStage,
+    },
     utils::uvgen,
 };
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, RwLock,
+    },
+};
+
+/// Lightmap generation running on a worker thread, so the editor stays responsive. Worker
+/// bakes a copy of the scene with its own copy of surface data, because generated UVs must
+/// not get into the edited scene until the lightmap is applied - cancelled or failed job
+/// leaves the scene as it was.
+struct LightmapJob {
+    scene: Handle<Scene>,
+    cancellation_token: CancellationToken,
+    progress_indicator: ProgressIndicator,
+    receiver: Receiver<Result<BakedLightmap, String>>,
+}
+
+/// Lightmap together with surface data of meshes with UVs it was baked for, meshes are
+/// nodes of the edited scene.
+struct BakedLightmap {
+    lightmap: Lightmap,
+    surfaces: Vec<(Handle<Node>, Vec<Arc<RwLock<SurfaceSharedData>>>)>,
+}
+
+impl BakedLightmap {
+    /// Copies baked surface data to meshes of the scene, the lightmap is sampled using UVs
+    /// that were generated while baking.
+    fn copy_surfaces(&self, scene: &Scene) {
+        for (node, surfaces) in self.surfaces.iter() {
+            if !scene.graph.is_valid_handle(*node) {
+                continue;
+            }
+            if let Node::Mesh(mesh) = &scene.graph[*node] {
+                for (surface, baked) in mesh.surfaces().iter().zip(surfaces.iter()) {
+                    *surface.data().write().unwrap() = baked.read().unwrap().clone();
+                }
+            }
+        }
+    }
+}
+
+/// Gives meshes of the scene their own copy of surface data, meshes that share data keep
+/// sharing their copy. Surfaces of the copy are only baked, so textures are not copied.
+fn detach_surface_data(scene: &mut Scene) {
+    let mut copies = HashMap::new();
+    for node in scene.graph.linear_iter_mut() {
+        if let Node::Mesh(mesh) = node {
+            for surface in mesh.surfaces_mut() {
+                let data = surface.data();
+                let copy = copies
+                    .entry(Arc::as_ptr(&data))
+                    .or_insert_with(|| Arc::new(RwLock::new(data.read().unwrap().clone())))
+                    .clone();
+                *surface = Surface::new(copy);
+            }
+        }
+    }
+}
+
+impl LightmapJob {
+    fn start(
+        scene: Handle<Scene>,
+        source: &Scene,
+        editor_root: Handle<Node>,
+        texels_per_unit: u32,
+        spacing: f32,
+        output_folder: PathBuf,
+        resource_manager: ResourceManager,
+    ) -> Self {
+        let cancellation_token = CancellationToken::new();
+        let progress_indicator = ProgressIndicator::new();
+        let (sender, receiver) = mpsc::channel();
+
+        // Editor nodes must not be lit and baked.
+        let (mut scene_copy, old_to_new) = source.clone(&mut |node, _| node != editor_root);
+        detach_surface_data(&mut scene_copy);
+        let new_to_old = old_to_new
+            .into_iter()
+            .map(|(old, new)| (new, old))
+            .collect::<HashMap<_, _>>();
+
+        let token = cancellation_token.clone();
+        let progress = progress_indicator.clone();
+        std::thread::spawn(move || {
+            let result = generate_lightmap(
+                &mut scene_copy,
+                &new_to_old,
+                texels_per_unit,
+                spacing,
+                &output_folder,
+                resource_manager,
+                token,
+                progress,
+            );
+            // Receiver is gone if the editor has exited, the result isn't needed then.
+            let _ = sender.send(result);
+        });
+
+        Self {
+            scene,
+            cancellation_token,
+            progress_indicator,
+            receiver,
+        }
+    }
+
+    /// Lightmap can be applied only to the scene it was baked for. Scene opened while the
+    /// job was running may reuse pool slot of the closed one, but never its handle.
+    fn is_for(&self, scene: Option<Handle<Scene>>) -> bool {
+        scene == Some(self.scene)
+    }
+}
+
+fn generate_uvs(
+    scene: &Scene,
+    spacing: f32,
+    cancellation_token: &CancellationToken,
+) -> Result<(), String> {
+    // Surface data of the copy is detached from the edited scene, see `LightmapJob::start`.
+    for node in scene.graph.linear_iter() {
+        if cancellation_token.is_cancelled() {
+            return Err("Lightmap generation was cancelled.".to_owned());
+        }
+        if let Node::Mesh(mesh) = node {
+            uvgen::generate_uvs_mesh(mesh, spacing);
+        }
+    }
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn generate_lightmap(
+    scene: &mut Scene,
+    new_to_old: &HashMap<Handle<Node>, Handle<Node>>,
+    texels_per_unit: u32,
+    spacing: f32,
+    output_folder: &Path,
+    resource_manager: ResourceManager,
+    cancellation_token: CancellationToken,
+    progress_indicator: ProgressIndicator,
+) -> Result<BakedLightmap, String> {
+    generate_uvs(scene, spacing, &cancellation_token)?;
+
+    let mut lightmap = Lightmap::new(
+        scene,
+        texels_per_unit,
+        cancellation_token,
+        progress_indicator,
+    )
+    .map_err(|e| match e {
+        LightmapGenerationError::Cancelled => "Lightmap generation was cancelled.".to_owned(),
+    })?;
+
+    // Lightmap was made for nodes of the copy, it is remapped to nodes of the edited scene
+    // before saving so texture names match them.
+    lightmap.map = lightmap
+        .map
+        .into_iter()
+        .filter_map(|(node, entries)| new_to_old.get(&node).map(|&old| (old, entries)))
+        .collect();
+
+    std::fs::create_dir_all(output_folder).map_err(|e| {
+        format!(
+            "Failed to create lightmap output folder {}! Reason: {}",
+            output_folder.display(),
+            e
+        )
+    })?;
+    lightmap
+        .save(output_folder, resource_manager)
+        .map_err(|e| format!("Failed to save lightmap! Reason: {:?}", e))?;
+
+    let surfaces = scene
+        .graph
+        .pair_iter()
+        .filter_map(|(handle, node)| match (node, new_to_old.get(&handle)) {
+            (Node::Mesh(mesh), Some(&old)) => Some((
+                old,
+                mesh.surfaces()
+                    .iter()
+                    .map(|surface| surface.data())
+                    .collect(),
+            )),
+            _ => None,
+        })
+        .collect();
+
+    Ok(BakedLightmap { lightmap, surfaces })
+}
+
+fn stage_name(stage: ProgressStage) -> &'static str {
+    match stage {
+        ProgressStage::LightsCaching => "Caching Lights",
+        ProgressStage::UvGeneration => "Generating UVs",
+        ProgressStage::GeometryCaching => "Caching Geometry",
+        ProgressStage::CalculatingLight => "Calculating Light",
+    }
+}
 
 pub struct LightPanel {
     pub window: Handle<UiNode>,
     nud_texels_per_unit: Handle<UiNode>,
     nud_spacing: Handle<UiNode>,
+    tb_output_folder: Handle<UiNode>,
     generate: Handle<UiNode>,
+    cancel: Handle<UiNode>,
+    progress_bar: Handle<UiNode>,
+    progress_text: Handle<UiNode>,
     texels_per_unit: u32,
     spacing: f32,
+    output_folder: String,
+    job: Option<LightmapJob>,
+    sender: Sender<Message>,
 }
 
 impl LightPanel {
-    pub fn new(engine: &mut GameEngine) -> Self {
+    pub fn new(engine: &mut GameEngine, sender: Sender<Message>) -> Self {
         let generate;
+        let cancel;
         let nud_texels_per_unit;
         let nud_spacing;
+        let tb_output_folder;
+        let progress_bar;
+        let progress_text;
+        let output_folder = "./".to_owned();
         let ctx = &mut engine.user_interface.build_ctx();
         let window = WindowBuilder::new(WidgetBuilder::new().with_width(300.0).with_height(400.0))
             .with_title(WindowTitle::Text("Light Settings".to_owned()))
@@ -93,16 +315,70 @@ impl LightPanel {
                             .build(ctx);
                             nud_spacing
                         })
+                        .with_child(
+                            TextBuilder::new(
+                                WidgetBuilder::new()
+                                    .on_row(2)
+                                    .on_column(0)
+                                    .with_vertical_alignment(VerticalAlignment::Center),
+                            )
+                            .with_text("Output Folder")
+                            .build(ctx),
+                        )
                         .with_child({
-                            generate = ButtonBuilder::new(
+                            tb_output_folder = TextBoxBuilder::new(
                                 WidgetBuilder::new()
                                     .on_row(2)
                                     .on_column(1)
                                     .with_margin(Thickness::uniform(1.0)),
                             )
+                            .with_vertical_text_alignment(VerticalAlignment::Center)
+                            .with_text(&output_folder)
+                            .build(ctx);
+                            tb_output_folder
+                        })
+                        .with_child({
+                            generate = ButtonBuilder::new(
+                                WidgetBuilder::new()
+                                    .on_row(3)
+                                    .on_column(1)
+                                    .with_margin(Thickness::uniform(1.0)),
+                            )
                             .with_text("Generate Lightmap")
                             .build(ctx);
                             generate
+                        })
+                        .with_child({
+                            progress_bar = ProgressBarBuilder::new(
+                                WidgetBuilder::new()
+                                    .on_row(4)
+                                    .on_column(1)
+                                    .with_margin(Thickness::uniform(1.0)),
+                            )
+                            .build(ctx);
+                            progress_bar
+                        })
+                        .with_child({
+                            progress_text = TextBuilder::new(
+                                WidgetBuilder::new()
+                                    .on_row(5)
+                                    .on_column(1)
+                                    .with_vertical_alignment(VerticalAlignment::Center),
+                            )
+                            .build(ctx);
+                            progress_text
+                        })
+                        .with_child({
+                            cancel = ButtonBuilder::new(
+                                WidgetBuilder::new()
+                                    .on_row(6)
+                                    .on_column(1)
+                                    .with_enabled(false)
+                                    .with_margin(Thickness::uniform(1.0)),
+                            )
+                            .with_text("Cancel")
+                            .build(ctx);
+                            cancel
                         }),
                 )
                 .add_column(Column::strict(100.0))
@@ -110,6 +386,10 @@ impl LightPanel {
                 .add_row(Row::strict(25.0))
                 .add_row(Row::strict(25.0))
                 .add_row(Row::strict(25.0))
+                .add_row(Row::strict(25.0))
+                .add_row(Row::strict(25.0))
+                .add_row(Row::strict(25.0))
+                .add_row(Row::strict(25.0))
                 .add_row(Row::stretch())
                 .build(ctx),
             )
@@ -118,13 +398,102 @@ impl LightPanel {
         Self {
             window,
             generate,
+            cancel,
             nud_texels_per_unit,
             texels_per_unit: 128,
             nud_spacing,
             spacing: 0.02,
+            tb_output_folder,
+            output_folder,
+            progress_bar,
+            progress_text,
+            job: None,
+            sender,
+        }
+    }
+
+    fn set_job_running(&self, engine: &GameEngine, running: bool) {
+        let ui = &engine.user_interface;
+        ui.send_message(WidgetMessage::enabled(
+            self.generate,
+            MessageDirection::ToWidget,
+            !running,
+        ));
+        ui.send_message(WidgetMessage::enabled(
+            self.cancel,
+            MessageDirection::ToWidget,
+            running,
+        ));
+        ui.send_message(ProgressBarMessage::progress(
+            self.progress_bar,
+            MessageDirection::ToWidget,
+            0.0,
+        ));
+        if !running {
+            ui.send_message(TextMessage::text(
+                self.progress_text,
+                MessageDirection::ToWidget,
+                Default::default(),
+            ));
         }
     }
 
+    /// Shows progress of lightmap generation and applies finished lightmap. Lightmap is
+    /// discarded if its scene was closed while it was generated.
+    pub fn update(&mut self, editor_scene: Option<&EditorScene>, engine: &mut GameEngine) {
+        scope_profile!();
+
+        let result = match self.job.as_ref() {
+            Some(job) => match job.receiver.try_recv() {
+                Ok(result) => result,
+                Err(mpsc::TryRecvError::Empty) => {
+                    let progress = &job.progress_indicator;
+                    engine
+                        .user_interface
+                        .send_message(ProgressBarMessage::progress(
+                            self.progress_bar,
+                            MessageDirection::ToWidget,
+                            progress.progress_percent() as f32 / 100.0,
+                        ));
+                    engine.user_interface.send_message(TextMessage::text(
+                        self.progress_text,
+                        MessageDirection::ToWidget,
+                        format!(
+                            "{}: {}%",
+                            stage_name(progress.stage()),
+                            progress.progress_percent()
+                        ),
+                    ));
+                    return;
+                }
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    Err("Lightmap generation has failed unexpectedly.".to_owned())
+                }
+            },
+            None => return,
+        };
+
+        let job = self.job.take().unwrap();
+        self.set_job_running(engine, false);
+
+        let message = match result {
+            Ok(baked) => {
+                if job.is_for(editor_scene.map(|editor_scene| editor_scene.scene)) {
+                    let scene = &mut engine.scenes[job.scene];
+                    baked.copy_surfaces(scene);
+                    match scene.set_lightmap(baked.lightmap) {
+                        Ok(_) => "Lightmap was successfully generated!".to_owned(),
+                        Err(e) => format!("Failed to apply lightmap! Reason: {:?}", e),
+                    }
+                } else {
+                    "Lightmap was discarded, because its scene was closed.".to_owned()
+                }
+            }
+            Err(e) => e,
+        };
+        self.sender.send(Message::Log(message)).unwrap();
+    }
+
     pub fn handle_ui_message(
         &mut self,
         message: &UiMessage,
@@ -135,26 +504,32 @@ impl LightPanel {
 
         match message.data() {
             UiMessageData::Button(ButtonMessage::Click) => {
-                if message.destination() == self.generate {
-                    let scene = &mut engine.scenes[editor_scene.scene];
-
-                    for node in scene.graph.linear_iter() {
-                        if let Node::Mesh(mesh) = node {
-                            uvgen::generate_uvs_mesh(mesh, self.spacing);
-                        }
+                if message.destination() == self.generate && self.job.is_none() {
+                    let output_folder = PathBuf::from(&self.output_folder);
+                    if output_folder.is_absolute() {
+                        self.sender
+                            .send(Message::Log(
+                                "Lightmap output folder must be relative to working directory!"
+                                    .to_owned(),
+                            ))
+                            .unwrap();
+                        return;
                     }
 
-                    let lightmap = Lightmap::new(
-                        scene,
+                    self.job = Some(LightmapJob::start(
+                        editor_scene.scene,
+                        &engine.scenes[editor_scene.scene],
+                        editor_scene.root,
                         self.texels_per_unit,
-                        Default::default(),
-                        Default::default(),
-                    )
-                    .unwrap();
-                    lightmap
-                        .save("./", engine.resource_manager.clone())
-                        .unwrap();
-                    scene.set_lightmap(lightmap).unwrap();
+                        self.spacing,
+                        output_folder,
+                        engine.resource_manager.clone(),
+                    ));
+                    self.set_job_running(engine, true);
+                } else if message.destination() == self.cancel {
+                    if let Some(job) = self.job.as_ref() {
+                        job.cancellation_token.cancel();
+                    }
                 }
             }
             UiMessageData::NumericUpDown(msg)
@@ -168,7 +543,90 @@ impl LightPanel {
                     }
                 }
             }
+            UiMessageData::TextBox(TextBoxMessage::Text(text))
+                if message.destination() == self.tb_output_folder
+                    && message.direction() == MessageDirection::FromWidget =>
+            {
+                self.output_folder = text.clone();
+            }
             _ => {}
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rg3d::{
+        core::{algebra::Matrix4, pool::Pool},
+        scene::mesh::Mesh,
+    };
+
+    fn add_mesh(scene: &mut Scene, data: Arc<RwLock<SurfaceSharedData>>) -> Handle<Node> {
+        let mut mesh = Mesh::default();
+        mesh.add_surface(Surface::new(data));
+        scene.graph.add_node(Node::Mesh(mesh))
+    }
+
+    fn surface_data(scene: &Scene, node: Handle<Node>) -> Arc<RwLock<SurfaceSharedData>> {
+        scene.graph[node].as_mesh().surfaces()[0].data()
+    }
+
+    #[test]
+    fn baked_copy_does_not_share_surfaces_with_scene() {
+        let mut scene = Scene::new();
+        let shared = Arc::new(RwLock::new(SurfaceSharedData::make_cube(
+            Matrix4::identity(),
+        )));
+        let a = add_mesh(&mut scene, shared.clone());
+        let b = add_mesh(&mut scene, shared);
+
+        let (mut copy, old_to_new) = scene.clone(&mut |_, _| true);
+        detach_surface_data(&mut copy);
+
+        let (copy_a, copy_b) = (
+            surface_data(&copy, old_to_new[&a]),
+            surface_data(&copy, old_to_new[&b]),
+        );
+        assert!(!Arc::ptr_eq(&copy_a, &surface_data(&scene, a)));
+        assert!(Arc::ptr_eq(&copy_a, &copy_b));
+        assert!(Arc::ptr_eq(
+            &surface_data(&scene, a),
+            &surface_data(&scene, b)
+        ));
+    }
+
+    #[test]
+    fn cancelled_job_stops_before_baking() {
+        let mut scene = Scene::new();
+        add_mesh(
+            &mut scene,
+            Arc::new(RwLock::new(SurfaceSharedData::make_cube(
+                Matrix4::identity(),
+            ))),
+        );
+        let token = CancellationToken::new();
+        token.cancel();
+
+        assert!(generate_uvs(&scene, 0.005, &token).is_err());
+        assert!(generate_uvs(&scene, 0.005, &CancellationToken::new()).is_ok());
+    }
+
+    #[test]
+    fn lightmap_of_closed_scene_is_discarded() {
+        let mut scenes = Pool::new();
+        let closed = scenes.spawn(Scene::new());
+        scenes.free(closed);
+        let reopened = scenes.spawn(Scene::new());
+        let job = LightmapJob {
+            scene: closed,
+            cancellation_token: CancellationToken::new(),
+            progress_indicator: ProgressIndicator::new(),
+            receiver: mpsc::channel().1,
+        };
+
+        assert!(job.is_for(Some(closed)));
+        assert!(!job.is_for(Some(reopened)));
+        assert!(!job.is_for(None));
+    }
+}
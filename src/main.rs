@@ -7,45 +7,64 @@ extern crate lazy_static;
 
 pub mod asset;
 pub mod camera;
+pub mod collider_fit;
 pub mod command;
 pub mod configurator;
+pub mod convex_hull;
 pub mod gui;
+pub mod icons;
 pub mod interaction;
 pub mod light;
 pub mod log;
+pub mod mass_properties;
 pub mod menu;
 pub mod physics;
+pub mod physics_layers;
+pub mod physics_materials;
 pub mod preview;
+pub mod render_mode;
 pub mod scene;
 pub mod settings;
 pub mod sidebar;
+pub mod simulation;
+pub mod visualizer;
 pub mod world_outliner;
 
 use crate::gui::Ui;
 use crate::{
     asset::{AssetBrowser, AssetKind},
     camera::CameraController,
+    collider_fit::ColliderFit,
     command::{CommandStack, CommandStackViewer},
     configurator::Configurator,
     gui::{BuildContext, EditorUiMessage, EditorUiNode, UiMessage, UiNode},
     interaction::{
         navmesh::{
+            areas::{NavmeshAreaCosts, NavmeshAreaTable},
             data_model::{Navmesh, NavmeshTriangle, NavmeshVertex},
+            links::NavmeshLinks,
+            path_test::NavmeshTestMode,
             EditNavmeshMode, NavmeshPanel,
         },
+        physics::EditPhysicsMode,
         InteractionMode, InteractionModeKind, InteractionModeTrait, MoveInteractionMode,
         RotateInteractionMode, ScaleInteractionMode, SelectInteractionMode,
     },
     light::LightPanel,
     log::Log,
     menu::{Menu, MenuContext},
-    physics::Physics,
+    physics::{Physics, ShapeSources},
+    physics_layers::PhysicsLayers,
+    physics_materials::PhysicsMaterialLibrary,
+    render_mode::{RenderMode, ViewportRenderMode},
     scene::{
         make_delete_selection_command, EditorScene, LoadModelCommand, PasteCommand, SceneCommand,
         SceneContext, Selection, SetMeshTextureCommand, SetParticleSystemTextureCommand,
         SetSpriteTextureCommand,
     },
-    sidebar::SideBar,
+    sidebar::{make_dropdown_list_option, SideBar},
+    simulation::PhysicsSimulation,
+    visualizer::Visualizers,
     world_outliner::WorldOutliner,
 };
 use rg3d::dpi::LogicalSize;
@@ -67,12 +86,14 @@ use rg3d::{
         canvas::CanvasBuilder,
         dock::{DockingManagerBuilder, TileBuilder, TileContent},
         draw,
+        dropdown_list::DropdownListBuilder,
         file_browser::{FileSelectorBuilder, Filter},
         grid::{Column, GridBuilder, Row},
         image::ImageBuilder,
         message::{
-            ButtonMessage, FileSelectorMessage, ImageMessage, KeyCode, MessageBoxMessage,
-            MessageDirection, MouseButton, UiMessageData, WidgetMessage, WindowMessage,
+            ButtonMessage, DropdownListMessage, FileSelectorMessage, ImageMessage, KeyCode,
+            MessageBoxMessage, MessageDirection, MouseButton, UiMessageData, WidgetMessage,
+            WindowMessage,
         },
         messagebox::{MessageBoxBuilder, MessageBoxButtons, MessageBoxResult},
         stack_panel::StackPanelBuilder,
@@ -142,6 +163,14 @@ pub struct ScenePreview {
     rotate_mode: Handle<UiNode>,
     scale_mode: Handle<UiNode>,
     navmesh_mode: Handle<UiNode>,
+    physics_mode: Handle<UiNode>,
+    navmesh_test_mode: Handle<UiNode>,
+    pilot_mode: Handle<UiNode>,
+    render_mode: Handle<UiNode>,
+    play_simulation: Handle<UiNode>,
+    pause_simulation: Handle<UiNode>,
+    stop_simulation: Handle<UiNode>,
+    keep_simulation: Handle<UiNode>,
     sender: Sender<Message>,
 }
 
@@ -168,6 +197,14 @@ impl ScenePreview {
         let rotate_mode;
         let scale_mode;
         let navmesh_mode;
+        let physics_mode;
+        let navmesh_test_mode;
+        let pilot_mode;
+        let render_mode;
+        let play_simulation;
+        let pause_simulation;
+        let stop_simulation;
+        let keep_simulation;
         let selection_frame;
         let window = WindowBuilder::new(WidgetBuilder::new())
             .can_close(false)
@@ -312,6 +349,93 @@ impl ScenePreview {
                                         )
                                         .build(ctx);
                                         navmesh_mode
+                                    })
+                                    .with_child({
+                                        physics_mode = ButtonBuilder::new(
+                                            WidgetBuilder::new()
+                                                .with_margin(Thickness::uniform(1.0))
+                                                .with_height(32.0),
+                                        )
+                                        .with_text("Physics")
+                                        .build(ctx);
+                                        physics_mode
+                                    })
+                                    .with_child({
+                                        navmesh_test_mode = ButtonBuilder::new(
+                                            WidgetBuilder::new()
+                                                .with_margin(Thickness::uniform(1.0))
+                                                .with_height(32.0),
+                                        )
+                                        .with_text("Path Test")
+                                        .build(ctx);
+                                        navmesh_test_mode
+                                    })
+                                    .with_child({
+                                        pilot_mode = ButtonBuilder::new(
+                                            WidgetBuilder::new()
+                                                .with_margin(Thickness::uniform(1.0))
+                                                .with_height(32.0),
+                                        )
+                                        .with_text("Pilot")
+                                        .build(ctx);
+                                        pilot_mode
+                                    })
+                                    .with_child({
+                                        render_mode = DropdownListBuilder::new(
+                                            WidgetBuilder::new()
+                                                .with_margin(Thickness::uniform(1.0))
+                                                .with_width(100.0)
+                                                .with_height(25.0),
+                                        )
+                                        .with_close_on_selection(true)
+                                        .with_items(
+                                            RenderMode::NAMES
+                                                .iter()
+                                                .map(|name| make_dropdown_list_option(ctx, name))
+                                                .collect(),
+                                        )
+                                        .build(ctx);
+                                        render_mode
+                                    })
+                                    .with_child({
+                                        play_simulation = ButtonBuilder::new(
+                                            WidgetBuilder::new()
+                                                .with_margin(Thickness::uniform(1.0))
+                                                .with_height(25.0),
+                                        )
+                                        .with_text("Play")
+                                        .build(ctx);
+                                        play_simulation
+                                    })
+                                    .with_child({
+                                        pause_simulation = ButtonBuilder::new(
+                                            WidgetBuilder::new()
+                                                .with_margin(Thickness::uniform(1.0))
+                                                .with_height(25.0),
+                                        )
+                                        .with_text("Pause")
+                                        .build(ctx);
+                                        pause_simulation
+                                    })
+                                    .with_child({
+                                        stop_simulation = ButtonBuilder::new(
+                                            WidgetBuilder::new()
+                                                .with_margin(Thickness::uniform(1.0))
+                                                .with_height(25.0),
+                                        )
+                                        .with_text("Stop")
+                                        .build(ctx);
+                                        stop_simulation
+                                    })
+                                    .with_child({
+                                        keep_simulation = ButtonBuilder::new(
+                                            WidgetBuilder::new()
+                                                .with_margin(Thickness::uniform(1.0))
+                                                .with_height(25.0),
+                                        )
+                                        .with_text("Stop & Keep")
+                                        .build(ctx);
+                                        keep_simulation
                                     }),
                             )
                             .build(ctx),
@@ -325,6 +449,14 @@ impl ScenePreview {
             .with_title(WindowTitle::text("Scene Preview"))
             .build(ctx);
 
+        engine
+            .user_interface
+            .send_message(DropdownListMessage::selection(
+                render_mode,
+                MessageDirection::ToWidget,
+                Some(RenderMode::Lit as usize),
+            ));
+
         Self {
             sender,
             window,
@@ -336,6 +468,14 @@ impl ScenePreview {
             selection_frame,
             select_mode,
             navmesh_mode,
+            physics_mode,
+            navmesh_test_mode,
+            pilot_mode,
+            render_mode,
+            play_simulation,
+            pause_simulation,
+            stop_simulation,
+            keep_simulation,
             click_mouse_pos: None,
         }
     }
@@ -366,6 +506,45 @@ impl ScenePreview {
                 self.sender
                     .send(Message::SetInteractionMode(InteractionModeKind::Navmesh))
                     .unwrap();
+            } else if message.destination() == self.physics_mode {
+                self.sender
+                    .send(Message::SetInteractionMode(InteractionModeKind::Physics))
+                    .unwrap();
+            } else if message.destination() == self.navmesh_test_mode {
+                self.sender
+                    .send(Message::SetInteractionMode(
+                        InteractionModeKind::NavmeshTest,
+                    ))
+                    .unwrap();
+            } else if message.destination() == self.pilot_mode {
+                self.sender.send(Message::TogglePilot).unwrap();
+            } else if message.destination() == self.play_simulation {
+                self.sender.send(Message::PlaySimulation).unwrap();
+            } else if message.destination() == self.pause_simulation {
+                self.sender.send(Message::PauseSimulation).unwrap();
+            } else if message.destination() == self.stop_simulation {
+                self.sender
+                    .send(Message::StopSimulation {
+                        keep_transforms: false,
+                    })
+                    .unwrap();
+            } else if message.destination() == self.keep_simulation {
+                self.sender
+                    .send(Message::StopSimulation {
+                        keep_transforms: true,
+                    })
+                    .unwrap();
+            }
+        } else if let UiMessageData::DropdownList(DropdownListMessage::SelectionChanged(Some(
+            index,
+        ))) = *message.data()
+        {
+            if message.destination() == self.render_mode
+                && message.direction() == MessageDirection::FromWidget
+            {
+                if let Some(mode) = RenderMode::from_index(index) {
+                    self.sender.send(Message::SetRenderMode(mode)).unwrap();
+                }
             }
         }
     }
@@ -383,6 +562,15 @@ pub enum Message {
     LoadScene(PathBuf),
     CloseScene,
     SetInteractionMode(InteractionModeKind),
+    TogglePilot,
+    SetRenderMode(RenderMode),
+    PreviewColliderFit(Option<ColliderFit>),
+    PlaySimulation,
+    PauseSimulation,
+    StopSimulation {
+        /// Whether simulated transforms should be applied to nodes as undoable command.
+        keep_transforms: bool,
+    },
     Log(String),
     Configure {
         working_directory: PathBuf,
@@ -437,6 +625,9 @@ struct Editor {
     command_stack_viewer: CommandStackViewer,
     validation_message_box: Handle<UiNode>,
     navmesh_panel: NavmeshPanel,
+    render_mode: ViewportRenderMode,
+    visualizers: Visualizers,
+    simulation: PhysicsSimulation,
 }
 
 impl Editor {
@@ -461,7 +652,7 @@ impl Editor {
         let preview = ScenePreview::new(engine, message_sender.clone());
         let asset_browser = AssetBrowser::new(engine);
         let menu = Menu::new(engine, message_sender.clone());
-        let light_panel = LightPanel::new(engine);
+        let light_panel = LightPanel::new(engine, message_sender.clone());
 
         let ctx = &mut engine.user_interface.build_ctx();
         let node_editor =
@@ -610,6 +801,9 @@ impl Editor {
             light_panel,
             command_stack_viewer,
             validation_message_box,
+            render_mode: Default::default(),
+            visualizers: Default::default(),
+            simulation: Default::default(),
         };
 
         editor.set_interaction_mode(Some(InteractionModeKind::Move), engine);
@@ -621,6 +815,8 @@ impl Editor {
         if let Some(previous_editor_scene) = self.scene.as_ref() {
             engine.scenes.remove(previous_editor_scene.scene);
         }
+        self.simulation.reset();
+        self.render_mode.invalidate();
         self.scene = None;
         self.sync_to_model(engine);
         poll_ui_messages(self, engine);
@@ -639,7 +835,7 @@ impl Editor {
         let root = BaseBuilder::new().build(&mut scene.graph);
 
         let graph = &mut scene.graph;
-        let camera_controller = CameraController::new(graph, root);
+        let camera_controller = CameraController::new(graph, root, &engine.resource_manager);
 
         let mut navmeshes = Pool::new();
 
@@ -655,21 +851,42 @@ impl Editor {
                 triangles: navmesh
                     .triangles()
                     .iter()
-                    .map(|triangle| NavmeshTriangle {
-                        a: Handle::new(triangle[0], 1),
-                        b: Handle::new(triangle[1], 1),
-                        c: Handle::new(triangle[2], 1),
+                    .map(|triangle| {
+                        NavmeshTriangle::new(
+                            Handle::new(triangle[0], 1),
+                            Handle::new(triangle[1], 1),
+                            Handle::new(triangle[2], 1),
+                        )
                     })
                     .collect(),
+                links: Default::default(),
             });
         }
 
+        let mut physics = Physics::new(&scene);
+        if let Some(sources) = path.as_deref().and_then(ShapeSources::load) {
+            physics.apply_shape_sources(sources);
+        }
+
+        if let Some(costs) = path.as_deref().and_then(NavmeshAreaCosts::load) {
+            costs.apply(&mut navmeshes);
+        }
+
+        if let Some(links) = path.as_deref().and_then(NavmeshLinks::load) {
+            for warning in links.apply(&mut navmeshes) {
+                self.message_sender.send(Message::Log(warning)).unwrap();
+            }
+        }
+
         let editor_scene = EditorScene {
             path: path.clone(),
             root,
             camera_controller,
-            physics: Physics::new(&scene),
+            physics,
             navmeshes,
+            navmesh_areas: NavmeshAreaTable::load(),
+            physics_layers: PhysicsLayers::load(),
+            physics_materials: PhysicsMaterialLibrary::load(),
             scene: engine.scenes.add(scene),
             selection: Default::default(),
             clipboard: Default::default(),
@@ -701,6 +918,12 @@ impl Editor {
                 engine,
                 self.message_sender.clone(),
             )),
+            InteractionMode::Physics(EditPhysicsMode::new(
+                &editor_scene,
+                engine,
+                self.message_sender.clone(),
+            )),
+            InteractionMode::NavmeshTest(NavmeshTestMode::new(self.message_sender.clone())),
         ];
 
         self.command_stack = CommandStack::new(false);
@@ -724,7 +947,7 @@ impl Editor {
     }
 
     fn set_interaction_mode(&mut self, mode: Option<InteractionModeKind>, engine: &mut GameEngine) {
-        if let Some(editor_scene) = self.scene.as_ref() {
+        if let Some(editor_scene) = self.scene.as_mut() {
             if self.current_interaction_mode != mode {
                 // Deactivate current first.
                 if let Some(current_mode) = self.current_interaction_mode {
@@ -799,14 +1022,24 @@ impl Editor {
                     match *msg {
                         WidgetMessage::MouseDown { button, pos, .. } => {
                             engine.user_interface.capture_mouse(self.preview.frame);
-                            if button == MouseButton::Left {
-                                if let Some(current_im) = self.current_interaction_mode {
-                                    let screen_bounds = engine
-                                        .user_interface
-                                        .node(self.preview.frame)
-                                        .screen_bounds();
-                                    let rel_pos = pos - screen_bounds.position;
-
+                            // Gizmos and visualizers would record simulated transforms,
+                            // only the camera can be used while simulating.
+                            if button == MouseButton::Left && !self.simulation.is_active() {
+                                let screen_bounds = engine
+                                    .user_interface
+                                    .node(self.preview.frame)
+                                    .screen_bounds();
+                                let rel_pos = pos - screen_bounds.position;
+
+                                if self.visualizers.try_begin_drag(
+                                    editor_scene,
+                                    engine,
+                                    rel_pos,
+                                    frame_size,
+                                ) {
+                                    // Visualizer handle is picked, interaction mode must
+                                    // not see this click.
+                                } else if let Some(current_im) = self.current_interaction_mode {
                                     self.preview.click_mouse_pos = Some(rel_pos);
 
                                     self.interaction_modes[current_im as usize]
@@ -823,9 +1056,17 @@ impl Editor {
                         WidgetMessage::MouseUp { button, pos, .. } => {
                             engine.user_interface.release_mouse_capture();
 
-                            if button == MouseButton::Left {
+                            if button == MouseButton::Left && !self.simulation.is_active() {
                                 self.preview.click_mouse_pos = None;
-                                if let Some(current_im) = self.current_interaction_mode {
+                                if self.visualizers.is_dragging() {
+                                    if let Some(command) =
+                                        self.visualizers.end_drag(editor_scene, engine)
+                                    {
+                                        self.message_sender
+                                            .send(Message::DoSceneCommand(command))
+                                            .unwrap();
+                                    }
+                                } else if let Some(current_im) = self.current_interaction_mode {
                                     let screen_bounds = engine
                                         .user_interface
                                         .node(self.preview.frame)
@@ -840,7 +1081,9 @@ impl Editor {
                                         );
                                 }
                             }
-                            editor_scene.camera_controller.on_mouse_button_up(button);
+                            editor_scene
+                                .camera_controller
+                                .on_mouse_button_up(button, &self.message_sender);
                         }
                         WidgetMessage::MouseWheel { amount, .. } => {
                             let graph = &mut engine.scenes[editor_scene.scene].graph;
@@ -856,7 +1099,10 @@ impl Editor {
                                 .screen_bounds();
                             let rel_pos = pos - screen_bounds.position;
 
-                            if let Some(current_im) = self.current_interaction_mode {
+                            if self.visualizers.is_dragging() {
+                                self.visualizers
+                                    .drag(editor_scene, engine, rel_pos, frame_size);
+                            } else if let Some(current_im) = self.current_interaction_mode {
                                 self.interaction_modes[current_im as usize].on_mouse_move(
                                     mouse_offset,
                                     rel_pos,
@@ -909,6 +1155,14 @@ impl Editor {
                                 ),
                                 KeyCode::Key4 => self
                                     .set_interaction_mode(Some(InteractionModeKind::Scale), engine),
+                                KeyCode::Key5 => self.set_interaction_mode(
+                                    Some(InteractionModeKind::Physics),
+                                    engine,
+                                ),
+                                KeyCode::Key6 => self.set_interaction_mode(
+                                    Some(InteractionModeKind::NavmeshTest),
+                                    engine,
+                                ),
                                 KeyCode::L
                                     if engine.user_interface.keyboard_modifiers().control =>
                                 {
@@ -1128,12 +1382,65 @@ impl Editor {
         }
     }
 
+    /// Renders a frame, render mode overrides exist only while the scene is rendered so
+    /// nothing else ever sees them.
+    fn render(&mut self, engine: &mut GameEngine, dt: f32) {
+        scope_profile!();
+
+        if let Some(editor_scene) = self.scene.as_ref() {
+            self.render_mode
+                .apply(&mut engine.scenes[editor_scene.scene], editor_scene.root);
+        }
+
+        engine.render(dt).unwrap();
+
+        if let Some(editor_scene) = self.scene.as_ref() {
+            self.render_mode
+                .revert(&mut engine.scenes[editor_scene.scene]);
+        }
+    }
+
     fn update(&mut self, engine: &mut GameEngine, dt: f32) {
         scope_profile!();
 
         let mut needs_sync = false;
 
         while let Ok(message) = self.message_receiver.try_recv() {
+            // Value dragged by a visualizer handle is not committed yet, anything else that
+            // changes the scene must not see it.
+            if let Message::DoSceneCommand(_)
+            | Message::UndoSceneCommand
+            | Message::RedoSceneCommand
+            | Message::SelectionChanged = message
+            {
+                if let Some(editor_scene) = self.scene.as_ref() {
+                    self.visualizers.cancel_drag(editor_scene, engine);
+                }
+            }
+
+            // Commands may change geometry of meshes, so cached debug lines are rebuilt.
+            if let Message::DoSceneCommand(_)
+            | Message::UndoSceneCommand
+            | Message::RedoSceneCommand = message
+            {
+                self.render_mode.invalidate();
+            }
+
+            // Simulated transforms must never be seen by commands or end up in saved scene -
+            // simulation is stopped before scene is changed or saved, so everything works
+            // with the scene as it was before simulation.
+            if let Message::DoSceneCommand(_)
+            | Message::UndoSceneCommand
+            | Message::RedoSceneCommand
+            | Message::ClearSceneCommandStack
+            | Message::SaveScene(_) = message
+            {
+                if let Some(editor_scene) = self.scene.as_ref() {
+                    self.simulation
+                        .stop(&mut engine.scenes[editor_scene.scene].graph, false);
+                }
+            }
+
             self.log.handle_message(&message, engine);
 
             match message {
@@ -1186,6 +1493,9 @@ impl Editor {
                 }
                 Message::SelectionChanged => {
                     self.world_outliner.sync_selection = true;
+                    if let Some(editor_scene) = self.scene.as_mut() {
+                        editor_scene.physics.fit_preview = None;
+                    }
                 }
                 Message::SyncToModel => {
                     needs_sync = true;
@@ -1241,6 +1551,77 @@ impl Editor {
                 Message::SetInteractionMode(mode_kind) => {
                     self.set_interaction_mode(Some(mode_kind), engine);
                 }
+                Message::SetRenderMode(mode) => {
+                    self.render_mode.set_mode(mode);
+                }
+                Message::PreviewColliderFit(fit) => {
+                    if let Some(editor_scene) = self.scene.as_mut() {
+                        editor_scene.physics.fit_preview = fit;
+                    }
+                }
+                Message::PlaySimulation => {
+                    if let Some(editor_scene) = self.scene.as_ref() {
+                        match self.simulation.play(
+                            &editor_scene.physics,
+                            &editor_scene.physics_layers,
+                            &engine.scenes[editor_scene.scene].graph,
+                            &engine.resource_manager,
+                        ) {
+                            Ok(warnings) => {
+                                for warning in warnings {
+                                    self.message_sender.send(Message::Log(warning)).unwrap();
+                                }
+                            }
+                            Err(e) => {
+                                self.message_sender.send(Message::Log(e)).unwrap();
+                            }
+                        }
+                    }
+                }
+                Message::PauseSimulation => {
+                    self.simulation.pause();
+                }
+                Message::StopSimulation { keep_transforms } => {
+                    if let Some(editor_scene) = self.scene.as_ref() {
+                        if let Some(command) = self.simulation.stop(
+                            &mut engine.scenes[editor_scene.scene].graph,
+                            keep_transforms,
+                        ) {
+                            self.message_sender
+                                .send(Message::DoSceneCommand(command))
+                                .unwrap();
+                        }
+                    }
+                }
+                Message::TogglePilot => {
+                    if let Some(editor_scene) = self.scene.as_mut() {
+                        let graph = &mut engine.scenes[editor_scene.scene].graph;
+                        if editor_scene.camera_controller.is_piloting() {
+                            if let Some(command) = editor_scene.camera_controller.end_pilot(graph) {
+                                self.message_sender
+                                    .send(Message::DoSceneCommand(command))
+                                    .unwrap();
+                            }
+                        } else {
+                            let pilotable = match &editor_scene.selection {
+                                Selection::Graph(selection) if selection.is_single_selection() => {
+                                    editor_scene
+                                        .camera_controller
+                                        .begin_pilot(graph, selection.nodes()[0])
+                                }
+                                _ => false,
+                            };
+                            if !pilotable {
+                                self.message_sender
+                                    .send(Message::Log(
+                                        "Select single camera or spot light to pilot it!"
+                                            .to_owned(),
+                                    ))
+                                    .unwrap();
+                            }
+                        }
+                    }
+                }
                 Message::Exit { force } => {
                     if force {
                         self.exit = true;
@@ -1261,6 +1642,7 @@ impl Editor {
                 Message::CloseScene => {
                     if let Some(editor_scene) = self.scene.take() {
                         engine.scenes.remove(editor_scene.scene);
+                        self.simulation.reset();
                         needs_sync = true;
 
                         // Preview frame has scene frame texture assigned, it must be cleared explicitly,
@@ -1315,12 +1697,29 @@ impl Editor {
             self.sync_to_model(engine);
         }
 
+        // Sidebar shows simulated transforms, edits made from it would be recorded with
+        // them, so it is disabled while simulating.
+        let simulating = self.simulation.is_active();
+        if engine.user_interface.node(self.sidebar.window).enabled() == simulating {
+            engine.user_interface.send_message(WidgetMessage::enabled(
+                self.sidebar.window,
+                MessageDirection::ToWidget,
+                !simulating,
+            ));
+        }
+
+        self.light_panel.update(self.scene.as_ref(), engine);
+
         if let Some(editor_scene) = self.scene.as_mut() {
             // Adjust camera viewport to size of frame.
             let scene = &mut engine.scenes[editor_scene.scene];
 
             scene.drawing_context.clear_lines();
 
+            self.simulation.update(&mut scene.graph, dt);
+
+            self.render_mode.draw(scene, editor_scene.root);
+
             // Create new render target if preview frame has changed its size.
             let (rt_width, rt_height) = if let TextureKind::Rectangle { width, height } =
                 scene.render_target.clone().unwrap().data_ref().kind()
@@ -1392,13 +1791,46 @@ impl Editor {
                 editor_scene,
             );
 
+            let selected_body = match &editor_scene.selection {
+                Selection::Graph(selection) if selection.is_single_selection() => editor_scene
+                    .physics
+                    .binder
+                    .value_of(&selection.nodes()[0])
+                    .cloned()
+                    .unwrap_or_default(),
+                _ => Handle::NONE,
+            };
+            let selected_joint = if selected_body.is_some() {
+                editor_scene.physics.find_joint(selected_body)
+            } else {
+                Handle::NONE
+            };
             editor_scene
                 .physics
-                .draw(&mut scene.drawing_context, &scene.graph);
+                .update_heightfield_textures(&engine.resource_manager);
+            editor_scene.physics.draw(
+                &mut scene.drawing_context,
+                &scene.graph,
+                selected_body,
+                selected_joint,
+            );
+
+            let frame_size = engine.user_interface.node(self.preview.frame).actual_size();
+            self.visualizers.update(
+                editor_scene,
+                &scene.graph,
+                &mut scene.drawing_context,
+                frame_size.x / frame_size.y.max(1.0),
+            );
 
             let graph = &mut scene.graph;
 
             editor_scene.camera_controller.update(graph, dt);
+            editor_scene.camera_controller.icons.update(
+                graph,
+                editor_scene.root,
+                editor_scene.camera_controller.camera,
+            );
 
             if let Some(mode) = self.current_interaction_mode {
                 self.interaction_modes[mode as usize].update(
@@ -1486,7 +1918,7 @@ fn main() {
             }
         }
         Event::RedrawRequested(_) => {
-            engine.render(fixed_timestep).unwrap();
+            editor.render(&mut engine, fixed_timestep);
         }
         Event::WindowEvent { event, .. } => {
             match event {
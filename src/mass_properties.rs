@@ -0,0 +1,322 @@
+//! Mass properties (mass, center of mass and inertia tensor) of rigid bodies computed
+//! from volumes of their colliders. Shapes without volume (segments, triangles, trimeshes
+//! and heightfields) do not contribute anything, same as in the physics engine.
+
+use crate::{
+    convex_hull::ConvexHull,
+    physics::{Physics, RigidBody},
+    physics_materials::DEFAULT_DENSITY,
+};
+use rg3d::{
+    core::{
+        algebra::{Matrix3, UnitQuaternion, Vector3},
+        pool::Handle,
+    },
+    scene::physics::ColliderShapeDesc,
+};
+use std::f32::consts::PI;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MassProperties {
+    pub mass: f32,
+    pub center_of_mass: Vector3<f32>,
+    /// Inertia tensor relative to the center of mass.
+    pub inertia: Matrix3<f32>,
+}
+
+impl Default for MassProperties {
+    fn default() -> Self {
+        Self {
+            mass: 0.0,
+            center_of_mass: Vector3::default(),
+            inertia: Matrix3::zeros(),
+        }
+    }
+}
+
+/// Inertia tensor of a point mass at given offset, used to move tensors between points.
+fn parallel_axis(mass: f32, offset: Vector3<f32>) -> Matrix3<f32> {
+    (Matrix3::identity().scale(offset.norm_squared()) - offset * offset.transpose()).scale(mass)
+}
+
+impl MassProperties {
+    /// Mass properties of a solid with diagonal inertia tensor, `unit_inertia` is the
+    /// tensor's diagonal for unit mass.
+    fn solid(
+        volume: f32,
+        density: f32,
+        center_of_mass: Vector3<f32>,
+        unit_inertia: Vector3<f32>,
+    ) -> Self {
+        let mass = volume * density;
+        Self {
+            mass,
+            center_of_mass,
+            inertia: Matrix3::from_diagonal(&unit_inertia.scale(mass)),
+        }
+    }
+
+    /// Moves properties from local frame of a shape to frame of its parent.
+    pub fn transform(&self, rotation: UnitQuaternion<f32>, translation: Vector3<f32>) -> Self {
+        let rotation = rotation.to_rotation_matrix();
+        Self {
+            mass: self.mass,
+            center_of_mass: rotation * self.center_of_mass + translation,
+            inertia: rotation.matrix() * self.inertia * rotation.matrix().transpose(),
+        }
+    }
+
+    /// Merges properties of two parts of a body, both must be in the same frame.
+    pub fn combine(&self, other: &Self) -> Self {
+        let mass = self.mass + other.mass;
+        if mass <= std::f32::EPSILON {
+            return Self::default();
+        }
+        let center_of_mass =
+            (self.center_of_mass.scale(self.mass) + other.center_of_mass.scale(other.mass)) / mass;
+        Self {
+            mass,
+            center_of_mass,
+            inertia: self.inertia
+                + parallel_axis(self.mass, self.center_of_mass - center_of_mass)
+                + other.inertia
+                + parallel_axis(other.mass, other.center_of_mass - center_of_mass),
+        }
+    }
+
+    /// Returns principal moments of inertia and their axes (columns of the matrix).
+    pub fn principal_inertia(&self) -> (Vector3<f32>, Matrix3<f32>) {
+        let eigen = self.inertia.symmetric_eigen();
+        (eigen.eigenvalues, eigen.eigenvectors)
+    }
+}
+
+/// Returns mass properties of a shape in its local frame. Cylinders and cones are aligned
+/// with Y axis, round cylinder is treated as plain one.
+pub fn shape_mass_properties(shape: &ColliderShapeDesc, density: f32) -> MassProperties {
+    match shape {
+        ColliderShapeDesc::Ball(ball) => {
+            let r = ball.radius;
+            MassProperties::solid(
+                4.0 / 3.0 * PI * r * r * r,
+                density,
+                Vector3::default(),
+                Vector3::repeat(0.4 * r * r),
+            )
+        }
+        ColliderShapeDesc::Cuboid(cuboid) => {
+            let e = cuboid.half_extents;
+            let sq = e.component_mul(&e);
+            MassProperties::solid(
+                8.0 * e.x * e.y * e.z,
+                density,
+                Vector3::default(),
+                Vector3::new(sq.y + sq.z, sq.x + sq.z, sq.x + sq.y) / 3.0,
+            )
+        }
+        ColliderShapeDesc::Cylinder(cylinder) => {
+            cylinder_mass_properties(cylinder.half_height, cylinder.radius, density)
+        }
+        ColliderShapeDesc::RoundCylinder(cylinder) => {
+            cylinder_mass_properties(cylinder.half_height, cylinder.radius, density)
+        }
+        ColliderShapeDesc::Cone(cone) => {
+            let (r, h) = (cone.radius, 2.0 * cone.half_height);
+            let lateral = 3.0 / 20.0 * r * r + 3.0 / 80.0 * h * h;
+            MassProperties::solid(
+                PI * r * r * h / 3.0,
+                density,
+                // Apex is at +half_height, so center of mass is a quarter of height above base.
+                Vector3::new(0.0, -cone.half_height * 0.5, 0.0),
+                Vector3::new(lateral, 0.3 * r * r, lateral),
+            )
+        }
+        ColliderShapeDesc::Capsule(capsule) => {
+            let r = capsule.radius;
+            let axis = capsule.end - capsule.begin;
+            let l = axis.norm();
+            let cylinder_mass = PI * r * r * l * density;
+            let sphere_mass = 4.0 / 3.0 * PI * r * r * r * density;
+            let axial = cylinder_mass * r * r * 0.5 + sphere_mass * 0.4 * r * r;
+            let lateral = cylinder_mass * (l * l / 12.0 + r * r / 4.0)
+                + sphere_mass * (0.4 * r * r + l * l / 4.0 + 3.0 * l * r / 8.0);
+            let local = MassProperties {
+                mass: cylinder_mass + sphere_mass,
+                center_of_mass: Vector3::default(),
+                inertia: Matrix3::from_diagonal(&Vector3::new(lateral, axial, lateral)),
+            };
+            let rotation = UnitQuaternion::rotation_between(&Vector3::y(), &axis)
+                .unwrap_or_else(UnitQuaternion::identity);
+            local.transform(rotation, (capsule.begin + capsule.end).scale(0.5))
+        }
+        ColliderShapeDesc::Segment(_)
+        | ColliderShapeDesc::Triangle(_)
+        | ColliderShapeDesc::Trimesh(_)
+        | ColliderShapeDesc::Heightfield(_) => MassProperties::default(),
+    }
+}
+
+fn cylinder_mass_properties(half_height: f32, radius: f32, density: f32) -> MassProperties {
+    let (r, h) = (radius, 2.0 * half_height);
+    let lateral = (3.0 * r * r + h * h) / 12.0;
+    MassProperties::solid(
+        PI * r * r * h,
+        density,
+        Vector3::default(),
+        Vector3::new(lateral, 0.5 * r * r, lateral),
+    )
+}
+
+/// Computes properties of a convex polyhedron by splitting it into tetrahedra with common
+/// vertex in the centroid of hull's vertices.
+pub fn convex_mass_properties(hull: &ConvexHull, density: f32) -> MassProperties {
+    if hull.vertices.is_empty() {
+        return MassProperties::default();
+    }
+
+    let origin = hull
+        .vertices
+        .iter()
+        .fold(Vector3::default(), |sum, v| sum + v)
+        / hull.vertices.len() as f32;
+
+    // Covariance of canonical tetrahedron (0, x, y, z) with unit determinant.
+    let canonical = Matrix3::new(2.0, 1.0, 1.0, 1.0, 2.0, 1.0, 1.0, 1.0, 2.0) / 120.0;
+
+    let mut volume = 0.0;
+    let mut first_moment = Vector3::default();
+    let mut covariance = Matrix3::zeros();
+    for triangle in hull.triangles.iter() {
+        let a = hull.vertices[triangle[0] as usize] - origin;
+        let b = hull.vertices[triangle[1] as usize] - origin;
+        let c = hull.vertices[triangle[2] as usize] - origin;
+        let edges = Matrix3::from_columns(&[a, b, c]);
+        let determinant = a.dot(&b.cross(&c));
+        volume += determinant / 6.0;
+        first_moment += (a + b + c).scale(determinant / 24.0);
+        covariance += edges * canonical * edges.transpose() * determinant;
+    }
+
+    if volume <= std::f32::EPSILON {
+        return MassProperties::default();
+    }
+
+    let mass = volume * density;
+    let center = first_moment / volume;
+    let covariance = covariance.scale(density) - (center * center.transpose()).scale(mass);
+    MassProperties {
+        mass,
+        center_of_mass: origin + center,
+        inertia: Matrix3::identity().scale(covariance.trace()) - covariance,
+    }
+}
+
+/// Sums properties of all colliders of a body in the body's frame.
+pub fn body_mass_properties(physics: &Physics, body: Handle<RigidBody>) -> MassProperties {
+    let mut result = MassProperties::default();
+    for &collider_handle in physics.bodies[body].colliders.iter() {
+        let collider = &physics.colliders[collider_handle.into()];
+        let density = collider.density.unwrap_or(DEFAULT_DENSITY);
+        // Degenerate hull is replaced with its enclosing cuboid, same as in the engine.
+        let local = match physics.convex_sources.get(&collider_handle.into()) {
+            Some(source) if !source.hull().is_empty() => {
+                convex_mass_properties(source.hull(), density)
+            }
+            _ => shape_mass_properties(&collider.shape, density),
+        };
+        result = result.combine(&local.transform(collider.rotation, collider.translation));
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rg3d::scene::physics::{BallDesc, CuboidDesc};
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() <= 1.0e-4 * b.abs().max(1.0), "{} != {}", a, b);
+    }
+
+    fn assert_diagonal(inertia: &Matrix3<f32>, diagonal: Vector3<f32>) {
+        for i in 0..3 {
+            for j in 0..3 {
+                let expected = if i == j { diagonal[i] } else { 0.0 };
+                assert_close(inertia[(i, j)], expected);
+            }
+        }
+    }
+
+    #[test]
+    fn box_inertia() {
+        let properties = shape_mass_properties(
+            &ColliderShapeDesc::Cuboid(CuboidDesc {
+                half_extents: Vector3::new(1.0, 2.0, 3.0),
+            }),
+            2.0,
+        );
+        // Box of 2x4x6 meters.
+        let mass = 48.0 * 2.0;
+        assert_close(properties.mass, mass);
+        assert_eq!(properties.center_of_mass, Vector3::default());
+        assert_diagonal(
+            &properties.inertia,
+            Vector3::new(16.0 + 36.0, 4.0 + 36.0, 4.0 + 16.0).scale(mass / 12.0),
+        );
+    }
+
+    #[test]
+    fn sphere_inertia() {
+        let properties =
+            shape_mass_properties(&ColliderShapeDesc::Ball(BallDesc { radius: 2.0 }), 1.0);
+        let mass = 4.0 / 3.0 * PI * 8.0;
+        assert_close(properties.mass, mass);
+        assert_diagonal(&properties.inertia, Vector3::repeat(0.4 * mass * 4.0));
+    }
+
+    #[test]
+    fn convex_box_matches_cuboid() {
+        let mut points = Vec::new();
+        for i in 0..8 {
+            points.push(Vector3::new(
+                if i & 1 == 0 { 0.0 } else { 2.0 },
+                if i & 2 == 0 { 0.0 } else { 4.0 },
+                if i & 4 == 0 { 0.0 } else { 6.0 },
+            ));
+        }
+        let convex = convex_mass_properties(&ConvexHull::new(&points, 0.0), 2.0);
+        let cuboid = shape_mass_properties(
+            &ColliderShapeDesc::Cuboid(CuboidDesc {
+                half_extents: Vector3::new(1.0, 2.0, 3.0),
+            }),
+            2.0,
+        );
+
+        assert_close(convex.mass, cuboid.mass);
+        for i in 0..3 {
+            assert_close(convex.center_of_mass[i], [1.0, 2.0, 3.0][i]);
+        }
+        assert_diagonal(&convex.inertia, cuboid.inertia.diagonal());
+    }
+
+    #[test]
+    fn combine_uses_parallel_axis() {
+        let ball = shape_mass_properties(&ColliderShapeDesc::Ball(BallDesc { radius: 1.0 }), 1.0);
+        let left = ball.transform(UnitQuaternion::identity(), Vector3::new(-2.0, 0.0, 0.0));
+        let right = ball.transform(UnitQuaternion::identity(), Vector3::new(2.0, 0.0, 0.0));
+
+        let combined = left.combine(&right);
+
+        assert_close(combined.mass, 2.0 * ball.mass);
+        assert_close(combined.center_of_mass.norm(), 0.0);
+        let own = ball.inertia[(0, 0)];
+        assert_diagonal(
+            &combined.inertia,
+            Vector3::new(
+                2.0 * own,
+                2.0 * (own + 4.0 * ball.mass),
+                2.0 * (own + 4.0 * ball.mass),
+            ),
+        );
+    }
+}
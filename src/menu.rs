@@ -1,6 +1,10 @@
 use crate::{
-    gui::{Ui, UiMessage, UiNode},
+    gui::{BuildContext, Ui, UiMessage, UiNode},
+    icons::{IconKind, NodeIcons},
+    interaction::navmesh::areas::NavmeshAreasWindow,
     make_save_file_selector, make_scene_file_filter,
+    physics_layers::PhysicsLayersWindow,
+    physics_materials::PhysicsMaterialsWindow,
     scene::{AddNodeCommand, EditorScene, PasteCommand, SceneCommand, Selection},
     send_sync_message,
     settings::Settings,
@@ -13,13 +17,15 @@ use rg3d::{
         scope_profile,
     },
     gui::{
+        check_box::CheckBoxBuilder,
         file_browser::FileSelectorBuilder,
         menu::{MenuBuilder, MenuItemBuilder, MenuItemContent},
         message::{
-            FileSelectorMessage, MenuItemMessage, MessageBoxMessage, MessageDirection,
-            UiMessageData, WidgetMessage, WindowMessage,
+            CheckBoxMessage, FileSelectorMessage, MenuItemMessage, MessageBoxMessage,
+            MessageDirection, UiMessageData, WidgetMessage, WindowMessage,
         },
         messagebox::{MessageBoxBuilder, MessageBoxButtons},
+        text::TextBuilder,
         widget::WidgetBuilder,
         window::{WindowBuilder, WindowTitle},
         Thickness,
@@ -48,6 +54,12 @@ pub struct Menu {
     redo: Handle<UiNode>,
     copy: Handle<UiNode>,
     paste: Handle<UiNode>,
+    open_physics_layers: Handle<UiNode>,
+    physics_layers: PhysicsLayersWindow,
+    open_physics_materials: Handle<UiNode>,
+    physics_materials: PhysicsMaterialsWindow,
+    open_navmesh_areas: Handle<UiNode>,
+    navmesh_areas: NavmeshAreasWindow,
     create_pivot: Handle<UiNode>,
     create_cube: Handle<UiNode>,
     create_cone: Handle<UiNode>,
@@ -74,6 +86,12 @@ pub struct Menu {
     log_panel: Handle<UiNode>,
     create: Handle<UiNode>,
     edit: Handle<UiNode>,
+    pivot_icons: Handle<UiNode>,
+    light_icons: Handle<UiNode>,
+    camera_icons: Handle<UiNode>,
+    pivot_icons_check: Handle<UiNode>,
+    light_icons_check: Handle<UiNode>,
+    camera_icons_check: Handle<UiNode>,
 }
 
 pub struct MenuContext<'a, 'b> {
@@ -87,6 +105,19 @@ pub struct MenuContext<'a, 'b> {
     pub log_panel: Handle<UiNode>,
 }
 
+/// Check box that is used as content of menu items which toggle an option, it only shows
+/// state of the option - the option is toggled by the item itself.
+fn make_check_mark(ctx: &mut BuildContext, text: &str) -> Handle<UiNode> {
+    CheckBoxBuilder::new(WidgetBuilder::new().with_hit_test_visibility(false))
+        .with_content(
+            TextBuilder::new(WidgetBuilder::new().with_margin(Thickness::left(2.0)))
+                .with_text(text)
+                .build(ctx),
+        )
+        .checked(Some(true))
+        .build(ctx)
+}
+
 fn switch_window_state(window: Handle<UiNode>, ui: &mut Ui, center: bool) {
     let current_state = ui.node(window).visibility();
     ui.send_message(if current_state {
@@ -108,6 +139,9 @@ impl Menu {
         let undo;
         let copy;
         let paste;
+        let open_physics_layers;
+        let open_physics_materials;
+        let open_navmesh_areas;
         let create_cube;
         let create_cone;
         let create_sphere;
@@ -127,7 +161,13 @@ impl Menu {
         let light_panel;
         let log_panel;
         let create_pivot;
+        let pivot_icons;
+        let light_icons;
+        let camera_icons;
         let ctx = &mut engine.user_interface.build_ctx();
+        let pivot_icons_check = make_check_mark(ctx, "Pivot Icons");
+        let light_icons_check = make_check_mark(ctx, "Light Icons");
+        let camera_icons_check = make_check_mark(ctx, "Camera Icons");
         let configure_message = MessageBoxBuilder::new(
             WindowBuilder::new(WidgetBuilder::new().with_width(250.0).with_height(150.0))
                 .open(false)
@@ -257,6 +297,27 @@ impl Menu {
                         .build(ctx);
                     paste
                 },
+                {
+                    open_physics_layers =
+                        MenuItemBuilder::new(WidgetBuilder::new().with_min_size(min_size))
+                            .with_content(MenuItemContent::text("Physics Layers..."))
+                            .build(ctx);
+                    open_physics_layers
+                },
+                {
+                    open_physics_materials =
+                        MenuItemBuilder::new(WidgetBuilder::new().with_min_size(min_size))
+                            .with_content(MenuItemContent::text("Physics Materials..."))
+                            .build(ctx);
+                    open_physics_materials
+                },
+                {
+                    open_navmesh_areas =
+                        MenuItemBuilder::new(WidgetBuilder::new().with_min_size(min_size))
+                            .with_content(MenuItemContent::text("Navmesh Areas..."))
+                            .build(ctx);
+                    open_navmesh_areas
+                },
             ])
             .build(ctx);
 
@@ -380,6 +441,27 @@ impl Menu {
                                     .build(ctx);
                             log_panel
                         },
+                        {
+                            pivot_icons =
+                                MenuItemBuilder::new(WidgetBuilder::new().with_min_size(min_size))
+                                    .with_content(MenuItemContent::Node(pivot_icons_check))
+                                    .build(ctx);
+                            pivot_icons
+                        },
+                        {
+                            light_icons =
+                                MenuItemBuilder::new(WidgetBuilder::new().with_min_size(min_size))
+                                    .with_content(MenuItemContent::Node(light_icons_check))
+                                    .build(ctx);
+                            light_icons
+                        },
+                        {
+                            camera_icons =
+                                MenuItemBuilder::new(WidgetBuilder::new().with_min_size(min_size))
+                                    .with_content(MenuItemContent::Node(camera_icons_check))
+                                    .build(ctx);
+                            camera_icons
+                        },
                     ])
                     .build(ctx),
             ])
@@ -387,6 +469,10 @@ impl Menu {
 
         let save_file_selector = make_save_file_selector(ctx);
 
+        let physics_layers = PhysicsLayersWindow::new(ctx, message_sender.clone());
+        let physics_materials = PhysicsMaterialsWindow::new(ctx, message_sender.clone());
+        let navmesh_areas = NavmeshAreasWindow::new(ctx, message_sender.clone());
+
         let load_file_selector = FileSelectorBuilder::new(
             WindowBuilder::new(WidgetBuilder::new().with_width(300.0).with_height(400.0))
                 .open(false)
@@ -428,10 +514,22 @@ impl Menu {
             light_panel,
             copy,
             paste,
+            open_physics_layers,
+            physics_layers,
+            open_physics_materials,
+            physics_materials,
+            open_navmesh_areas,
+            navmesh_areas,
             log_panel,
             create_pivot,
             create,
             edit,
+            pivot_icons,
+            light_icons,
+            camera_icons,
+            pivot_icons_check,
+            light_icons_check,
+            camera_icons_check,
         }
     }
 
@@ -465,13 +563,45 @@ impl Menu {
                 WidgetMessage::enabled(widget, MessageDirection::ToWidget, editor_scene.is_some()),
             );
         }
+
+        if let Some(editor_scene) = editor_scene {
+            self.sync_icon_checks(&editor_scene.camera_controller.icons, ui);
+            self.physics_layers
+                .sync_to_model(&editor_scene.physics_layers, ui);
+            self.physics_materials
+                .sync_to_model(&editor_scene.physics_materials, ui);
+            self.navmesh_areas
+                .sync_to_model(&editor_scene.navmesh_areas, ui);
+        }
     }
 
-    pub fn handle_ui_message(&mut self, message: &UiMessage, ctx: MenuContext) {
+    fn sync_icon_checks(&self, icons: &NodeIcons, ui: &mut Ui) {
+        for &(check, value) in [
+            (self.pivot_icons_check, icons.show_pivots),
+            (self.light_icons_check, icons.show_lights),
+            (self.camera_icons_check, icons.show_cameras),
+        ]
+        .iter()
+        {
+            send_sync_message(
+                ui,
+                CheckBoxMessage::checked(check, MessageDirection::ToWidget, Some(value)),
+            );
+        }
+    }
+
+    pub fn handle_ui_message(&mut self, message: &UiMessage, mut ctx: MenuContext) {
         scope_profile!();
 
-        if let Some(scene) = ctx.editor_scene.as_ref() {
+        if let Some(scene) = ctx.editor_scene.as_mut() {
             self.settings.handle_message(message, scene, ctx.engine);
+            self.physics_layers.handle_message(message, scene);
+            self.physics_materials.handle_message(
+                message,
+                scene,
+                &ctx.engine.scenes[scene.scene].graph,
+            );
+            self.navmesh_areas.handle_message(message, scene);
         }
 
         match &message.data() {
@@ -682,6 +812,12 @@ impl Menu {
                                 .unwrap();
                         }
                     }
+                } else if message.destination() == self.open_physics_layers {
+                    self.physics_layers.open(&ctx.engine.user_interface);
+                } else if message.destination() == self.open_physics_materials {
+                    self.physics_materials.open(&ctx.engine.user_interface);
+                } else if message.destination() == self.open_navmesh_areas {
+                    self.navmesh_areas.open(&ctx.engine.user_interface);
                 } else if message.destination() == self.undo {
                     self.message_sender.send(Message::UndoSceneCommand).unwrap();
                 } else if message.destination() == self.redo {
@@ -706,13 +842,35 @@ impl Menu {
                     switch_window_state(ctx.sidebar_window, &mut ctx.engine.user_interface, false);
                 } else if message.destination() == self.log_panel {
                     switch_window_state(ctx.log_panel, &mut ctx.engine.user_interface, false);
+                } else if message.destination() == self.pivot_icons
+                    || message.destination() == self.light_icons
+                    || message.destination() == self.camera_icons
+                {
+                    if let Some(editor_scene) = ctx.editor_scene {
+                        let kind = if message.destination() == self.pivot_icons {
+                            IconKind::Pivot
+                        } else if message.destination() == self.light_icons {
+                            IconKind::Light
+                        } else {
+                            IconKind::Camera
+                        };
+                        editor_scene.camera_controller.icons.toggle(kind);
+                        self.sync_icon_checks(
+                            &editor_scene.camera_controller.icons,
+                            &mut ctx.engine.user_interface,
+                        );
+                    }
                 } else if message.destination() == self.open_settings {
                     if let Some(scene) = ctx.editor_scene.as_ref() {
                         let camera = ctx.engine.scenes[scene.scene].graph
                             [scene.camera_controller.camera]
                             .as_camera();
 
-                        self.settings.open(&ctx.engine.user_interface, camera);
+                        self.settings.open(
+                            &ctx.engine.user_interface,
+                            camera,
+                            &scene.camera_controller.settings,
+                        );
                     }
                 } else if message.destination() == self.configure {
                     if ctx.editor_scene.is_none() {
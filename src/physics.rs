@@ -1,23 +1,34 @@
+use crate::{
+    collider_fit::ColliderFit, convex_hull::ConvexHull, mass_properties::body_mass_properties,
+};
 use rg3d::core::algebra::Translation;
 use rg3d::core::BiDirHashMap;
 use rg3d::{
     core::{
-        algebra::{Isometry3, Point3, Translation3, Vector3},
+        algebra::{Isometry3, Matrix4, Point3, Translation3, Vector3},
         color::Color,
         math::aabb::AxisAlignedBoundingBox,
         pool::{ErasedHandle, Handle, Pool},
     },
+    engine::resource_manager::ResourceManager,
     physics::data::arena::Index,
+    resource::texture::Texture,
     scene::{
         graph::Graph,
         node::Node,
         physics::{
-            ColliderDesc, ColliderShapeDesc, JointDesc, JointParamsDesc, PhysicsDesc, RigidBodyDesc,
+            ColliderDesc, ColliderShapeDesc, CuboidDesc, JointDesc, JointParamsDesc, PhysicsDesc,
+            RigidBodyDesc,
         },
         ColliderHandle, Line, RigidBodyHandle, Scene, SceneDrawingContext,
     },
 };
-use std::collections::HashMap;
+use rusty_editor_runtime::{read_heights, trimesh_meshes_of};
+pub use rusty_editor_runtime::{
+    BodyDynamics, ColliderKey, ConvexShape, HeightfieldSource, JointKey, JointMotion, ShapeSources,
+    TrimeshSource,
+};
+use std::{collections::HashMap, path::PathBuf};
 
 pub type RigidBody = RigidBodyDesc<ErasedHandle>;
 pub type Collider = ColliderDesc<ErasedHandle>;
@@ -28,7 +39,9 @@ pub type Joint = JointDesc<ErasedHandle>;
 /// 1) After scene is loaded - convert its physics to editor's
 /// 2) Operate with editor's representation
 /// 3) On save: convert physics back to engine representation and save.
-/// This works ok because we don't need physics simulation while editing scene.
+/// This works ok because we don't need physics simulation while editing scene. Simulation
+/// preview (see `simulation` module) builds its own engine physics from the descriptors
+/// and never modifies them.
 ///
 /// We using Pool to store descriptors because it allows to temporarily move
 /// object out, reserving entry for later re-use. This is very handy because
@@ -40,6 +53,372 @@ pub struct Physics {
     pub colliders: Pool<Collider>,
     pub joints: Pool<Joint>,
     pub binder: BiDirHashMap<Handle<Node>, Handle<RigidBody>>,
+    /// Parameters of trimesh colliders, collider without an entry uses default source.
+    pub trimesh_sources: HashMap<Handle<Collider>, TrimeshSource>,
+    /// Parameters of heightfield colliders, collider without an entry uses default source.
+    pub heightfield_sources: HashMap<Handle<Collider>, HeightfieldSource>,
+    /// Height maps of heightfield colliders, see `update_heightfield_textures`.
+    heightfield_textures: HashMap<PathBuf, Texture>,
+    /// Point clouds of convex polyhedron colliders, collider with an entry is convex.
+    pub convex_sources: HashMap<Handle<Collider>, ConvexSource>,
+    /// Limits and motors of revolute and prismatic joints, joint without an entry has
+    /// neither.
+    pub joint_motions: HashMap<Handle<Joint>, JointMotion>,
+    /// Damping, CCD and locked axes of rigid bodies, body without an entry uses defaults.
+    pub body_dynamics: HashMap<Handle<RigidBody>, BodyDynamics>,
+    /// Ids of materials colliders were given, see `PhysicsMaterial`.
+    pub collider_materials: HashMap<Handle<Collider>, u32>,
+    /// Automatically fitted colliders that are shown, but not yet added to the scene.
+    pub fit_preview: Option<ColliderFit>,
+}
+
+/// Editor-side convex polyhedron collider. Engine's descriptors have no convex shape, so
+/// collider's descriptor holds a cuboid that encloses the hull, and the hull is saved next
+/// to the scene as `ConvexShape` (see `ShapeSources`). The cuboid is replaced with the hull
+/// once sources are applied, the cuboid is used only if the hull is degenerate.
+#[derive(Clone, Debug, Default)]
+pub struct ConvexSource {
+    /// Point cloud in collider's space.
+    pub points: Vec<Vector3<f32>>,
+    /// Points that are closer than this to the hull are dropped, bigger tolerance gives
+    /// simpler hull.
+    pub tolerance: f32,
+    hull: ConvexHull,
+}
+
+impl ConvexSource {
+    pub fn new(points: Vec<Vector3<f32>>, tolerance: f32) -> Self {
+        let mut source = Self {
+            points,
+            tolerance,
+            hull: Default::default(),
+        };
+        source.rebuild();
+        source
+    }
+
+    pub fn from_shape(shape: ConvexShape) -> Self {
+        Self::new(shape.points, shape.tolerance)
+    }
+
+    pub fn hull(&self) -> &ConvexHull {
+        &self.hull
+    }
+
+    pub fn shape(&self) -> ConvexShape {
+        ConvexShape {
+            points: self.points.clone(),
+            tolerance: self.tolerance,
+            hull: self.hull.vertices.clone(),
+        }
+    }
+
+    /// Must be called after points or tolerance were changed.
+    pub fn rebuild(&mut self) {
+        self.hull = ConvexHull::new(&self.points, self.tolerance);
+    }
+
+    /// Cuboid centered at collider's origin that encloses the hull.
+    pub fn enclosing_cuboid(&self) -> CuboidDesc {
+        if self.hull.is_empty() {
+            return CuboidDesc {
+                half_extents: Vector3::new(0.5, 0.5, 0.5),
+            };
+        }
+        let (min, max) = self.hull.bounds();
+        CuboidDesc {
+            half_extents: min.abs().sup(&max.abs()),
+        }
+    }
+}
+
+/// Draws shapes that are fully described by their descriptors.
+fn draw_primitive_shape(
+    context: &mut SceneDrawingContext,
+    shape: &ColliderShapeDesc,
+    transform: Matrix4<f32>,
+    color: Color,
+) {
+    match shape {
+        ColliderShapeDesc::Ball(ball) => {
+            context.draw_sphere(
+                transform.transform_point(&Point3::origin()).coords,
+                10,
+                10,
+                ball.radius,
+                color,
+            );
+        }
+        ColliderShapeDesc::Cylinder(cylinder) => {
+            context.draw_cylinder(
+                10,
+                cylinder.radius,
+                cylinder.half_height * 2.0,
+                true,
+                transform,
+                color,
+            );
+        }
+        ColliderShapeDesc::RoundCylinder(round_cylinder) => {
+            context.draw_cylinder(
+                10,
+                round_cylinder.radius,
+                round_cylinder.half_height * 2.0,
+                false,
+                transform,
+                color,
+            );
+        }
+        ColliderShapeDesc::Cone(cone) => {
+            context.draw_cone(10, cone.radius, cone.half_height * 2.0, transform, color);
+        }
+        ColliderShapeDesc::Cuboid(cuboid) => {
+            let min = -cuboid.half_extents;
+            let max = cuboid.half_extents;
+            context.draw_oob(
+                &AxisAlignedBoundingBox::from_min_max(min, max),
+                transform,
+                color,
+            );
+        }
+        ColliderShapeDesc::Capsule(capsule) => context.draw_segment_capsule(
+            capsule.begin,
+            capsule.end,
+            capsule.radius,
+            10,
+            10,
+            transform,
+            color,
+        ),
+        ColliderShapeDesc::Segment(segment) => {
+            context.add_line(Line {
+                begin: segment.begin,
+                end: segment.end,
+                color,
+            });
+        }
+        ColliderShapeDesc::Triangle(triangle) => {
+            context.draw_triangle(triangle.a, triangle.b, triangle.c, color);
+        }
+        // These need additional data, see `Physics::draw`.
+        ColliderShapeDesc::Trimesh(_) | ColliderShapeDesc::Heightfield(_) => (),
+    }
+}
+
+fn draw_convex(
+    context: &mut SceneDrawingContext,
+    hull: &ConvexHull,
+    transform: Matrix4<f32>,
+    color: Color,
+) {
+    for (begin, end) in hull.edges() {
+        context.add_line(Line {
+            begin: transform.transform_point(&Point3::from(begin)).coords,
+            end: transform.transform_point(&Point3::from(end)).coords,
+            color,
+        });
+    }
+}
+
+/// Anchors of a joint that are farther than this from each other in world space are
+/// reported, joint will snap bodies together when simulation starts.
+pub const JOINT_ANCHOR_TOLERANCE: f32 = 0.05;
+
+/// Radius of revolute joint limits arc.
+const JOINT_LIMITS_ARC_RADIUS: f32 = 0.5;
+
+/// Returns some unit vector perpendicular to given one.
+fn any_perpendicular(v: Vector3<f32>) -> Vector3<f32> {
+    v.cross(&Vector3::z())
+        .try_normalize(std::f32::EPSILON)
+        .unwrap_or_else(|| v.cross(&Vector3::x()).normalize())
+}
+
+fn draw_arrow(
+    context: &mut SceneDrawingContext,
+    begin: Vector3<f32>,
+    end: Vector3<f32>,
+    color: Color,
+) {
+    let direction = end - begin;
+    let length = direction.norm();
+    if length <= std::f32::EPSILON {
+        return;
+    }
+    context.add_line(Line { begin, end, color });
+    let head = (length * 0.2).min(0.25);
+    let back = end - direction.scale(head / length);
+    let side = any_perpendicular(direction).scale(head * 0.5);
+    let up = direction.cross(&side).normalize().scale(head * 0.5);
+    for &offset in &[side, -side, up, -up] {
+        context.add_line(Line {
+            begin: end,
+            end: back + offset,
+            color,
+        });
+    }
+}
+
+fn draw_revolute_limits(
+    context: &mut SceneDrawingContext,
+    anchor: Vector3<f32>,
+    axis: Vector3<f32>,
+    motion: &JointMotion,
+) {
+    let reference = any_perpendicular(axis);
+    let side = axis.cross(&reference);
+    let point = |angle: f32| {
+        anchor
+            + (reference.scale(angle.cos()) + side.scale(angle.sin()))
+                .scale(JOINT_LIMITS_ARC_RADIUS)
+    };
+
+    let color = Color::opaque(0, 200, 255);
+    context.add_line(Line {
+        begin: anchor,
+        end: anchor + axis,
+        color,
+    });
+
+    if motion.limits_enabled {
+        const SEGMENTS: usize = 24;
+        let (min, max) = (motion.min_limit, motion.max_limit.max(motion.min_limit));
+        let mut prev = point(min);
+        for i in 1..=SEGMENTS {
+            let next = point(min + (max - min) * i as f32 / SEGMENTS as f32);
+            context.add_line(Line {
+                begin: prev,
+                end: next,
+                color,
+            });
+            prev = next;
+        }
+        for &angle in &[min, max] {
+            context.add_line(Line {
+                begin: anchor,
+                end: point(angle),
+                color,
+            });
+        }
+    }
+
+    if motion.motor_enabled {
+        context.add_line(Line {
+            begin: anchor,
+            end: point(motion.motor_target_position),
+            color: Color::opaque(255, 255, 0),
+        });
+    }
+}
+
+fn draw_prismatic_limits(
+    context: &mut SceneDrawingContext,
+    anchor: Vector3<f32>,
+    axis: Vector3<f32>,
+    motion: &JointMotion,
+) {
+    let color = Color::opaque(0, 200, 255);
+    let tick = any_perpendicular(axis).scale(0.1);
+
+    if motion.limits_enabled {
+        let begin = anchor + axis.scale(motion.min_limit);
+        let end = anchor + axis.scale(motion.max_limit);
+        context.add_line(Line { begin, end, color });
+        for &position in &[begin, end] {
+            context.add_line(Line {
+                begin: position - tick,
+                end: position + tick,
+                color,
+            });
+        }
+    } else {
+        context.add_line(Line {
+            begin: anchor - axis,
+            end: anchor + axis,
+            color,
+        });
+    }
+
+    if motion.motor_enabled {
+        let target = anchor + axis.scale(motion.motor_target_position);
+        context.add_line(Line {
+            begin: target - tick.scale(2.0),
+            end: target + tick.scale(2.0),
+            color: Color::opaque(255, 255, 0),
+        });
+    }
+}
+
+/// Max amount of grid lines along each side of heightfield.
+const HEIGHTFIELD_DRAW_RESOLUTION: usize = 64;
+
+fn draw_heightfield(
+    context: &mut SceneDrawingContext,
+    source: &HeightfieldSource,
+    transform: Matrix4<f32>,
+    texture: Option<&Texture>,
+    color: Color,
+) {
+    let scale = source.scale;
+    let point = |u: f32, v: f32, height: f32| {
+        transform
+            .transform_point(&Point3::new(
+                (u - 0.5) * scale.x,
+                height * scale.y,
+                (v - 0.5) * scale.z,
+            ))
+            .coords
+    };
+
+    if let Some(heights) = texture.and_then(read_heights) {
+        let (height, width) = heights.shape();
+        let step = (width.max(height) / HEIGHTFIELD_DRAW_RESOLUTION).max(1);
+        let sample = |x: usize, z: usize| {
+            let x = x.min(width - 1);
+            let z = z.min(height - 1);
+            point(
+                x as f32 / (width - 1) as f32,
+                z as f32 / (height - 1) as f32,
+                heights[(z, x)],
+            )
+        };
+
+        for z in (0..height).step_by(step) {
+            for x in (0..width).step_by(step) {
+                let begin = sample(x, z);
+                if x + 1 < width {
+                    context.add_line(Line {
+                        begin,
+                        end: sample(x + step, z),
+                        color,
+                    });
+                }
+                if z + 1 < height {
+                    context.add_line(Line {
+                        begin,
+                        end: sample(x, z + step),
+                        color,
+                    });
+                }
+            }
+        }
+        return;
+    }
+
+    // No heights (yet) - show flat bounds.
+    let corners = [
+        point(0.0, 0.0, 0.0),
+        point(1.0, 0.0, 0.0),
+        point(1.0, 1.0, 0.0),
+        point(0.0, 1.0, 0.0),
+    ];
+    for i in 0..corners.len() {
+        context.add_line(Line {
+            begin: corners[i],
+            end: corners[(i + 1) % corners.len()],
+            color,
+        });
+    }
 }
 
 impl Physics {
@@ -117,6 +496,267 @@ impl Physics {
             colliders,
             joints,
             binder,
+            trimesh_sources: Default::default(),
+            heightfield_sources: Default::default(),
+            heightfield_textures: Default::default(),
+            convex_sources: Default::default(),
+            joint_motions: Default::default(),
+            body_dynamics: Default::default(),
+            collider_materials: Default::default(),
+            fit_preview: None,
+        }
+    }
+
+    pub fn trimesh_source(&self, collider: Handle<Collider>) -> TrimeshSource {
+        self.trimesh_sources
+            .get(&collider)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn heightfield_source(&self, collider: Handle<Collider>) -> HeightfieldSource {
+        self.heightfield_sources
+            .get(&collider)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn joint_motion(&self, joint: Handle<Joint>) -> JointMotion {
+        self.joint_motions.get(&joint).cloned().unwrap_or_default()
+    }
+
+    pub fn body_dynamics(&self, body: Handle<RigidBody>) -> BodyDynamics {
+        self.body_dynamics.get(&body).cloned().unwrap_or_default()
+    }
+
+    /// Returns mass body will have in simulation.
+    pub fn body_mass(&self, body: Handle<RigidBody>) -> f32 {
+        if self.body_dynamics(body).mass_from_density {
+            body_mass_properties(self, body).mass
+        } else {
+            self.bodies[body].mass
+        }
+    }
+
+    /// Rebuilds hull of convex collider and updates its enclosing cuboid, the cuboid is
+    /// what engine's descriptor holds until the hull is applied (see `ShapeSources`).
+    pub fn sync_convex_shape(&mut self, collider: Handle<Collider>) {
+        if let Some(source) = self.convex_sources.get_mut(&collider) {
+            source.rebuild();
+            self.colliders[collider].shape = ColliderShapeDesc::Cuboid(source.enclosing_cuboid());
+        }
+    }
+
+    /// Requests height maps of heightfield colliders which weren't requested yet and
+    /// forgets the ones that are not used anymore.
+    pub fn update_heightfield_textures(&mut self, resource_manager: &ResourceManager) {
+        let sources = &self.heightfield_sources;
+        self.heightfield_textures.retain(|path, _| {
+            sources
+                .values()
+                .any(|source| source.texture.as_ref() == Some(path))
+        });
+        for source in self.heightfield_sources.values() {
+            if let Some(path) = source.texture.as_ref() {
+                if !self.heightfield_textures.contains_key(path) {
+                    self.heightfield_textures
+                        .insert(path.clone(), resource_manager.request_texture(path));
+                }
+            }
+        }
+    }
+
+    /// Returns list of meshes trimesh collider takes its geometry from.
+    pub fn trimesh_meshes(&self, collider: Handle<Collider>, graph: &Graph) -> Vec<Handle<Node>> {
+        let parent = self.colliders[collider].parent.into();
+        trimesh_meshes_of(
+            self.trimesh_source(collider).node,
+            self.binder.key_of(&parent).cloned().unwrap_or_default(),
+            graph,
+        )
+    }
+
+    /// Returns keys colliders of bound bodies are saved with. Engine resolves colliders in
+    /// the order of descriptors, so index of a collider is its position among colliders of
+    /// the same body in the pool.
+    fn collider_keys(&self) -> HashMap<Handle<Collider>, ColliderKey> {
+        let mut counts = HashMap::new();
+        let mut keys = HashMap::new();
+        for (handle, collider) in self.colliders.pair_iter() {
+            let body: Handle<RigidBody> = collider.parent.into();
+            if let Some(&node) = self.binder.key_of(&body) {
+                let index = counts.entry(body).or_insert(0);
+                keys.insert(
+                    handle,
+                    ColliderKey {
+                        body: node,
+                        index: *index,
+                    },
+                );
+                *index += 1;
+            }
+        }
+        keys
+    }
+
+    /// Returns keys joints between bound bodies are saved with, see `collider_keys`.
+    fn joint_keys(&self) -> HashMap<Handle<Joint>, JointKey> {
+        let mut keys = HashMap::new();
+        let mut taken = Vec::new();
+        for (handle, joint) in self.joints.pair_iter() {
+            let body1 = self.binder.key_of(&joint.body1.into());
+            let body2 = self.binder.key_of(&joint.body2.into());
+            if let (Some(&body1), Some(&body2)) = (body1, body2) {
+                let mut key = JointKey {
+                    body1,
+                    body2,
+                    index: 0,
+                };
+                while taken.contains(&key) {
+                    key.index += 1;
+                }
+                taken.push(key);
+                keys.insert(handle, key);
+            }
+        }
+        keys
+    }
+
+    /// Collects parameters engine's descriptors can't hold, see `ShapeSources`. Nodes are
+    /// remapped using given function, it must map nodes the same way they were mapped to
+    /// produce saved scene (simulation preview keeps them as is). Parameters of bodies that
+    /// are not bound to any node can't be referenced and are reported to `warnings`.
+    pub fn generate_shape_sources(
+        &self,
+        remap_node: &dyn Fn(Handle<Node>) -> Handle<Node>,
+        warnings: &mut Vec<String>,
+    ) -> ShapeSources {
+        let mut sources = ShapeSources::default();
+
+        let collider_keys = self.collider_keys();
+        for (handle, collider) in self.colliders.pair_iter() {
+            let key = match collider_keys.get(&handle) {
+                Some(key) => ColliderKey {
+                    body: remap_node(key.body),
+                    index: key.index,
+                },
+                None => {
+                    if self.collider_materials.contains_key(&handle)
+                        || self.convex_sources.contains_key(&handle)
+                        || self.trimesh_sources.contains_key(&handle)
+                        || self.heightfield_sources.contains_key(&handle)
+                    {
+                        warnings.push(
+                            "Shape of a collider of unbound body is not saved, its \
+                             descriptor is used as is."
+                                .to_owned(),
+                        );
+                    }
+                    continue;
+                }
+            };
+
+            if let Some(&material) = self.collider_materials.get(&handle) {
+                sources.materials.insert(key, material);
+            }
+
+            if let Some(source) = self.convex_sources.get(&handle) {
+                sources.convexes.insert(key, source.shape());
+                continue;
+            }
+
+            match collider.shape {
+                ColliderShapeDesc::Trimesh(_) => {
+                    if let Some(source) = self.trimesh_sources.get(&handle) {
+                        if *source != TrimeshSource::default() {
+                            sources.trimeshes.insert(
+                                key,
+                                TrimeshSource {
+                                    node: remap_node(source.node),
+                                    surfaces: source.surfaces.clone(),
+                                },
+                            );
+                        }
+                    }
+                }
+                ColliderShapeDesc::Heightfield(_) => {
+                    if let Some(source) = self.heightfield_sources.get(&handle) {
+                        sources.heightfields.insert(key, source.clone());
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        let joint_keys = self.joint_keys();
+        for (handle, _) in self.joints.pair_iter() {
+            let motion = match self.joint_motions.get(&handle) {
+                Some(motion) if *motion != JointMotion::default() => motion,
+                _ => continue,
+            };
+            match joint_keys.get(&handle) {
+                Some(key) => {
+                    sources.joint_motions.insert(
+                        JointKey {
+                            body1: remap_node(key.body1),
+                            body2: remap_node(key.body2),
+                            index: key.index,
+                        },
+                        motion.clone(),
+                    );
+                }
+                None => warnings
+                    .push("Limits and motor of a joint of unbound body are not saved.".to_owned()),
+            }
+        }
+
+        for (handle, _) in self.bodies.pair_iter() {
+            let dynamics = match self.body_dynamics.get(&handle) {
+                Some(dynamics) if *dynamics != BodyDynamics::default() => dynamics,
+                _ => continue,
+            };
+            match self.binder.key_of(&handle) {
+                Some(&node) => {
+                    sources
+                        .body_dynamics
+                        .insert(remap_node(node), dynamics.clone());
+                }
+                None => warnings
+                    .push("Damping, CCD and locks of unbound body are not saved.".to_owned()),
+            }
+        }
+
+        sources
+    }
+
+    /// Applies shape parameters loaded with the scene, must be called right after `new`.
+    pub fn apply_shape_sources(&mut self, mut sources: ShapeSources) {
+        for (handle, key) in self.collider_keys() {
+            if let Some(source) = sources.trimeshes.remove(&key) {
+                self.trimesh_sources.insert(handle, source);
+            }
+            if let Some(source) = sources.heightfields.remove(&key) {
+                self.heightfield_sources.insert(handle, source);
+            }
+            if let Some(shape) = sources.convexes.remove(&key) {
+                self.convex_sources
+                    .insert(handle, ConvexSource::from_shape(shape));
+            }
+            if let Some(material) = sources.materials.remove(&key) {
+                self.collider_materials.insert(handle, material);
+            }
+        }
+
+        for (handle, key) in self.joint_keys() {
+            if let Some(motion) = sources.joint_motions.remove(&key) {
+                self.joint_motions.insert(handle, motion);
+            }
+        }
+
+        for (&node, &body) in self.binder.forward_map().iter() {
+            if let Some(dynamics) = sources.body_dynamics.remove(&node) {
+                self.body_dynamics.insert(body, dynamics);
+            }
         }
     }
 
@@ -132,7 +772,9 @@ impl Physics {
             .pair_iter()
             .enumerate()
             .map(|(i, (h, r))| {
-                // Sparse to dense mapping.
+                // Sparse to dense mapping. Dense handles are positions in descriptor lists,
+                // engine gives resolved bodies handles of its own - they must be taken from
+                // scene's binder.
                 let dense_handle = RigidBodyHandle::from(Index::from_raw_parts(i, 0));
                 body_map.insert(h, dense_handle);
                 RigidBodyDesc {
@@ -144,7 +786,7 @@ impl Physics {
                     status: r.status,
                     // Filled later.
                     colliders: vec![],
-                    mass: r.mass,
+                    mass: self.body_mass(h),
                 }
             })
             .collect::<Vec<_>>();
@@ -212,6 +854,141 @@ impl Physics {
         )
     }
 
+    /// Returns frame in which body's colliders and joint anchors are defined - global
+    /// transform of the bound node, or body's own transform if it is not bound.
+    pub fn body_frame(&self, body: Handle<RigidBody>, graph: &Graph) -> Isometry3<f32> {
+        if let Some(&node) = self.binder.key_of(&body) {
+            if graph.is_valid_handle(node) {
+                let (rotation, position) = graph.isometric_global_rotation_position(node);
+                return Isometry3 {
+                    rotation,
+                    translation: Translation { vector: position },
+                };
+            }
+        }
+        let body = &self.bodies[body];
+        Isometry3 {
+            rotation: body.rotation,
+            translation: Translation {
+                vector: body.position,
+            },
+        }
+    }
+
+    /// Returns world positions of joint's anchors, second one is `None` if joint is not
+    /// connected to a body yet.
+    pub fn joint_world_anchors(
+        &self,
+        joint: Handle<Joint>,
+        graph: &Graph,
+    ) -> (Vector3<f32>, Option<Vector3<f32>>) {
+        let joint = &self.joints[joint];
+        let (anchor1, anchor2) = match &joint.params {
+            JointParamsDesc::BallJoint(ball) => (ball.local_anchor1, ball.local_anchor2),
+            JointParamsDesc::FixedJoint(fixed) => (
+                fixed.local_anchor1_translation,
+                fixed.local_anchor2_translation,
+            ),
+            JointParamsDesc::PrismaticJoint(prismatic) => {
+                (prismatic.local_anchor1, prismatic.local_anchor2)
+            }
+            JointParamsDesc::RevoluteJoint(revolute) => {
+                (revolute.local_anchor1, revolute.local_anchor2)
+            }
+        };
+
+        let to_world = |body: Handle<RigidBody>, anchor: Vector3<f32>| {
+            self.body_frame(body, graph)
+                .transform_point(&Point3::from(anchor))
+                .coords
+        };
+
+        let body2: Handle<RigidBody> = joint.body2.into();
+        (
+            to_world(joint.body1.into(), anchor1),
+            if self.bodies.is_valid_handle(body2) {
+                Some(to_world(body2, anchor2))
+            } else {
+                None
+            },
+        )
+    }
+
+    /// Returns distance between anchors of connected joint in world space.
+    pub fn joint_anchor_distance(&self, joint: Handle<Joint>, graph: &Graph) -> Option<f32> {
+        match self.joint_world_anchors(joint, graph) {
+            (anchor1, Some(anchor2)) => Some(anchor1.metric_distance(&anchor2)),
+            _ => None,
+        }
+    }
+
+    fn draw_joint_limits(
+        &self,
+        context: &mut SceneDrawingContext,
+        handle: Handle<Joint>,
+        graph: &Graph,
+    ) {
+        let joint = &self.joints[handle];
+        let (anchor1, anchor2) = self.joint_world_anchors(handle, graph);
+        let rotation = self.body_frame(joint.body1.into(), graph).rotation;
+        let motion = self.joint_motion(handle);
+
+        match &joint.params {
+            JointParamsDesc::RevoluteJoint(revolute) => {
+                if let Some(axis) = revolute.local_axis1.try_normalize(std::f32::EPSILON) {
+                    draw_revolute_limits(context, anchor1, rotation * axis, &motion);
+                }
+            }
+            JointParamsDesc::PrismaticJoint(prismatic) => {
+                if let Some(axis) = prismatic.local_axis1.try_normalize(std::f32::EPSILON) {
+                    draw_prismatic_limits(context, anchor1, rotation * axis, &motion);
+                }
+            }
+            _ => (),
+        }
+
+        if let Some(anchor2) = anchor2 {
+            if anchor1.metric_distance(&anchor2) > JOINT_ANCHOR_TOLERANCE {
+                context.add_line(Line {
+                    begin: anchor1,
+                    end: anchor2,
+                    color: Color::RED,
+                });
+            }
+        }
+    }
+
+    /// Draws center of mass of a body and its principal axes of inertia, length of each
+    /// axis is radius of gyration around it.
+    fn draw_mass_properties(
+        &self,
+        context: &mut SceneDrawingContext,
+        body: Handle<RigidBody>,
+        graph: &Graph,
+    ) {
+        let properties = body_mass_properties(self, body);
+        if properties.mass <= std::f32::EPSILON {
+            return;
+        }
+
+        let frame = self.body_frame(body, graph);
+        let center = frame
+            .transform_point(&Point3::from(properties.center_of_mass))
+            .coords;
+        context.draw_sphere(center, 8, 8, 0.05, Color::opaque(255, 255, 0));
+
+        let (moments, axes) = properties.principal_inertia();
+        for (i, &color) in [Color::RED, Color::GREEN, Color::BLUE].iter().enumerate() {
+            let radius = (moments[i].max(0.0) / properties.mass).sqrt();
+            let axis = frame.rotation * axes.column(i).into_owned();
+            context.add_line(Line {
+                begin: center - axis.scale(radius),
+                end: center + axis.scale(radius),
+                color,
+            });
+        }
+    }
+
     /// Searches joint by its **first** body.
     pub fn find_joint(&self, body1: Handle<RigidBody>) -> Handle<Joint> {
         for (handle, joint) in self.joints.pair_iter() {
@@ -222,8 +999,14 @@ impl Physics {
         Handle::NONE
     }
 
-    pub fn draw(&self, context: &mut SceneDrawingContext, graph: &Graph) {
-        for body in self.bodies.iter() {
+    pub fn draw(
+        &self,
+        context: &mut SceneDrawingContext,
+        graph: &Graph,
+        selected_body: Handle<RigidBody>,
+        selected_joint: Handle<Joint>,
+    ) {
+        for (handle, body) in self.bodies.pair_iter() {
             context.draw_transform(
                 Isometry3 {
                     rotation: body.rotation,
@@ -233,22 +1016,26 @@ impl Physics {
                 }
                 .to_homogeneous(),
             );
+
+            // Initial velocities, angular one points along rotation axis.
+            let origin = self.body_frame(handle, graph).translation.vector;
+            draw_arrow(
+                context,
+                origin,
+                origin + body.linvel,
+                Color::opaque(0, 200, 255),
+            );
+            draw_arrow(
+                context,
+                origin,
+                origin + body.angvel,
+                Color::opaque(255, 150, 0),
+            );
         }
 
         let color = Color::opaque(255, 0, 255);
 
-        for collider in self.colliders.iter() {
-            let parent = collider.parent.into();
-            let body = self.bodies.borrow(parent);
-
-            let body_global_transform = Isometry3 {
-                rotation: body.rotation,
-                translation: Translation3 {
-                    vector: body.position,
-                },
-            }
-            .to_homogeneous();
-
+        for (collider_handle, collider) in self.colliders.pair_iter() {
             let collider_local_tranform = Isometry3 {
                 rotation: collider.rotation,
                 translation: Translation3 {
@@ -257,115 +1044,88 @@ impl Physics {
             }
             .to_homogeneous();
 
-            let transform = if let Some(&node) = self.binder.key_of(&parent) {
-                let (rotation, position) = graph.isometric_global_rotation_position(node);
-                Isometry3 {
-                    rotation,
-                    translation: Translation { vector: position },
-                }
+            let transform = self
+                .body_frame(collider.parent.into(), graph)
                 .to_homogeneous()
-                    * collider_local_tranform
-            } else {
-                body_global_transform * collider_local_tranform
-            };
+                * collider_local_tranform;
+
+            if let Some(source) = self.convex_sources.get(&collider_handle) {
+                draw_convex(context, source.hull(), transform, color);
+                continue;
+            }
 
             match &collider.shape {
-                ColliderShapeDesc::Ball(ball) => {
-                    context.draw_sphere(body.position, 10, 10, ball.radius, color);
-                }
-                ColliderShapeDesc::Cylinder(cylinder) => {
-                    context.draw_cylinder(
-                        10,
-                        cylinder.radius,
-                        cylinder.half_height * 2.0,
-                        true,
-                        transform,
-                        color,
-                    );
-                }
-                ColliderShapeDesc::RoundCylinder(round_cylinder) => {
-                    context.draw_cylinder(
-                        10,
-                        round_cylinder.radius,
-                        round_cylinder.half_height * 2.0,
-                        false,
-                        transform,
-                        color,
-                    );
-                }
-                ColliderShapeDesc::Cone(cone) => {
-                    context.draw_cone(10, cone.radius, cone.half_height * 2.0, transform, color);
-                }
-                ColliderShapeDesc::Cuboid(cuboid) => {
-                    let min = -cuboid.half_extents;
-                    let max = cuboid.half_extents;
-                    context.draw_oob(
-                        &AxisAlignedBoundingBox::from_min_max(min, max),
-                        transform,
-                        color,
-                    );
-                }
-                ColliderShapeDesc::Capsule(capsule) => context.draw_segment_capsule(
-                    capsule.begin,
-                    capsule.end,
-                    capsule.radius,
-                    10,
-                    10,
-                    transform,
-                    color,
-                ),
-                ColliderShapeDesc::Segment(segment) => {
-                    context.add_line(Line {
-                        begin: segment.begin,
-                        end: segment.end,
-                        color,
-                    });
-                }
-                ColliderShapeDesc::Triangle(triangle) => {
-                    context.draw_triangle(triangle.a, triangle.b, triangle.c, color);
-                }
                 ColliderShapeDesc::Trimesh(_) => {
-                    let mut node = Handle::NONE;
-                    for (&n, &b) in self.binder.forward_map().iter() {
-                        if b == parent {
-                            node = n;
-                            break;
-                        }
-                    }
-
-                    if node.is_some() {
-                        let mut stack = vec![node];
-                        while let Some(handle) = stack.pop() {
-                            let node = &graph[handle];
-                            if let Node::Mesh(mesh) = node {
-                                // Trimesh's transform is special - it has transform baked into vertices.
-                                // We have to emulate it here.
-                                let transform = mesh.global_transform();
-                                for surface in mesh.surfaces() {
-                                    let data = surface.data();
-                                    let data = data.read().unwrap();
-                                    for triangle in data.triangles() {
-                                        let a = transform.transform_point(&Point3::from(
-                                            data.get_vertices()[triangle[0] as usize].position,
-                                        ));
-                                        let b = transform.transform_point(&Point3::from(
-                                            data.get_vertices()[triangle[1] as usize].position,
-                                        ));
-                                        let c = transform.transform_point(&Point3::from(
-                                            data.get_vertices()[triangle[2] as usize].position,
-                                        ));
-                                        context.draw_triangle(a.coords, b.coords, c.coords, color);
-                                    }
+                    let source = self.trimesh_source(collider_handle);
+                    for handle in self.trimesh_meshes(collider_handle, graph) {
+                        if let Node::Mesh(mesh) = &graph[handle] {
+                            // Trimesh's transform is special - it has transform baked into vertices.
+                            // We have to emulate it here.
+                            let transform = mesh.global_transform();
+                            for (i, surface) in mesh.surfaces().iter().enumerate() {
+                                if !source.takes_surface(i) {
+                                    continue;
+                                }
+                                let data = surface.data();
+                                let data = data.read().unwrap();
+                                for triangle in data.triangles() {
+                                    let a = transform.transform_point(&Point3::from(
+                                        data.get_vertices()[triangle[0] as usize].position,
+                                    ));
+                                    let b = transform.transform_point(&Point3::from(
+                                        data.get_vertices()[triangle[1] as usize].position,
+                                    ));
+                                    let c = transform.transform_point(&Point3::from(
+                                        data.get_vertices()[triangle[2] as usize].position,
+                                    ));
+                                    context.draw_triangle(a.coords, b.coords, c.coords, color);
                                 }
                             }
-                            stack.extend_from_slice(node.children());
                         }
                     }
                 }
-                ColliderShapeDesc::Heightfield(_) => {} // TODO
+                ColliderShapeDesc::Heightfield(_) => {
+                    let source = self.heightfield_source(collider_handle);
+                    let texture = source
+                        .texture
+                        .as_ref()
+                        .and_then(|path| self.heightfield_textures.get(path));
+                    draw_heightfield(context, &source, transform, texture, color)
+                }
+                shape => draw_primitive_shape(context, shape, transform, color),
             }
         }
 
+        if let Some(fit) = self.fit_preview.as_ref() {
+            if graph.is_valid_handle(fit.node) {
+                let frame = fit.frame(graph).to_homogeneous();
+                let color = Color::opaque(0, 255, 0);
+                for fitted in fit.colliders.iter() {
+                    let collider = &fitted.collider;
+                    let local_transform = Isometry3 {
+                        rotation: collider.rotation,
+                        translation: Translation3 {
+                            vector: collider.translation,
+                        },
+                    }
+                    .to_homogeneous();
+                    let transform = frame * local_transform;
+                    match fitted.convex.as_ref() {
+                        Some(source) => draw_convex(context, source.hull(), transform, color),
+                        None => draw_primitive_shape(context, &collider.shape, transform, color),
+                    }
+                }
+            }
+        }
+
+        if self.bodies.is_valid_handle(selected_body) {
+            self.draw_mass_properties(context, selected_body, graph);
+        }
+
+        if self.joints.is_valid_handle(selected_joint) {
+            self.draw_joint_limits(context, selected_joint, graph);
+        }
+
         for joint in self.joints.iter() {
             match &joint.params {
                 JointParamsDesc::BallJoint(ball) => {
@@ -0,0 +1,497 @@
+use crate::{
+    gui::{BuildContext, Ui, UiMessage, UiNode},
+    physics::Collider,
+    scene::{
+        CommandGroup, EditorScene, SceneCommand, Selection, SetColliderCollisionGroupsCommand,
+        SetColliderSolverGroupsCommand,
+    },
+    send_sync_message,
+    sidebar::make_dropdown_list_option,
+    Message,
+};
+use rg3d::{
+    core::{
+        pool::Handle,
+        visitor::{Visit, VisitResult, Visitor},
+    },
+    gui::{
+        button::ButtonBuilder,
+        check_box::CheckBoxBuilder,
+        dropdown_list::DropdownListBuilder,
+        grid::{Column, GridBuilder, Row},
+        message::{
+            ButtonMessage, CheckBoxMessage, DropdownListMessage, MessageDirection, TextBoxMessage,
+            UiMessageData, WindowMessage,
+        },
+        scroll_viewer::ScrollViewerBuilder,
+        stack_panel::StackPanelBuilder,
+        text::TextBuilder,
+        text_box::TextBoxBuilder,
+        widget::WidgetBuilder,
+        window::{WindowBuilder, WindowTitle},
+        HorizontalAlignment, Orientation, Thickness, VerticalAlignment,
+    },
+    scene::physics::PhysicsDesc,
+};
+use std::sync::mpsc::Sender;
+
+/// Amount of layers, it is limited by the size of membership and filter parts of
+/// interaction groups.
+pub const LAYER_COUNT: usize = 16;
+
+/// Layers belong to a project rather than a scene, so they're saved in working directory.
+/// Same as other project tables, changes of layers are not undoable and saved at once.
+pub const PHYSICS_LAYERS_PATH: &str = "physics_layers.bin";
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LayerMatrix {
+    /// Which layers generate contacts with each other.
+    Collision,
+    /// Which layers exchange contact forces with each other.
+    Solver,
+}
+
+/// Named physics layers and matrices of their interactions. Collider's layers are the
+/// membership part (upper 16 bits) of its interaction groups, filter part is derived from
+/// matrices - a collider interacts with every layer that any of its layers interacts with.
+/// Filter part of editor's colliders could be derived from older matrices, so groups are
+/// derived again from current layers for descriptors that are saved or simulated.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PhysicsLayers {
+    pub names: Vec<String>,
+    /// Mask of interacting layers per layer, always symmetric.
+    collision: Vec<u16>,
+    /// Same as `collision`, but for solver groups.
+    solver: Vec<u16>,
+}
+
+impl Default for PhysicsLayers {
+    fn default() -> Self {
+        Self {
+            names: (0..LAYER_COUNT)
+                .map(|i| {
+                    if i == 0 {
+                        "Default".to_owned()
+                    } else {
+                        format!("Layer {}", i)
+                    }
+                })
+                .collect(),
+            collision: vec![u16::MAX; LAYER_COUNT],
+            solver: vec![u16::MAX; LAYER_COUNT],
+        }
+    }
+}
+
+impl Visit for PhysicsLayers {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.names.visit("Names", visitor)?;
+        self.collision.visit("Collision", visitor)?;
+        self.solver.visit("Solver", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+impl PhysicsLayers {
+    /// Loads layers of current project, missing or malformed file gives default layers.
+    pub fn load() -> Self {
+        let mut layers = Self::default();
+        if let Ok(mut visitor) = Visitor::load_binary(PHYSICS_LAYERS_PATH) {
+            if layers.visit("PhysicsLayers", &mut visitor).is_err()
+                || layers.names.len() != LAYER_COUNT
+                || layers.collision.len() != LAYER_COUNT
+                || layers.solver.len() != LAYER_COUNT
+            {
+                layers = Self::default();
+            }
+        }
+        layers
+    }
+
+    pub fn save(&mut self) -> VisitResult {
+        let mut visitor = Visitor::new();
+        self.visit("PhysicsLayers", &mut visitor)?;
+        visitor.save_binary(PHYSICS_LAYERS_PATH)
+    }
+
+    fn matrix(&self, matrix: LayerMatrix) -> &[u16] {
+        match matrix {
+            LayerMatrix::Collision => &self.collision,
+            LayerMatrix::Solver => &self.solver,
+        }
+    }
+
+    pub fn interacts(&self, matrix: LayerMatrix, a: usize, b: usize) -> bool {
+        self.matrix(matrix)[a] & (1 << b) != 0
+    }
+
+    pub fn set_interacts(&mut self, matrix: LayerMatrix, a: usize, b: usize, value: bool) {
+        let masks = match matrix {
+            LayerMatrix::Collision => &mut self.collision,
+            LayerMatrix::Solver => &mut self.solver,
+        };
+        for &(row, column) in &[(a, b), (b, a)] {
+            if value {
+                masks[row] |= 1 << column;
+            } else {
+                masks[row] &= !(1 << column);
+            }
+        }
+    }
+
+    fn filter(&self, matrix: LayerMatrix, memberships: u16) -> u16 {
+        self.matrix(matrix)
+            .iter()
+            .enumerate()
+            .filter(|(layer, _)| memberships & (1 << layer) != 0)
+            .fold(0, |filter, (_, mask)| filter | mask)
+    }
+
+    /// Returns collision and solver groups of a collider that belongs to given layers.
+    pub fn groups(&self, memberships: u16) -> (u32, u32) {
+        let make = |matrix| ((memberships as u32) << 16) | self.filter(matrix, memberships) as u32;
+        (make(LayerMatrix::Collision), make(LayerMatrix::Solver))
+    }
+
+    pub fn memberships(collider: &Collider) -> u16 {
+        (collider.collision_groups >> 16) as u16
+    }
+
+    /// Derives groups of every collider of engine's descriptor from its layers.
+    pub fn derive_groups(&self, desc: &mut PhysicsDesc) {
+        for collider in desc.colliders.iter_mut() {
+            let memberships = (collider.collision_groups >> 16) as u16;
+            let (collision_groups, solver_groups) = self.groups(memberships);
+            collider.collision_groups = collision_groups;
+            collider.solver_groups = solver_groups;
+        }
+    }
+
+    /// Makes command that sets groups of colliders that belong to given layers, it is `None`
+    /// if groups are the same already.
+    pub fn make_groups_command(
+        &self,
+        colliders: &[(Handle<Collider>, &Collider)],
+        memberships: u16,
+    ) -> Option<SceneCommand> {
+        let (collision_groups, solver_groups) = self.groups(memberships);
+        let mut commands = Vec::new();
+        for &(handle, collider) in colliders {
+            if collider.collision_groups != collision_groups {
+                commands.push(SceneCommand::SetColliderCollisionGroups(
+                    SetColliderCollisionGroupsCommand::new(handle, collision_groups),
+                ));
+            }
+            if collider.solver_groups != solver_groups {
+                commands.push(SceneCommand::SetColliderSolverGroups(
+                    SetColliderSolverGroupsCommand::new(handle, solver_groups),
+                ));
+            }
+        }
+        if commands.is_empty() {
+            None
+        } else {
+            Some(SceneCommand::CommandGroup(CommandGroup::from(commands)))
+        }
+    }
+}
+
+/// Makes command that moves every collider of bodies bound to selected nodes to given layer.
+fn assign_layer_to_selection(editor_scene: &EditorScene, layer: usize) -> Option<SceneCommand> {
+    let physics = &editor_scene.physics;
+    let mut colliders = Vec::new();
+    if let Selection::Graph(selection) = &editor_scene.selection {
+        for node in selection.nodes() {
+            if let Some(&body) = physics.binder.value_of(node) {
+                for &collider in physics.bodies[body].colliders.iter() {
+                    colliders.push((collider.into(), &physics.colliders[collider.into()]));
+                }
+            }
+        }
+    }
+    editor_scene
+        .physics_layers
+        .make_groups_command(&colliders, 1 << layer)
+}
+
+pub struct PhysicsLayersWindow {
+    window: Handle<UiNode>,
+    matrix_kind: Handle<UiNode>,
+    names: Vec<Handle<UiNode>>,
+    /// Lower triangle of the matrix, `cells[a][b]` exists for every `b <= a`.
+    cells: Vec<Vec<Handle<UiNode>>>,
+    assign_layer: Handle<UiNode>,
+    assign: Handle<UiNode>,
+    assign_index: usize,
+    matrix: LayerMatrix,
+    cached_names: Vec<String>,
+    sender: Sender<Message>,
+}
+
+fn make_cell_text(ctx: &mut BuildContext, text: &str, row: usize, column: usize) -> Handle<UiNode> {
+    TextBuilder::new(
+        WidgetBuilder::new()
+            .on_row(row)
+            .on_column(column)
+            .with_vertical_alignment(VerticalAlignment::Center)
+            .with_horizontal_alignment(HorizontalAlignment::Center),
+    )
+    .with_text(text)
+    .build(ctx)
+}
+
+impl PhysicsLayersWindow {
+    pub fn new(ctx: &mut BuildContext, sender: Sender<Message>) -> Self {
+        let matrix_kind;
+        let assign_layer;
+        let assign;
+
+        let mut children = Vec::new();
+        for layer in 0..LAYER_COUNT {
+            children.push(make_cell_text(ctx, &layer.to_string(), 0, layer + 2));
+        }
+        let mut names = Vec::new();
+        let mut cells = Vec::new();
+        for a in 0..LAYER_COUNT {
+            let row = a + 1;
+            children.push(make_cell_text(ctx, &a.to_string(), row, 0));
+            let name = TextBoxBuilder::new(
+                WidgetBuilder::new()
+                    .on_row(row)
+                    .on_column(1)
+                    .with_margin(Thickness::uniform(1.0)),
+            )
+            .build(ctx);
+            children.push(name);
+            names.push(name);
+            let mut row_cells = Vec::new();
+            for b in 0..=a {
+                let cell = CheckBoxBuilder::new(
+                    WidgetBuilder::new()
+                        .on_row(row)
+                        .on_column(b + 2)
+                        .with_margin(Thickness::uniform(2.0)),
+                )
+                .build(ctx);
+                children.push(cell);
+                row_cells.push(cell);
+            }
+            cells.push(row_cells);
+        }
+
+        let mut matrix_grid = GridBuilder::new(WidgetBuilder::new().with_children(&children))
+            .add_column(Column::strict(20.0))
+            .add_column(Column::strict(110.0));
+        for _ in 0..LAYER_COUNT {
+            matrix_grid = matrix_grid.add_column(Column::strict(20.0));
+        }
+        for _ in 0..=LAYER_COUNT {
+            matrix_grid = matrix_grid.add_row(Row::strict(22.0));
+        }
+        let matrix_grid = matrix_grid.build(ctx);
+
+        let window = WindowBuilder::new(WidgetBuilder::new().with_width(500.0).with_height(500.0))
+            .open(false)
+            .with_title(WindowTitle::Text("Physics Layers".to_owned()))
+            .with_content(
+                GridBuilder::new(
+                    WidgetBuilder::new()
+                        .with_child({
+                            matrix_kind = DropdownListBuilder::new(
+                                WidgetBuilder::new()
+                                    .on_row(0)
+                                    .with_margin(Thickness::uniform(1.0)),
+                            )
+                            .with_close_on_selection(true)
+                            .with_items(vec![
+                                make_dropdown_list_option(ctx, "Collision Matrix"),
+                                make_dropdown_list_option(ctx, "Solver Matrix"),
+                            ])
+                            .build(ctx);
+                            matrix_kind
+                        })
+                        .with_child(
+                            ScrollViewerBuilder::new(WidgetBuilder::new().on_row(1))
+                                .with_content(matrix_grid)
+                                .build(ctx),
+                        )
+                        .with_child(
+                            StackPanelBuilder::new(
+                                WidgetBuilder::new()
+                                    .on_row(2)
+                                    .with_horizontal_alignment(HorizontalAlignment::Right)
+                                    .with_child({
+                                        assign_layer = DropdownListBuilder::new(
+                                            WidgetBuilder::new()
+                                                .with_width(150.0)
+                                                .with_margin(Thickness::uniform(1.0)),
+                                        )
+                                        .with_close_on_selection(true)
+                                        .build(ctx);
+                                        assign_layer
+                                    })
+                                    .with_child({
+                                        assign = ButtonBuilder::new(
+                                            WidgetBuilder::new()
+                                                .with_width(140.0)
+                                                .with_margin(Thickness::uniform(1.0)),
+                                        )
+                                        .with_text("Assign To Selection")
+                                        .build(ctx);
+                                        assign
+                                    }),
+                            )
+                            .with_orientation(Orientation::Horizontal)
+                            .build(ctx),
+                        ),
+                )
+                .add_row(Row::strict(26.0))
+                .add_row(Row::stretch())
+                .add_row(Row::strict(26.0))
+                .add_column(Column::stretch())
+                .build(ctx),
+            )
+            .build(ctx);
+
+        Self {
+            window,
+            matrix_kind,
+            names,
+            cells,
+            assign_layer,
+            assign,
+            assign_index: 0,
+            matrix: LayerMatrix::Collision,
+            cached_names: Default::default(),
+            sender,
+        }
+    }
+
+    pub fn open(&self, ui: &Ui) {
+        ui.send_message(WindowMessage::open(
+            self.window,
+            MessageDirection::ToWidget,
+            true,
+        ));
+    }
+
+    pub fn sync_to_model(&mut self, layers: &PhysicsLayers, ui: &mut Ui) {
+        send_sync_message(
+            ui,
+            DropdownListMessage::selection(
+                self.matrix_kind,
+                MessageDirection::ToWidget,
+                Some(match self.matrix {
+                    LayerMatrix::Collision => 0,
+                    LayerMatrix::Solver => 1,
+                }),
+            ),
+        );
+
+        for (a, row_cells) in self.cells.iter().enumerate() {
+            for (b, &cell) in row_cells.iter().enumerate() {
+                send_sync_message(
+                    ui,
+                    CheckBoxMessage::checked(
+                        cell,
+                        MessageDirection::ToWidget,
+                        Some(layers.interacts(self.matrix, a, b)),
+                    ),
+                );
+            }
+        }
+
+        if self.cached_names != layers.names {
+            self.cached_names = layers.names.clone();
+
+            for (&name, text) in self.names.iter().zip(layers.names.iter()) {
+                send_sync_message(
+                    ui,
+                    TextBoxMessage::text(name, MessageDirection::ToWidget, text.clone()),
+                );
+            }
+
+            let ctx = &mut ui.build_ctx();
+            let items = layers
+                .names
+                .iter()
+                .map(|name| make_dropdown_list_option(ctx, name))
+                .collect::<Vec<_>>();
+            send_sync_message(
+                ui,
+                DropdownListMessage::items(self.assign_layer, MessageDirection::ToWidget, items),
+            );
+            send_sync_message(
+                ui,
+                DropdownListMessage::selection(
+                    self.assign_layer,
+                    MessageDirection::ToWidget,
+                    Some(self.assign_index),
+                ),
+            );
+        }
+    }
+
+    /// Layers are not a part of scene, so their changes are not undoable and saved at once.
+    pub fn handle_message(&mut self, message: &UiMessage, editor_scene: &mut EditorScene) {
+        if message.direction() != MessageDirection::FromWidget {
+            return;
+        }
+
+        let old_layers = editor_scene.physics_layers.clone();
+        let new_layers = &mut editor_scene.physics_layers;
+
+        match message.data() {
+            UiMessageData::CheckBox(CheckBoxMessage::Check(checked)) => {
+                for (a, row_cells) in self.cells.iter().enumerate() {
+                    if let Some(b) = row_cells.iter().position(|&c| c == message.destination()) {
+                        new_layers.set_interacts(self.matrix, a, b, checked.unwrap_or_default());
+                    }
+                }
+            }
+            UiMessageData::TextBox(TextBoxMessage::Text(text)) => {
+                if let Some(layer) = self.names.iter().position(|&n| n == message.destination()) {
+                    new_layers.names[layer] = text.clone();
+                }
+            }
+            &UiMessageData::DropdownList(DropdownListMessage::SelectionChanged(Some(index)))
+                if message.destination() == self.matrix_kind =>
+            {
+                self.matrix = if index == 0 {
+                    LayerMatrix::Collision
+                } else {
+                    LayerMatrix::Solver
+                };
+                // Force full sync, cells have to show another matrix.
+                self.sender.send(Message::SyncToModel).unwrap();
+            }
+            &UiMessageData::DropdownList(DropdownListMessage::SelectionChanged(Some(index)))
+                if message.destination() == self.assign_layer =>
+            {
+                self.assign_index = index;
+            }
+            UiMessageData::Button(ButtonMessage::Click) if message.destination() == self.assign => {
+                if let Some(command) = assign_layer_to_selection(editor_scene, self.assign_index) {
+                    self.sender.send(Message::DoSceneCommand(command)).unwrap();
+                }
+            }
+            _ => (),
+        }
+
+        let layers = &mut editor_scene.physics_layers;
+        if *layers != old_layers {
+            if let Err(e) = layers.save() {
+                self.sender
+                    .send(Message::Log(format!(
+                        "Unable to save physics layers: {:?}",
+                        e
+                    )))
+                    .unwrap();
+            }
+            self.sender.send(Message::SyncToModel).unwrap();
+        }
+    }
+}
@@ -0,0 +1,528 @@
+use crate::{
+    gui::{BuildContext, Ui, UiMessage, UiNode},
+    physics::{Collider, Physics},
+    scene::{
+        ChangeSelectionCommand, EditorScene, GraphSelection, SceneCommand, Selection,
+        SetColliderMaterialCommand,
+    },
+    send_sync_message,
+    sidebar::{make_button, make_dropdown_list_option, make_text_mark},
+    Message,
+};
+use rg3d::{
+    core::{
+        pool::Handle,
+        visitor::{Visit, VisitResult, Visitor},
+    },
+    gui::{
+        dropdown_list::DropdownListBuilder,
+        grid::{Column, GridBuilder, Row},
+        message::{
+            ButtonMessage, DropdownListMessage, MessageDirection, NumericUpDownMessage,
+            TextBoxMessage, UiMessageData, WidgetMessage, WindowMessage,
+        },
+        numeric::NumericUpDownBuilder,
+        stack_panel::StackPanelBuilder,
+        text_box::TextBoxBuilder,
+        widget::WidgetBuilder,
+        window::{WindowBuilder, WindowTitle},
+        HorizontalAlignment, Orientation, Thickness,
+    },
+    scene::{graph::Graph, node::Node},
+};
+use std::{collections::HashSet, sync::mpsc::Sender};
+
+/// Materials belong to a project rather than a scene, so they're saved in working directory.
+/// Same as other project tables, changes of materials are not undoable and saved at once.
+pub const PHYSICS_MATERIALS_PATH: &str = "physics_materials.bin";
+
+/// Density of a collider that has none set explicitly.
+pub const DEFAULT_DENSITY: f32 = 1.0;
+
+/// Applying a material copies its values to a collider and links the collider to the
+/// material by id (see `Physics::collider_materials`), so materials can be renamed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PhysicsMaterial {
+    /// Unique within library, assigned by the library.
+    pub id: u32,
+    pub name: String,
+    pub friction: f32,
+    pub restitution: f32,
+    pub density: f32,
+}
+
+impl Default for PhysicsMaterial {
+    fn default() -> Self {
+        Self::new("Material", 0.5, 0.0, DEFAULT_DENSITY)
+    }
+}
+
+impl Visit for PhysicsMaterial {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        // Older libraries have no ids, they're assigned on load.
+        let _ = self.id.visit("Id", visitor);
+        self.name.visit("Name", visitor)?;
+        self.friction.visit("Friction", visitor)?;
+        self.restitution.visit("Restitution", visitor)?;
+        self.density.visit("Density", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+impl PhysicsMaterial {
+    pub fn new(name: &str, friction: f32, restitution: f32, density: f32) -> Self {
+        Self {
+            id: 0,
+            name: name.to_owned(),
+            friction,
+            restitution,
+            density,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PhysicsMaterialLibrary {
+    pub materials: Vec<PhysicsMaterial>,
+}
+
+impl Default for PhysicsMaterialLibrary {
+    fn default() -> Self {
+        let mut library = Self {
+            materials: vec![
+                PhysicsMaterial::new("Ice", 0.02, 0.05, 0.9),
+                PhysicsMaterial::new("Rubber", 1.0, 0.8, 1.1),
+                PhysicsMaterial::new("Wood", 0.5, 0.3, 0.7),
+                PhysicsMaterial::new("Metal", 0.4, 0.1, 7.8),
+            ],
+        };
+        library.assign_ids();
+        library
+    }
+}
+
+impl Visit for PhysicsMaterialLibrary {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.materials.visit("Materials", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+impl PhysicsMaterialLibrary {
+    /// Loads materials of current project, missing or malformed file gives default presets.
+    pub fn load() -> Self {
+        let mut library = Self::default();
+        if let Ok(mut visitor) = Visitor::load_binary(PHYSICS_MATERIALS_PATH) {
+            if library.visit("PhysicsMaterials", &mut visitor).is_err() {
+                library = Self::default();
+            }
+        }
+        library.assign_ids();
+        library
+    }
+
+    /// Gives unique id to every material that has none or has the same id as another one.
+    fn assign_ids(&mut self) {
+        let mut next_id = self.materials.iter().map(|m| m.id).max().unwrap_or(0) + 1;
+        let mut used = HashSet::new();
+        for material in self.materials.iter_mut() {
+            if material.id == 0 || !used.insert(material.id) {
+                material.id = next_id;
+                used.insert(next_id);
+                next_id += 1;
+            }
+        }
+    }
+
+    /// Adds default material with new id, returns its index.
+    pub fn add(&mut self) -> usize {
+        self.materials.push(Default::default());
+        self.assign_ids();
+        self.materials.len() - 1
+    }
+
+    pub fn save(&mut self) -> VisitResult {
+        let mut visitor = Visitor::new();
+        self.visit("PhysicsMaterials", &mut visitor)?;
+        visitor.save_binary(PHYSICS_MATERIALS_PATH)
+    }
+
+    /// Returns index of material collider is linked to, material could be removed already.
+    pub fn material_of(&self, collider: Handle<Collider>, physics: &Physics) -> Option<usize> {
+        let id = *physics.collider_materials.get(&collider)?;
+        self.materials.iter().position(|m| m.id == id)
+    }
+
+    /// Returns every node whose body has at least one collider using given material.
+    pub fn users(&self, material: usize, physics: &Physics, graph: &Graph) -> Vec<Handle<Node>> {
+        let id = self.materials[material].id;
+        graph
+            .pair_iter()
+            .filter_map(|(node, _)| {
+                let body = *physics.binder.value_of(&node)?;
+                if physics.bodies[body]
+                    .colliders
+                    .iter()
+                    .any(|&c| physics.collider_materials.get(&c.into()) == Some(&id))
+                {
+                    Some(node)
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+/// Colliders of bodies bound to selected nodes.
+fn selected_colliders(editor_scene: &EditorScene) -> Vec<Handle<Collider>> {
+    let physics = &editor_scene.physics;
+    let mut colliders = Vec::new();
+    if let Selection::Graph(selection) = &editor_scene.selection {
+        for node in selection.nodes() {
+            if let Some(&body) = physics.binder.value_of(node) {
+                colliders.extend(physics.bodies[body].colliders.iter().map(|&c| c.into()));
+            }
+        }
+    }
+    colliders
+}
+
+pub struct PhysicsMaterialsWindow {
+    window: Handle<UiNode>,
+    materials: Handle<UiNode>,
+    name: Handle<UiNode>,
+    friction: Handle<UiNode>,
+    restitution: Handle<UiNode>,
+    density: Handle<UiNode>,
+    add: Handle<UiNode>,
+    remove: Handle<UiNode>,
+    select_users: Handle<UiNode>,
+    apply: Handle<UiNode>,
+    selected: Option<usize>,
+    cached_names: Vec<String>,
+    sender: Sender<Message>,
+}
+
+fn make_f32_input_field(ctx: &mut BuildContext, row: usize) -> Handle<UiNode> {
+    NumericUpDownBuilder::new(
+        WidgetBuilder::new()
+            .on_column(1)
+            .on_row(row)
+            .with_margin(Thickness::uniform(1.0)),
+    )
+    .with_min_value(0.0)
+    .with_step(0.05)
+    .build(ctx)
+}
+
+impl PhysicsMaterialsWindow {
+    pub fn new(ctx: &mut BuildContext, sender: Sender<Message>) -> Self {
+        let materials;
+        let name;
+        let friction;
+        let restitution;
+        let density;
+        let add;
+        let remove;
+        let select_users;
+        let apply;
+        let window = WindowBuilder::new(WidgetBuilder::new().with_width(300.0).with_height(250.0))
+            .open(false)
+            .with_title(WindowTitle::Text("Physics Materials".to_owned()))
+            .with_content(
+                GridBuilder::new(
+                    WidgetBuilder::new()
+                        .with_child(
+                            GridBuilder::new(
+                                WidgetBuilder::new()
+                                    .on_row(0)
+                                    .with_child(make_text_mark(ctx, "Material", 0))
+                                    .with_child({
+                                        materials = DropdownListBuilder::new(
+                                            WidgetBuilder::new()
+                                                .on_row(0)
+                                                .on_column(1)
+                                                .with_margin(Thickness::uniform(1.0)),
+                                        )
+                                        .with_close_on_selection(true)
+                                        .build(ctx);
+                                        materials
+                                    })
+                                    .with_child(make_text_mark(ctx, "Name", 1))
+                                    .with_child({
+                                        name = TextBoxBuilder::new(
+                                            WidgetBuilder::new()
+                                                .on_row(1)
+                                                .on_column(1)
+                                                .with_margin(Thickness::uniform(1.0)),
+                                        )
+                                        .build(ctx);
+                                        name
+                                    })
+                                    .with_child(make_text_mark(ctx, "Friction", 2))
+                                    .with_child({
+                                        friction = make_f32_input_field(ctx, 2);
+                                        friction
+                                    })
+                                    .with_child(make_text_mark(ctx, "Restitution", 3))
+                                    .with_child({
+                                        restitution = make_f32_input_field(ctx, 3);
+                                        restitution
+                                    })
+                                    .with_child(make_text_mark(ctx, "Density", 4))
+                                    .with_child({
+                                        density = make_f32_input_field(ctx, 4);
+                                        density
+                                    }),
+                            )
+                            .add_row(Row::strict(25.0))
+                            .add_row(Row::strict(25.0))
+                            .add_row(Row::strict(25.0))
+                            .add_row(Row::strict(25.0))
+                            .add_row(Row::strict(25.0))
+                            .add_column(Column::strict(100.0))
+                            .add_column(Column::stretch())
+                            .build(ctx),
+                        )
+                        .with_child(
+                            StackPanelBuilder::new(
+                                WidgetBuilder::new()
+                                    .on_row(1)
+                                    .with_horizontal_alignment(HorizontalAlignment::Right)
+                                    .with_child({
+                                        add = make_button(ctx, "Add");
+                                        add
+                                    })
+                                    .with_child({
+                                        remove = make_button(ctx, "Remove");
+                                        remove
+                                    }),
+                            )
+                            .with_orientation(Orientation::Horizontal)
+                            .build(ctx),
+                        )
+                        .with_child(
+                            StackPanelBuilder::new(
+                                WidgetBuilder::new()
+                                    .on_row(2)
+                                    .with_horizontal_alignment(HorizontalAlignment::Right)
+                                    .with_child({
+                                        select_users = make_button(ctx, "Select Users");
+                                        select_users
+                                    })
+                                    .with_child({
+                                        apply = make_button(ctx, "Apply To Selection");
+                                        apply
+                                    }),
+                            )
+                            .with_orientation(Orientation::Horizontal)
+                            .build(ctx),
+                        ),
+                )
+                .add_row(Row::stretch())
+                .add_row(Row::strict(25.0))
+                .add_row(Row::strict(25.0))
+                .add_column(Column::stretch())
+                .build(ctx),
+            )
+            .build(ctx);
+
+        Self {
+            window,
+            materials,
+            name,
+            friction,
+            restitution,
+            density,
+            add,
+            remove,
+            select_users,
+            apply,
+            selected: None,
+            cached_names: Default::default(),
+            sender,
+        }
+    }
+
+    pub fn open(&self, ui: &Ui) {
+        ui.send_message(WindowMessage::open(
+            self.window,
+            MessageDirection::ToWidget,
+            true,
+        ));
+    }
+
+    pub fn sync_to_model(&mut self, library: &PhysicsMaterialLibrary, ui: &mut Ui) {
+        let names = library
+            .materials
+            .iter()
+            .map(|m| m.name.clone())
+            .collect::<Vec<_>>();
+        if self.cached_names != names {
+            let ctx = &mut ui.build_ctx();
+            let items = names
+                .iter()
+                .map(|name| make_dropdown_list_option(ctx, name))
+                .collect::<Vec<_>>();
+            send_sync_message(
+                ui,
+                DropdownListMessage::items(self.materials, MessageDirection::ToWidget, items),
+            );
+            self.cached_names = names;
+        }
+
+        if self
+            .selected
+            .map_or(false, |i| i >= library.materials.len())
+        {
+            self.selected = library.materials.len().checked_sub(1);
+        }
+
+        send_sync_message(
+            ui,
+            DropdownListMessage::selection(
+                self.materials,
+                MessageDirection::ToWidget,
+                self.selected,
+            ),
+        );
+
+        if let Some(material) = self.selected.map(|i| &library.materials[i]) {
+            send_sync_message(
+                ui,
+                TextBoxMessage::text(self.name, MessageDirection::ToWidget, material.name.clone()),
+            );
+            for &(field, value) in &[
+                (self.friction, material.friction),
+                (self.restitution, material.restitution),
+                (self.density, material.density),
+            ] {
+                send_sync_message(
+                    ui,
+                    NumericUpDownMessage::value(field, MessageDirection::ToWidget, value),
+                );
+            }
+        }
+
+        for &widget in &[
+            self.name,
+            self.friction,
+            self.restitution,
+            self.density,
+            self.remove,
+            self.select_users,
+            self.apply,
+        ] {
+            send_sync_message(
+                ui,
+                WidgetMessage::enabled(widget, MessageDirection::ToWidget, self.selected.is_some()),
+            );
+        }
+    }
+
+    /// Library is not a part of scene, so its changes are not undoable and saved at once.
+    pub fn handle_message(
+        &mut self,
+        message: &UiMessage,
+        editor_scene: &mut EditorScene,
+        graph: &Graph,
+    ) {
+        if message.direction() != MessageDirection::FromWidget {
+            return;
+        }
+
+        let library = &mut editor_scene.physics_materials;
+        let old_library = library.clone();
+
+        match message.data() {
+            &UiMessageData::DropdownList(DropdownListMessage::SelectionChanged(selection))
+                if message.destination() == self.materials =>
+            {
+                if self.selected != selection {
+                    self.selected = selection;
+                    self.sender.send(Message::SyncToModel).unwrap();
+                }
+            }
+            UiMessageData::TextBox(TextBoxMessage::Text(text))
+                if message.destination() == self.name =>
+            {
+                if let Some(i) = self.selected {
+                    library.materials[i].name = text.clone();
+                }
+            }
+            &UiMessageData::NumericUpDown(NumericUpDownMessage::Value(value)) => {
+                if let Some(material) = self.selected.map(|i| &mut library.materials[i]) {
+                    if message.destination() == self.friction {
+                        material.friction = value;
+                    } else if message.destination() == self.restitution {
+                        material.restitution = value;
+                    } else if message.destination() == self.density {
+                        material.density = value;
+                    }
+                }
+            }
+            UiMessageData::Button(ButtonMessage::Click) => {
+                if message.destination() == self.add {
+                    self.selected = Some(library.add());
+                } else if message.destination() == self.remove {
+                    if let Some(i) = self.selected {
+                        library.materials.remove(i);
+                    }
+                } else if message.destination() == self.select_users {
+                    if let Some(i) = self.selected {
+                        let users =
+                            editor_scene
+                                .physics_materials
+                                .users(i, &editor_scene.physics, graph);
+                        let new_selection = Selection::Graph(GraphSelection::from_list(users));
+                        if new_selection != editor_scene.selection {
+                            self.sender
+                                .send(Message::DoSceneCommand(SceneCommand::ChangeSelection(
+                                    ChangeSelectionCommand::new(
+                                        new_selection,
+                                        editor_scene.selection.clone(),
+                                    ),
+                                )))
+                                .unwrap();
+                        }
+                    }
+                } else if message.destination() == self.apply {
+                    if let Some(i) = self.selected {
+                        let colliders = selected_colliders(editor_scene);
+                        if !colliders.is_empty() {
+                            self.sender
+                                .send(Message::DoSceneCommand(SceneCommand::SetColliderMaterial(
+                                    SetColliderMaterialCommand::new(
+                                        colliders,
+                                        &editor_scene.physics_materials.materials[i],
+                                    ),
+                                )))
+                                .unwrap();
+                        }
+                    }
+                }
+            }
+            _ => (),
+        }
+
+        let library = &mut editor_scene.physics_materials;
+        if *library != old_library {
+            if let Err(e) = library.save() {
+                self.sender
+                    .send(Message::Log(format!(
+                        "Unable to save physics materials: {:?}",
+                        e
+                    )))
+                    .unwrap();
+            }
+            self.sender.send(Message::SyncToModel).unwrap();
+        }
+    }
+}
@@ -0,0 +1,271 @@
+//! Viewport debug render modes. Everything here is editor-only - material overrides are
+//! applied right before the scene is rendered and reverted right after, so nothing else
+//! (commands, saving, lightmap baking) ever sees them. The rest is done with debug drawing.
+
+use crate::scene::scene_nodes;
+use rg3d::{
+    core::{
+        algebra::{Point3, Vector3},
+        color::Color,
+        pool::Handle,
+    },
+    resource::texture::{Texture, TextureKind, TexturePixelKind},
+    scene::{mesh::Mesh, node::Node, Line, Scene},
+};
+use std::collections::HashMap;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum RenderMode {
+    Lit = 0,
+    Unlit = 1,
+    Wireframe = 2,
+    Normals = 3,
+    LightmapUv = 4,
+    BoundingBoxes = 5,
+}
+
+impl RenderMode {
+    pub const NAMES: [&'static str; 6] = [
+        "Lit",
+        "Unlit",
+        "Wireframe",
+        "Normals",
+        "Lightmap UV",
+        "Bounding Boxes",
+    ];
+
+    pub fn from_index(index: usize) -> Option<Self> {
+        match index {
+            0 => Some(RenderMode::Lit),
+            1 => Some(RenderMode::Unlit),
+            2 => Some(RenderMode::Wireframe),
+            3 => Some(RenderMode::Normals),
+            4 => Some(RenderMode::LightmapUv),
+            5 => Some(RenderMode::BoundingBoxes),
+            _ => None,
+        }
+    }
+
+    fn overrides_materials(self) -> bool {
+        matches!(self, RenderMode::Unlit | RenderMode::LightmapUv)
+    }
+}
+
+struct SurfaceBackup {
+    diffuse_texture: Option<Texture>,
+    lightmap_texture: Option<Texture>,
+}
+
+pub struct ViewportRenderMode {
+    mode: RenderMode,
+    checker: Option<Texture>,
+    applied: bool,
+    ambient_lighting_color: Color,
+    lights: Vec<(Handle<Node>, bool)>,
+    meshes: Vec<(Handle<Node>, Vec<SurfaceBackup>)>,
+    // Segments of wireframe (edge begin and end) or normals (vertex position and normal)
+    // in local space of each mesh. Reading every vertex buffer each frame is too slow for
+    // big scenes, so they're collected once and only transformed each frame.
+    segments: HashMap<Handle<Node>, Vec<(Vector3<f32>, Vector3<f32>)>>,
+}
+
+const NORMAL_LENGTH: f32 = 0.1;
+
+fn collect_segments(mesh: &Mesh, mode: RenderMode) -> Vec<(Vector3<f32>, Vector3<f32>)> {
+    let mut segments = Vec::new();
+    for surface in mesh.surfaces() {
+        let data = surface.data();
+        let data = data.read().unwrap();
+        let vertices = data.get_vertices();
+        if mode == RenderMode::Wireframe {
+            let position = |i: u32| vertices[i as usize].position;
+            for triangle in data.triangles() {
+                let [a, b, c] = [
+                    position(triangle[0]),
+                    position(triangle[1]),
+                    position(triangle[2]),
+                ];
+                segments.push((a, b));
+                segments.push((b, c));
+                segments.push((c, a));
+            }
+        } else {
+            segments.extend(vertices.iter().map(|v| (v.position, v.normal)));
+        }
+    }
+    segments
+}
+
+fn make_checker_texture() -> Option<Texture> {
+    const SIZE: usize = 64;
+    const CELL: usize = 8;
+
+    let mut pixels = Vec::with_capacity(SIZE * SIZE * 4);
+    for y in 0..SIZE {
+        for x in 0..SIZE {
+            let (r, g, b) = if (x / CELL + y / CELL) % 2 == 0 {
+                (255, 255, 255)
+            } else {
+                (255, 80, 0)
+            };
+            pixels.extend_from_slice(&[r, g, b, 255]);
+        }
+    }
+
+    Texture::from_bytes(
+        TextureKind::Rectangle {
+            width: SIZE as u32,
+            height: SIZE as u32,
+        },
+        TexturePixelKind::RGBA8,
+        pixels,
+    )
+    .ok()
+}
+
+impl Default for ViewportRenderMode {
+    fn default() -> Self {
+        Self {
+            mode: RenderMode::Lit,
+            checker: make_checker_texture(),
+            applied: false,
+            ambient_lighting_color: Default::default(),
+            lights: Default::default(),
+            meshes: Default::default(),
+            segments: Default::default(),
+        }
+    }
+}
+
+impl ViewportRenderMode {
+    pub fn mode(&self) -> RenderMode {
+        self.mode
+    }
+
+    pub fn set_mode(&mut self, mode: RenderMode) {
+        if self.mode != mode {
+            self.mode = mode;
+            self.invalidate();
+        }
+    }
+
+    /// Drops cached debug lines, must be called when meshes of the scene could change.
+    pub fn invalidate(&mut self) {
+        self.segments.clear();
+    }
+
+    /// Overrides materials and lights of the scene, must be paired with `revert` once the
+    /// scene is rendered.
+    pub fn apply(&mut self, scene: &mut Scene, editor_root: Handle<Node>) {
+        if self.applied || !self.mode.overrides_materials() {
+            return;
+        }
+
+        self.applied = true;
+
+        // Lights are turned off and replaced with white ambient light, so only albedo
+        // (or whatever we put in surface textures) is visible.
+        self.ambient_lighting_color = scene.ambient_lighting_color;
+        scene.ambient_lighting_color = Color::WHITE;
+
+        for handle in scene_nodes(&scene.graph, editor_root) {
+            match &mut scene.graph[handle] {
+                Node::Light(light) => {
+                    self.lights.push((handle, light.visibility()));
+                    light.set_visibility(false);
+                }
+                Node::Mesh(mesh) => {
+                    let mut backup = Vec::new();
+                    for surface in mesh.surfaces_mut() {
+                        backup.push(SurfaceBackup {
+                            diffuse_texture: surface.diffuse_texture(),
+                            lightmap_texture: surface.lightmap_texture(),
+                        });
+                        if self.mode == RenderMode::LightmapUv {
+                            // Lightmap is sampled using second texture coordinates so checker
+                            // texture shows how lightmap will be laid out on the surface.
+                            surface.set_diffuse_texture(None);
+                            surface.set_lightmap_texture(self.checker.clone());
+                        } else {
+                            surface.set_lightmap_texture(None);
+                        }
+                    }
+                    self.meshes.push((handle, backup));
+                }
+                _ => (),
+            }
+        }
+    }
+
+    pub fn revert(&mut self, scene: &mut Scene) {
+        if !self.applied {
+            return;
+        }
+
+        self.applied = false;
+
+        scene.ambient_lighting_color = self.ambient_lighting_color;
+
+        for (handle, visibility) in self.lights.drain(..) {
+            if scene.graph.is_valid_handle(handle) {
+                scene.graph[handle].set_visibility(visibility);
+            }
+        }
+
+        for (handle, backup) in self.meshes.drain(..) {
+            if scene.graph.is_valid_handle(handle) {
+                if let Node::Mesh(mesh) = &mut scene.graph[handle] {
+                    for (surface, backup) in mesh.surfaces_mut().iter_mut().zip(backup) {
+                        surface.set_diffuse_texture(backup.diffuse_texture);
+                        surface.set_lightmap_texture(backup.lightmap_texture);
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn draw(&mut self, scene: &mut Scene, editor_root: Handle<Node>) {
+        let color = match self.mode {
+            RenderMode::Wireframe => Color::opaque(0, 200, 255),
+            RenderMode::Normals => Color::opaque(255, 0, 200),
+            RenderMode::BoundingBoxes => Color::opaque(255, 255, 0),
+            _ => return,
+        };
+
+        let ctx = &mut scene.drawing_context;
+        for handle in scene_nodes(&scene.graph, editor_root) {
+            if let Node::Mesh(mesh) = &scene.graph[handle] {
+                if !mesh.global_visibility() {
+                    continue;
+                }
+
+                let transform = mesh.global_transform();
+
+                match self.mode {
+                    RenderMode::BoundingBoxes => {
+                        ctx.draw_oob(&mesh.bounding_box(), transform, color);
+                    }
+                    mode => {
+                        let segments = self
+                            .segments
+                            .entry(handle)
+                            .or_insert_with(|| collect_segments(mesh, mode));
+                        for (first, second) in segments.iter() {
+                            let begin = transform.transform_point(&Point3::from(*first)).coords;
+                            let end = if mode == RenderMode::Wireframe {
+                                transform.transform_point(&Point3::from(*second)).coords
+                            } else {
+                                let normal = transform
+                                    .transform_vector(second)
+                                    .try_normalize(std::f32::EPSILON)
+                                    .unwrap_or_else(Vector3::y);
+                                begin + normal.scale(NORMAL_LENGTH)
+                            };
+                            ctx.add_line(Line { begin, end, color });
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
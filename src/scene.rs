@@ -2,17 +2,26 @@ use crate::{
     camera::CameraController,
     command::Command,
     interaction::navmesh::{
-        data_model::{Navmesh, NavmeshEdge, NavmeshEntity, NavmeshTriangle, NavmeshVertex},
+        areas::{NavmeshAreaCosts, NavmeshAreaTable},
+        data_model::{
+            Navmesh, NavmeshEdge, NavmeshEntity, NavmeshLink, NavmeshTriangle, NavmeshVertex,
+        },
+        links::NavmeshLinks,
         selection::NavmeshSelection,
+        topology::{self, NavmeshCorner, NavmeshTopologyPlan},
     },
-    physics::{Collider, Joint, Physics, RigidBody},
+    physics::{
+        BodyDynamics, Collider, ConvexSource, Joint, JointMotion, Physics, RigidBody, TrimeshSource,
+    },
+    physics_layers::PhysicsLayers,
+    physics_materials::{PhysicsMaterial, PhysicsMaterialLibrary},
     GameEngine, Message,
 };
 use rg3d::scene::base::{LevelOfDetail, LodGroup};
 use rg3d::{
     animation::Animation,
     core::{
-        algebra::{UnitQuaternion, Vector3},
+        algebra::{Point3, UnitQuaternion, Vector3},
         color::Color,
         math::Matrix4Ext,
         numeric_range::NumericRange,
@@ -27,10 +36,9 @@ use rg3d::{
         mesh::{Mesh, RenderPath},
         node::Node,
         particle_system::{Emitter, ParticleLimit, ParticleSystem},
-        physics::{ColliderShapeDesc, JointParamsDesc},
+        physics::{BodyStatusDesc, ColliderShapeDesc, JointParamsDesc},
         Scene,
     },
-    sound::math::TriangleDefinition,
 };
 use std::{collections::HashMap, fmt::Write, path::PathBuf, sync::mpsc::Sender};
 
@@ -97,13 +105,43 @@ fn deep_clone_nodes(
 
                 // Also copy colliders.
                 for &collider in body.colliders.iter() {
-                    let mut collider_clone = source_physics.colliders[collider.into()].clone();
+                    let collider: Handle<Collider> = collider.into();
+                    let mut collider_clone = source_physics.colliders[collider].clone();
                     collider_clone.parent = body_clone_handle.into();
                     let collider_clone_handle = dest_physics.colliders.spawn(collider_clone);
                     dest_physics.bodies[body_clone_handle]
                         .colliders
                         .push(collider_clone_handle.into());
 
+                    if let Some(source) = source_physics.trimesh_sources.get(&collider) {
+                        dest_physics.trimesh_sources.insert(
+                            collider_clone_handle,
+                            TrimeshSource {
+                                // Source outside of copied hierarchy can't be kept.
+                                node: old_new_mapping
+                                    .get(&source.node)
+                                    .cloned()
+                                    .unwrap_or_default(),
+                                surfaces: source.surfaces.clone(),
+                            },
+                        );
+                    }
+                    if let Some(source) = source_physics.heightfield_sources.get(&collider) {
+                        dest_physics
+                            .heightfield_sources
+                            .insert(collider_clone_handle, source.clone());
+                    }
+                    if let Some(source) = source_physics.convex_sources.get(&collider) {
+                        dest_physics
+                            .convex_sources
+                            .insert(collider_clone_handle, source.clone());
+                    }
+                    if let Some(&material) = source_physics.collider_materials.get(&collider) {
+                        dest_physics
+                            .collider_materials
+                            .insert(collider_clone_handle, material);
+                    }
+
                     result.colliders.push(collider_clone_handle);
                 }
 
@@ -180,6 +218,12 @@ pub struct EditorScene {
     // but some parts are not because of incompatible data model.
     pub physics: Physics,
     pub navmeshes: Pool<Navmesh>,
+    /// Area types of the project, see `NavmeshAreaTable`.
+    pub navmesh_areas: NavmeshAreaTable,
+    /// Physics layers of the project, see `PhysicsLayers`.
+    pub physics_layers: PhysicsLayers,
+    /// Physics materials of the project, see `PhysicsMaterialLibrary`.
+    pub physics_materials: PhysicsMaterialLibrary,
 }
 
 impl EditorScene {
@@ -230,37 +274,15 @@ impl EditorScene {
             pure_scene.navmeshes.clear();
 
             for navmesh in self.navmeshes.iter() {
-                // Sparse-to-dense mapping - handle to index.
-                let mut vertex_map = HashMap::new();
-
-                let vertices = navmesh
-                    .vertices
-                    .pair_iter()
-                    .enumerate()
-                    .map(|(i, (handle, vertex))| {
-                        vertex_map.insert(handle, i);
-                        vertex.position
-                    })
-                    .collect::<Vec<_>>();
-
-                let triangles = navmesh
-                    .triangles
-                    .iter()
-                    .map(|triangle| {
-                        TriangleDefinition([
-                            vertex_map[&triangle.a] as u32,
-                            vertex_map[&triangle.b] as u32,
-                            vertex_map[&triangle.c] as u32,
-                        ])
-                    })
-                    .collect::<Vec<_>>();
+                let (vertices, triangles) = navmesh.to_dense();
 
                 pure_scene
                     .navmeshes
                     .add(rg3d::utils::navmesh::Navmesh::new(&triangles, &vertices));
             }
 
-            let (desc, binder) = self.physics.generate_engine_desc();
+            let (mut desc, binder) = self.physics.generate_engine_desc();
+            self.physics_layers.derive_groups(&mut desc);
             pure_scene.physics.desc = Some(desc);
             pure_scene.physics_binder.enabled = true;
             pure_scene.physics_binder.clear();
@@ -271,10 +293,39 @@ impl EditorScene {
             }
             let mut visitor = Visitor::new();
             pure_scene.visit("Scene", &mut visitor).unwrap();
+            let mut warnings = Vec::new();
             if let Err(e) = visitor.save_binary(&path) {
                 Err(format!("Failed to save scene! Reason: {}", e.to_string()))
+            } else if let Err(e) = self
+                .physics
+                .generate_shape_sources(
+                    &|node| old_to_new.get(&node).cloned().unwrap_or_default(),
+                    &mut warnings,
+                )
+                .save(&path)
+            {
+                Err(format!(
+                    "Failed to save collider shapes of scene! Reason: {}",
+                    e.to_string()
+                ))
+            } else if let Err(e) =
+                NavmeshAreaCosts::new(&self.navmeshes, &self.navmesh_areas).save(&path)
+            {
+                Err(format!(
+                    "Failed to save navmesh areas of scene! Reason: {}",
+                    e.to_string()
+                ))
+            } else if let Err(e) = NavmeshLinks::new(&self.navmeshes, &mut warnings).save(&path) {
+                Err(format!(
+                    "Failed to save navmesh links of scene! Reason: {}",
+                    e.to_string()
+                ))
             } else {
-                Ok(format!("Scene {} was successfully saved!", path.display()))
+                let mut message = format!("Scene {} was successfully saved!", path.display());
+                for warning in warnings {
+                    write!(&mut message, "\n{}", warning).unwrap();
+                }
+                Ok(message)
             }
         } else {
             writeln!(&mut reason, "\nPlease fix errors and try again.").unwrap();
@@ -318,6 +369,14 @@ pub enum SceneCommand {
     SetColliderRotation(SetColliderRotationCommand),
     SetColliderIsSensor(SetColliderIsSensorCommand),
     SetColliderCollisionGroups(SetColliderCollisionGroupsCommand),
+    SetColliderSolverGroups(SetColliderSolverGroupsCommand),
+    SetColliderMaterial(SetColliderMaterialCommand),
+    SetColliderDensity(SetColliderDensityCommand),
+    SetBodyStatus(SetBodyStatusCommand),
+    SetBodyLinVel(SetBodyLinVelCommand),
+    SetBodyAngVel(SetBodyAngVelCommand),
+    SetBodySleeping(SetBodySleepingCommand),
+    SetBodyDynamics(SetBodyDynamicsCommand),
     SetCylinderHalfHeight(SetCylinderHalfHeightCommand),
     SetCylinderRadius(SetCylinderRadiusCommand),
     SetCapsuleRadius(SetCapsuleRadiusCommand),
@@ -326,6 +385,14 @@ pub enum SceneCommand {
     SetConeHalfHeight(SetConeHalfHeightCommand),
     SetConeRadius(SetConeRadiusCommand),
     SetBallRadius(SetBallRadiusCommand),
+    SetTrimeshSourceNode(SetTrimeshSourceNodeCommand),
+    SetTrimeshSurfaces(SetTrimeshSurfacesCommand),
+    SetHeightfieldTexture(SetHeightfieldTextureCommand),
+    SetHeightfieldScale(SetHeightfieldScaleCommand),
+    SetConvexPoints(SetConvexPointsCommand),
+    SetConvexTolerance(SetConvexToleranceCommand),
+    SetJointMotion(SetJointMotionCommand),
+    SwapJointBodies(SwapJointBodiesCommand),
     SetBallJointAnchor1(SetBallJointAnchor1Command),
     SetBallJointAnchor2(SetBallJointAnchor2Command),
     SetFixedJointAnchor1Translation(SetFixedJointAnchor1TranslationCommand),
@@ -382,6 +449,17 @@ pub enum SceneCommand {
     AddNavmeshEdge(AddNavmeshEdgeCommand),
     DeleteNavmeshVertex(DeleteNavmeshVertexCommand),
     ConnectNavmeshEdges(ConnectNavmeshEdgesCommand),
+    SetNavmeshTrianglesArea(SetNavmeshTrianglesAreaCommand),
+    AddNavmeshLink(AddNavmeshLinkCommand),
+    DeleteNavmeshLink(DeleteNavmeshLinkCommand),
+    SetNavmeshLink(SetNavmeshLinkCommand),
+    WeldNavmeshVertices(WeldNavmeshVerticesCommand),
+    SplitNavmeshEdge(SplitNavmeshEdgeCommand),
+    SubdivideNavmeshTriangles(SubdivideNavmeshTrianglesCommand),
+    FlipNavmeshEdge(FlipNavmeshEdgeCommand),
+    DeleteNavmeshTriangles(DeleteNavmeshTrianglesCommand),
+    ExtrudeNavmeshTriangles(ExtrudeNavmeshTrianglesCommand),
+    FlattenNavmeshTriangles(FlattenNavmeshTrianglesCommand),
     SetPhysicsBinding(SetPhysicsBindingCommand),
 }
 
@@ -427,6 +505,14 @@ macro_rules! static_dispatch {
             SceneCommand::SetColliderRotation(v) => v.$func($($args),*),
             SceneCommand::SetColliderIsSensor(v) => v.$func($($args),*),
             SceneCommand::SetColliderCollisionGroups(v) => v.$func($($args),*),
+            SceneCommand::SetColliderSolverGroups(v) => v.$func($($args),*),
+            SceneCommand::SetColliderMaterial(v) => v.$func($($args),*),
+            SceneCommand::SetColliderDensity(v) => v.$func($($args),*),
+            SceneCommand::SetBodyStatus(v) => v.$func($($args),*),
+            SceneCommand::SetBodyLinVel(v) => v.$func($($args),*),
+            SceneCommand::SetBodyAngVel(v) => v.$func($($args),*),
+            SceneCommand::SetBodySleeping(v) => v.$func($($args),*),
+            SceneCommand::SetBodyDynamics(v) => v.$func($($args),*),
             SceneCommand::SetCylinderHalfHeight(v) => v.$func($($args),*),
             SceneCommand::SetCylinderRadius(v) => v.$func($($args),*),
             SceneCommand::SetCapsuleRadius(v) => v.$func($($args),*),
@@ -435,6 +521,14 @@ macro_rules! static_dispatch {
             SceneCommand::SetConeHalfHeight(v) => v.$func($($args),*),
             SceneCommand::SetConeRadius(v) => v.$func($($args),*),
             SceneCommand::SetBallRadius(v) => v.$func($($args),*),
+            SceneCommand::SetTrimeshSourceNode(v) => v.$func($($args),*),
+            SceneCommand::SetTrimeshSurfaces(v) => v.$func($($args),*),
+            SceneCommand::SetHeightfieldTexture(v) => v.$func($($args),*),
+            SceneCommand::SetHeightfieldScale(v) => v.$func($($args),*),
+            SceneCommand::SetConvexPoints(v) => v.$func($($args),*),
+            SceneCommand::SetConvexTolerance(v) => v.$func($($args),*),
+            SceneCommand::SetJointMotion(v) => v.$func($($args),*),
+            SceneCommand::SwapJointBodies(v) => v.$func($($args),*),
             SceneCommand::SetBallJointAnchor1(v) => v.$func($($args),*),
             SceneCommand::SetBallJointAnchor2(v) => v.$func($($args),*),
             SceneCommand::SetFixedJointAnchor1Translation(v) => v.$func($($args),*),
@@ -491,6 +585,17 @@ macro_rules! static_dispatch {
             SceneCommand::AddNavmeshEdge(v) => v.$func($($args),*),
             SceneCommand::DeleteNavmeshVertex(v) => v.$func($($args),*),
             SceneCommand::ConnectNavmeshEdges(v) => v.$func($($args),*),
+            SceneCommand::SetNavmeshTrianglesArea(v) => v.$func($($args),*),
+            SceneCommand::AddNavmeshLink(v) => v.$func($($args),*),
+            SceneCommand::DeleteNavmeshLink(v) => v.$func($($args),*),
+            SceneCommand::SetNavmeshLink(v) => v.$func($($args),*),
+            SceneCommand::WeldNavmeshVertices(v) => v.$func($($args),*),
+            SceneCommand::SplitNavmeshEdge(v) => v.$func($($args),*),
+            SceneCommand::SubdivideNavmeshTriangles(v) => v.$func($($args),*),
+            SceneCommand::FlipNavmeshEdge(v) => v.$func($($args),*),
+            SceneCommand::DeleteNavmeshTriangles(v) => v.$func($($args),*),
+            SceneCommand::ExtrudeNavmeshTriangles(v) => v.$func($($args),*),
+            SceneCommand::FlattenNavmeshTriangles(v) => v.$func($($args),*),
             SceneCommand::SetPhysicsBinding(v) => v.$func($($args),*),
         }
     };
@@ -718,16 +823,16 @@ impl<'a> Command<'a> for AddNavmeshEdgeCommand {
             AddNavmeshEdgeCommandState::NonExecuted { edge } => {
                 let begin_handle = navmesh.vertices.spawn(edge.0);
                 let end_handle = navmesh.vertices.spawn(edge.1);
-                let triangle_a = navmesh.triangles.spawn(NavmeshTriangle {
-                    a: self.opposite_edge.begin,
-                    b: begin_handle,
-                    c: self.opposite_edge.end,
-                });
-                let triangle_b = navmesh.triangles.spawn(NavmeshTriangle {
-                    a: begin_handle,
-                    b: end_handle,
-                    c: self.opposite_edge.end,
-                });
+                let triangle_a = navmesh.triangles.spawn(NavmeshTriangle::new(
+                    self.opposite_edge.begin,
+                    begin_handle,
+                    self.opposite_edge.end,
+                ));
+                let triangle_b = navmesh.triangles.spawn(NavmeshTriangle::new(
+                    begin_handle,
+                    end_handle,
+                    self.opposite_edge.end,
+                ));
                 self.state = AddNavmeshEdgeCommandState::Executed {
                     triangles: [triangle_a, triangle_b],
                     vertices: [begin_handle, end_handle],
@@ -855,16 +960,16 @@ impl<'a> Command<'a> for ConnectNavmeshEdgesCommand {
 
         match std::mem::replace(&mut self.state, ConnectNavmeshEdgesCommandState::Undefined) {
             ConnectNavmeshEdgesCommandState::NonExecuted { edges } => {
-                let ta = navmesh.triangles.spawn(NavmeshTriangle {
-                    a: edges[0].begin,
-                    b: edges[0].end,
-                    c: edges[1].begin,
-                });
-                let tb = navmesh.triangles.spawn(NavmeshTriangle {
-                    a: edges[1].begin,
-                    b: edges[1].end,
-                    c: edges[0].begin,
-                });
+                let ta = navmesh.triangles.spawn(NavmeshTriangle::new(
+                    edges[0].begin,
+                    edges[0].end,
+                    edges[1].begin,
+                ));
+                let tb = navmesh.triangles.spawn(NavmeshTriangle::new(
+                    edges[1].begin,
+                    edges[1].end,
+                    edges[0].begin,
+                ));
 
                 self.state = ConnectNavmeshEdgesCommandState::Executed {
                     triangles: [ta, tb],
@@ -912,6 +1017,49 @@ impl<'a> Command<'a> for ConnectNavmeshEdgesCommand {
     }
 }
 
+#[derive(Debug)]
+pub struct SetNavmeshTrianglesAreaCommand {
+    navmesh: Handle<Navmesh>,
+    /// Area per triangle.
+    values: Vec<(Handle<NavmeshTriangle>, usize)>,
+}
+
+impl SetNavmeshTrianglesAreaCommand {
+    pub fn new(
+        navmesh: Handle<Navmesh>,
+        triangles: Vec<Handle<NavmeshTriangle>>,
+        area: usize,
+    ) -> Self {
+        Self {
+            navmesh,
+            values: triangles.into_iter().map(|t| (t, area)).collect(),
+        }
+    }
+
+    fn swap(&mut self, context: &mut SceneContext) {
+        let navmesh = &mut context.editor_scene.navmeshes[self.navmesh];
+        for (handle, area) in self.values.iter_mut() {
+            std::mem::swap(&mut navmesh.triangles[*handle].area, area);
+        }
+    }
+}
+
+impl<'a> Command<'a> for SetNavmeshTrianglesAreaCommand {
+    type Context = SceneContext<'a>;
+
+    fn name(&mut self, _context: &Self::Context) -> String {
+        "Set Navmesh Triangles Area".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut Self::Context) {
+        self.swap(context);
+    }
+
+    fn revert(&mut self, context: &mut Self::Context) {
+        self.swap(context);
+    }
+}
+
 #[derive(Debug)]
 pub struct DeleteEmitterCommand {
     particle_system: Handle<Node>,
@@ -1082,6 +1230,116 @@ define_pool_command!(
     navmesh: Handle<Navmesh>
 );
 
+define_pool_command!(
+    AddNavmeshLinkCommand,
+    NavmeshLink,
+    "Add Navmesh Link",
+    ctx,
+    self,
+    { &mut ctx.editor_scene.navmeshes[self.navmesh].links },
+    navmesh: Handle<Navmesh>
+);
+
+impl AddNavmeshLinkCommand {
+    pub fn new(navmesh: Handle<Navmesh>, link: NavmeshLink) -> Self {
+        Self {
+            ticket: None,
+            handle: Default::default(),
+            value: Some(link),
+            navmesh,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct DeleteNavmeshLinkCommand {
+    navmesh: Handle<Navmesh>,
+    handle: Handle<NavmeshLink>,
+    ticket: Option<Ticket<NavmeshLink>>,
+    link: Option<NavmeshLink>,
+}
+
+impl DeleteNavmeshLinkCommand {
+    pub fn new(navmesh: Handle<Navmesh>, handle: Handle<NavmeshLink>) -> Self {
+        Self {
+            navmesh,
+            handle,
+            ticket: None,
+            link: None,
+        }
+    }
+}
+
+impl<'a> Command<'a> for DeleteNavmeshLinkCommand {
+    type Context = SceneContext<'a>;
+
+    fn name(&mut self, _context: &Self::Context) -> String {
+        "Delete Navmesh Link".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut Self::Context) {
+        let (ticket, link) = context.editor_scene.navmeshes[self.navmesh]
+            .links
+            .take_reserve(self.handle);
+        self.ticket = Some(ticket);
+        self.link = Some(link);
+    }
+
+    fn revert(&mut self, context: &mut Self::Context) {
+        self.handle = context.editor_scene.navmeshes[self.navmesh]
+            .links
+            .put_back(self.ticket.take().unwrap(), self.link.take().unwrap());
+    }
+
+    fn finalize(&mut self, context: &mut Self::Context) {
+        if let Some(ticket) = self.ticket.take() {
+            if let Some(navmesh) = context.editor_scene.navmeshes.try_borrow_mut(self.navmesh) {
+                navmesh.links.forget_ticket(ticket)
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct SetNavmeshLinkCommand {
+    navmesh: Handle<Navmesh>,
+    link: Handle<NavmeshLink>,
+    value: NavmeshLink,
+}
+
+impl SetNavmeshLinkCommand {
+    pub fn new(navmesh: Handle<Navmesh>, link: Handle<NavmeshLink>, value: NavmeshLink) -> Self {
+        Self {
+            navmesh,
+            link,
+            value,
+        }
+    }
+
+    fn swap(&mut self, context: &mut SceneContext) {
+        std::mem::swap(
+            &mut context.editor_scene.navmeshes[self.navmesh].links[self.link],
+            &mut self.value,
+        );
+    }
+}
+
+impl<'a> Command<'a> for SetNavmeshLinkCommand {
+    type Context = SceneContext<'a>;
+
+    fn name(&mut self, _context: &Self::Context) -> String {
+        "Set Navmesh Link".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut Self::Context) {
+        self.swap(context);
+    }
+
+    fn revert(&mut self, context: &mut Self::Context) {
+        self.swap(context);
+    }
+}
+
 #[derive(Debug)]
 pub struct DeleteNavmeshCommand {
     handle: Handle<Navmesh>,
@@ -1219,6 +1477,319 @@ impl<'a> Command<'a> for DeleteNavmeshVertexCommand {
     }
 }
 
+/// Applies topology plans to a navmesh. Removed entities are kept together with their tickets
+/// and added ones are taken out with tickets on revert, so every handle stays the same across
+/// undo and redo.
+#[derive(Debug)]
+enum NavmeshTopologyChangeState {
+    Undefined,
+    NonExecuted,
+    Executed {
+        added_vertices: Vec<Handle<NavmeshVertex>>,
+        added_triangles: Vec<Handle<NavmeshTriangle>>,
+        removed_vertices: Vec<(Ticket<NavmeshVertex>, NavmeshVertex)>,
+        removed_triangles: Vec<(Ticket<NavmeshTriangle>, NavmeshTriangle)>,
+    },
+    Reverted {
+        added_vertices: Vec<(Ticket<NavmeshVertex>, NavmeshVertex)>,
+        added_triangles: Vec<(Ticket<NavmeshTriangle>, NavmeshTriangle)>,
+        removed_vertices: Vec<Handle<NavmeshVertex>>,
+        removed_triangles: Vec<Handle<NavmeshTriangle>>,
+    },
+}
+
+#[derive(Debug)]
+struct NavmeshTopologyChange {
+    state: NavmeshTopologyChangeState,
+    /// Triangles and positions of vertices which are swapped with the ones in navmesh on each
+    /// execute and revert.
+    modified_triangles: Vec<(Handle<NavmeshTriangle>, NavmeshTriangle)>,
+    moved_vertices: Vec<(Handle<NavmeshVertex>, Vector3<f32>)>,
+}
+
+impl NavmeshTopologyChange {
+    fn new() -> Self {
+        Self {
+            state: NavmeshTopologyChangeState::NonExecuted,
+            modified_triangles: Default::default(),
+            moved_vertices: Default::default(),
+        }
+    }
+
+    fn swap_modified(&mut self, navmesh: &mut Navmesh) {
+        for (handle, triangle) in self.modified_triangles.iter_mut() {
+            std::mem::swap(&mut navmesh.triangles[*handle], triangle);
+        }
+        for (handle, position) in self.moved_vertices.iter_mut() {
+            std::mem::swap(&mut navmesh.vertices[*handle].position, position);
+        }
+    }
+
+    /// Applies a plan on first execution and re-applies the change made by it on redo.
+    fn execute<F>(&mut self, navmesh: &mut Navmesh, make_plan: F)
+    where
+        F: FnOnce(&Navmesh) -> NavmeshTopologyPlan,
+    {
+        match std::mem::replace(&mut self.state, NavmeshTopologyChangeState::Undefined) {
+            NavmeshTopologyChangeState::NonExecuted => {
+                let plan = make_plan(navmesh);
+
+                let added_vertices = plan
+                    .added_vertices
+                    .into_iter()
+                    .map(|vertex| navmesh.vertices.spawn(vertex))
+                    .collect::<Vec<_>>();
+                let resolve = |corners: [NavmeshCorner; 3], area: usize| {
+                    let resolve_corner = |corner| match corner {
+                        NavmeshCorner::Existing(handle) => handle,
+                        NavmeshCorner::Added(index) => added_vertices[index],
+                    };
+                    NavmeshTriangle {
+                        a: resolve_corner(corners[0]),
+                        b: resolve_corner(corners[1]),
+                        c: resolve_corner(corners[2]),
+                        area,
+                    }
+                };
+
+                let added_triangles = plan
+                    .added_triangles
+                    .into_iter()
+                    .map(|(corners, area)| navmesh.triangles.spawn(resolve(corners, area)))
+                    .collect();
+                // Modified triangles keep their areas.
+                let modified_triangles = plan
+                    .modified_triangles
+                    .into_iter()
+                    .map(|(handle, corners)| {
+                        (handle, resolve(corners, navmesh.triangles[handle].area))
+                    })
+                    .collect();
+                self.modified_triangles = modified_triangles;
+                self.moved_vertices = plan.moved_vertices;
+                self.swap_modified(navmesh);
+
+                self.state = NavmeshTopologyChangeState::Executed {
+                    added_vertices,
+                    added_triangles,
+                    removed_triangles: plan
+                        .removed_triangles
+                        .into_iter()
+                        .map(|t| navmesh.triangles.take_reserve(t))
+                        .collect(),
+                    removed_vertices: plan
+                        .removed_vertices
+                        .into_iter()
+                        .map(|v| navmesh.vertices.take_reserve(v))
+                        .collect(),
+                };
+            }
+            NavmeshTopologyChangeState::Reverted {
+                added_vertices,
+                added_triangles,
+                removed_vertices,
+                removed_triangles,
+            } => {
+                let added_vertices = added_vertices
+                    .into_iter()
+                    .map(|(ticket, vertex)| navmesh.vertices.put_back(ticket, vertex))
+                    .collect();
+                let added_triangles = added_triangles
+                    .into_iter()
+                    .map(|(ticket, triangle)| navmesh.triangles.put_back(ticket, triangle))
+                    .collect();
+                self.swap_modified(navmesh);
+
+                self.state = NavmeshTopologyChangeState::Executed {
+                    added_vertices,
+                    added_triangles,
+                    removed_triangles: removed_triangles
+                        .into_iter()
+                        .map(|t| navmesh.triangles.take_reserve(t))
+                        .collect(),
+                    removed_vertices: removed_vertices
+                        .into_iter()
+                        .map(|v| navmesh.vertices.take_reserve(v))
+                        .collect(),
+                };
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn revert(&mut self, navmesh: &mut Navmesh) {
+        match std::mem::replace(&mut self.state, NavmeshTopologyChangeState::Undefined) {
+            NavmeshTopologyChangeState::Executed {
+                added_vertices,
+                added_triangles,
+                removed_vertices,
+                removed_triangles,
+            } => {
+                let removed_vertices = removed_vertices
+                    .into_iter()
+                    .map(|(ticket, vertex)| navmesh.vertices.put_back(ticket, vertex))
+                    .collect();
+                let removed_triangles = removed_triangles
+                    .into_iter()
+                    .map(|(ticket, triangle)| navmesh.triangles.put_back(ticket, triangle))
+                    .collect();
+                self.swap_modified(navmesh);
+
+                self.state = NavmeshTopologyChangeState::Reverted {
+                    added_triangles: added_triangles
+                        .into_iter()
+                        .map(|t| navmesh.triangles.take_reserve(t))
+                        .collect(),
+                    added_vertices: added_vertices
+                        .into_iter()
+                        .map(|v| navmesh.vertices.take_reserve(v))
+                        .collect(),
+                    removed_vertices,
+                    removed_triangles,
+                };
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn finalize(&mut self, navmesh: &mut Navmesh) {
+        match std::mem::replace(&mut self.state, NavmeshTopologyChangeState::Undefined) {
+            NavmeshTopologyChangeState::Executed {
+                removed_vertices,
+                removed_triangles,
+                ..
+            } => {
+                for (ticket, _) in removed_vertices {
+                    navmesh.vertices.forget_ticket(ticket);
+                }
+                for (ticket, _) in removed_triangles {
+                    navmesh.triangles.forget_ticket(ticket);
+                }
+            }
+            NavmeshTopologyChangeState::Reverted {
+                added_vertices,
+                added_triangles,
+                ..
+            } => {
+                for (ticket, _) in added_vertices {
+                    navmesh.vertices.forget_ticket(ticket);
+                }
+                for (ticket, _) in added_triangles {
+                    navmesh.triangles.forget_ticket(ticket);
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+macro_rules! define_navmesh_topology_command {
+    ($name:ident, $human_readable_name:expr, $make_plan:expr, $($field:ident:$type:ty),*) => {
+        #[derive(Debug)]
+        pub struct $name {
+            navmesh: Handle<Navmesh>,
+            change: NavmeshTopologyChange,
+            $($field: $type,)*
+        }
+
+        impl $name {
+            pub fn new(navmesh: Handle<Navmesh>, $($field: $type),*) -> Self {
+                Self {
+                    navmesh,
+                    change: NavmeshTopologyChange::new(),
+                    $($field,)*
+                }
+            }
+        }
+
+        impl<'a> Command<'a> for $name {
+            type Context = SceneContext<'a>;
+
+            fn name(&mut self, _context: &Self::Context) -> String {
+                $human_readable_name.to_owned()
+            }
+
+            fn execute(&mut self, context: &mut Self::Context) {
+                let navmesh = &mut context.editor_scene.navmeshes[self.navmesh];
+                $(let $field = &self.$field;)*
+                self.change.execute(navmesh, |navmesh| $make_plan(navmesh, $($field),*));
+            }
+
+            fn revert(&mut self, context: &mut Self::Context) {
+                self.change
+                    .revert(&mut context.editor_scene.navmeshes[self.navmesh]);
+            }
+
+            fn finalize(&mut self, context: &mut Self::Context) {
+                if let Some(navmesh) = context.editor_scene.navmeshes.try_borrow_mut(self.navmesh) {
+                    self.change.finalize(navmesh);
+                }
+            }
+        }
+    };
+}
+
+define_navmesh_topology_command!(
+    WeldNavmeshVerticesCommand,
+    "Weld Navmesh Vertices",
+    |navmesh, vertices: &Vec<Handle<NavmeshVertex>>, tolerance: &f32| {
+        topology::weld_vertices(navmesh, vertices, *tolerance)
+    },
+    vertices: Vec<Handle<NavmeshVertex>>,
+    tolerance: f32
+);
+
+define_navmesh_topology_command!(
+    SplitNavmeshEdgeCommand,
+    "Split Navmesh Edge",
+    |navmesh, edge: &NavmeshEdge| topology::split_edge(navmesh, *edge),
+    edge: NavmeshEdge
+);
+
+define_navmesh_topology_command!(
+    SubdivideNavmeshTrianglesCommand,
+    "Subdivide Navmesh Triangles",
+    |navmesh, triangles: &Vec<Handle<NavmeshTriangle>>| {
+        topology::subdivide_triangles(navmesh, triangles)
+    },
+    triangles: Vec<Handle<NavmeshTriangle>>
+);
+
+define_navmesh_topology_command!(
+    FlipNavmeshEdgeCommand,
+    "Flip Navmesh Edge",
+    |navmesh, edge: &NavmeshEdge| topology::flip_edge(navmesh, *edge).unwrap_or_default(),
+    edge: NavmeshEdge
+);
+
+define_navmesh_topology_command!(
+    ExtrudeNavmeshTrianglesCommand,
+    "Extrude Navmesh Triangles",
+    |navmesh, triangles: &Vec<Handle<NavmeshTriangle>>, distance: &f32| {
+        topology::extrude_triangles(navmesh, triangles, *distance)
+    },
+    triangles: Vec<Handle<NavmeshTriangle>>,
+    distance: f32
+);
+
+define_navmesh_topology_command!(
+    FlattenNavmeshTrianglesCommand,
+    "Flatten Navmesh Triangles",
+    |navmesh, triangles: &Vec<Handle<NavmeshTriangle>>| {
+        topology::flatten_triangles(navmesh, triangles)
+    },
+    triangles: Vec<Handle<NavmeshTriangle>>
+);
+
+define_navmesh_topology_command!(
+    DeleteNavmeshTrianglesCommand,
+    "Delete Navmesh Triangles",
+    |navmesh, triangles: &Vec<Handle<NavmeshTriangle>>| {
+        topology::delete_triangles(navmesh, triangles)
+    },
+    triangles: Vec<Handle<NavmeshTriangle>>
+);
+
 #[derive(Debug)]
 pub struct AddJointCommand {
     ticket: Option<Ticket<Joint>>,
@@ -1329,6 +1900,165 @@ impl<'a> Command<'a> for DeleteJointCommand {
     }
 }
 
+/// Makes joint's connected body its first body and vice versa, anchors, axes and limits
+/// are swapped too so joint stays the same. Joint is shown for its first body, so node of
+/// new first body gets selected. Does nothing if joint has no connected body.
+#[derive(Debug)]
+pub struct SwapJointBodiesCommand {
+    handle: Handle<Joint>,
+    old_selection: Option<Selection>,
+}
+
+impl SwapJointBodiesCommand {
+    pub fn new(handle: Handle<Joint>) -> Self {
+        Self {
+            handle,
+            old_selection: None,
+        }
+    }
+
+    fn swap(&mut self, context: &mut SceneContext) -> bool {
+        let physics = &mut context.editor_scene.physics;
+        if !physics
+            .bodies
+            .is_valid_handle(physics.joints[self.handle].body2.into())
+        {
+            return false;
+        }
+
+        // Displacement along the axis (or angle) is measured from the other body now.
+        if let Some(motion) = physics.joint_motions.get_mut(&self.handle) {
+            let (min_limit, max_limit) = (-motion.max_limit, -motion.min_limit);
+            motion.min_limit = min_limit;
+            motion.max_limit = max_limit;
+            motion.motor_target_position = -motion.motor_target_position;
+            motion.motor_target_velocity = -motion.motor_target_velocity;
+        }
+
+        let joint = &mut physics.joints[self.handle];
+        std::mem::swap(&mut joint.body1, &mut joint.body2);
+        match &mut joint.params {
+            JointParamsDesc::BallJoint(ball) => {
+                std::mem::swap(&mut ball.local_anchor1, &mut ball.local_anchor2);
+            }
+            JointParamsDesc::FixedJoint(fixed) => {
+                std::mem::swap(
+                    &mut fixed.local_anchor1_translation,
+                    &mut fixed.local_anchor2_translation,
+                );
+                std::mem::swap(
+                    &mut fixed.local_anchor1_rotation,
+                    &mut fixed.local_anchor2_rotation,
+                );
+            }
+            JointParamsDesc::PrismaticJoint(prismatic) => {
+                std::mem::swap(&mut prismatic.local_anchor1, &mut prismatic.local_anchor2);
+                std::mem::swap(&mut prismatic.local_axis1, &mut prismatic.local_axis2);
+            }
+            JointParamsDesc::RevoluteJoint(revolute) => {
+                std::mem::swap(&mut revolute.local_anchor1, &mut revolute.local_anchor2);
+                std::mem::swap(&mut revolute.local_axis1, &mut revolute.local_axis2);
+            }
+        }
+        true
+    }
+}
+
+impl<'a> Command<'a> for SwapJointBodiesCommand {
+    type Context = SceneContext<'a>;
+
+    fn name(&mut self, _context: &Self::Context) -> String {
+        "Swap Joint Bodies".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut Self::Context) {
+        if self.swap(context) {
+            let physics = &context.editor_scene.physics;
+            let body1 = physics.joints[self.handle].body1.into();
+            let node = physics.binder.key_of(&body1).cloned().unwrap_or_default();
+            self.old_selection = Some(std::mem::replace(
+                &mut context.editor_scene.selection,
+                Selection::Graph(GraphSelection::single_or_empty(node)),
+            ));
+            context
+                .message_sender
+                .send(Message::SelectionChanged)
+                .unwrap();
+        }
+    }
+
+    fn revert(&mut self, context: &mut Self::Context) {
+        if self.swap(context) {
+            if let Some(selection) = self.old_selection.take() {
+                context.editor_scene.selection = selection;
+                context
+                    .message_sender
+                    .send(Message::SelectionChanged)
+                    .unwrap();
+            }
+        }
+    }
+}
+
+/// Applies physics material to many colliders at once, colliders are linked to the material.
+#[derive(Debug)]
+pub struct SetColliderMaterialCommand {
+    name: String,
+    /// Material id, friction, restitution and density per collider.
+    values: Vec<(Handle<Collider>, Option<u32>, f32, f32, Option<f32>)>,
+}
+
+impl SetColliderMaterialCommand {
+    pub fn new(colliders: Vec<Handle<Collider>>, material: &PhysicsMaterial) -> Self {
+        Self {
+            name: material.name.clone(),
+            values: colliders
+                .into_iter()
+                .map(|c| {
+                    (
+                        c,
+                        Some(material.id),
+                        material.friction,
+                        material.restitution,
+                        Some(material.density),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    fn swap(&mut self, context: &mut SceneContext) {
+        let physics = &mut context.editor_scene.physics;
+        for (handle, material, friction, restitution, density) in self.values.iter_mut() {
+            let collider = &mut physics.colliders[*handle];
+            std::mem::swap(&mut collider.friction, friction);
+            std::mem::swap(&mut collider.restitution, restitution);
+            std::mem::swap(&mut collider.density, density);
+            let old_material = match material.take() {
+                Some(id) => physics.collider_materials.insert(*handle, id),
+                None => physics.collider_materials.remove(handle),
+            };
+            *material = old_material;
+        }
+    }
+}
+
+impl<'a> Command<'a> for SetColliderMaterialCommand {
+    type Context = SceneContext<'a>;
+
+    fn name(&mut self, _context: &Self::Context) -> String {
+        format!("Apply Physics Material {}", self.name)
+    }
+
+    fn execute(&mut self, context: &mut Self::Context) {
+        self.swap(context);
+    }
+
+    fn revert(&mut self, context: &mut Self::Context) {
+        self.swap(context);
+    }
+}
+
 #[derive(Debug)]
 pub struct ChangeSelectionCommand {
     new_selection: Selection,
@@ -1964,20 +2694,43 @@ impl<'a> Command<'a> for SetBodyCommand {
 #[derive(Debug)]
 pub struct SetColliderCommand {
     body: Handle<RigidBody>,
+    node: Handle<Node>,
     ticket: Option<Ticket<Collider>>,
     handle: Handle<Collider>,
     collider: Option<Collider>,
+    convex: Option<ConvexSource>,
 }
 
 impl SetColliderCommand {
     pub fn new(body: Handle<RigidBody>, collider: Collider) -> Self {
         Self {
             body,
+            node: Handle::NONE,
+            ticket: None,
+            handle: Default::default(),
+            collider: Some(collider),
+            convex: None,
+        }
+    }
+
+    /// Creates command that adds collider to the body of given node, body is looked up
+    /// on first execution so it can be created by previous command of the same group.
+    pub fn for_node(node: Handle<Node>, collider: Collider) -> Self {
+        Self {
+            body: Handle::NONE,
+            node,
             ticket: None,
             handle: Default::default(),
             collider: Some(collider),
+            convex: None,
         }
     }
+
+    /// Makes collider convex polyhedron, its shape must be the enclosing cuboid of the source.
+    pub fn with_convex_source(mut self, source: ConvexSource) -> Self {
+        self.convex = Some(source);
+        self
+    }
 }
 
 impl<'a> Command<'a> for SetColliderCommand {
@@ -1988,6 +2741,14 @@ impl<'a> Command<'a> for SetColliderCommand {
     }
 
     fn execute(&mut self, context: &mut Self::Context) {
+        if self.body.is_none() {
+            self.body = *context
+                .editor_scene
+                .physics
+                .binder
+                .value_of(&self.node)
+                .unwrap();
+        }
         match self.ticket.take() {
             None => {
                 self.handle = context
@@ -2008,6 +2769,13 @@ impl<'a> Command<'a> for SetColliderCommand {
         context.editor_scene.physics.bodies[self.body]
             .colliders
             .push(self.handle.into());
+        if let Some(convex) = self.convex.take() {
+            context
+                .editor_scene
+                .physics
+                .convex_sources
+                .insert(self.handle, convex);
+        }
     }
 
     fn revert(&mut self, context: &mut Self::Context) {
@@ -2019,6 +2787,11 @@ impl<'a> Command<'a> for SetColliderCommand {
         collider.parent = Default::default();
         self.ticket = Some(ticket);
         self.collider = Some(collider);
+        self.convex = context
+            .editor_scene
+            .physics
+            .convex_sources
+            .remove(&self.handle);
 
         let body = &mut context.editor_scene.physics.bodies[self.body];
         body.colliders.remove(
@@ -3071,6 +3844,27 @@ define_body_command!(SetBodyMassCommand("Set Body Mass", f32) where fn swap(self
     std::mem::swap(&mut body.mass, &mut self.value);
 });
 
+define_body_command!(SetBodyStatusCommand("Set Body Status", BodyStatusDesc) where fn swap(self, physics, body) {
+    std::mem::swap(&mut body.status, &mut self.value);
+});
+
+define_body_command!(SetBodyLinVelCommand("Set Body Linear Velocity", Vector3<f32>) where fn swap(self, physics, body) {
+    std::mem::swap(&mut body.linvel, &mut self.value);
+});
+
+define_body_command!(SetBodyAngVelCommand("Set Body Angular Velocity", Vector3<f32>) where fn swap(self, physics, body) {
+    std::mem::swap(&mut body.angvel, &mut self.value);
+});
+
+define_body_command!(SetBodySleepingCommand("Set Body Sleeping", bool) where fn swap(self, physics, body) {
+    std::mem::swap(&mut body.sleeping, &mut self.value);
+});
+
+define_physics_command!(SetBodyDynamicsCommand("Set Body Dynamics", RigidBody, BodyDynamics) where fn swap(self, physics) {
+    let dynamics = physics.body_dynamics.entry(self.handle).or_default();
+    std::mem::swap(dynamics, &mut self.value);
+});
+
 define_collider_command!(SetColliderFrictionCommand("Set Collider Friction", f32) where fn swap(self, physics, collider) {
     std::mem::swap(&mut collider.friction, &mut self.value);
 });
@@ -3087,6 +3881,10 @@ define_collider_command!(SetColliderRotationCommand("Set Collider Rotation", Uni
     std::mem::swap(&mut collider.rotation, &mut self.value);
 });
 
+define_collider_command!(SetColliderDensityCommand("Set Collider Density", Option<f32>) where fn swap(self, physics, collider) {
+    std::mem::swap(&mut collider.density, &mut self.value);
+});
+
 define_collider_command!(SetColliderIsSensorCommand("Set Collider Is Sensor", bool) where fn swap(self, physics, collider) {
     std::mem::swap(&mut collider.is_sensor, &mut self.value);
 });
@@ -3095,6 +3893,10 @@ define_collider_command!(SetColliderCollisionGroupsCommand("Set Collider Collisi
     std::mem::swap(&mut collider.collision_groups, &mut self.value);
 });
 
+define_collider_command!(SetColliderSolverGroupsCommand("Set Collider Solver Groups", u32) where fn swap(self, physics, collider) {
+    std::mem::swap(&mut collider.solver_groups, &mut self.value);
+});
+
 define_collider_variant_command!(SetCylinderHalfHeightCommand("Set Cylinder Half Height", f32) where fn swap(self, physics, Cylinder, cylinder) {
     std::mem::swap(&mut cylinder.half_height, &mut self.value);
 });
@@ -3127,6 +3929,40 @@ define_collider_variant_command!(SetCapsuleEndCommand("Set Capsule End", Vector3
     std::mem::swap(&mut capsule.end, &mut self.value);
 });
 
+define_physics_command!(SetTrimeshSourceNodeCommand("Set Trimesh Source Node", Collider, Handle<Node>) where fn swap(self, physics) {
+    let source = physics.trimesh_sources.entry(self.handle).or_default();
+    std::mem::swap(&mut source.node, &mut self.value);
+});
+
+define_physics_command!(SetTrimeshSurfacesCommand("Set Trimesh Surfaces", Collider, Vec<u32>) where fn swap(self, physics) {
+    let source = physics.trimesh_sources.entry(self.handle).or_default();
+    std::mem::swap(&mut source.surfaces, &mut self.value);
+});
+
+define_physics_command!(SetHeightfieldTextureCommand("Set Heightfield Texture", Collider, Option<PathBuf>) where fn swap(self, physics) {
+    let source = physics.heightfield_sources.entry(self.handle).or_default();
+    std::mem::swap(&mut source.texture, &mut self.value);
+});
+
+define_physics_command!(SetHeightfieldScaleCommand("Set Heightfield Scale", Collider, Vector3<f32>) where fn swap(self, physics) {
+    let source = physics.heightfield_sources.entry(self.handle).or_default();
+    std::mem::swap(&mut source.scale, &mut self.value);
+});
+
+define_physics_command!(SetConvexPointsCommand("Set Convex Points", Collider, Vec<Vector3<f32>>) where fn swap(self, physics) {
+    if let Some(source) = physics.convex_sources.get_mut(&self.handle) {
+        std::mem::swap(&mut source.points, &mut self.value);
+    }
+    physics.sync_convex_shape(self.handle);
+});
+
+define_physics_command!(SetConvexToleranceCommand("Set Convex Tolerance", Collider, f32) where fn swap(self, physics) {
+    if let Some(source) = physics.convex_sources.get_mut(&self.handle) {
+        std::mem::swap(&mut source.tolerance, &mut self.value);
+    }
+    physics.sync_convex_shape(self.handle);
+});
+
 define_collider_variant_command!(SetBallRadiusCommand("Set Ball Radius", f32) where fn swap(self, physics, Ball, ball) {
     std::mem::swap(&mut ball.radius, &mut self.value);
 });
@@ -3187,6 +4023,11 @@ define_joint_variant_command!(SetPrismaticJointAxis2Command("Set Prismatic Joint
     std::mem::swap(&mut prismatic.local_axis2, &mut self.value);
 });
 
+define_physics_command!(SetJointMotionCommand("Set Joint Motion", Joint, JointMotion) where fn swap(self, physics) {
+    let motion = physics.joint_motions.entry(self.handle).or_default();
+    std::mem::swap(motion, &mut self.value);
+});
+
 define_joint_command!(SetJointConnectedBodyCommand("Set Joint Connected Body", ErasedHandle) where fn swap(self, physics, joint) {
     std::mem::swap(&mut joint.body2, &mut self.value);
 });
@@ -3524,3 +4365,106 @@ pub fn make_delete_selection_command(
 
     SceneCommand::CommandGroup(command_group)
 }
+
+/// Returns every node of the scene except editor's ones (gizmos, etc.) which are all under
+/// `editor_root`.
+pub fn scene_nodes(graph: &Graph, editor_root: Handle<Node>) -> Vec<Handle<Node>> {
+    let mut nodes = Vec::new();
+    let mut stack = vec![graph.get_root()];
+    while let Some(handle) = stack.pop() {
+        if handle == editor_root {
+            continue;
+        }
+        nodes.push(handle);
+        stack.extend_from_slice(graph[handle].children());
+    }
+    nodes
+}
+
+/// Passes world-space triangles of every surface of the mesh to `func`.
+pub fn for_each_mesh_triangle<F>(mesh: &Mesh, mut func: F)
+where
+    F: FnMut([Vector3<f32>; 3]),
+{
+    let transform = mesh.global_transform();
+    for surface in mesh.surfaces() {
+        let data = surface.data();
+        let data = data.read().unwrap();
+        let vertices = data.get_vertices();
+        let point = |i: u32| {
+            transform
+                .transform_point(&Point3::from(vertices[i as usize].position))
+                .coords
+        };
+        for triangle in data.triangles() {
+            func([point(triangle[0]), point(triangle[1]), point(triangle[2])]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    type NavmeshState = (
+        Vec<(Handle<NavmeshVertex>, Vector3<f32>)>,
+        Vec<(Handle<NavmeshTriangle>, [Handle<NavmeshVertex>; 3])>,
+    );
+
+    fn navmesh_state(navmesh: &Navmesh) -> NavmeshState {
+        (
+            navmesh
+                .vertices
+                .pair_iter()
+                .map(|(handle, vertex)| (handle, vertex.position))
+                .collect(),
+            navmesh
+                .triangles
+                .pair_iter()
+                .map(|(handle, triangle)| (handle, triangle.vertices()))
+                .collect(),
+        )
+    }
+
+    fn check_undo_redo<F>(mut navmesh: Navmesh, make_plan: F)
+    where
+        F: FnOnce(&Navmesh) -> NavmeshTopologyPlan,
+    {
+        let original = navmesh_state(&navmesh);
+
+        let mut change = NavmeshTopologyChange::new();
+        change.execute(&mut navmesh, make_plan);
+        let executed = navmesh_state(&navmesh);
+        assert_ne!(executed, original);
+
+        change.revert(&mut navmesh);
+        assert_eq!(navmesh_state(&navmesh), original);
+
+        // Redo must bring back the same handles, selection and links refer to them.
+        change.execute(&mut navmesh, |_| unreachable!());
+        assert_eq!(navmesh_state(&navmesh), executed);
+    }
+
+    #[test]
+    fn navmesh_topology_change_subdivide_undo_redo() {
+        let navmesh = Navmesh::new();
+        let triangle = navmesh.triangles.pair_iter().next().unwrap().0;
+        check_undo_redo(navmesh, |navmesh| {
+            topology::subdivide_triangles(navmesh, &[triangle])
+        });
+    }
+
+    #[test]
+    fn navmesh_topology_change_weld_undo_redo() {
+        let navmesh = Navmesh::new();
+        let vertices = navmesh
+            .vertices
+            .pair_iter()
+            .map(|(handle, _)| handle)
+            .take(2)
+            .collect::<Vec<_>>();
+        check_undo_redo(navmesh, |navmesh| {
+            topology::weld_vertices(navmesh, &vertices, 10.0)
+        });
+    }
+}
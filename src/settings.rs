@@ -1,4 +1,5 @@
 use crate::{
+    camera::CameraSettings,
     gui::{BuildContext, Ui, UiMessage, UiNode},
     scene::EditorScene,
     GameEngine, Message,
@@ -37,6 +38,10 @@ pub struct Settings {
     light_scatter: Handle<UiNode>,
     near_plane: Handle<UiNode>,
     far_plane: Handle<UiNode>,
+    camera_speed: Handle<UiNode>,
+    fast_multiplier: Handle<UiNode>,
+    slow_multiplier: Handle<UiNode>,
+    acceleration: Handle<UiNode>,
 }
 
 fn make_text_mark(ctx: &mut BuildContext, text: &str, row: usize) -> Handle<UiNode> {
@@ -51,6 +56,18 @@ fn make_text_mark(ctx: &mut BuildContext, text: &str, row: usize) -> Handle<UiNo
     .build(ctx)
 }
 
+fn make_f32_input_field(ctx: &mut BuildContext, row: usize, min: f32, max: f32) -> Handle<UiNode> {
+    NumericUpDownBuilder::new(
+        WidgetBuilder::new()
+            .on_column(1)
+            .on_row(row)
+            .with_margin(Thickness::uniform(1.0)),
+    )
+    .with_min_value(min)
+    .with_max_value(max)
+    .build(ctx)
+}
+
 fn make_bool_input_field(ctx: &mut BuildContext, row: usize, value: bool) -> Handle<UiNode> {
     CheckBoxBuilder::new(
         WidgetBuilder::new()
@@ -73,13 +90,17 @@ impl Settings {
         let light_scatter;
         let near_plane;
         let far_plane;
+        let camera_speed;
+        let fast_multiplier;
+        let slow_multiplier;
+        let acceleration;
         let ctx = &mut engine.user_interface.build_ctx();
         let settings = engine.renderer.get_quality_settings();
         let text =
             "Here you can select graphics settings to improve performance and/or to understand how \
             you scene will look like with different graphics settings. Please note that these settings won't be saved \
             with scene!";
-        let window = WindowBuilder::new(WidgetBuilder::new().with_width(300.0).with_height(400.0))
+        let window = WindowBuilder::new(WidgetBuilder::new().with_width(300.0).with_height(500.0))
             .open(false)
             .with_title(WindowTitle::Text("Settings".to_owned()))
             .with_content(
@@ -160,6 +181,31 @@ impl Settings {
                                         )
                                         .build(ctx);
                                         far_plane
+                                    })
+                                    .with_child(make_text_mark(ctx, "Camera Speed", 7))
+                                    .with_child({
+                                        camera_speed = make_f32_input_field(
+                                            ctx,
+                                            7,
+                                            CameraSettings::MIN_SPEED,
+                                            CameraSettings::MAX_SPEED,
+                                        );
+                                        camera_speed
+                                    })
+                                    .with_child(make_text_mark(ctx, "Shift Multiplier", 8))
+                                    .with_child({
+                                        fast_multiplier = make_f32_input_field(ctx, 8, 1.0, 100.0);
+                                        fast_multiplier
+                                    })
+                                    .with_child(make_text_mark(ctx, "Ctrl Multiplier", 9))
+                                    .with_child({
+                                        slow_multiplier = make_f32_input_field(ctx, 9, 0.01, 1.0);
+                                        slow_multiplier
+                                    })
+                                    .with_child(make_text_mark(ctx, "Acceleration", 10))
+                                    .with_child({
+                                        acceleration = make_f32_input_field(ctx, 10, 0.1, 100.0);
+                                        acceleration
                                     }),
                             )
                             .add_row(Row::strict(25.0))
@@ -169,6 +215,10 @@ impl Settings {
                             .add_row(Row::strict(25.0))
                             .add_row(Row::strict(25.0))
                             .add_row(Row::strict(25.0))
+                            .add_row(Row::strict(25.0))
+                            .add_row(Row::strict(25.0))
+                            .add_row(Row::strict(25.0))
+                            .add_row(Row::strict(25.0))
                             .add_row(Row::stretch())
                             .add_row(Row::stretch())
                             .add_column(Column::strict(100.0))
@@ -225,10 +275,14 @@ impl Settings {
             light_scatter,
             near_plane,
             far_plane,
+            camera_speed,
+            fast_multiplier,
+            slow_multiplier,
+            acceleration,
         }
     }
 
-    pub fn open(&self, ui: &Ui, camera: &Camera) {
+    pub fn open(&self, ui: &Ui, camera: &Camera, camera_settings: &CameraSettings) {
         ui.send_message(WindowMessage::open(
             self.window,
             MessageDirection::ToWidget,
@@ -244,12 +298,27 @@ impl Settings {
             MessageDirection::ToWidget,
             camera.z_far(),
         ));
+
+        for &(field, value) in [
+            (self.camera_speed, camera_settings.speed),
+            (self.fast_multiplier, camera_settings.fast_multiplier),
+            (self.slow_multiplier, camera_settings.slow_multiplier),
+            (self.acceleration, camera_settings.acceleration),
+        ]
+        .iter()
+        {
+            ui.send_message(NumericUpDownMessage::value(
+                field,
+                MessageDirection::ToWidget,
+                value,
+            ));
+        }
     }
 
     pub fn handle_message(
         &mut self,
         message: &UiMessage,
-        editor_scene: &EditorScene,
+        editor_scene: &mut EditorScene,
         engine: &mut GameEngine,
     ) {
         scope_profile!();
@@ -305,10 +374,23 @@ impl Settings {
                 let camera = engine.scenes[editor_scene.scene].graph
                     [editor_scene.camera_controller.camera]
                     .as_camera_mut();
+                let camera_settings = &mut editor_scene.camera_controller.settings;
+                let old_camera_settings = camera_settings.clone();
                 if message.destination() == self.near_plane {
                     camera.set_z_near(*value);
                 } else if message.destination() == self.far_plane {
                     camera.set_z_far(*value);
+                } else if message.destination() == self.camera_speed {
+                    camera_settings.speed = *value;
+                } else if message.destination() == self.fast_multiplier {
+                    camera_settings.fast_multiplier = *value;
+                } else if message.destination() == self.slow_multiplier {
+                    camera_settings.slow_multiplier = *value;
+                } else if message.destination() == self.acceleration {
+                    camera_settings.acceleration = *value;
+                }
+                if *camera_settings != old_camera_settings {
+                    editor_scene.camera_controller.save_settings(&self.sender);
                 }
             }
             _ => {}
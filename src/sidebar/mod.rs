@@ -87,7 +87,7 @@ pub struct SideBar {
     physics_section: PhysicsSection,
 }
 
-fn make_text_mark(ctx: &mut BuildContext, text: &str, row: usize) -> Handle<UiNode> {
+pub fn make_text_mark(ctx: &mut BuildContext, text: &str, row: usize) -> Handle<UiNode> {
     TextBuilder::new(
         WidgetBuilder::new()
             .with_vertical_alignment(VerticalAlignment::Center)
@@ -128,26 +128,6 @@ fn make_f32_input_field(
     .build(ctx)
 }
 
-fn make_int_input_field(
-    ctx: &mut BuildContext,
-    row: usize,
-    min: i32,
-    max: i32,
-    step: i32,
-) -> Handle<UiNode> {
-    NumericUpDownBuilder::new(
-        WidgetBuilder::new()
-            .on_row(row)
-            .with_margin(Thickness::uniform(1.0))
-            .on_column(1),
-    )
-    .with_min_value(min as f32)
-    .with_max_value(max as f32)
-    .with_step(step as f32)
-    .with_precision(0)
-    .build(ctx)
-}
-
 fn make_color_input_field(ctx: &mut BuildContext, row: usize) -> Handle<UiNode> {
     ColorFieldBuilder::new(
         WidgetBuilder::new()
@@ -169,7 +149,17 @@ fn make_bool_input_field(ctx: &mut BuildContext, row: usize) -> Handle<UiNode> {
     .build(ctx)
 }
 
-fn make_dropdown_list_option(ctx: &mut BuildContext, name: &str) -> Handle<UiNode> {
+pub fn make_button(ctx: &mut BuildContext, text: &str) -> Handle<UiNode> {
+    ButtonBuilder::new(
+        WidgetBuilder::new()
+            .with_width(120.0)
+            .with_margin(Thickness::uniform(1.0)),
+    )
+    .with_text(text)
+    .build(ctx)
+}
+
+pub fn make_dropdown_list_option(ctx: &mut BuildContext, name: &str) -> Handle<UiNode> {
     DecoratorBuilder::new(BorderBuilder::new(
         WidgetBuilder::new().with_height(26.0).with_child(
             TextBuilder::new(WidgetBuilder::new())
@@ -1,17 +1,30 @@
 use crate::{
     gui::{BuildContext, Ui, UiMessage, UiNode},
-    physics::RigidBody,
-    scene::{SceneCommand, SetBodyMassCommand},
+    physics::{BodyDynamics, RigidBody},
+    scene::{
+        SceneCommand, SetBodyAngVelCommand, SetBodyDynamicsCommand, SetBodyLinVelCommand,
+        SetBodyMassCommand, SetBodySleepingCommand,
+    },
     send_sync_message,
-    sidebar::{make_f32_input_field, make_text_mark, COLUMN_WIDTH, ROW_HEIGHT},
+    sidebar::{
+        make_bool_input_field, make_f32_input_field, make_text_mark, make_vec3_input_field,
+        COLUMN_WIDTH, ROW_HEIGHT,
+    },
     Message,
 };
 use rg3d::{
     core::pool::Handle,
     gui::{
+        check_box::CheckBoxBuilder,
         grid::{Column, GridBuilder, Row},
-        message::{MessageDirection, NumericUpDownMessage, UiMessageData},
+        message::{
+            CheckBoxMessage, MessageDirection, NumericUpDownMessage, UiMessageData,
+            Vec3EditorMessage, WidgetMessage,
+        },
+        stack_panel::StackPanelBuilder,
+        text::TextBuilder,
         widget::WidgetBuilder,
+        Orientation, Thickness,
     },
 };
 use std::sync::mpsc::Sender;
@@ -19,56 +32,267 @@ use std::sync::mpsc::Sender;
 pub struct BodySection {
     pub section: Handle<UiNode>,
     mass: Handle<UiNode>,
+    mass_from_density: Handle<UiNode>,
+    linvel: Handle<UiNode>,
+    angvel: Handle<UiNode>,
+    sleeping: Handle<UiNode>,
+    linear_damping: Handle<UiNode>,
+    angular_damping: Handle<UiNode>,
+    ccd: Handle<UiNode>,
+    locked_translations: [Handle<UiNode>; 3],
+    locked_rotations: [Handle<UiNode>; 3],
     sender: Sender<Message>,
 }
 
+/// Makes a row of X, Y and Z check boxes.
+fn make_axes_locks(ctx: &mut BuildContext, row: usize) -> (Handle<UiNode>, [Handle<UiNode>; 3]) {
+    let mut locks = [Handle::NONE; 3];
+    for (lock, name) in locks.iter_mut().zip(["X", "Y", "Z"].iter()) {
+        *lock = CheckBoxBuilder::new(
+            WidgetBuilder::new()
+                .with_width(40.0)
+                .with_margin(Thickness::uniform(1.0)),
+        )
+        .with_content(
+            TextBuilder::new(WidgetBuilder::new())
+                .with_text(name)
+                .build(ctx),
+        )
+        .build(ctx);
+    }
+    let panel = StackPanelBuilder::new(
+        WidgetBuilder::new()
+            .on_row(row)
+            .on_column(1)
+            .with_children(&locks),
+    )
+    .with_orientation(Orientation::Horizontal)
+    .build(ctx);
+    (panel, locks)
+}
+
 impl BodySection {
     pub fn new(ctx: &mut BuildContext, sender: Sender<Message>) -> Self {
         let mass;
+        let mass_from_density;
+        let linvel;
+        let angvel;
+        let sleeping;
+        let linear_damping;
+        let angular_damping;
+        let ccd;
+        let (translation_locks_panel, locked_translations) = make_axes_locks(ctx, 8);
+        let (rotation_locks_panel, locked_rotations) = make_axes_locks(ctx, 9);
         let section = GridBuilder::new(
             WidgetBuilder::new()
                 .with_child(make_text_mark(ctx, "Mass", 0))
                 .with_child({
                     mass = make_f32_input_field(ctx, 0, 0.0, std::f32::MAX, 0.1);
                     mass
-                }),
+                })
+                .with_child(make_text_mark(ctx, "Mass From Density", 1))
+                .with_child({
+                    mass_from_density = make_bool_input_field(ctx, 1);
+                    mass_from_density
+                })
+                .with_child(make_text_mark(ctx, "Linear Velocity", 2))
+                .with_child({
+                    linvel = make_vec3_input_field(ctx, 2);
+                    linvel
+                })
+                .with_child(make_text_mark(ctx, "Angular Velocity", 3))
+                .with_child({
+                    angvel = make_vec3_input_field(ctx, 3);
+                    angvel
+                })
+                .with_child(make_text_mark(ctx, "Sleeping", 4))
+                .with_child({
+                    sleeping = make_bool_input_field(ctx, 4);
+                    sleeping
+                })
+                .with_child(make_text_mark(ctx, "Linear Damping", 5))
+                .with_child({
+                    linear_damping = make_f32_input_field(ctx, 5, 0.0, std::f32::MAX, 0.1);
+                    linear_damping
+                })
+                .with_child(make_text_mark(ctx, "Angular Damping", 6))
+                .with_child({
+                    angular_damping = make_f32_input_field(ctx, 6, 0.0, std::f32::MAX, 0.1);
+                    angular_damping
+                })
+                .with_child(make_text_mark(ctx, "CCD", 7))
+                .with_child({
+                    ccd = make_bool_input_field(ctx, 7);
+                    ccd
+                })
+                .with_child(make_text_mark(ctx, "Lock Translation", 8))
+                .with_child(translation_locks_panel)
+                .with_child(make_text_mark(ctx, "Lock Rotation", 9))
+                .with_child(rotation_locks_panel),
         )
         .add_column(Column::strict(COLUMN_WIDTH))
         .add_column(Column::stretch())
         .add_row(Row::strict(ROW_HEIGHT))
+        .add_row(Row::strict(ROW_HEIGHT))
+        .add_row(Row::strict(ROW_HEIGHT))
+        .add_row(Row::strict(ROW_HEIGHT))
+        .add_row(Row::strict(ROW_HEIGHT))
+        .add_row(Row::strict(ROW_HEIGHT))
+        .add_row(Row::strict(ROW_HEIGHT))
+        .add_row(Row::strict(ROW_HEIGHT))
+        .add_row(Row::strict(ROW_HEIGHT))
+        .add_row(Row::strict(ROW_HEIGHT))
         .build(ctx);
 
         Self {
             section,
             sender,
             mass,
+            mass_from_density,
+            linvel,
+            angvel,
+            sleeping,
+            linear_damping,
+            angular_damping,
+            ccd,
+            locked_translations,
+            locked_rotations,
         }
     }
 
-    pub fn sync_to_model(&mut self, body: &RigidBody, ui: &mut Ui) {
+    /// `mass` is the mass body will have in simulation, it differs from body's own mass
+    /// when it is computed from density.
+    pub fn sync_to_model(
+        &mut self,
+        body: &RigidBody,
+        dynamics: &BodyDynamics,
+        mass: f32,
+        ui: &mut Ui,
+    ) {
         send_sync_message(
             ui,
-            NumericUpDownMessage::value(self.mass, MessageDirection::ToWidget, body.mass),
+            WidgetMessage::enabled(
+                self.mass,
+                MessageDirection::ToWidget,
+                !dynamics.mass_from_density,
+            ),
         );
+
+        for &(field, value) in &[
+            (self.mass, mass),
+            (self.linear_damping, dynamics.linear_damping),
+            (self.angular_damping, dynamics.angular_damping),
+        ] {
+            send_sync_message(
+                ui,
+                NumericUpDownMessage::value(field, MessageDirection::ToWidget, value),
+            );
+        }
+
+        for &(field, value) in &[(self.linvel, body.linvel), (self.angvel, body.angvel)] {
+            send_sync_message(
+                ui,
+                Vec3EditorMessage::value(field, MessageDirection::ToWidget, value),
+            );
+        }
+
+        let mut checks = vec![
+            (self.sleeping, body.sleeping),
+            (self.ccd, dynamics.ccd_enabled),
+            (self.mass_from_density, dynamics.mass_from_density),
+        ];
+        for i in 0..3 {
+            checks.push((self.locked_translations[i], dynamics.locked_translations[i]));
+            checks.push((self.locked_rotations[i], dynamics.locked_rotations[i]));
+        }
+        for (field, value) in checks {
+            send_sync_message(
+                ui,
+                CheckBoxMessage::checked(field, MessageDirection::ToWidget, Some(value)),
+            );
+        }
     }
 
     pub fn handle_message(
         &mut self,
         message: &UiMessage,
         body: &RigidBody,
+        dynamics: &BodyDynamics,
         handle: Handle<RigidBody>,
     ) {
-        if let UiMessageData::NumericUpDown(NumericUpDownMessage::Value(value)) = *message.data() {
-            if message.direction() == MessageDirection::FromWidget
-                && message.destination() == self.mass
-                && body.mass.ne(&value)
-            {
-                self.sender
-                    .send(Message::DoSceneCommand(SceneCommand::SetBodyMass(
-                        SetBodyMassCommand::new(handle, value),
-                    )))
-                    .unwrap();
+        if message.direction() != MessageDirection::FromWidget {
+            return;
+        }
+
+        let mut new_dynamics = dynamics.clone();
+
+        match *message.data() {
+            UiMessageData::NumericUpDown(NumericUpDownMessage::Value(value)) => {
+                if message.destination() == self.mass
+                    && !dynamics.mass_from_density
+                    && body.mass.ne(&value)
+                {
+                    self.sender
+                        .send(Message::DoSceneCommand(SceneCommand::SetBodyMass(
+                            SetBodyMassCommand::new(handle, value),
+                        )))
+                        .unwrap();
+                } else if message.destination() == self.linear_damping {
+                    new_dynamics.linear_damping = value;
+                } else if message.destination() == self.angular_damping {
+                    new_dynamics.angular_damping = value;
+                }
+            }
+            UiMessageData::Vec3Editor(Vec3EditorMessage::Value(value)) => {
+                if message.destination() == self.linvel && body.linvel.ne(&value) {
+                    self.sender
+                        .send(Message::DoSceneCommand(SceneCommand::SetBodyLinVel(
+                            SetBodyLinVelCommand::new(handle, value),
+                        )))
+                        .unwrap();
+                } else if message.destination() == self.angvel && body.angvel.ne(&value) {
+                    self.sender
+                        .send(Message::DoSceneCommand(SceneCommand::SetBodyAngVel(
+                            SetBodyAngVelCommand::new(handle, value),
+                        )))
+                        .unwrap();
+                }
+            }
+            UiMessageData::CheckBox(CheckBoxMessage::Check(checked)) => {
+                let value = checked.unwrap_or_default();
+                if message.destination() == self.sleeping && body.sleeping != value {
+                    self.sender
+                        .send(Message::DoSceneCommand(SceneCommand::SetBodySleeping(
+                            SetBodySleepingCommand::new(handle, value),
+                        )))
+                        .unwrap();
+                } else if message.destination() == self.ccd {
+                    new_dynamics.ccd_enabled = value;
+                } else if message.destination() == self.mass_from_density {
+                    new_dynamics.mass_from_density = value;
+                } else if let Some(i) = self
+                    .locked_translations
+                    .iter()
+                    .position(|&l| l == message.destination())
+                {
+                    new_dynamics.locked_translations[i] = value;
+                } else if let Some(i) = self
+                    .locked_rotations
+                    .iter()
+                    .position(|&l| l == message.destination())
+                {
+                    new_dynamics.locked_rotations[i] = value;
+                }
             }
+            _ => (),
+        }
+
+        if new_dynamics != *dynamics {
+            self.sender
+                .send(Message::DoSceneCommand(SceneCommand::SetBodyDynamics(
+                    SetBodyDynamicsCommand::new(handle, new_dynamics),
+                )))
+                .unwrap();
         }
     }
 }
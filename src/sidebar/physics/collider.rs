@@ -1,85 +1,130 @@
 use crate::scene::{
-    SetColliderCollisionGroupsCommand, SetColliderIsSensorCommand, SetColliderPositionCommand,
-    SetColliderRotationCommand,
+    SetColliderDensityCommand, SetColliderIsSensorCommand, SetColliderMaterialCommand,
+    SetColliderPositionCommand, SetColliderRotationCommand,
 };
-use crate::sidebar::{make_bool_input_field, make_int_input_field, make_vec3_input_field};
+use crate::sidebar::{make_bool_input_field, make_dropdown_list_option, make_vec3_input_field};
 use crate::{
     gui::{BuildContext, Ui, UiMessage, UiNode},
     physics::Collider,
+    physics_layers::{PhysicsLayers, LAYER_COUNT},
+    physics_materials::{PhysicsMaterialLibrary, DEFAULT_DENSITY},
     scene::{SceneCommand, SetColliderFrictionCommand, SetColliderRestitutionCommand},
     send_sync_message,
     sidebar::{make_f32_input_field, make_text_mark, COLUMN_WIDTH, ROW_HEIGHT},
     Message,
 };
 use rg3d::core::math::{quat_from_euler, RotationOrder, UnitQuaternionExt};
-use rg3d::gui::message::{CheckBoxMessage, Vec3EditorMessage};
+use rg3d::gui::message::{CheckBoxMessage, DropdownListMessage, TextMessage, Vec3EditorMessage};
 use rg3d::{
     core::algebra::Vector3,
     core::pool::Handle,
     gui::{
+        check_box::CheckBoxBuilder,
+        dropdown_list::DropdownListBuilder,
         grid::{Column, GridBuilder, Row},
         message::{MessageDirection, NumericUpDownMessage, UiMessageData},
+        text::TextBuilder,
         widget::WidgetBuilder,
+        wrap_panel::WrapPanelBuilder,
+        Orientation, Thickness, VerticalAlignment,
     },
 };
 use std::sync::mpsc::Sender;
 
 pub struct ColliderSection {
     pub section: Handle<UiNode>,
+    material: Handle<UiNode>,
     friction: Handle<UiNode>,
     restitution: Handle<UiNode>,
+    density: Handle<UiNode>,
     position: Handle<UiNode>,
     rotation: Handle<UiNode>,
-    collision_groups: Handle<UiNode>,
-    collision_mask: Handle<UiNode>,
+    /// Membership checkbox per physics layer.
+    layers: Vec<Handle<UiNode>>,
+    layer_names: Vec<Handle<UiNode>>,
     is_sensor: Handle<UiNode>,
+    material_names: Vec<String>,
     sender: Sender<Message>,
 }
 
 impl ColliderSection {
     pub fn new(ctx: &mut BuildContext, sender: Sender<Message>) -> Self {
+        let material;
         let friction;
         let restitution;
+        let density;
         let position;
         let rotation;
-        let collision_groups;
-        let collision_mask;
         let is_sensor;
+        let mut layers = Vec::new();
+        let mut layer_names = Vec::new();
+        for _ in 0..LAYER_COUNT {
+            let name = TextBuilder::new(WidgetBuilder::new())
+                .with_vertical_text_alignment(VerticalAlignment::Center)
+                .build(ctx);
+            layer_names.push(name);
+            layers.push(
+                CheckBoxBuilder::new(
+                    WidgetBuilder::new()
+                        .with_width(90.0)
+                        .with_margin(Thickness::uniform(1.0)),
+                )
+                .with_content(name)
+                .build(ctx),
+            );
+        }
+        let layers_panel = WrapPanelBuilder::new(
+            WidgetBuilder::new()
+                .on_row(6)
+                .on_column(1)
+                .with_children(&layers),
+        )
+        .with_orientation(Orientation::Horizontal)
+        .build(ctx);
         let section = GridBuilder::new(
             WidgetBuilder::new()
-                .with_child(make_text_mark(ctx, "Friction", 0))
+                .with_child(make_text_mark(ctx, "Material", 0))
                 .with_child({
-                    friction = make_f32_input_field(ctx, 0, 0.0, std::f32::MAX, 0.1);
+                    material = DropdownListBuilder::new(
+                        WidgetBuilder::new()
+                            .on_row(0)
+                            .on_column(1)
+                            .with_margin(Thickness::uniform(1.0)),
+                    )
+                    .with_close_on_selection(true)
+                    .build(ctx);
+                    material
+                })
+                .with_child(make_text_mark(ctx, "Friction", 1))
+                .with_child({
+                    friction = make_f32_input_field(ctx, 1, 0.0, std::f32::MAX, 0.1);
                     friction
                 })
-                .with_child(make_text_mark(ctx, "Restitution", 1))
+                .with_child(make_text_mark(ctx, "Restitution", 2))
                 .with_child({
-                    restitution = make_f32_input_field(ctx, 1, 0.0, std::f32::MAX, 0.1);
+                    restitution = make_f32_input_field(ctx, 2, 0.0, std::f32::MAX, 0.1);
                     restitution
                 })
-                .with_child(make_text_mark(ctx, "Collider Position", 2))
+                .with_child(make_text_mark(ctx, "Density", 3))
                 .with_child({
-                    position = make_vec3_input_field(ctx, 2);
-                    position
+                    density = make_f32_input_field(ctx, 3, 0.0, std::f32::MAX, 0.1);
+                    density
                 })
-                .with_child(make_text_mark(ctx, "Collider Rotation", 3))
+                .with_child(make_text_mark(ctx, "Collider Position", 4))
                 .with_child({
-                    rotation = make_vec3_input_field(ctx, 3);
-                    rotation
-                })
-                .with_child(make_text_mark(ctx, "Collision Groups", 4))
-                .with_child({
-                    collision_groups = make_int_input_field(ctx, 4, 0, u16::MAX as i32, 1);
-                    collision_groups
+                    position = make_vec3_input_field(ctx, 4);
+                    position
                 })
-                .with_child(make_text_mark(ctx, "Collision Mask", 5))
+                .with_child(make_text_mark(ctx, "Collider Rotation", 5))
                 .with_child({
-                    collision_mask = make_int_input_field(ctx, 5, 0, u16::MAX as i32, 1);
-                    collision_mask
+                    rotation = make_vec3_input_field(ctx, 5);
+                    rotation
                 })
-                .with_child(make_text_mark(ctx, "Is Sensor", 6))
+                .with_child(make_text_mark(ctx, "Layers", 6))
+                .with_child(layers_panel)
+                .with_child(make_text_mark(ctx, "Is Sensor", 7))
                 .with_child({
-                    is_sensor = make_bool_input_field(ctx, 6);
+                    is_sensor = make_bool_input_field(ctx, 7);
                     is_sensor
                 }),
         )
@@ -91,23 +136,68 @@ impl ColliderSection {
         .add_row(Row::strict(ROW_HEIGHT))
         .add_row(Row::strict(ROW_HEIGHT))
         .add_row(Row::strict(ROW_HEIGHT))
+        .add_row(Row::auto())
         .add_row(Row::strict(ROW_HEIGHT))
         .build(ctx);
 
         Self {
             section,
             sender,
+            material,
             friction,
             restitution,
+            density,
             position,
             rotation,
             is_sensor,
-            collision_mask,
-            collision_groups,
+            layers,
+            layer_names,
+            material_names: Default::default(),
         }
     }
 
-    pub fn sync_to_model(&mut self, collider: &Collider, ui: &mut Ui) {
+    pub fn sync_to_model(
+        &mut self,
+        collider: &Collider,
+        material: Option<usize>,
+        materials: &PhysicsMaterialLibrary,
+        layers: &PhysicsLayers,
+        ui: &mut Ui,
+    ) {
+        let materials = &materials.materials;
+        if materials.len() != self.material_names.len()
+            || materials
+                .iter()
+                .zip(self.material_names.iter())
+                .any(|(m, name)| m.name != *name)
+        {
+            self.material_names = materials.iter().map(|m| m.name.clone()).collect();
+            let ctx = &mut ui.build_ctx();
+            let items = self
+                .material_names
+                .iter()
+                .map(|name| make_dropdown_list_option(ctx, name))
+                .collect::<Vec<_>>();
+            send_sync_message(
+                ui,
+                DropdownListMessage::items(self.material, MessageDirection::ToWidget, items),
+            );
+        }
+
+        send_sync_message(
+            ui,
+            DropdownListMessage::selection(self.material, MessageDirection::ToWidget, material),
+        );
+
+        send_sync_message(
+            ui,
+            NumericUpDownMessage::value(
+                self.density,
+                MessageDirection::ToWidget,
+                collider.density.unwrap_or(DEFAULT_DENSITY),
+            ),
+        );
+
         send_sync_message(
             ui,
             NumericUpDownMessage::value(
@@ -155,29 +245,36 @@ impl ColliderSection {
             ),
         );
 
-        send_sync_message(
-            ui,
-            NumericUpDownMessage::value(
-                self.collision_groups,
-                MessageDirection::ToWidget,
-                (collider.collision_groups >> 16) as f32,
-            ),
-        );
-
-        send_sync_message(
-            ui,
-            NumericUpDownMessage::value(
-                self.collision_mask,
-                MessageDirection::ToWidget,
-                (collider.collision_groups & 0x0000FFFF) as f32,
-            ),
-        );
+        let memberships = PhysicsLayers::memberships(collider);
+        for (layer, (&check_box, &name)) in
+            self.layers.iter().zip(self.layer_names.iter()).enumerate()
+        {
+            send_sync_message(
+                ui,
+                CheckBoxMessage::checked(
+                    check_box,
+                    MessageDirection::ToWidget,
+                    Some(memberships & (1 << layer) != 0),
+                ),
+            );
+            send_sync_message(
+                ui,
+                TextMessage::text(
+                    name,
+                    MessageDirection::ToWidget,
+                    layers.names[layer].clone(),
+                ),
+            );
+        }
     }
 
     pub fn handle_message(
         &mut self,
         message: &UiMessage,
         collider: &Collider,
+        material: Option<usize>,
+        materials: &PhysicsMaterialLibrary,
+        layers: &PhysicsLayers,
         handle: Handle<Collider>,
     ) {
         if message.direction() == MessageDirection::FromWidget {
@@ -189,6 +286,14 @@ impl ColliderSection {
                                 SetColliderFrictionCommand::new(handle, value),
                             )))
                             .unwrap();
+                    } else if message.destination() == self.density
+                        && collider.density.unwrap_or(DEFAULT_DENSITY).ne(&value)
+                    {
+                        self.sender
+                            .send(Message::DoSceneCommand(SceneCommand::SetColliderDensity(
+                                SetColliderDensityCommand::new(handle, Some(value)),
+                            )))
+                            .unwrap();
                     } else if message.destination() == self.restitution
                         && collider.restitution.ne(&value)
                     {
@@ -199,25 +304,6 @@ impl ColliderSection {
                                 ),
                             ))
                             .unwrap();
-                    } else if message.destination() == self.collision_mask {
-                        let mask = (collider.collision_groups & 0xFFFF0000) | value as u32;
-                        self.sender
-                            .send(Message::DoSceneCommand(
-                                SceneCommand::SetColliderCollisionGroups(
-                                    SetColliderCollisionGroupsCommand::new(handle, mask),
-                                ),
-                            ))
-                            .unwrap();
-                    } else if message.destination() == self.collision_groups {
-                        let groups =
-                            (collider.collision_groups & 0x0000FFFF) | ((value as u32) << 16);
-                        self.sender
-                            .send(Message::DoSceneCommand(
-                                SceneCommand::SetColliderCollisionGroups(
-                                    SetColliderCollisionGroupsCommand::new(handle, groups),
-                                ),
-                            ))
-                            .unwrap();
                     }
                 }
                 UiMessageData::Vec3Editor(Vec3EditorMessage::Value(value)) => {
@@ -244,8 +330,35 @@ impl ColliderSection {
                         }
                     }
                 }
+                &UiMessageData::DropdownList(DropdownListMessage::SelectionChanged(Some(
+                    index,
+                ))) if message.destination() == self.material => {
+                    if material != Some(index) {
+                        if let Some(material) = materials.materials.get(index) {
+                            self.sender
+                                .send(Message::DoSceneCommand(SceneCommand::SetColliderMaterial(
+                                    SetColliderMaterialCommand::new(vec![handle], material),
+                                )))
+                                .unwrap();
+                        }
+                    }
+                }
                 UiMessageData::CheckBox(CheckBoxMessage::Check(checked)) => {
-                    if message.destination() == self.is_sensor {
+                    if let Some(layer) =
+                        self.layers.iter().position(|&l| l == message.destination())
+                    {
+                        let mut memberships = PhysicsLayers::memberships(collider);
+                        if checked.unwrap_or_default() {
+                            memberships |= 1 << layer;
+                        } else {
+                            memberships &= !(1 << layer);
+                        }
+                        if let Some(command) =
+                            layers.make_groups_command(&[(handle, collider)], memberships)
+                        {
+                            self.sender.send(Message::DoSceneCommand(command)).unwrap();
+                        }
+                    } else if message.destination() == self.is_sensor {
                         let value = checked.unwrap_or_default();
                         if value != collider.is_sensor {
                             self.sender
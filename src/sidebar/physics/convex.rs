@@ -0,0 +1,235 @@
+use crate::{
+    collider_fit::collect_points,
+    convex_hull::ConvexHull,
+    gui::{BuildContext, Ui, UiMessage, UiNode},
+    physics::{Collider, ConvexSource},
+    scene::{SceneCommand, SetConvexPointsCommand, SetConvexToleranceCommand},
+    send_sync_message,
+    sidebar::{make_f32_input_field, make_text_mark, COLUMN_WIDTH, ROW_HEIGHT},
+    Message,
+};
+use rg3d::{
+    core::{
+        algebra::{Isometry3, Point3, Translation3, Vector3},
+        pool::Handle,
+    },
+    gui::{
+        button::ButtonBuilder,
+        grid::{Column, GridBuilder, Row},
+        message::{
+            ButtonMessage, MessageDirection, NumericUpDownMessage, TextBoxMessage, TextMessage,
+            UiMessageData,
+        },
+        text::TextBuilder,
+        text_box::TextBoxBuilder,
+        widget::WidgetBuilder,
+        Thickness, VerticalAlignment,
+    },
+    scene::{graph::Graph, node::Node},
+};
+use std::sync::mpsc::Sender;
+
+pub struct ConvexSection {
+    pub section: Handle<UiNode>,
+    vertices: Handle<UiNode>,
+    tolerance: Handle<UiNode>,
+    points: Handle<UiNode>,
+    from_mesh: Handle<UiNode>,
+    sender: Sender<Message>,
+}
+
+/// Points are written as `x y z` triplets separated by `;`.
+fn points_to_string(points: &[Vector3<f32>]) -> String {
+    points
+        .iter()
+        .map(|p| format!("{} {} {}", p.x, p.y, p.z))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+fn parse_points(text: &str) -> Option<Vec<Vector3<f32>>> {
+    text.split(';')
+        .filter(|s| !s.trim().is_empty())
+        .map(|s| {
+            let coords = s
+                .split_whitespace()
+                .map(|c| c.parse::<f32>().ok())
+                .collect::<Option<Vec<_>>>()?;
+            match coords.as_slice() {
+                [x, y, z] => Some(Vector3::new(*x, *y, *z)),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+impl ConvexSection {
+    pub fn new(ctx: &mut BuildContext, sender: Sender<Message>) -> Self {
+        let vertices;
+        let tolerance;
+        let points;
+        let from_mesh;
+        let section = GridBuilder::new(
+            WidgetBuilder::new()
+                .with_child(make_text_mark(ctx, "Vertices", 0))
+                .with_child({
+                    vertices = TextBuilder::new(
+                        WidgetBuilder::new()
+                            .on_row(0)
+                            .on_column(1)
+                            .with_vertical_alignment(VerticalAlignment::Center)
+                            .with_margin(Thickness::uniform(1.0)),
+                    )
+                    .build(ctx);
+                    vertices
+                })
+                .with_child(make_text_mark(ctx, "Tolerance", 1))
+                .with_child({
+                    tolerance = make_f32_input_field(ctx, 1, 0.0, std::f32::MAX, 0.01);
+                    tolerance
+                })
+                .with_child(make_text_mark(ctx, "Points", 2))
+                .with_child({
+                    points = TextBoxBuilder::new(
+                        WidgetBuilder::new()
+                            .on_row(2)
+                            .on_column(1)
+                            .with_margin(Thickness::uniform(1.0)),
+                    )
+                    .build(ctx);
+                    points
+                })
+                .with_child({
+                    from_mesh = ButtonBuilder::new(
+                        WidgetBuilder::new()
+                            .on_row(3)
+                            .on_column(1)
+                            .with_margin(Thickness::uniform(1.0)),
+                    )
+                    .with_text("Take From Mesh")
+                    .build(ctx);
+                    from_mesh
+                }),
+        )
+        .add_column(Column::strict(COLUMN_WIDTH))
+        .add_column(Column::stretch())
+        .add_row(Row::strict(ROW_HEIGHT))
+        .add_row(Row::strict(ROW_HEIGHT))
+        .add_row(Row::strict(ROW_HEIGHT))
+        .add_row(Row::strict(ROW_HEIGHT))
+        .build(ctx);
+
+        Self {
+            section,
+            vertices,
+            tolerance,
+            points,
+            from_mesh,
+            sender,
+        }
+    }
+
+    pub fn sync_to_model(&mut self, source: &ConvexSource, ui: &mut Ui) {
+        send_sync_message(
+            ui,
+            TextMessage::text(
+                self.vertices,
+                MessageDirection::ToWidget,
+                format!(
+                    "{} of {} points",
+                    source.hull().vertices.len(),
+                    source.points.len()
+                ),
+            ),
+        );
+        send_sync_message(
+            ui,
+            NumericUpDownMessage::value(
+                self.tolerance,
+                MessageDirection::ToWidget,
+                source.tolerance,
+            ),
+        );
+        send_sync_message(
+            ui,
+            TextBoxMessage::text(
+                self.points,
+                MessageDirection::ToWidget,
+                points_to_string(&source.points),
+            ),
+        );
+    }
+
+    fn set_points(
+        &self,
+        source: &ConvexSource,
+        handle: Handle<Collider>,
+        points: Vec<Vector3<f32>>,
+    ) {
+        if points != source.points {
+            self.sender
+                .send(Message::DoSceneCommand(SceneCommand::SetConvexPoints(
+                    SetConvexPointsCommand::new(handle, points),
+                )))
+                .unwrap();
+        }
+    }
+
+    pub fn handle_message(
+        &mut self,
+        message: &UiMessage,
+        source: &ConvexSource,
+        collider: &Collider,
+        handle: Handle<Collider>,
+        node: Handle<Node>,
+        graph: &Graph,
+    ) {
+        if message.direction() != MessageDirection::FromWidget {
+            return;
+        }
+
+        match message.data() {
+            UiMessageData::NumericUpDown(NumericUpDownMessage::Value(value))
+                if message.destination() == self.tolerance =>
+            {
+                if source.tolerance.ne(value) {
+                    self.sender
+                        .send(Message::DoSceneCommand(SceneCommand::SetConvexTolerance(
+                            SetConvexToleranceCommand::new(handle, *value),
+                        )))
+                        .unwrap();
+                }
+            }
+            UiMessageData::TextBox(TextBoxMessage::Text(text))
+                if message.destination() == self.points =>
+            {
+                if let Some(points) = parse_points(text) {
+                    self.set_points(source, handle, points);
+                }
+            }
+            UiMessageData::Button(ButtonMessage::Click)
+                if message.destination() == self.from_mesh =>
+            {
+                // Mesh points are in body space, move them to collider's space.
+                let inv_local = Isometry3 {
+                    rotation: collider.rotation,
+                    translation: Translation3 {
+                        vector: collider.translation,
+                    },
+                }
+                .inverse();
+                let points = collect_points(graph, node)
+                    .into_iter()
+                    .map(|p| inv_local.transform_point(&Point3::from(p)).coords)
+                    .collect::<Vec<_>>();
+                // Keep only points that lie on the hull, interior ones can never affect it
+                // and there could be lots of them.
+                let points = ConvexHull::new(&points, 0.0).vertices;
+                if !points.is_empty() {
+                    self.set_points(source, handle, points);
+                }
+            }
+            _ => (),
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use crate::{
+    asset::AssetKind,
+    gui::{BuildContext, EditorUiNode, Ui, UiMessage, UiNode},
+    make_relative_path,
+    physics::{Collider, HeightfieldSource},
+    scene::{SceneCommand, SetHeightfieldScaleCommand, SetHeightfieldTextureCommand},
+    send_sync_message,
+    sidebar::{make_text_mark, make_vec3_input_field, COLUMN_WIDTH, ROW_HEIGHT},
+    Message,
+};
+use rg3d::{
+    core::pool::Handle,
+    gui::{
+        grid::{Column, GridBuilder, Row},
+        message::{
+            MessageDirection, TextBoxMessage, UiMessageData, Vec3EditorMessage, WidgetMessage,
+        },
+        text_box::TextBoxBuilder,
+        widget::WidgetBuilder,
+        Thickness,
+    },
+};
+use std::{path::PathBuf, sync::mpsc::Sender};
+
+pub struct HeightfieldSection {
+    pub section: Handle<UiNode>,
+    texture: Handle<UiNode>,
+    scale: Handle<UiNode>,
+    sender: Sender<Message>,
+}
+
+impl HeightfieldSection {
+    pub fn new(ctx: &mut BuildContext, sender: Sender<Message>) -> Self {
+        let texture;
+        let scale;
+        let section = GridBuilder::new(
+            WidgetBuilder::new()
+                .with_child(make_text_mark(ctx, "Height Map", 0))
+                .with_child({
+                    texture = TextBoxBuilder::new(
+                        WidgetBuilder::new()
+                            .on_row(0)
+                            .on_column(1)
+                            .with_margin(Thickness::uniform(1.0))
+                            .with_allow_drop(true),
+                    )
+                    .build(ctx);
+                    texture
+                })
+                .with_child(make_text_mark(ctx, "Scale", 1))
+                .with_child({
+                    scale = make_vec3_input_field(ctx, 1);
+                    scale
+                }),
+        )
+        .add_column(Column::strict(COLUMN_WIDTH))
+        .add_column(Column::stretch())
+        .add_row(Row::strict(ROW_HEIGHT))
+        .add_row(Row::strict(ROW_HEIGHT))
+        .build(ctx);
+
+        Self {
+            section,
+            texture,
+            scale,
+            sender,
+        }
+    }
+
+    pub fn sync_to_model(&mut self, source: &HeightfieldSource, ui: &mut Ui) {
+        send_sync_message(
+            ui,
+            TextBoxMessage::text(
+                self.texture,
+                MessageDirection::ToWidget,
+                source
+                    .texture
+                    .as_ref()
+                    .map(|path| path.to_string_lossy().into_owned())
+                    .unwrap_or_default(),
+            ),
+        );
+        send_sync_message(
+            ui,
+            Vec3EditorMessage::value(self.scale, MessageDirection::ToWidget, source.scale),
+        );
+    }
+
+    fn set_texture(&self, source: &HeightfieldSource, handle: Handle<Collider>, path: &str) {
+        let texture = if path.is_empty() {
+            None
+        } else {
+            Some(PathBuf::from(path))
+        };
+        if texture != source.texture {
+            self.sender
+                .send(Message::DoSceneCommand(
+                    SceneCommand::SetHeightfieldTexture(SetHeightfieldTextureCommand::new(
+                        handle, texture,
+                    )),
+                ))
+                .unwrap();
+        }
+    }
+
+    pub fn handle_message(
+        &mut self,
+        message: &UiMessage,
+        source: &HeightfieldSource,
+        handle: Handle<Collider>,
+        ui: &Ui,
+    ) {
+        match message.data() {
+            UiMessageData::TextBox(TextBoxMessage::Text(text))
+                if message.destination() == self.texture
+                    && message.direction() == MessageDirection::FromWidget =>
+            {
+                self.set_texture(source, handle, text.trim());
+            }
+            UiMessageData::Widget(WidgetMessage::Drop(dropped))
+                if message.destination() == self.texture =>
+            {
+                if let UiNode::User(EditorUiNode::AssetItem(item)) = ui.node(*dropped) {
+                    if let AssetKind::Texture = item.kind {
+                        let path = make_relative_path(&item.path);
+                        self.set_texture(source, handle, &path.to_string_lossy());
+                    }
+                }
+            }
+            UiMessageData::Vec3Editor(Vec3EditorMessage::Value(value))
+                if message.destination() == self.scale
+                    && message.direction() == MessageDirection::FromWidget =>
+            {
+                if source.scale != *value {
+                    self.sender
+                        .send(Message::DoSceneCommand(SceneCommand::SetHeightfieldScale(
+                            SetHeightfieldScaleCommand::new(handle, *value),
+                        )))
+                        .unwrap();
+                }
+            }
+            _ => (),
+        }
+    }
+}
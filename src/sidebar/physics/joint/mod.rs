@@ -1,13 +1,14 @@
 use crate::sidebar::physics::joint::prismatic::PrismaticJointSection;
 use crate::{
     gui::{BuildContext, Ui, UiMessage, UiNode},
-    physics::{Joint, RigidBody},
-    scene::{SceneCommand, SetJointConnectedBodyCommand},
+    physics::{Joint, JointMotion, RigidBody, JOINT_ANCHOR_TOLERANCE},
+    scene::{SceneCommand, SetJointConnectedBodyCommand, SwapJointBodiesCommand},
     send_sync_message,
     sidebar::{
         make_text_mark,
         physics::joint::{
-            ball::BallJointSection, fixed::FixedJointSection, revolute::RevoluteJointSection,
+            ball::BallJointSection, fixed::FixedJointSection, motion::JointMotionSection,
+            revolute::RevoluteJointSection,
         },
         COLUMN_WIDTH, ROW_HEIGHT,
     },
@@ -19,14 +20,18 @@ use rg3d::{
     gui::{
         border::BorderBuilder,
         brush::Brush,
+        button::ButtonBuilder,
         decorator::DecoratorBuilder,
         dropdown_list::DropdownListBuilder,
         grid::{Column, GridBuilder, Row},
-        message::{DropdownListMessage, MessageDirection, UiMessageData, WidgetMessage},
+        message::{
+            ButtonMessage, DropdownListMessage, MessageDirection, TextMessage, UiMessageData,
+            WidgetMessage,
+        },
         stack_panel::StackPanelBuilder,
         text::TextBuilder,
         widget::WidgetBuilder,
-        HorizontalAlignment, VerticalAlignment,
+        HorizontalAlignment, Thickness, VerticalAlignment,
     },
     scene::{graph::Graph, node::Node, physics::JointParamsDesc},
 };
@@ -34,6 +39,7 @@ use std::sync::mpsc::Sender;
 
 mod ball;
 mod fixed;
+mod motion;
 mod prismatic;
 mod revolute;
 
@@ -41,11 +47,14 @@ pub struct JointSection {
     pub section: Handle<UiNode>,
     connected_body: Handle<UiNode>,
     connected_body_text: Handle<UiNode>,
+    swap_bodies: Handle<UiNode>,
+    anchor_warning: Handle<UiNode>,
     sender: Sender<Message>,
     ball_section: BallJointSection,
     fixed_section: FixedJointSection,
     revolute_section: RevoluteJointSection,
     prismatic_section: PrismaticJointSection,
+    motion_section: JointMotionSection,
     available_bodies: Vec<Handle<RigidBody>>,
 }
 
@@ -53,10 +62,13 @@ impl JointSection {
     pub fn new(ctx: &mut BuildContext, sender: Sender<Message>) -> Self {
         let connected_body;
         let connected_body_text;
+        let swap_bodies;
+        let anchor_warning;
         let ball_section = BallJointSection::new(ctx, sender.clone());
         let fixed_section = FixedJointSection::new(ctx, sender.clone());
         let revolute_section = RevoluteJointSection::new(ctx, sender.clone());
         let prismatic_section = PrismaticJointSection::new(ctx, sender.clone());
+        let motion_section = JointMotionSection::new(ctx, sender.clone());
         let section = StackPanelBuilder::new(
             WidgetBuilder::new().with_children(&[
                 GridBuilder::new(
@@ -70,16 +82,42 @@ impl JointSection {
                                 DropdownListBuilder::new(WidgetBuilder::new().on_column(1))
                                     .build(ctx);
                             connected_body
+                        })
+                        .with_child({
+                            swap_bodies = ButtonBuilder::new(
+                                WidgetBuilder::new()
+                                    .on_row(1)
+                                    .on_column(1)
+                                    .with_margin(Thickness::uniform(1.0)),
+                            )
+                            .with_text("Swap Bodies")
+                            .build(ctx);
+                            swap_bodies
+                        })
+                        .with_child({
+                            anchor_warning = TextBuilder::new(
+                                WidgetBuilder::new()
+                                    .on_row(2)
+                                    .on_column(0)
+                                    .with_margin(Thickness::left(4.0))
+                                    .with_foreground(Brush::Solid(Color::RED)),
+                            )
+                            .with_vertical_text_alignment(VerticalAlignment::Center)
+                            .build(ctx);
+                            anchor_warning
                         }),
                 )
                 .add_column(Column::strict(COLUMN_WIDTH))
                 .add_column(Column::stretch())
                 .add_row(Row::strict(ROW_HEIGHT))
+                .add_row(Row::strict(ROW_HEIGHT))
+                .add_row(Row::strict(ROW_HEIGHT))
                 .build(ctx),
                 ball_section.section,
                 fixed_section.section,
                 revolute_section.section,
                 prismatic_section.section,
+                motion_section.section,
             ]),
         )
         .build(ctx);
@@ -89,10 +127,13 @@ impl JointSection {
             sender,
             connected_body_text,
             connected_body,
+            swap_bodies,
+            anchor_warning,
             ball_section,
             fixed_section,
             revolute_section,
             prismatic_section,
+            motion_section,
             available_bodies: Default::default(),
         }
     }
@@ -100,6 +141,8 @@ impl JointSection {
     pub fn sync_to_model(
         &mut self,
         joint: &Joint,
+        motion: &JointMotion,
+        anchor_distance: Option<f32>,
         graph: &Graph,
         binder: &BiDirHashMap<Handle<Node>, Handle<RigidBody>>,
         ui: &mut Ui,
@@ -115,6 +158,7 @@ impl JointSection {
         toggle_visibility(ui, self.fixed_section.section, false);
         toggle_visibility(ui, self.revolute_section.section, false);
         toggle_visibility(ui, self.prismatic_section.section, false);
+        toggle_visibility(ui, self.motion_section.section, false);
 
         match &joint.params {
             JointParamsDesc::BallJoint(ball) => {
@@ -128,13 +172,38 @@ impl JointSection {
             JointParamsDesc::PrismaticJoint(prismatic) => {
                 toggle_visibility(ui, self.prismatic_section.section, true);
                 self.prismatic_section.sync_to_model(prismatic, ui);
+                toggle_visibility(ui, self.motion_section.section, true);
+                self.motion_section.sync_to_model(motion, false, ui);
             }
             JointParamsDesc::RevoluteJoint(revolute) => {
                 toggle_visibility(ui, self.revolute_section.section, true);
                 self.revolute_section.sync_to_model(revolute, ui);
+                toggle_visibility(ui, self.motion_section.section, true);
+                self.motion_section.sync_to_model(motion, true, ui);
             }
         };
 
+        // Anchor distance is known only if joint has connected body.
+        send_sync_message(
+            ui,
+            WidgetMessage::enabled(
+                self.swap_bodies,
+                MessageDirection::ToWidget,
+                anchor_distance.is_some(),
+            ),
+        );
+
+        let warning = match anchor_distance {
+            Some(distance) if distance > JOINT_ANCHOR_TOLERANCE => {
+                format!("Anchors are {:.2} apart!", distance)
+            }
+            _ => String::new(),
+        };
+        send_sync_message(
+            ui,
+            TextMessage::text(self.anchor_warning, MessageDirection::ToWidget, warning),
+        );
+
         self.available_bodies.clear();
         let mut items = Vec::new();
         let ctx = &mut ui.build_ctx();
@@ -193,7 +262,13 @@ impl JointSection {
         );
     }
 
-    pub fn handle_message(&mut self, message: &UiMessage, joint: &Joint, handle: Handle<Joint>) {
+    pub fn handle_message(
+        &mut self,
+        message: &UiMessage,
+        joint: &Joint,
+        motion: &JointMotion,
+        handle: Handle<Joint>,
+    ) {
         match &joint.params {
             JointParamsDesc::BallJoint(ball) => {
                 self.ball_section.handle_message(message, ball, handle);
@@ -204,10 +279,24 @@ impl JointSection {
             JointParamsDesc::PrismaticJoint(prismatic) => {
                 self.prismatic_section
                     .handle_message(message, prismatic, handle);
+                self.motion_section
+                    .handle_message(message, motion, false, handle);
             }
             JointParamsDesc::RevoluteJoint(revolute) => {
                 self.revolute_section
                     .handle_message(message, revolute, handle);
+                self.motion_section
+                    .handle_message(message, motion, true, handle);
+            }
+        }
+
+        if let UiMessageData::Button(ButtonMessage::Click) = message.data() {
+            if message.destination() == self.swap_bodies {
+                self.sender
+                    .send(Message::DoSceneCommand(SceneCommand::SwapJointBodies(
+                        SwapJointBodiesCommand::new(handle),
+                    )))
+                    .unwrap();
             }
         }
 
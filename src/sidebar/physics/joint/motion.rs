@@ -0,0 +1,214 @@
+use crate::{
+    gui::{BuildContext, Ui, UiMessage, UiNode},
+    physics::{Joint, JointMotion},
+    scene::{SceneCommand, SetJointMotionCommand},
+    send_sync_message,
+    sidebar::{
+        make_bool_input_field, make_f32_input_field, make_text_mark, COLUMN_WIDTH, ROW_HEIGHT,
+    },
+    Message,
+};
+use rg3d::{
+    core::pool::Handle,
+    gui::{
+        grid::{Column, GridBuilder, Row},
+        message::{CheckBoxMessage, MessageDirection, NumericUpDownMessage, UiMessageData},
+        widget::WidgetBuilder,
+    },
+};
+use std::sync::mpsc::Sender;
+
+/// Limits and motor of revolute and prismatic joints. Angles are shown in degrees.
+pub struct JointMotionSection {
+    pub section: Handle<UiNode>,
+    limits_enabled: Handle<UiNode>,
+    min_limit: Handle<UiNode>,
+    max_limit: Handle<UiNode>,
+    motor_enabled: Handle<UiNode>,
+    motor_target_velocity: Handle<UiNode>,
+    motor_target_position: Handle<UiNode>,
+    motor_stiffness: Handle<UiNode>,
+    motor_damping: Handle<UiNode>,
+    motor_max_impulse: Handle<UiNode>,
+    sender: Sender<Message>,
+}
+
+impl JointMotionSection {
+    pub fn new(ctx: &mut BuildContext, sender: Sender<Message>) -> Self {
+        let limits_enabled;
+        let min_limit;
+        let max_limit;
+        let motor_enabled;
+        let motor_target_velocity;
+        let motor_target_position;
+        let motor_stiffness;
+        let motor_damping;
+        let motor_max_impulse;
+        let section = GridBuilder::new(
+            WidgetBuilder::new()
+                .with_child(make_text_mark(ctx, "Limits", 0))
+                .with_child({
+                    limits_enabled = make_bool_input_field(ctx, 0);
+                    limits_enabled
+                })
+                .with_child(make_text_mark(ctx, "Min Limit", 1))
+                .with_child({
+                    min_limit = make_f32_input_field(ctx, 1, -std::f32::MAX, std::f32::MAX, 0.1);
+                    min_limit
+                })
+                .with_child(make_text_mark(ctx, "Max Limit", 2))
+                .with_child({
+                    max_limit = make_f32_input_field(ctx, 2, -std::f32::MAX, std::f32::MAX, 0.1);
+                    max_limit
+                })
+                .with_child(make_text_mark(ctx, "Motor", 3))
+                .with_child({
+                    motor_enabled = make_bool_input_field(ctx, 3);
+                    motor_enabled
+                })
+                .with_child(make_text_mark(ctx, "Target Velocity", 4))
+                .with_child({
+                    motor_target_velocity =
+                        make_f32_input_field(ctx, 4, -std::f32::MAX, std::f32::MAX, 0.1);
+                    motor_target_velocity
+                })
+                .with_child(make_text_mark(ctx, "Target Position", 5))
+                .with_child({
+                    motor_target_position =
+                        make_f32_input_field(ctx, 5, -std::f32::MAX, std::f32::MAX, 0.1);
+                    motor_target_position
+                })
+                .with_child(make_text_mark(ctx, "Stiffness", 6))
+                .with_child({
+                    motor_stiffness = make_f32_input_field(ctx, 6, 0.0, std::f32::MAX, 0.1);
+                    motor_stiffness
+                })
+                .with_child(make_text_mark(ctx, "Damping", 7))
+                .with_child({
+                    motor_damping = make_f32_input_field(ctx, 7, 0.0, std::f32::MAX, 0.1);
+                    motor_damping
+                })
+                .with_child(make_text_mark(ctx, "Max Impulse", 8))
+                .with_child({
+                    motor_max_impulse = make_f32_input_field(ctx, 8, 0.0, std::f32::MAX, 0.1);
+                    motor_max_impulse
+                }),
+        )
+        .add_column(Column::strict(COLUMN_WIDTH))
+        .add_column(Column::stretch())
+        .add_row(Row::strict(ROW_HEIGHT))
+        .add_row(Row::strict(ROW_HEIGHT))
+        .add_row(Row::strict(ROW_HEIGHT))
+        .add_row(Row::strict(ROW_HEIGHT))
+        .add_row(Row::strict(ROW_HEIGHT))
+        .add_row(Row::strict(ROW_HEIGHT))
+        .add_row(Row::strict(ROW_HEIGHT))
+        .add_row(Row::strict(ROW_HEIGHT))
+        .add_row(Row::strict(ROW_HEIGHT))
+        .build(ctx);
+
+        Self {
+            section,
+            limits_enabled,
+            min_limit,
+            max_limit,
+            motor_enabled,
+            motor_target_velocity,
+            motor_target_position,
+            motor_stiffness,
+            motor_damping,
+            motor_max_impulse,
+            sender,
+        }
+    }
+
+    /// `angular` must be set for revolute joints, their limits and motor position are
+    /// angles.
+    pub fn sync_to_model(&mut self, motion: &JointMotion, angular: bool, ui: &mut Ui) {
+        let to_ui = |value: f32| if angular { value.to_degrees() } else { value };
+
+        for &(destination, value) in &[
+            (self.limits_enabled, motion.limits_enabled),
+            (self.motor_enabled, motion.motor_enabled),
+        ] {
+            send_sync_message(
+                ui,
+                CheckBoxMessage::checked(destination, MessageDirection::ToWidget, Some(value)),
+            );
+        }
+
+        for &(destination, value) in &[
+            (self.min_limit, to_ui(motion.min_limit)),
+            (self.max_limit, to_ui(motion.max_limit)),
+            (
+                self.motor_target_velocity,
+                to_ui(motion.motor_target_velocity),
+            ),
+            (
+                self.motor_target_position,
+                to_ui(motion.motor_target_position),
+            ),
+            (self.motor_stiffness, motion.motor_stiffness),
+            (self.motor_damping, motion.motor_damping),
+            (self.motor_max_impulse, motion.motor_max_impulse),
+        ] {
+            send_sync_message(
+                ui,
+                NumericUpDownMessage::value(destination, MessageDirection::ToWidget, value),
+            );
+        }
+    }
+
+    pub fn handle_message(
+        &mut self,
+        message: &UiMessage,
+        motion: &JointMotion,
+        angular: bool,
+        handle: Handle<Joint>,
+    ) {
+        if message.direction() != MessageDirection::FromWidget {
+            return;
+        }
+
+        let from_ui = |value: f32| if angular { value.to_radians() } else { value };
+
+        let mut new_motion = motion.clone();
+        match *message.data() {
+            UiMessageData::CheckBox(CheckBoxMessage::Check(checked)) => {
+                let value = checked.unwrap_or_default();
+                if message.destination() == self.limits_enabled {
+                    new_motion.limits_enabled = value;
+                } else if message.destination() == self.motor_enabled {
+                    new_motion.motor_enabled = value;
+                }
+            }
+            UiMessageData::NumericUpDown(NumericUpDownMessage::Value(value)) => {
+                let destination = message.destination();
+                if destination == self.min_limit {
+                    new_motion.min_limit = from_ui(value);
+                } else if destination == self.max_limit {
+                    new_motion.max_limit = from_ui(value);
+                } else if destination == self.motor_target_velocity {
+                    new_motion.motor_target_velocity = from_ui(value);
+                } else if destination == self.motor_target_position {
+                    new_motion.motor_target_position = from_ui(value);
+                } else if destination == self.motor_stiffness {
+                    new_motion.motor_stiffness = value;
+                } else if destination == self.motor_damping {
+                    new_motion.motor_damping = value;
+                } else if destination == self.motor_max_impulse {
+                    new_motion.motor_max_impulse = value;
+                }
+            }
+            _ => (),
+        }
+
+        if new_motion != *motion {
+            self.sender
+                .send(Message::DoSceneCommand(SceneCommand::SetJointMotion(
+                    SetJointMotionCommand::new(handle, new_motion),
+                )))
+                .unwrap();
+        }
+    }
+}
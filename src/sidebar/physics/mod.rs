@@ -1,10 +1,11 @@
 use crate::{
+    collider_fit::{collect_points, make_convex, ColliderFit, FitKind},
     gui::{BuildContext, Ui, UiMessage, UiNode},
     physics::{Collider, Joint, RigidBody},
     scene::{
         AddJointCommand, CommandGroup, DeleteBodyCommand, DeleteColliderCommand,
         DeleteJointCommand, EditorScene, SceneCommand, Selection, SetBallRadiusCommand,
-        SetBodyCommand, SetColliderCommand, SetColliderPositionCommand,
+        SetBodyCommand, SetBodyStatusCommand, SetColliderCommand, SetColliderPositionCommand,
         SetCuboidHalfExtentsCommand, SetCylinderHalfHeightCommand, SetCylinderRadiusCommand,
     },
     send_sync_message,
@@ -12,8 +13,9 @@ use crate::{
         make_dropdown_list_option, make_text_mark,
         physics::{
             ball::BallSection, body::BodySection, capsule::CapsuleSection,
-            collider::ColliderSection, cone::ConeSection, cuboid::CuboidSection,
-            cylinder::CylinderSection, joint::JointSection,
+            collider::ColliderSection, cone::ConeSection, convex::ConvexSection,
+            cuboid::CuboidSection, cylinder::CylinderSection, heightfield::HeightfieldSection,
+            joint::JointSection, trimesh::TrimeshSection,
         },
         COLUMN_WIDTH, ROW_HEIGHT,
     },
@@ -53,8 +55,10 @@ mod body;
 mod capsule;
 mod collider;
 mod cone;
+mod convex;
 mod cuboid;
 mod cylinder;
+mod heightfield;
 mod joint;
 mod segment;
 mod triangle;
@@ -68,6 +72,11 @@ pub struct PhysicsSection {
     joint: Handle<UiNode>,
     joint_text: Handle<UiNode>,
     fit: Handle<UiNode>,
+    auto_collider_text: Handle<UiNode>,
+    auto_collider: Handle<UiNode>,
+    auto_collider_buttons: Handle<UiNode>,
+    preview_auto_collider: Handle<UiNode>,
+    apply_auto_collider: Handle<UiNode>,
     sender: Sender<Message>,
     pub body_section: BodySection,
     pub collider_section: ColliderSection,
@@ -76,6 +85,9 @@ pub struct PhysicsSection {
     pub cuboid_section: CuboidSection,
     pub capsule_section: CapsuleSection,
     pub ball_section: BallSection,
+    pub trimesh_section: TrimeshSection,
+    pub heightfield_section: HeightfieldSection,
+    pub convex_section: ConvexSection,
     pub joint_section: JointSection,
 }
 
@@ -87,6 +99,11 @@ impl PhysicsSection {
         let joint;
         let joint_text;
         let fit;
+        let auto_collider_text;
+        let auto_collider;
+        let auto_collider_buttons;
+        let preview_auto_collider;
+        let apply_auto_collider;
         let body_section = BodySection::new(ctx, sender.clone());
         let collider_section = ColliderSection::new(ctx, sender.clone());
         let cylinder_section = CylinderSection::new(ctx, sender.clone());
@@ -94,6 +111,9 @@ impl PhysicsSection {
         let cuboid_section = CuboidSection::new(ctx, sender.clone());
         let capsule_section = CapsuleSection::new(ctx, sender.clone());
         let ball_section = BallSection::new(ctx, sender.clone());
+        let trimesh_section = TrimeshSection::new(ctx, sender.clone());
+        let heightfield_section = HeightfieldSection::new(ctx, sender.clone());
+        let convex_section = ConvexSection::new(ctx, sender.clone());
         let joint_section = JointSection::new(ctx, sender.clone());
         let section = StackPanelBuilder::new(
             WidgetBuilder::new()
@@ -141,6 +161,7 @@ impl PhysicsSection {
                                     make_dropdown_list_option(ctx, "Triangle"),
                                     make_dropdown_list_option(ctx, "Trimesh"),
                                     make_dropdown_list_option(ctx, "Heightfield"),
+                                    make_dropdown_list_option(ctx, "Convex Polyhedron"),
                                 ])
                                 .build(ctx);
                                 collider
@@ -177,6 +198,57 @@ impl PhysicsSection {
                                 .with_text("Fit Collider")
                                 .build(ctx);
                                 fit
+                            })
+                            .with_child({
+                                auto_collider_text = make_text_mark(ctx, "Auto Collider", 4);
+                                auto_collider_text
+                            })
+                            .with_child({
+                                auto_collider = DropdownListBuilder::new(
+                                    WidgetBuilder::new()
+                                        .on_row(4)
+                                        .on_column(1)
+                                        .with_margin(Thickness::uniform(1.0)),
+                                )
+                                .with_close_on_selection(true)
+                                .with_items(
+                                    FitKind::NAMES
+                                        .iter()
+                                        .map(|name| make_dropdown_list_option(ctx, name))
+                                        .collect(),
+                                )
+                                .build(ctx);
+                                auto_collider
+                            })
+                            .with_child({
+                                auto_collider_buttons = StackPanelBuilder::new(
+                                    WidgetBuilder::new()
+                                        .on_row(5)
+                                        .on_column(1)
+                                        .with_child({
+                                            preview_auto_collider = ButtonBuilder::new(
+                                                WidgetBuilder::new()
+                                                    .with_width(80.0)
+                                                    .with_margin(Thickness::uniform(1.0)),
+                                            )
+                                            .with_text("Preview")
+                                            .build(ctx);
+                                            preview_auto_collider
+                                        })
+                                        .with_child({
+                                            apply_auto_collider = ButtonBuilder::new(
+                                                WidgetBuilder::new()
+                                                    .with_width(80.0)
+                                                    .with_margin(Thickness::uniform(1.0)),
+                                            )
+                                            .with_text("Apply")
+                                            .build(ctx);
+                                            apply_auto_collider
+                                        }),
+                                )
+                                .with_orientation(Orientation::Horizontal)
+                                .build(ctx);
+                                auto_collider_buttons
                             }),
                     )
                     .add_column(Column::strict(COLUMN_WIDTH))
@@ -185,6 +257,8 @@ impl PhysicsSection {
                     .add_row(Row::strict(ROW_HEIGHT))
                     .add_row(Row::strict(ROW_HEIGHT))
                     .add_row(Row::strict(ROW_HEIGHT))
+                    .add_row(Row::strict(ROW_HEIGHT))
+                    .add_row(Row::strict(ROW_HEIGHT))
                     .build(ctx),
                 )
                 .with_children(&[
@@ -195,6 +269,9 @@ impl PhysicsSection {
                     cuboid_section.section,
                     capsule_section.section,
                     ball_section.section,
+                    trimesh_section.section,
+                    heightfield_section.section,
+                    convex_section.section,
                     joint_section.section,
                 ]),
         )
@@ -209,6 +286,9 @@ impl PhysicsSection {
             cuboid_section,
             capsule_section,
             ball_section,
+            trimesh_section,
+            heightfield_section,
+            convex_section,
             section,
             body,
             collider,
@@ -218,6 +298,11 @@ impl PhysicsSection {
             joint,
             joint_text,
             fit,
+            auto_collider_text,
+            auto_collider,
+            auto_collider_buttons,
+            preview_auto_collider,
+            apply_auto_collider,
         }
     }
 
@@ -279,20 +364,33 @@ impl PhysicsSection {
                     toggle_visibility(ui, self.cuboid_section.section, false);
                     toggle_visibility(ui, self.capsule_section.section, false);
                     toggle_visibility(ui, self.ball_section.section, false);
+                    toggle_visibility(ui, self.trimesh_section.section, false);
+                    toggle_visibility(ui, self.heightfield_section.section, false);
+                    toggle_visibility(ui, self.convex_section.section, false);
                     toggle_visibility(ui, self.body_section.section, false);
                     toggle_visibility(ui, self.fit, false);
 
-                    if joint.is_some() {
-                        let joint = &editor_scene.physics.joints[joint];
+                    let has_geometry = scene
+                        .graph
+                        .traverse_handle_iter(node_handle)
+                        .any(|handle| matches!(scene.graph[handle], Node::Mesh(_)));
+                    toggle_visibility(ui, self.auto_collider_text, has_geometry);
+                    toggle_visibility(ui, self.auto_collider, has_geometry);
+                    toggle_visibility(ui, self.auto_collider_buttons, has_geometry);
 
+                    if joint.is_some() {
                         self.joint_section.sync_to_model(
-                            joint,
+                            &editor_scene.physics.joints[joint],
+                            &editor_scene.physics.joint_motion(joint),
+                            editor_scene
+                                .physics
+                                .joint_anchor_distance(joint, &scene.graph),
                             &scene.graph,
                             &editor_scene.physics.binder,
                             ui,
                         );
 
-                        let joint_index = match joint.params {
+                        let joint_index = match editor_scene.physics.joints[joint].params {
                             JointParamsDesc::BallJoint(_) => 1,
                             JointParamsDesc::FixedJoint(_) => 2,
                             JointParamsDesc::PrismaticJoint(_) => 3,
@@ -324,14 +422,32 @@ impl PhysicsSection {
                             let collider = &editor_scene.physics.colliders[collider_handle.into()];
                             toggle_visibility(ui, self.collider_section.section, true);
                             toggle_visibility(ui, self.fit, true);
-                            self.collider_section.sync_to_model(collider, ui);
+                            self.collider_section.sync_to_model(
+                                collider,
+                                editor_scene
+                                    .physics_materials
+                                    .material_of(collider_handle.into(), &editor_scene.physics),
+                                &editor_scene.physics_materials,
+                                &editor_scene.physics_layers,
+                                ui,
+                            );
                         }
 
-                        self.body_section.sync_to_model(body, ui);
+                        self.body_section.sync_to_model(
+                            body,
+                            &editor_scene.physics.body_dynamics(body_handle),
+                            editor_scene.physics.body_mass(body_handle),
+                            ui,
+                        );
                         toggle_visibility(ui, self.body_section.section, true);
 
                         if let Some(&collider) = body.colliders.get(0) {
-                            let collider_index =
+                            let convex = editor_scene.physics.convex_sources.get(&collider.into());
+                            let collider_index = if let Some(source) = convex {
+                                toggle_visibility(ui, self.convex_section.section, true);
+                                self.convex_section.sync_to_model(source, ui);
+                                10
+                            } else {
                                 match &editor_scene.physics.colliders[collider.into()].shape {
                                     ColliderShapeDesc::Ball(ball) => {
                                         toggle_visibility(ui, self.ball_section.section, true);
@@ -368,14 +484,35 @@ impl PhysicsSection {
                                         7
                                     }
                                     ColliderShapeDesc::Trimesh(_) => {
-                                        // Nothing to edit.
+                                        let collider = collider.into();
+                                        toggle_visibility(ui, self.trimesh_section.section, true);
+                                        self.trimesh_section.sync_to_model(
+                                            &editor_scene.physics.trimesh_source(collider),
+                                            &editor_scene
+                                                .physics
+                                                .trimesh_meshes(collider, &scene.graph),
+                                            &scene.graph,
+                                            editor_scene.root,
+                                            ui,
+                                        );
                                         8
                                     }
                                     ColliderShapeDesc::Heightfield(_) => {
-                                        // TODO
+                                        toggle_visibility(
+                                            ui,
+                                            self.heightfield_section.section,
+                                            true,
+                                        );
+                                        self.heightfield_section.sync_to_model(
+                                            &editor_scene
+                                                .physics
+                                                .heightfield_source(collider.into()),
+                                            ui,
+                                        );
                                         9
                                     }
-                                };
+                                }
+                            };
                             send_sync_message(
                                 ui,
                                 DropdownListMessage::selection(
@@ -416,7 +553,13 @@ impl PhysicsSection {
                 let node_handle = selection.nodes()[0];
 
                 if message.direction() == MessageDirection::FromWidget {
-                    self.subsections_handle_ui_message(message, editor_scene, node_handle);
+                    self.subsections_handle_ui_message(
+                        message,
+                        editor_scene,
+                        node_handle,
+                        graph,
+                        &engine.user_interface,
+                    );
                 }
 
                 match &message.data() {
@@ -426,7 +569,7 @@ impl PhysicsSection {
                         if message.destination() == self.body {
                             self.select_body(editor_scene, node_handle, graph, *index);
                         } else if message.destination() == self.collider {
-                            self.select_collider(editor_scene, node_handle, *index);
+                            self.select_collider(editor_scene, node_handle, graph, *index);
                         } else if message.destination() == self.joint {
                             self.select_joint(editor_scene, node_handle, *index);
                         }
@@ -436,6 +579,18 @@ impl PhysicsSection {
                     {
                         self.fit_collider(editor_scene, node_handle, graph);
                     }
+                    UiMessageData::Button(ButtonMessage::Click)
+                        if message.destination() == self.preview_auto_collider
+                            || message.destination() == self.apply_auto_collider =>
+                    {
+                        self.auto_collider(
+                            editor_scene,
+                            node_handle,
+                            graph,
+                            &engine.user_interface,
+                            message.destination() == self.apply_auto_collider,
+                        );
+                    }
                     _ => {}
                 }
             }
@@ -447,55 +602,93 @@ impl PhysicsSection {
         message: &UiMessage,
         editor_scene: &EditorScene,
         node_handle: Handle<Node>,
+        graph: &Graph,
+        ui: &Ui,
     ) {
         if let Some(&body_handle) = editor_scene.physics.binder.value_of(&node_handle) {
             let body = &editor_scene.physics.bodies[body_handle];
-            self.body_section.handle_message(message, body, body_handle);
+            self.body_section.handle_message(
+                message,
+                body,
+                &editor_scene.physics.body_dynamics(body_handle),
+                body_handle,
+            );
 
             if let Some(&collider_handle) = body.colliders.first() {
                 let collider = &editor_scene.physics.colliders[collider_handle.into()];
-                self.collider_section
-                    .handle_message(message, collider, collider_handle.into());
+                self.collider_section.handle_message(
+                    message,
+                    collider,
+                    editor_scene
+                        .physics_materials
+                        .material_of(collider_handle.into(), &editor_scene.physics),
+                    &editor_scene.physics_materials,
+                    &editor_scene.physics_layers,
+                    collider_handle.into(),
+                );
             }
 
             if let Some(&collider) = body.colliders.get(0) {
-                match &editor_scene.physics.colliders[collider.into()].shape {
-                    ColliderShapeDesc::Ball(ball) => {
-                        self.ball_section
-                            .handle_message(message, ball, collider.into());
-                    }
-                    ColliderShapeDesc::Cylinder(cylinder) => {
-                        self.cylinder_section
-                            .handle_message(message, cylinder, collider.into());
-                    }
-                    ColliderShapeDesc::RoundCylinder(_) => {
-                        // TODO
-                    }
-                    ColliderShapeDesc::Cone(cone) => {
-                        self.cone_section
-                            .handle_message(message, cone, collider.into());
-                    }
-                    ColliderShapeDesc::Cuboid(cuboid) => {
-                        self.cuboid_section
-                            .handle_message(message, cuboid, collider.into());
-                    }
-                    ColliderShapeDesc::Capsule(capsule) => {
-                        self.capsule_section
-                            .handle_message(message, capsule, collider.into());
-                    }
-                    ColliderShapeDesc::Segment(_) => {
-                        // TODO
-                    }
-                    ColliderShapeDesc::Triangle(_) => {
-                        // TODO
-                    }
-                    ColliderShapeDesc::Trimesh(_) => {
-                        // Nothing to edit.
-                    }
-                    ColliderShapeDesc::Heightfield(_) => {
-                        // TODO
-                    }
-                };
+                if let Some(source) = editor_scene.physics.convex_sources.get(&collider.into()) {
+                    self.convex_section.handle_message(
+                        message,
+                        source,
+                        &editor_scene.physics.colliders[collider.into()],
+                        collider.into(),
+                        node_handle,
+                        graph,
+                    );
+                } else {
+                    match &editor_scene.physics.colliders[collider.into()].shape {
+                        ColliderShapeDesc::Ball(ball) => {
+                            self.ball_section
+                                .handle_message(message, ball, collider.into());
+                        }
+                        ColliderShapeDesc::Cylinder(cylinder) => {
+                            self.cylinder_section.handle_message(
+                                message,
+                                cylinder,
+                                collider.into(),
+                            );
+                        }
+                        ColliderShapeDesc::RoundCylinder(_) => {
+                            // TODO
+                        }
+                        ColliderShapeDesc::Cone(cone) => {
+                            self.cone_section
+                                .handle_message(message, cone, collider.into());
+                        }
+                        ColliderShapeDesc::Cuboid(cuboid) => {
+                            self.cuboid_section
+                                .handle_message(message, cuboid, collider.into());
+                        }
+                        ColliderShapeDesc::Capsule(capsule) => {
+                            self.capsule_section
+                                .handle_message(message, capsule, collider.into());
+                        }
+                        ColliderShapeDesc::Segment(_) => {
+                            // TODO
+                        }
+                        ColliderShapeDesc::Triangle(_) => {
+                            // TODO
+                        }
+                        ColliderShapeDesc::Trimesh(_) => {
+                            self.trimesh_section.handle_message(
+                                message,
+                                &editor_scene.physics.trimesh_source(collider.into()),
+                                collider.into(),
+                            );
+                        }
+                        ColliderShapeDesc::Heightfield(_) => {
+                            self.heightfield_section.handle_message(
+                                message,
+                                &editor_scene.physics.heightfield_source(collider.into()),
+                                collider.into(),
+                                ui,
+                            );
+                        }
+                    };
+                }
             }
 
             let mut joint = Handle::NONE;
@@ -510,6 +703,7 @@ impl PhysicsSection {
                 self.joint_section.handle_message(
                     message,
                     &editor_scene.physics.joints[joint],
+                    &editor_scene.physics.joint_motion(joint),
                     joint,
                 );
             }
@@ -556,7 +750,21 @@ impl PhysicsSection {
                     };
                 }
 
-                if index != current_status {
+                if current_status != 0 && index != current_status {
+                    // Body already exists, change its status in place to keep its colliders.
+                    let body = *editor_scene.physics.binder.value_of(&node_handle).unwrap();
+                    let status = match index {
+                        1 => BodyStatusDesc::Dynamic,
+                        2 => BodyStatusDesc::Static,
+                        3 => BodyStatusDesc::Kinematic,
+                        _ => unreachable!(),
+                    };
+                    self.sender
+                        .send(Message::DoSceneCommand(SceneCommand::SetBodyStatus(
+                            SetBodyStatusCommand::new(body, status),
+                        )))
+                        .unwrap();
+                } else if index != current_status {
                     // Create body.
                     let node = &graph[node_handle];
                     let body = RigidBody {
@@ -599,16 +807,30 @@ impl PhysicsSection {
         };
     }
 
-    fn select_collider(&self, editor_scene: &EditorScene, node_handle: Handle<Node>, index: usize) {
+    fn select_collider(
+        &self,
+        editor_scene: &EditorScene,
+        node_handle: Handle<Node>,
+        graph: &Graph,
+        index: usize,
+    ) {
         if let Some(&body) = editor_scene.physics.binder.value_of(&node_handle) {
             let mut current_index = 0;
             if let Some(&first_collider) = editor_scene.physics.bodies[body].colliders.first() {
-                current_index = editor_scene.physics.colliders[first_collider.into()]
-                    .shape
-                    .id();
+                let first_collider = first_collider.into();
+                current_index = if editor_scene
+                    .physics
+                    .convex_sources
+                    .contains_key(&first_collider)
+                {
+                    10
+                } else {
+                    editor_scene.physics.colliders[first_collider].shape.id()
+                };
             }
 
             if current_index != index as u32 {
+                let mut convex = None;
                 let collider = match index {
                     0 => Collider {
                         shape: ColliderShapeDesc::Ball(BallDesc { radius: 0.5 }),
@@ -673,6 +895,25 @@ impl PhysicsSection {
                         shape: ColliderShapeDesc::Heightfield(HeightfieldDesc),
                         ..Default::default()
                     },
+                    10 => {
+                        // Start with hull of node's geometry, or with unit cube if there
+                        // is no geometry.
+                        let mut points = collect_points(graph, node_handle);
+                        if points.len() < 4 {
+                            points = (0..8)
+                                .map(|i| {
+                                    Vector3::new(
+                                        if i & 1 == 0 { -0.5 } else { 0.5 },
+                                        if i & 2 == 0 { -0.5 } else { 0.5 },
+                                        if i & 4 == 0 { -0.5 } else { 0.5 },
+                                    )
+                                })
+                                .collect();
+                        }
+                        let fitted = make_convex(&points);
+                        convex = fitted.convex;
+                        fitted.collider
+                    }
                     _ => unreachable!(),
                 };
                 let mut commands = Vec::new();
@@ -683,9 +924,11 @@ impl PhysicsSection {
                         first_collider.into(),
                     )))
                 }
-                commands.push(SceneCommand::SetCollider(SetColliderCommand::new(
-                    body, collider,
-                )));
+                let mut command = SetColliderCommand::new(body, collider);
+                if let Some(convex) = convex {
+                    command = command.with_convex_source(convex);
+                }
+                commands.push(SceneCommand::SetCollider(command));
                 self.sender
                     .send(Message::DoSceneCommand(SceneCommand::CommandGroup(
                         CommandGroup::from(commands),
@@ -778,6 +1021,46 @@ impl PhysicsSection {
         }
     }
 
+    fn auto_collider(
+        &self,
+        editor_scene: &EditorScene,
+        node_handle: Handle<Node>,
+        graph: &Graph,
+        ui: &Ui,
+        apply: bool,
+    ) {
+        let kind = ui
+            .node(self.auto_collider)
+            .as_dropdown_list()
+            .selection()
+            .and_then(FitKind::from_index)
+            .unwrap_or(FitKind::Cuboid);
+
+        match ColliderFit::new(kind, node_handle, graph) {
+            Some(fit) => {
+                if apply {
+                    self.sender
+                        .send(Message::DoSceneCommand(
+                            fit.into_command(&editor_scene.physics, graph),
+                        ))
+                        .unwrap();
+                    self.sender.send(Message::PreviewColliderFit(None)).unwrap();
+                } else {
+                    self.sender
+                        .send(Message::PreviewColliderFit(Some(fit)))
+                        .unwrap();
+                }
+            }
+            None => {
+                self.sender
+                    .send(Message::Log(
+                        "Unable to fit collider - selected node has no geometry!".to_owned(),
+                    ))
+                    .unwrap();
+            }
+        }
+    }
+
     fn fit_collider(&self, editor_scene: &EditorScene, node_handle: Handle<Node>, graph: &Graph) {
         if let Some(&body_handle) = editor_scene.physics.binder.value_of(&node_handle) {
             let body = &editor_scene.physics.bodies[body_handle];
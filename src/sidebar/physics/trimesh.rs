@@ -1 +1,219 @@
-// TODO
+use crate::{
+    gui::{BuildContext, Ui, UiMessage, UiNode},
+    physics::{Collider, TrimeshSource},
+    scene::{SceneCommand, SetTrimeshSourceNodeCommand, SetTrimeshSurfacesCommand},
+    send_sync_message,
+    sidebar::{make_dropdown_list_option, make_text_mark, COLUMN_WIDTH, ROW_HEIGHT},
+    Message,
+};
+use rg3d::{
+    core::pool::Handle,
+    gui::{
+        dropdown_list::DropdownListBuilder,
+        grid::{Column, GridBuilder, Row},
+        message::{
+            DropdownListMessage, MessageDirection, TextBoxMessage, TextMessage, UiMessageData,
+        },
+        text::TextBuilder,
+        text_box::TextBoxBuilder,
+        widget::WidgetBuilder,
+        Thickness, VerticalAlignment,
+    },
+    scene::{graph::Graph, node::Node},
+};
+use std::sync::mpsc::Sender;
+
+pub struct TrimeshSection {
+    pub section: Handle<UiNode>,
+    source: Handle<UiNode>,
+    surfaces: Handle<UiNode>,
+    triangles: Handle<UiNode>,
+    sender: Sender<Message>,
+    // First item in source list is always "bound node".
+    available_meshes: Vec<Handle<Node>>,
+}
+
+fn surfaces_to_string(surfaces: &[u32]) -> String {
+    surfaces
+        .iter()
+        .map(|s| s.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn parse_surfaces(text: &str) -> Vec<u32> {
+    let mut surfaces = text
+        .split(',')
+        .filter_map(|s| s.trim().parse::<u32>().ok())
+        .collect::<Vec<_>>();
+    surfaces.sort_unstable();
+    surfaces.dedup();
+    surfaces
+}
+
+impl TrimeshSection {
+    pub fn new(ctx: &mut BuildContext, sender: Sender<Message>) -> Self {
+        let source;
+        let surfaces;
+        let triangles;
+        let section = GridBuilder::new(
+            WidgetBuilder::new()
+                .with_child(make_text_mark(ctx, "Source Mesh", 0))
+                .with_child({
+                    source = DropdownListBuilder::new(
+                        WidgetBuilder::new()
+                            .on_row(0)
+                            .on_column(1)
+                            .with_margin(Thickness::uniform(1.0)),
+                    )
+                    .with_close_on_selection(true)
+                    .build(ctx);
+                    source
+                })
+                .with_child(make_text_mark(ctx, "Surfaces", 1))
+                .with_child({
+                    surfaces = TextBoxBuilder::new(
+                        WidgetBuilder::new()
+                            .on_row(1)
+                            .on_column(1)
+                            .with_margin(Thickness::uniform(1.0)),
+                    )
+                    .build(ctx);
+                    surfaces
+                })
+                .with_child(make_text_mark(ctx, "Triangles", 2))
+                .with_child({
+                    triangles = TextBuilder::new(
+                        WidgetBuilder::new()
+                            .on_row(2)
+                            .on_column(1)
+                            .with_vertical_alignment(VerticalAlignment::Center)
+                            .with_margin(Thickness::uniform(1.0)),
+                    )
+                    .build(ctx);
+                    triangles
+                }),
+        )
+        .add_column(Column::strict(COLUMN_WIDTH))
+        .add_column(Column::stretch())
+        .add_row(Row::strict(ROW_HEIGHT))
+        .add_row(Row::strict(ROW_HEIGHT))
+        .add_row(Row::strict(ROW_HEIGHT))
+        .build(ctx);
+
+        Self {
+            section,
+            source,
+            surfaces,
+            triangles,
+            sender,
+            available_meshes: Default::default(),
+        }
+    }
+
+    pub fn sync_to_model(
+        &mut self,
+        source: &TrimeshSource,
+        meshes: &[Handle<Node>],
+        graph: &Graph,
+        editor_root: Handle<Node>,
+        ui: &mut Ui,
+    ) {
+        self.available_meshes.clear();
+        self.available_meshes.push(Handle::NONE);
+
+        let ctx = &mut ui.build_ctx();
+        let mut items = vec![make_dropdown_list_option(ctx, "Bound Node")];
+        let mut selection = Some(0);
+        let mut stack = vec![graph.get_root()];
+        while let Some(handle) = stack.pop() {
+            // Editor's meshes (gizmos, etc.) must not be used as source.
+            if handle == editor_root {
+                continue;
+            }
+            let node = &graph[handle];
+            if let Node::Mesh(_) = node {
+                if handle == source.node {
+                    selection = Some(items.len());
+                }
+                items.push(make_dropdown_list_option(ctx, node.name()));
+                self.available_meshes.push(handle);
+            }
+            stack.extend_from_slice(node.children());
+        }
+
+        send_sync_message(
+            ui,
+            DropdownListMessage::items(self.source, MessageDirection::ToWidget, items),
+        );
+        send_sync_message(
+            ui,
+            DropdownListMessage::selection(self.source, MessageDirection::ToWidget, selection),
+        );
+        send_sync_message(
+            ui,
+            TextBoxMessage::text(
+                self.surfaces,
+                MessageDirection::ToWidget,
+                surfaces_to_string(&source.surfaces),
+            ),
+        );
+
+        let mut triangle_count = 0;
+        for &mesh in meshes {
+            for (i, surface) in graph[mesh].as_mesh().surfaces().iter().enumerate() {
+                if source.takes_surface(i) {
+                    triangle_count += surface.data().read().unwrap().triangles().len();
+                }
+            }
+        }
+        send_sync_message(
+            ui,
+            TextMessage::text(
+                self.triangles,
+                MessageDirection::ToWidget,
+                triangle_count.to_string(),
+            ),
+        );
+    }
+
+    pub fn handle_message(
+        &mut self,
+        message: &UiMessage,
+        source: &TrimeshSource,
+        handle: Handle<Collider>,
+    ) {
+        if message.direction() != MessageDirection::FromWidget {
+            return;
+        }
+
+        match message.data() {
+            UiMessageData::DropdownList(DropdownListMessage::SelectionChanged(Some(index)))
+                if message.destination() == self.source =>
+            {
+                if let Some(&node) = self.available_meshes.get(*index) {
+                    if node != source.node {
+                        self.sender
+                            .send(Message::DoSceneCommand(SceneCommand::SetTrimeshSourceNode(
+                                SetTrimeshSourceNodeCommand::new(handle, node),
+                            )))
+                            .unwrap();
+                    }
+                }
+            }
+            UiMessageData::TextBox(TextBoxMessage::Text(text))
+                if message.destination() == self.surfaces =>
+            {
+                let surfaces = parse_surfaces(text);
+                if surfaces != source.surfaces {
+                    self.sender
+                        .send(Message::DoSceneCommand(SceneCommand::SetTrimeshSurfaces(
+                            SetTrimeshSurfacesCommand::new(handle, surfaces),
+                        )))
+                        .unwrap();
+                }
+            }
+            _ => (),
+        }
+    }
+}
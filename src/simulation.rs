@@ -0,0 +1,237 @@
+//! Physics simulation preview. Simulation runs in a separate engine physics world which
+//! is built from editor's physics descriptors, so editor's `Physics` is never touched
+//! while simulating. The only thing that changes is local transforms of nodes bound to
+//! rigid bodies, they're saved when simulation starts and restored when it stops.
+//!
+//! Parameters that engine's descriptors can't hold (see `ShapeSources`) are applied to
+//! resolved world the same way a game applies them to a loaded scene.
+
+use crate::{
+    physics::Physics,
+    physics_layers::PhysicsLayers,
+    scene::{CommandGroup, MoveNodeCommand, RotateNodeCommand, SceneCommand},
+};
+use rg3d::{
+    core::{
+        algebra::{UnitQuaternion, Vector3},
+        pool::Handle,
+    },
+    engine::resource_manager::ResourceManager,
+    scene::{
+        graph::Graph, node::Node, physics::Physics as EnginePhysics, PhysicsBinder, RigidBodyHandle,
+    },
+};
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum SimulationState {
+    Stopped,
+    Playing,
+    Paused,
+}
+
+struct NodeState {
+    node: Handle<Node>,
+    position: Vector3<f32>,
+    rotation: UnitQuaternion<f32>,
+}
+
+/// Max amount of physics steps per editor update, if the editor can't keep up simulation
+/// slows down instead of freezing the editor even more.
+const MAX_STEPS_PER_UPDATE: usize = 8;
+
+pub struct PhysicsSimulation {
+    state: SimulationState,
+    world: EnginePhysics,
+    binder: Vec<(Handle<Node>, RigidBodyHandle)>,
+    snapshot: Vec<NodeState>,
+    /// Time that is not simulated yet, physics is stepped with fixed timestep.
+    accumulator: f32,
+}
+
+impl Default for PhysicsSimulation {
+    fn default() -> Self {
+        Self {
+            state: SimulationState::Stopped,
+            world: Default::default(),
+            binder: Default::default(),
+            snapshot: Default::default(),
+            accumulator: 0.0,
+        }
+    }
+}
+
+impl PhysicsSimulation {
+    pub fn state(&self) -> SimulationState {
+        self.state
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.state != SimulationState::Stopped
+    }
+
+    /// Starts new simulation or resumes paused one. Simulation can't be started while
+    /// there are joints without connected body, same as scene can't be saved with them.
+    /// Returns descriptions of physics parameters that couldn't be simulated.
+    pub fn play(
+        &mut self,
+        physics: &Physics,
+        layers: &PhysicsLayers,
+        graph: &Graph,
+        resource_manager: &ResourceManager,
+    ) -> Result<Vec<String>, String> {
+        let mut warnings = Vec::new();
+        match self.state {
+            SimulationState::Playing => (),
+            SimulationState::Paused => self.state = SimulationState::Playing,
+            SimulationState::Stopped => {
+                if let Some(joint) = physics
+                    .joints
+                    .iter()
+                    .find(|joint| !physics.bodies.is_valid_handle(joint.body2.into()))
+                {
+                    let name = physics
+                        .binder
+                        .key_of(&joint.body1.into())
+                        .filter(|&&node| graph.is_valid_handle(node))
+                        .map_or("<unbound>", |&node| graph[node].name());
+                    return Err(format!(
+                        "Unable to start simulation: joint of node {} has no connected body!",
+                        name
+                    ));
+                }
+
+                let (mut desc, binder) = physics.generate_engine_desc();
+                layers.derive_groups(&mut desc);
+
+                self.snapshot = binder
+                    .keys()
+                    .map(|&node| {
+                        let transform = graph[node].local_transform();
+                        NodeState {
+                            node,
+                            position: **transform.position(),
+                            rotation: **transform.rotation(),
+                        }
+                    })
+                    .collect();
+
+                // Engine needs its binder to resolve trimeshes from bound meshes.
+                let mut engine_binder = PhysicsBinder::default();
+                for (&node, &body) in binder.iter() {
+                    engine_binder.bind(node, body);
+                }
+
+                self.world = Default::default();
+                self.world.desc = Some(desc);
+                self.world.resolve(&engine_binder, graph);
+                let sources = physics.generate_shape_sources(&|node| node, &mut warnings);
+                warnings.extend(sources.apply_to_engine(
+                    &mut self.world,
+                    &engine_binder,
+                    graph,
+                    resource_manager,
+                ));
+
+                self.binder = binder.into_iter().collect();
+                self.accumulator = 0.0;
+                self.state = SimulationState::Playing;
+            }
+        }
+        Ok(warnings)
+    }
+
+    pub fn pause(&mut self) {
+        if self.state == SimulationState::Playing {
+            self.state = SimulationState::Paused;
+        }
+    }
+
+    /// Stops simulation and puts every simulated node back to where it was before
+    /// simulation has started. If `keep_transforms` is set, returns command that moves
+    /// nodes to their simulated transforms so the result can be undone.
+    pub fn stop(&mut self, graph: &mut Graph, keep_transforms: bool) -> Option<SceneCommand> {
+        if self.state == SimulationState::Stopped {
+            return None;
+        }
+
+        self.state = SimulationState::Stopped;
+        self.world = Default::default();
+        self.binder.clear();
+
+        let mut commands = Vec::new();
+        for state in self.snapshot.drain(..) {
+            // Node could be deleted in the meantime.
+            if !graph.is_valid_handle(state.node) {
+                continue;
+            }
+
+            let transform = graph[state.node].local_transform_mut();
+            let new_position = **transform.position();
+            let new_rotation = **transform.rotation();
+
+            transform
+                .set_position(state.position)
+                .set_rotation(state.rotation);
+
+            if keep_transforms {
+                if new_position != state.position {
+                    commands.push(SceneCommand::MoveNode(MoveNodeCommand::new(
+                        state.node,
+                        state.position,
+                        new_position,
+                    )));
+                }
+                if new_rotation != state.rotation {
+                    commands.push(SceneCommand::RotateNode(RotateNodeCommand::new(
+                        state.node,
+                        state.rotation,
+                        new_rotation,
+                    )));
+                }
+            }
+        }
+
+        if commands.is_empty() {
+            None
+        } else {
+            Some(SceneCommand::CommandGroup(CommandGroup::from(commands)))
+        }
+    }
+
+    /// Forgets about simulation without restoring anything, must be used when scene is
+    /// destroyed.
+    pub fn reset(&mut self) {
+        self.state = SimulationState::Stopped;
+        self.world = Default::default();
+        self.binder.clear();
+        self.snapshot.clear();
+    }
+
+    pub fn update(&mut self, graph: &mut Graph, dt: f32) {
+        if self.state != SimulationState::Playing {
+            return;
+        }
+
+        let timestep = self.world.integration_parameters.dt();
+        self.accumulator += dt;
+        let mut steps = 0;
+        while self.accumulator >= timestep {
+            self.accumulator -= timestep;
+            if steps < MAX_STEPS_PER_UPDATE {
+                self.world.step();
+                steps += 1;
+            }
+        }
+
+        for &(node, body) in self.binder.iter() {
+            if let Some(body) = self.world.bodies.get(body.into()) {
+                if graph.is_valid_handle(node) {
+                    graph[node]
+                        .local_transform_mut()
+                        .set_position(body.position().translation.vector)
+                        .set_rotation(body.position().rotation);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,553 @@
+//! Visualizers for values that have spatial meaning - light ranges, spot light cones,
+//! camera frusta and LOD ranges of selected nodes. Every visualizer has drag handles, a
+//! handle changes the value "live" while it is dragged and produces the same command as
+//! the sidebar does when it is released.
+
+use crate::{
+    icons::icon_size,
+    scene::{
+        ChangeLodRangeBeginCommand, ChangeLodRangeEndCommand, EditorScene, SceneCommand, Selection,
+        SetFovCommand, SetPointLightRadiusCommand, SetSpotLightDistanceCommand,
+        SetSpotLightFalloffAngleDeltaCommand, SetSpotLightHotspotCommand, SetZFarCommand,
+    },
+    GameEngine,
+};
+use rg3d::{
+    core::{
+        algebra::{Vector2, Vector3},
+        color::Color,
+        math::{plane::Plane, Matrix4Ext},
+        pool::Handle,
+    },
+    scene::{graph::Graph, light::Light, node::Node, Line, SceneDrawingContext},
+};
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+enum HandleKind {
+    PointLightRadius,
+    SpotLightDistance,
+    SpotLightHotspot,
+    SpotLightFalloff,
+    CameraFov,
+    CameraZFar,
+    LodBegin(usize),
+    LodEnd(usize),
+}
+
+struct VisualizerHandle {
+    node: Handle<Node>,
+    kind: HandleKind,
+    position: Vector3<f32>,
+}
+
+struct Drag {
+    node: Handle<Node>,
+    kind: HandleKind,
+    initial_value: f32,
+}
+
+#[derive(Default)]
+pub struct Visualizers {
+    handles: Vec<VisualizerHandle>,
+    drag: Option<Drag>,
+}
+
+const LIGHT_COLOR: Color = Color::opaque(255, 220, 0);
+const CAMERA_COLOR: Color = Color::opaque(200, 200, 200);
+const LOD_COLOR: Color = Color::opaque(0, 200, 120);
+const HANDLE_COLOR: Color = Color::opaque(255, 255, 255);
+const ACTIVE_HANDLE_COLOR: Color = Color::opaque(255, 0, 0);
+
+/// Basis of a node without scale - visualizers must not be distorted by scaling.
+struct Frame {
+    position: Vector3<f32>,
+    side: Vector3<f32>,
+    up: Vector3<f32>,
+    look: Vector3<f32>,
+}
+
+impl Frame {
+    fn of(node: &Node) -> Self {
+        let transform = node.global_transform();
+        Self {
+            position: transform.position(),
+            side: transform
+                .side()
+                .try_normalize(std::f32::EPSILON)
+                .unwrap_or_else(Vector3::x),
+            up: transform
+                .up()
+                .try_normalize(std::f32::EPSILON)
+                .unwrap_or_else(Vector3::y),
+            look: transform
+                .look()
+                .try_normalize(std::f32::EPSILON)
+                .unwrap_or_else(Vector3::z),
+        }
+    }
+}
+
+fn draw_circle(
+    ctx: &mut SceneDrawingContext,
+    center: Vector3<f32>,
+    a: Vector3<f32>,
+    b: Vector3<f32>,
+    radius: f32,
+    color: Color,
+) {
+    let segments = 32;
+    let point = |i: usize| {
+        let angle = i as f32 / segments as f32 * 2.0 * std::f32::consts::PI;
+        center + a.scale(radius * angle.cos()) + b.scale(radius * angle.sin())
+    };
+    for i in 0..segments {
+        ctx.add_line(Line {
+            begin: point(i),
+            end: point(i + 1),
+            color,
+        });
+    }
+}
+
+fn draw_cone(
+    ctx: &mut SceneDrawingContext,
+    frame: &Frame,
+    direction: Vector3<f32>,
+    length: f32,
+    angle: f32,
+    color: Color,
+) {
+    let radius = length * (angle * 0.5).tan();
+    let center = frame.position + direction.scale(length);
+    draw_circle(ctx, center, frame.side, frame.look, radius, color);
+    for &offset in [frame.side, -frame.side, frame.look, -frame.look].iter() {
+        ctx.add_line(Line {
+            begin: frame.position,
+            end: center + offset.scale(radius),
+            color,
+        });
+    }
+}
+
+fn lod_distance(value: f32, z_near: f32, z_far: f32) -> f32 {
+    z_near + value * (z_far - z_near)
+}
+
+/// Returns value handle changes, `None` if node does not have it (anymore).
+fn get_value(node: &Node, kind: HandleKind) -> Option<f32> {
+    let value = match (node, kind) {
+        (Node::Light(Light::Point(point)), HandleKind::PointLightRadius) => point.radius(),
+        (Node::Light(Light::Spot(spot)), HandleKind::SpotLightDistance) => spot.distance(),
+        (Node::Light(Light::Spot(spot)), HandleKind::SpotLightHotspot) => spot.hotspot_cone_angle(),
+        (Node::Light(Light::Spot(spot)), HandleKind::SpotLightFalloff) => {
+            spot.falloff_angle_delta()
+        }
+        (Node::Camera(camera), HandleKind::CameraFov) => camera.fov(),
+        (Node::Camera(camera), HandleKind::CameraZFar) => camera.z_far(),
+        (_, HandleKind::LodBegin(level)) => node.lod_group()?.levels.get(level)?.begin(),
+        (_, HandleKind::LodEnd(level)) => node.lod_group()?.levels.get(level)?.end(),
+        _ => return None,
+    };
+    Some(value)
+}
+
+fn set_value(node: &mut Node, kind: HandleKind, value: f32) {
+    match (node, kind) {
+        (Node::Light(Light::Point(point)), HandleKind::PointLightRadius) => {
+            point.set_radius(value);
+        }
+        (Node::Light(Light::Spot(spot)), HandleKind::SpotLightDistance) => {
+            spot.set_distance(value);
+        }
+        (Node::Light(Light::Spot(spot)), HandleKind::SpotLightHotspot) => {
+            spot.set_hotspot_cone_angle(value);
+        }
+        (Node::Light(Light::Spot(spot)), HandleKind::SpotLightFalloff) => {
+            spot.set_falloff_angle_delta(value);
+        }
+        (Node::Camera(camera), HandleKind::CameraFov) => {
+            camera.set_fov(value);
+        }
+        (Node::Camera(camera), HandleKind::CameraZFar) => {
+            camera.set_z_far(value);
+        }
+        (node, HandleKind::LodBegin(level)) => {
+            if let Some(level) = node
+                .lod_group_mut()
+                .and_then(|lod_group| lod_group.levels.get_mut(level))
+            {
+                level.set_begin(value);
+            }
+        }
+        (node, HandleKind::LodEnd(level)) => {
+            if let Some(level) = node
+                .lod_group_mut()
+                .and_then(|lod_group| lod_group.levels.get_mut(level))
+            {
+                level.set_end(value);
+            }
+        }
+        _ => (),
+    }
+}
+
+fn make_command(node: Handle<Node>, kind: HandleKind, value: f32) -> SceneCommand {
+    match kind {
+        HandleKind::PointLightRadius => {
+            SceneCommand::SetPointLightRadius(SetPointLightRadiusCommand::new(node, value))
+        }
+        HandleKind::SpotLightDistance => {
+            SceneCommand::SetSpotLightDistance(SetSpotLightDistanceCommand::new(node, value))
+        }
+        HandleKind::SpotLightHotspot => {
+            SceneCommand::SetSpotLightHotspot(SetSpotLightHotspotCommand::new(node, value))
+        }
+        HandleKind::SpotLightFalloff => SceneCommand::SetSpotLightFalloffAngleDelta(
+            SetSpotLightFalloffAngleDeltaCommand::new(node, value),
+        ),
+        HandleKind::CameraFov => SceneCommand::SetFov(SetFovCommand::new(node, value)),
+        HandleKind::CameraZFar => SceneCommand::SetZFar(SetZFarCommand::new(node, value)),
+        HandleKind::LodBegin(level) => {
+            SceneCommand::ChangeLodRangeBegin(ChangeLodRangeBeginCommand::new(node, level, value))
+        }
+        HandleKind::LodEnd(level) => {
+            SceneCommand::ChangeLodRangeEnd(ChangeLodRangeEndCommand::new(node, level, value))
+        }
+    }
+}
+
+impl Visualizers {
+    pub fn is_dragging(&self) -> bool {
+        self.drag.is_some()
+    }
+
+    /// Draws visualizers of every selected node and remembers positions of their handles.
+    pub fn update(
+        &mut self,
+        editor_scene: &EditorScene,
+        graph: &Graph,
+        ctx: &mut SceneDrawingContext,
+        aspect: f32,
+    ) {
+        self.handles.clear();
+
+        let selection = if let Selection::Graph(selection) = &editor_scene.selection {
+            selection
+        } else {
+            return;
+        };
+
+        let editor_camera = graph[editor_scene.camera_controller.camera].as_camera();
+        let (z_near, z_far) = (editor_camera.z_near(), editor_camera.z_far());
+
+        for &handle in selection.nodes() {
+            // Piloted node is the viewer itself, its visualizer will only obstruct the view.
+            if handle == editor_scene.camera_controller.pilot() {
+                continue;
+            }
+
+            let node = &graph[handle];
+            let frame = Frame::of(node);
+
+            match node {
+                Node::Light(Light::Point(point)) => {
+                    let radius = point.radius();
+                    draw_circle(
+                        ctx,
+                        frame.position,
+                        frame.side,
+                        frame.up,
+                        radius,
+                        LIGHT_COLOR,
+                    );
+                    draw_circle(
+                        ctx,
+                        frame.position,
+                        frame.side,
+                        frame.look,
+                        radius,
+                        LIGHT_COLOR,
+                    );
+                    draw_circle(
+                        ctx,
+                        frame.position,
+                        frame.up,
+                        frame.look,
+                        radius,
+                        LIGHT_COLOR,
+                    );
+                    self.handles.push(VisualizerHandle {
+                        node: handle,
+                        kind: HandleKind::PointLightRadius,
+                        position: frame.position + frame.side.scale(radius),
+                    });
+                }
+                Node::Light(Light::Spot(spot)) => {
+                    // Spot light shines along negative Y axis.
+                    let direction = -frame.up;
+                    let distance = spot.distance();
+                    let hotspot = spot.hotspot_cone_angle();
+                    let full = hotspot + spot.falloff_angle_delta();
+                    draw_cone(ctx, &frame, direction, distance, hotspot, LIGHT_COLOR);
+                    draw_cone(ctx, &frame, direction, distance, full, LIGHT_COLOR);
+
+                    let end = frame.position + direction.scale(distance);
+                    self.handles.push(VisualizerHandle {
+                        node: handle,
+                        kind: HandleKind::SpotLightDistance,
+                        position: end,
+                    });
+                    self.handles.push(VisualizerHandle {
+                        node: handle,
+                        kind: HandleKind::SpotLightHotspot,
+                        position: end + frame.side.scale(distance * (hotspot * 0.5).tan()),
+                    });
+                    self.handles.push(VisualizerHandle {
+                        node: handle,
+                        kind: HandleKind::SpotLightFalloff,
+                        position: end + frame.side.scale(distance * (full * 0.5).tan()),
+                    });
+                }
+                Node::Camera(camera) => {
+                    let half_height = |d: f32| d * (camera.fov() * 0.5).tan();
+                    let corners = |d: f32| {
+                        let h = half_height(d);
+                        let w = h * aspect;
+                        let c = frame.position + frame.look.scale(d);
+                        [
+                            c + frame.side.scale(w) + frame.up.scale(h),
+                            c - frame.side.scale(w) + frame.up.scale(h),
+                            c - frame.side.scale(w) - frame.up.scale(h),
+                            c + frame.side.scale(w) - frame.up.scale(h),
+                        ]
+                    };
+                    let near = corners(camera.z_near());
+                    let far = corners(camera.z_far());
+                    for i in 0..4 {
+                        let next = (i + 1) % 4;
+                        for &(begin, end) in [
+                            (near[i], near[next]),
+                            (far[i], far[next]),
+                            (near[i], far[i]),
+                        ]
+                        .iter()
+                        {
+                            ctx.add_line(Line {
+                                begin,
+                                end,
+                                color: CAMERA_COLOR,
+                            });
+                        }
+                    }
+
+                    let far_center = frame.position + frame.look.scale(camera.z_far());
+                    self.handles.push(VisualizerHandle {
+                        node: handle,
+                        kind: HandleKind::CameraZFar,
+                        position: far_center,
+                    });
+                    self.handles.push(VisualizerHandle {
+                        node: handle,
+                        kind: HandleKind::CameraFov,
+                        position: far_center + frame.up.scale(half_height(camera.z_far())),
+                    });
+                }
+                _ => (),
+            }
+
+            // LOD ranges are measured from the camera that looks at the node, so show them
+            // relative to editor camera.
+            if let Some(lod_group) = node.lod_group() {
+                for (i, level) in lod_group.levels.iter().enumerate() {
+                    for &(value, kind) in [
+                        (level.begin(), HandleKind::LodBegin(i)),
+                        (level.end(), HandleKind::LodEnd(i)),
+                    ]
+                    .iter()
+                    {
+                        let radius = lod_distance(value, z_near, z_far);
+                        draw_circle(
+                            ctx,
+                            frame.position,
+                            Vector3::x(),
+                            Vector3::z(),
+                            radius,
+                            LOD_COLOR,
+                        );
+                        self.handles.push(VisualizerHandle {
+                            node: handle,
+                            kind,
+                            position: frame.position + Vector3::x().scale(radius),
+                        });
+                    }
+                }
+            }
+        }
+
+        let camera_position = editor_camera.global_position();
+        let fov = editor_camera.fov();
+        for visualizer_handle in self.handles.iter() {
+            let color = match self.drag.as_ref() {
+                Some(drag)
+                    if drag.node == visualizer_handle.node
+                        && drag.kind == visualizer_handle.kind =>
+                {
+                    ACTIVE_HANDLE_COLOR
+                }
+                _ => HANDLE_COLOR,
+            };
+            let radius = handle_radius(visualizer_handle.position, camera_position, fov);
+            ctx.draw_sphere(visualizer_handle.position, 6, 6, radius, color);
+        }
+    }
+
+    /// Tries to pick a handle under cursor, returns `true` if dragging has started and
+    /// the click must not be passed further.
+    pub fn try_begin_drag(
+        &mut self,
+        editor_scene: &EditorScene,
+        engine: &GameEngine,
+        mouse_pos: Vector2<f32>,
+        frame_size: Vector2<f32>,
+    ) -> bool {
+        let graph = &engine.scenes[editor_scene.scene].graph;
+        let camera = graph[editor_scene.camera_controller.camera].as_camera();
+        let ray = camera.make_ray(mouse_pos, frame_size);
+
+        let mut closest = None;
+        let mut closest_distance = std::f32::MAX;
+        for visualizer_handle in self.handles.iter() {
+            let radius = handle_radius(
+                visualizer_handle.position,
+                camera.global_position(),
+                camera.fov(),
+            );
+            if ray
+                .sphere_intersection(&visualizer_handle.position, radius)
+                .is_some()
+            {
+                let distance = visualizer_handle.position.metric_distance(&ray.origin);
+                if distance < closest_distance {
+                    closest_distance = distance;
+                    closest = Some(visualizer_handle);
+                }
+            }
+        }
+
+        match closest.and_then(|visualizer_handle| {
+            let initial_value = get_value(&graph[visualizer_handle.node], visualizer_handle.kind)?;
+            Some(Drag {
+                node: visualizer_handle.node,
+                kind: visualizer_handle.kind,
+                initial_value,
+            })
+        }) {
+            Some(drag) => {
+                self.drag = Some(drag);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn drag(
+        &mut self,
+        editor_scene: &EditorScene,
+        engine: &mut GameEngine,
+        mouse_pos: Vector2<f32>,
+        frame_size: Vector2<f32>,
+    ) {
+        let (handle, kind) = if let Some(drag) = self.drag.as_ref() {
+            (drag.node, drag.kind)
+        } else {
+            return;
+        };
+
+        let graph = &mut engine.scenes[editor_scene.scene].graph;
+        if !graph.is_valid_handle(handle) {
+            self.drag = None;
+            return;
+        }
+
+        let camera = graph[editor_scene.camera_controller.camera].as_camera();
+        let ray = camera.make_ray(mouse_pos, frame_size);
+        let (editor_z_near, editor_z_far) = (camera.z_near(), camera.z_far());
+        let frame = Frame::of(&graph[handle]);
+
+        // Handles are moved in a plane that faces camera and goes through the node.
+        let camera_look = camera.global_transform().look();
+        let point = match Plane::from_normal_and_point(&camera_look, &frame.position)
+            .and_then(|plane| ray.plane_intersection_point(&plane))
+        {
+            Some(point) => point - frame.position,
+            None => return,
+        };
+
+        let node = &mut graph[handle];
+        let (hotspot, z_near) = match &*node {
+            Node::Light(Light::Spot(spot)) => (spot.hotspot_cone_angle(), 0.0),
+            Node::Camera(camera) => (0.0, camera.z_near()),
+            _ => (0.0, 0.0),
+        };
+        let cone_angle = |direction: Vector3<f32>| {
+            let along = point.dot(&direction).max(std::f32::EPSILON);
+            let across = (point - direction.scale(along)).norm();
+            2.0 * across.atan2(along)
+        };
+        let value = match kind {
+            HandleKind::PointLightRadius => point.norm(),
+            HandleKind::SpotLightDistance => point.dot(&-frame.up).max(0.0),
+            HandleKind::SpotLightHotspot => cone_angle(-frame.up),
+            HandleKind::SpotLightFalloff => (cone_angle(-frame.up) - hotspot).max(0.0),
+            HandleKind::CameraFov => cone_angle(frame.look),
+            HandleKind::CameraZFar => point.dot(&frame.look).max(z_near + std::f32::EPSILON),
+            HandleKind::LodBegin(_) | HandleKind::LodEnd(_) => ((point.norm() - editor_z_near)
+                / (editor_z_far - editor_z_near))
+                .max(0.0)
+                .min(1.0),
+        };
+
+        set_value(node, kind, value);
+    }
+
+    /// Finishes dragging. Returns a command that commits new value, the node itself is
+    /// put back in initial state so the command could be executed as usual.
+    pub fn end_drag(
+        &mut self,
+        editor_scene: &EditorScene,
+        engine: &mut GameEngine,
+    ) -> Option<SceneCommand> {
+        let drag = self.drag.take()?;
+
+        let graph = &mut engine.scenes[editor_scene.scene].graph;
+        if !graph.is_valid_handle(drag.node) {
+            return None;
+        }
+
+        let node = &mut graph[drag.node];
+        let value = get_value(node, drag.kind)?;
+        set_value(node, drag.kind, drag.initial_value);
+
+        if value != drag.initial_value {
+            Some(make_command(drag.node, drag.kind, value))
+        } else {
+            None
+        }
+    }
+
+    /// Stops dragging and puts the node back in initial state. Must be called before scene
+    /// is changed by anything else, otherwise the handle could end up changing a node that
+    /// does not have the value anymore.
+    pub fn cancel_drag(&mut self, editor_scene: &EditorScene, engine: &mut GameEngine) {
+        if let Some(drag) = self.drag.take() {
+            let graph = &mut engine.scenes[editor_scene.scene].graph;
+            if graph.is_valid_handle(drag.node) {
+                set_value(&mut graph[drag.node], drag.kind, drag.initial_value);
+            }
+        }
+    }
+}
+
+fn handle_radius(position: Vector3<f32>, camera_position: Vector3<f32>, fov: f32) -> f32 {
+    icon_size(position.metric_distance(&camera_position), fov) * 0.25
+}